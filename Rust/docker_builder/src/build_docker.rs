@@ -1,3 +1,5 @@
+#[allow(clippy::module_inception)]
 pub mod build_docker;
 pub mod build_docker_command;
 pub mod create_dockerfile;
+pub mod push_docker;
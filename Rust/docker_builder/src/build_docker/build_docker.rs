@@ -2,7 +2,7 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use crate::configuration::build_docker_configuration::BuildDockerConfiguration;
-use super::create_dockerfile::create_dockerfile;
+use super::create_dockerfile::create_dockerfile_from_data;
 use super::build_docker_command::build_docker_build_command;
 
 /// Arguments from CLI for building
@@ -11,6 +11,8 @@ pub struct BuildDockerArgs {
     pub build_dir: PathBuf,
     pub no_cache: bool,
     pub network_host: bool,
+    /// `--var name=value` overrides for `vars:` in build_configuration.yml
+    pub vars: Vec<(String, String)>,
 }
 
 //------------------------------------------------------------------------------
@@ -46,7 +48,8 @@ pub fn build_docker_image_from_args(
     }
 
     println!("    Loading configuration from: {}", config_file.display());
-    let config = BuildDockerConfiguration::load_data(Some(&config_file))?;
+    let config = BuildDockerConfiguration::load_data_with_vars(
+        Some(&config_file), &args.vars)?;
 
     println!("    Image name: {}", config.docker_image_name);
     println!("    Base image: {}", config.base_image);
@@ -60,7 +63,7 @@ pub fn build_docker_image_from_args(
     let dockerfile_path = build_dir.join("Dockerfile");
     println!("\n==> Creating Dockerfile at: {}", dockerfile_path.display());
 
-    create_dockerfile(&config_file, &dockerfile_path)?;
+    create_dockerfile_from_data(&config, &dockerfile_path)?;
 
     // Verify Dockerfile was created
     if !dockerfile_path.exists() {
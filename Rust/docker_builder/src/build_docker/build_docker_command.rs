@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use std::path::Path;
 
 use crate::configuration::build_docker_configuration::BuildDockerConfigurationData;
+use crate::run_docker::digest::split_digest;
 
 /// Builds the Docker build command as a list of arguments.
 ///
@@ -35,8 +36,24 @@ pub fn build_docker_build_command(
         docker_build_cmd.push("host".to_string());
     }
 
-    // Add build_args from configuration (dynamic from YAML)
+    // Cache import is pointless with --no-cache; export still refreshes the
+    // cache for the next build.
+    if use_cache {
+        for source in &build_configuration.cache_from {
+            docker_build_cmd.push("--cache-from".to_string());
+            docker_build_cmd.push(source.clone());
+        }
+    }
+    for destination in &build_configuration.cache_to {
+        docker_build_cmd.push("--cache-to".to_string());
+        docker_build_cmd.push(destination.clone());
+    }
+
+    // Add build_args from configuration (dynamic from YAML), sorted so the
+    // command is stable between runs
     let build_args: &HashMap<String, String> = &build_configuration.build_args;
+    let mut build_args: Vec<(&String, &String)> = build_args.iter().collect();
+    build_args.sort();
     for (key, value) in build_args {
         docker_build_cmd.push("--build-arg".to_string());
         docker_build_cmd.push(format!("{}={}", key.to_uppercase(), value));
@@ -61,9 +78,10 @@ pub fn build_docker_build_command(
     docker_build_cmd.push("-f".to_string());
     docker_build_cmd.push(dockerfile_path.to_string_lossy().into_owned());
 
-    // Tag the image
+    // Tag the image (a pinned @sha256: digest can't be used as a tag)
     docker_build_cmd.push("-t".to_string());
-    docker_build_cmd.push(build_configuration.docker_image_name.clone());
+    docker_build_cmd.push(
+        split_digest(&build_configuration.docker_image_name).0.to_string());
 
     // Build context (.)
     docker_build_cmd.push(".".to_string());
@@ -86,6 +104,7 @@ mod tests {
             base_image: "ubuntu:22.04".to_string(),
             build_args,
             dockerfile_components: vec![],
+            ..Default::default()
         };
 
         let dockerfile_path = Path::new("Dockerfile");
@@ -106,4 +125,43 @@ mod tests {
             "DOCKER_IMAGE_NAME=test-image:latest")));
         assert!(cmd.last() == Some(&".".to_string()));
     }
+
+    #[test]
+    fn test_build_args_are_sorted() {
+        let build_args: HashMap<String, String> = [("b", "2"), ("a", "1"), ("c", "3")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let config = BuildDockerConfigurationData {
+            docker_image_name: "img".to_string(),
+            base_image: "ubuntu:22.04".to_string(),
+            build_args,
+            dockerfile_components: vec![],
+            ..Default::default()
+        };
+
+        let cmd = build_docker_build_command(Path::new("Dockerfile"), &config, true, false);
+        let args: Vec<&String> = cmd.iter().skip(3).step_by(2).take(3).collect();
+        assert_eq!(args, ["A=1", "B=2", "C=3"]);
+    }
+
+    #[test]
+    fn test_cache_from_and_cache_to() {
+        let config = BuildDockerConfigurationData {
+            docker_image_name: "img".to_string(),
+            base_image: "ubuntu:22.04".to_string(),
+            cache_from: vec!["type=registry,ref=reg/app:cache".to_string()],
+            cache_to: vec!["type=registry,ref=reg/app:cache,mode=max".to_string()],
+            ..Default::default()
+        };
+
+        let cmd = build_docker_build_command(Path::new("Dockerfile"), &config, true, false);
+        let joined = cmd.join(" ");
+        assert!(joined.contains("--cache-from type=registry,ref=reg/app:cache "));
+        assert!(joined.contains("--cache-to type=registry,ref=reg/app:cache,mode=max "));
+
+        let cmd = build_docker_build_command(Path::new("Dockerfile"), &config, false, false);
+        assert!(!cmd.contains(&"--cache-from".to_string()));
+        assert!(cmd.contains(&"--cache-to".to_string()));
+    }
 }
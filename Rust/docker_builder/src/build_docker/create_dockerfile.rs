@@ -1,7 +1,8 @@
 use std::fs;
 use std::path::Path;
 
-use crate::configuration::build_docker_configuration::BuildDockerConfiguration;
+use crate::configuration::build_docker_configuration::{
+    BuildDockerConfiguration, BuildDockerConfigurationData};
 
 /// Create a concatenated Dockerfile from configuration components
 ///
@@ -18,7 +19,15 @@ pub fn create_dockerfile<P: AsRef<Path>, Q: AsRef<Path>>(
 ) -> Result<(), String> {
     // Load the configuration data
     let data = BuildDockerConfiguration::load_data(Some(configuration_path))?;
+    create_dockerfile_from_data(&data, output_path)
+}
 
+/// Create a concatenated Dockerfile from already-loaded configuration data
+/// (component paths resolved by `load_data`).
+pub fn create_dockerfile_from_data<Q: AsRef<Path>>(
+    data: &BuildDockerConfigurationData,
+    output_path: Q,
+) -> Result<(), String> {
     // Concatenate components
     let mut dockerfile_content = String::new();
 
@@ -32,7 +41,7 @@ pub fn create_dockerfile<P: AsRef<Path>, Q: AsRef<Path>>(
 
         dockerfile_content.push_str(
             &format!("# --- Section: {} ---\n", component.label));
-        dockerfile_content.push_str("\n");
+        dockerfile_content.push('\n');
 
         let component_content = fs::read_to_string(&component.path)
             .map_err(|e| format!(
@@ -0,0 +1,192 @@
+//! Tag and push a built image: docker_image_name, optionally re-rooted under
+//! `registry`, plus `additional_tags` and the git commit SHA.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::configuration::build_docker_configuration::{
+    BuildDockerConfiguration,
+    BuildDockerConfigurationData};
+use crate::run_docker::digest::split_digest;
+
+/// Arguments from CLI for pushing
+#[derive(Debug, Clone)]
+pub struct PushDockerArgs {
+    pub build_dir: PathBuf,
+}
+
+//------------------------------------------------------------------------------
+/// Split an image reference into repository and tag. The tag is whatever
+/// follows the last ':' after the last '/', so registry ports
+/// (`localhost:5000/app`) are not mistaken for tags. Defaults to `latest`.
+/// A pinned `@sha256:` digest is dropped; pushing produces a new one.
+//------------------------------------------------------------------------------
+pub fn split_image_name(image: &str) -> (&str, &str) {
+    let image = split_digest(image).0;
+    let name_start = image.rfind('/').map_or(0, |i| i + 1);
+    match image[name_start..].rfind(':') {
+        Some(i) => (&image[..name_start + i], &image[name_start + i + 1..]),
+        None => (image, "latest"),
+    }
+}
+
+//------------------------------------------------------------------------------
+/// All references to push, in order: the primary tag, additional tags, then
+/// the git SHA tag. Duplicates are dropped.
+//------------------------------------------------------------------------------
+pub fn image_references(
+    config: &BuildDockerConfigurationData,
+    git_sha: Option<&str>,
+) -> Vec<String> {
+    let (repository, tag) = split_image_name(&config.docker_image_name);
+    let repository = match config.registry.as_deref().map(str::trim) {
+        Some(registry) if !registry.is_empty() => {
+            // Keep only the last path component when re-rooting, so
+            // `old.registry/app` becomes `<registry>/app`.
+            let name = repository.rsplit('/').next().unwrap_or(repository);
+            format!("{}/{}", registry.trim_end_matches('/'), name)
+        }
+        _ => repository.to_string(),
+    };
+
+    let tags = std::iter::once(tag)
+        .chain(config.additional_tags.iter().map(|t| t.trim()))
+        .chain(git_sha);
+
+    let mut references: Vec<String> = Vec::new();
+    for tag in tags.filter(|t| !t.is_empty()) {
+        let reference = format!("{}:{}", repository, tag);
+        if !references.contains(&reference) {
+            references.push(reference);
+        }
+    }
+    references
+}
+
+//------------------------------------------------------------------------------
+/// Short commit SHA of the git repository containing `dir`.
+//------------------------------------------------------------------------------
+pub fn git_short_sha(dir: &Path) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "git rev-parse failed in '{}': {}",
+            dir.display(),
+            String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+//------------------------------------------------------------------------------
+/// # Steps:
+/// 1. Load build_configuration.yml from build_dir
+/// 2. Compute target references (registry, additional tags, git SHA)
+/// 3. `docker tag` the built image to each reference
+/// 4. `docker push` each reference
+///
+/// # Returns
+/// * `Ok(Vec<String>)` - Pushed references
+/// * `Err(String)` - Error at any step
+//------------------------------------------------------------------------------
+pub fn push_docker_image_from_args(
+    args: &PushDockerArgs,
+) -> Result<Vec<String>, String> {
+    let build_dir = args.build_dir
+        .canonicalize()
+        .map_err(|e| format!(
+            "Invalid build directory '{}': {}",
+            args.build_dir.display(), e))?;
+
+    let config_file = build_dir.join(BuildDockerConfiguration::DEFAULT_FILE_NAME);
+    let config = BuildDockerConfiguration::load_data(Some(&config_file))?;
+
+    let git_sha = if config.tag_with_git_sha {
+        Some(git_short_sha(&build_dir)?)
+    } else {
+        None
+    };
+
+    let references = image_references(&config, git_sha.as_deref());
+
+    println!("==> Pushing image: {}", config.docker_image_name);
+    let local_image = split_digest(&config.docker_image_name).0;
+    for reference in &references {
+        if reference != local_image {
+            println!("    Tagging {}", reference);
+            run_docker(&["tag", local_image, reference])?;
+        }
+    }
+
+    for reference in &references {
+        println!("\n==> docker push {}", reference);
+        run_docker(&["push", reference])?;
+    }
+
+    Ok(references)
+}
+
+fn run_docker(args: &[&str]) -> Result<(), String> {
+    let status = Command::new("docker")
+        .args(args)
+        .status()
+        .map_err(|e| format!("Failed to execute docker {}: {}", args[0], e))?;
+
+    if !status.success() {
+        return Err(format!(
+            "docker {} failed with exit code: {}",
+            args.join(" "),
+            status.code().unwrap_or(-1)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_image_name() {
+        assert_eq!(split_image_name("app:1.0"), ("app", "1.0"));
+        assert_eq!(split_image_name("app"), ("app", "latest"));
+        assert_eq!(
+            split_image_name("localhost:5000/team/app"),
+            ("localhost:5000/team/app", "latest"));
+        assert_eq!(
+            split_image_name("localhost:5000/app:dev"),
+            ("localhost:5000/app", "dev"));
+        assert_eq!(
+            split_image_name("app:1.0@sha256:0123"),
+            ("app", "1.0"));
+    }
+
+    #[test]
+    fn test_image_references() {
+        let mut config = BuildDockerConfigurationData {
+            docker_image_name: "cuda-dev:12.4".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(image_references(&config, None), vec!["cuda-dev:12.4"]);
+
+        config.registry = Some("ghcr.io/inserviceofx/".to_string());
+        config.additional_tags = vec!["latest".to_string(), "12.4".to_string()];
+        assert_eq!(
+            image_references(&config, Some("abc1234")),
+            vec![
+                "ghcr.io/inserviceofx/cuda-dev:12.4",
+                "ghcr.io/inserviceofx/cuda-dev:latest",
+                "ghcr.io/inserviceofx/cuda-dev:abc1234",
+            ]);
+
+        config.docker_image_name = "docker.io/old/cuda-dev".to_string();
+        config.additional_tags.clear();
+        assert_eq!(
+            image_references(&config, None),
+            vec!["ghcr.io/inserviceofx/cuda-dev:latest"]);
+    }
+}
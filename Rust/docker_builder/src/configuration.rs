@@ -1,2 +1,8 @@
 pub mod build_docker_configuration;
+pub mod interpolation;
+pub mod overlay;
 pub mod run_docker_configuration;
+pub mod schema;
+pub mod stack_configuration;
+pub mod validation;
+pub mod variables;
@@ -1,7 +1,13 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_yaml::Value;
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use super::interpolation::from_str_with_vars;
+use super::schema::BUILD_CONFIGURATION_SCHEMA;
+use super::validation::check_schema;
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct DockerfileComponent {
     /// Human-readable label or filename for identification (e.g.,
@@ -12,20 +18,22 @@ pub struct DockerfileComponent {
     pub path: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct BuildDockerConfigurationData {
     pub docker_image_name: String,
 
     /// Base Docker image to use
     pub base_image: String,
 
-    /// Build arguments as key-value pairs
-    /// These are typically environment variables passed to the Docker build
-    /// command.
+    /// Build arguments as key-value pairs, passed as `--build-arg KEY=VALUE`
+    /// (e.g. CUDA version, user id, proxy). Values may be numbers or booleans
+    /// and may reference host environment variables as `${VAR}` or
+    /// `${VAR:-default}`.
     #[serde(
         default,
-        skip_serializing_if = "std::collections::HashMap::is_empty")]
-    pub build_args: std::collections::HashMap<String, String>,
+        deserialize_with = "deserialize_build_args",
+        skip_serializing_if = "HashMap::is_empty")]
+    pub build_args: HashMap<String, String>,
 
     /// Ordered list of Dockerfile components to concatenate into the final
     /// Dockerfile.
@@ -33,17 +41,87 @@ pub struct BuildDockerConfigurationData {
     /// absolute).
     #[serde(default)]
     pub dockerfile_components: Vec<DockerfileComponent>,
+
+    /// External cache sources, passed as `--cache-from` (e.g.
+    /// `type=registry,ref=ghcr.io/org/app:buildcache` or
+    /// `type=local,src=/var/cache/buildx`). A single string or a list.
+    #[serde(default, deserialize_with = "deserialize_string_or_list")]
+    pub cache_from: Vec<String>,
+
+    /// Cache export destinations, passed as `--cache-to` (e.g.
+    /// `type=registry,ref=ghcr.io/org/app:buildcache,mode=max`). Exporting to
+    /// a registry or local directory needs a buildx builder with the
+    /// docker-container driver; `type=inline` works with the default builder.
+    #[serde(default, deserialize_with = "deserialize_string_or_list")]
+    pub cache_to: Vec<String>,
+
+    /// Registry (and namespace) to push to, e.g. `ghcr.io/org`. Prefixed to
+    /// the repository part of docker_image_name when pushing.
+    #[serde(default)]
+    pub registry: Option<String>,
+
+    /// Extra tags to push alongside the tag in docker_image_name.
+    #[serde(default, deserialize_with = "deserialize_string_or_list")]
+    pub additional_tags: Vec<String>,
+
+    /// Also push a tag with the short git commit SHA of the build directory.
+    #[serde(default)]
+    pub tag_with_git_sha: bool,
 }
 
-impl Default for BuildDockerConfigurationData {
-    fn default() -> Self {
-        Self {
-            docker_image_name: String::new(),
-            base_image: String::new(),
-            build_args: std::collections::HashMap::new(),
-            dockerfile_components: Vec::new(),
-        }
+//------------------------------------------------------------------------------
+/// Deserialize a single string or a list of strings into a list.
+//------------------------------------------------------------------------------
+fn deserialize_string_or_list<'de, D>(
+    deserializer: D,
+) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrList {
+        Single(String),
+        List(Vec<String>),
     }
+
+    Ok(match Option::<StringOrList>::deserialize(deserializer)? {
+        None => Vec::new(),
+        Some(StringOrList::Single(s)) if s.trim().is_empty() => Vec::new(),
+        Some(StringOrList::Single(s)) => vec![s],
+        Some(StringOrList::List(v)) => v,
+    })
+}
+
+//------------------------------------------------------------------------------
+/// Deserialize `build_args`, accepting scalar values of any type so that
+/// `CUDA_MAJOR: 12` or `INSTALL_EXTRAS: true` work without quoting. Note YAML
+/// reads `11.10` as the float 11.1; quote version strings like that.
+//------------------------------------------------------------------------------
+fn deserialize_build_args<'de, D>(
+    deserializer: D,
+) -> Result<HashMap<String, String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw = Option::<HashMap<String, Value>>::deserialize(deserializer)?
+        .unwrap_or_default();
+    raw.into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                Value::String(s) => s,
+                Value::Number(n) => n.to_string(),
+                Value::Bool(b) => b.to_string(),
+                Value::Null => String::new(),
+                _ => {
+                    return Err(serde::de::Error::custom(format!(
+                        "build_args.{} must be a string, number, or boolean",
+                        key)));
+                }
+            };
+            Ok((key, value))
+        })
+        .collect()
 }
 
 /// Builder for loading Docker build configuration from YAML files
@@ -67,13 +145,12 @@ impl BuildDockerConfiguration {
     ///
     /// # Arguments
     /// * `file_path` - Optional path to the YAML configuration file.
-    ///                  If `None`, uses the default file name in the current
-    ///                  directory.
+    ///   If `None`, uses the default file name in the current directory.
     ///
     /// # Returns
     /// * `Ok(BuildDockerConfigurationData)` - Successfully loaded configuration
     /// * `Err(String)` - Error message if file not found, invalid YAML, or
-    /// missing required fields
+    ///   missing required fields
     ///
     /// # Errors
     /// * Returns error if the file doesn't exist
@@ -83,6 +160,16 @@ impl BuildDockerConfiguration {
     //--------------------------------------------------------------------------
     pub fn load_data<P: AsRef<Path>>(
         file_path: Option<P>
+    ) -> Result<BuildDockerConfigurationData, String> {
+        Self::load_data_with_vars(file_path, &[])
+    }
+
+    //--------------------------------------------------------------------------
+    /// `load_data` with `--var name=value` overrides for the file's `vars:`.
+    //--------------------------------------------------------------------------
+    pub fn load_data_with_vars<P: AsRef<Path>>(
+        file_path: Option<P>,
+        vars: &[(String, String)],
     ) -> Result<BuildDockerConfigurationData, String> {
         // Determine the file path
         let path = match file_path {
@@ -107,10 +194,13 @@ impl BuildDockerConfiguration {
         let content = fs::read_to_string(&path)
             .map_err(|e| format!("Failed to read configuration file: {}", e))?;
 
-        // Parse YAML
-        let mut data: BuildDockerConfigurationData = serde_yaml::from_str(
-            &content)
-            .map_err(|e| format!("Failed to parse YAML: {}", e))?;
+        // Reject unknown keys and mistyped values before deserializing
+        check_schema(&path, &content, BUILD_CONFIGURATION_SCHEMA)?;
+
+        // Parse YAML, interpolating ${VAR} from the host environment and
+        // {{ var }} from `vars:`
+        let mut data: BuildDockerConfigurationData = from_str_with_vars(
+            &content, vars)?;
 
         // Validate required fields
         if data.docker_image_name.is_empty() {
@@ -125,7 +215,7 @@ impl BuildDockerConfiguration {
         // Ensure build_args is initialized (serde default should handle this,
         // but be explicit)
         if data.build_args.is_empty() {
-            data.build_args = std::collections::HashMap::new();
+            data.build_args = HashMap::new();
         }
 
         // Resolve Dockerfile component paths to absolute and check existence
@@ -226,6 +316,68 @@ base_image: ubuntu:22.04
         assert!(config.build_args.is_empty());
     }
 
+    #[test]
+    fn test_load_data_build_args_scalars_and_interpolation() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("build_configuration.yml");
+
+        let yaml_content = r#"
+docker_image_name: test-image:latest
+base_image: ubuntu:22.04
+build_args:
+  CUDA_VERSION: "12.4.1"
+  USER_ID: 1000
+  INSTALL_EXTRAS: true
+  HOME_DIR: ${HOME}
+  HTTP_PROXY: ${DOCKER_BUILDER_TEST_UNSET_PROXY:-}
+"#;
+
+        fs::write(&config_path, yaml_content).unwrap();
+
+        let config = BuildDockerConfiguration::load_data(
+            Some(&config_path)).unwrap();
+        let arg = |k: &str| config.build_args.get(k).cloned().unwrap();
+        assert_eq!(arg("CUDA_VERSION"), "12.4.1");
+        assert_eq!(arg("USER_ID"), "1000");
+        assert_eq!(arg("INSTALL_EXTRAS"), "true");
+        assert_eq!(arg("HOME_DIR"), std::env::var("HOME").unwrap());
+        assert_eq!(arg("HTTP_PROXY"), "");
+
+        fs::write(&config_path, r#"
+docker_image_name: test-image:latest
+base_image: ubuntu:22.04
+build_args:
+  TOKEN: ${DOCKER_BUILDER_TEST_UNSET_TOKEN}
+"#).unwrap();
+        let err = BuildDockerConfiguration::load_data(Some(&config_path))
+            .unwrap_err();
+        assert!(err.contains("DOCKER_BUILDER_TEST_UNSET_TOKEN"), "{}", err);
+    }
+
+    #[test]
+    fn test_load_data_cache_options() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("build_configuration.yml");
+
+        let yaml_content = r#"
+docker_image_name: test-image:latest
+base_image: ubuntu:22.04
+cache_from: type=registry,ref=ghcr.io/org/app:buildcache
+cache_to:
+  - type=registry,ref=ghcr.io/org/app:buildcache,mode=max
+  - type=local,dest=/tmp/buildcache
+"#;
+
+        fs::write(&config_path, yaml_content).unwrap();
+
+        let config = BuildDockerConfiguration::load_data(
+            Some(&config_path)).unwrap();
+        assert_eq!(
+            config.cache_from,
+            vec!["type=registry,ref=ghcr.io/org/app:buildcache"]);
+        assert_eq!(config.cache_to.len(), 2);
+    }
+
     #[test]
     fn test_load_data_file_not_found() {
         let result = BuildDockerConfiguration::load_data(
@@ -0,0 +1,198 @@
+//! Host environment interpolation for YAML configuration values.
+//!
+//! Supported forms inside string values:
+//! * `${VAR}` - value of VAR; error if unset
+//! * `${VAR:-default}` - value of VAR, or `default` if unset or empty
+//! * `$$` - a literal `$`
+//!
+//! A `$` not followed by `{` or `$` is kept as-is, so shell snippets such as
+//! `$HOME` in a command pass through untouched.
+//!
+//! The loaders below also resolve the file's `vars:` (see `variables`) after
+//! environment interpolation.
+
+use serde_yaml::Value;
+
+use super::variables::apply_vars;
+
+//------------------------------------------------------------------------------
+/// Interpolate `${VAR}` references in `input` using the host environment.
+//------------------------------------------------------------------------------
+pub fn interpolate_env(input: &str) -> Result<String, String> {
+    interpolate_with(input, |name| std::env::var(name).ok())
+}
+
+//------------------------------------------------------------------------------
+/// Interpolate `${VAR}` references in `input` using `lookup` for values.
+//------------------------------------------------------------------------------
+pub fn interpolate_with<F>(input: &str, lookup: F) -> Result<String, String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(idx) = rest.find('$') {
+        out.push_str(&rest[..idx]);
+        let after = &rest[idx + 1..];
+
+        if let Some(stripped) = after.strip_prefix('$') {
+            out.push('$');
+            rest = stripped;
+        } else if let Some(body) = after.strip_prefix('{') {
+            let end = body.find('}').ok_or_else(|| format!(
+                "Unterminated '${{' in '{}'", input))?;
+            let expr = &body[..end];
+            let (name, default) = match expr.split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (expr, None),
+            };
+
+            if name.is_empty()
+                || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                return Err(format!(
+                    "Invalid variable name '{}' in '{}'", name, input));
+            }
+
+            match (lookup(name), default) {
+                (Some(value), Some(default)) if value.is_empty() => {
+                    out.push_str(default)
+                }
+                (Some(value), _) => out.push_str(&value),
+                (None, Some(default)) => out.push_str(default),
+                (None, None) => {
+                    return Err(format!(
+                        "Environment variable '{}' is not set (use ${{{}:-default}} \
+                         to provide a fallback)",
+                        name, name));
+                }
+            }
+            rest = &body[end + 1..];
+        } else {
+            out.push('$');
+            rest = after;
+        }
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
+//------------------------------------------------------------------------------
+/// Interpolate every string scalar (not mapping keys) in a parsed YAML value.
+//------------------------------------------------------------------------------
+pub fn interpolate_yaml_value(value: &mut Value) -> Result<(), String> {
+    match value {
+        Value::String(s) => {
+            *s = interpolate_env(s)?;
+        }
+        Value::Sequence(items) => {
+            for item in items {
+                interpolate_yaml_value(item)?;
+            }
+        }
+        Value::Mapping(map) => {
+            for (_, v) in map.iter_mut() {
+                interpolate_yaml_value(v)?;
+            }
+        }
+        Value::Tagged(tagged) => interpolate_yaml_value(&mut tagged.value)?,
+        Value::Null | Value::Bool(_) | Value::Number(_) => {}
+    }
+    Ok(())
+}
+
+//------------------------------------------------------------------------------
+/// Parse YAML text, interpolate host environment variables in string values,
+/// and deserialize into `T`.
+//------------------------------------------------------------------------------
+pub fn from_str_interpolated<T>(content: &str) -> Result<T, String>
+where
+    T: serde::de::DeserializeOwned,
+{
+    from_str_with_vars(content, &[])
+}
+
+//------------------------------------------------------------------------------
+/// `from_str_interpolated` with `--var` overrides for the file's `vars:`.
+//------------------------------------------------------------------------------
+pub fn from_str_with_vars<T>(content: &str, vars: &[(String, String)]) -> Result<T, String>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let value: Value = serde_yaml::from_str(content)
+        .map_err(|e| format!("Failed to parse YAML: {}", e))?;
+    from_value_with_vars(value, vars)
+}
+
+//------------------------------------------------------------------------------
+/// Interpolate host environment variables in an already-parsed YAML value
+/// (e.g. one merged from overlays) and deserialize into `T`.
+//------------------------------------------------------------------------------
+pub fn from_value_interpolated<T>(value: Value) -> Result<T, String>
+where
+    T: serde::de::DeserializeOwned,
+{
+    from_value_with_vars(value, &[])
+}
+
+//------------------------------------------------------------------------------
+/// `from_value_interpolated` with `--var` overrides for the file's `vars:`.
+//------------------------------------------------------------------------------
+pub fn from_value_with_vars<T>(mut value: Value, vars: &[(String, String)]) -> Result<T, String>
+where
+    T: serde::de::DeserializeOwned,
+{
+    interpolate_yaml_value(&mut value)?;
+    apply_vars(&mut value, vars)?;
+    serde_yaml::from_value(value)
+        .map_err(|e| format!("Failed to parse YAML: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "HF_TOKEN" => Some("hf_abc".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_interpolate_with() {
+        assert_eq!(
+            interpolate_with("token=${HF_TOKEN}", lookup).unwrap(),
+            "token=hf_abc");
+        assert_eq!(
+            interpolate_with("${MISSING:-/models}/x", lookup).unwrap(),
+            "/models/x");
+        assert_eq!(interpolate_with("${EMPTY:-fallback}", lookup).unwrap(), "fallback");
+        assert_eq!(interpolate_with("${EMPTY}", lookup).unwrap(), "");
+        assert_eq!(
+            interpolate_with("echo $HOME $$HF_TOKEN 5$", lookup).unwrap(),
+            "echo $HOME $HF_TOKEN 5$");
+
+        let err = interpolate_with("${MISSING}", lookup).unwrap_err();
+        assert!(err.contains("MISSING"));
+        assert!(interpolate_with("${HF_TOKEN", lookup).is_err());
+        assert!(interpolate_with("${BAD-NAME}", lookup).is_err());
+    }
+
+    #[test]
+    fn test_interpolate_yaml_value_leaves_keys_and_scalars() {
+        let mut value: Value = serde_yaml::from_str(r#"
+"${KEY}": "$$literal"
+count: 3
+list:
+  - "${MISSING:-a}"
+"#).unwrap();
+        interpolate_yaml_value(&mut value).unwrap();
+        assert_eq!(value["${KEY}"], Value::String("$literal".to_string()));
+        assert_eq!(value["count"], Value::Number(3.into()));
+        assert_eq!(value["list"][0], Value::String("a".to_string()));
+    }
+}
@@ -0,0 +1,226 @@
+//! Configuration overlays - per-machine tweaks deep-merged over the committed
+//! run_configuration.yml - and profiles.
+//!
+//! A profile is an entry of the base file's `profiles:` section (e.g. `dev`,
+//! `debug`) merged over the base when selected. Then
+//! `run_configuration.override.yml` next to the base file is picked up
+//! automatically, and further overlays can be given explicitly; they apply in
+//! order, after the profile. Merge rules: mappings merge key by key, recursively; any other
+//! value (scalar or list) replaces the base value; `null` (`~`) removes the
+//! key from the base.
+
+use serde_yaml::{Mapping, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Override file picked up automatically from the build directory.
+pub const OVERRIDE_FILE_NAME: &str = "run_configuration.override.yml";
+
+/// Top-level key holding named profiles.
+pub const PROFILES_KEY: &str = "profiles";
+
+//------------------------------------------------------------------------------
+/// Deep-merge `overlay` into `base`.
+//------------------------------------------------------------------------------
+pub fn deep_merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Mapping(base_map), Value::Mapping(overlay_map)) => {
+            for (key, value) in overlay_map {
+                if value.is_null() {
+                    base_map.remove(&key);
+                } else if let Some(existing) = base_map.get_mut(&key) {
+                    deep_merge(existing, value);
+                } else {
+                    base_map.insert(key, value);
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Read a YAML file; an empty file is an empty mapping.
+fn read_yaml_file(path: &Path) -> Result<Value, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+    let value: Value = serde_yaml::from_str(&content)
+        .map_err(|e| format!("Failed to parse YAML in '{}': {}", path.display(), e))?;
+    match value {
+        Value::Null => Ok(Value::Mapping(Mapping::new())),
+        Value::Mapping(_) => Ok(value),
+        _ => Err(format!(
+            "'{}' must contain a mapping of keys", path.display())),
+    }
+}
+
+//------------------------------------------------------------------------------
+/// Overlays to apply for a build directory: the automatic override file (if
+/// present) followed by `explicit` overlays. Explicit overlays must exist.
+//------------------------------------------------------------------------------
+pub fn overlay_paths(
+    build_dir: &Path,
+    explicit: &[PathBuf],
+) -> Result<Vec<PathBuf>, String> {
+    let mut paths = Vec::new();
+    let override_file = build_dir.join(OVERRIDE_FILE_NAME);
+    if override_file.exists() {
+        paths.push(override_file);
+    }
+    for path in explicit {
+        if !path.exists() {
+            return Err(format!(
+                "Configuration overlay not found: {}", path.display()));
+        }
+        paths.push(path.clone());
+    }
+    Ok(paths)
+}
+
+//------------------------------------------------------------------------------
+/// Deep-merge the named profile from `value`'s `profiles:` section over it.
+/// Errors if the profile doesn't exist, listing the available ones.
+//------------------------------------------------------------------------------
+pub fn apply_profile(value: &mut Value, profile: &str) -> Result<(), String> {
+    let profiles = value.get(PROFILES_KEY).and_then(Value::as_mapping);
+    let selected = profiles.and_then(|p| p.get(profile)).cloned();
+
+    let Some(mut selected) = selected else {
+        let mut available: Vec<&str> = profiles
+            .map(|p| p.keys().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        available.sort();
+        return Err(format!(
+            "Unknown profile '{}' (available: {})",
+            profile,
+            if available.is_empty() { "none".to_string() } else { available.join(", ") }));
+    };
+
+    match &mut selected {
+        Value::Mapping(map) => {
+            map.remove(PROFILES_KEY);
+        }
+        Value::Null => return Ok(()),
+        _ => return Err(format!(
+            "Profile '{}' must be a mapping of configuration keys", profile)),
+    }
+    deep_merge(value, selected);
+    Ok(())
+}
+
+//------------------------------------------------------------------------------
+/// Load `base` (if it exists), apply `profile`, and deep-merge each overlay
+/// over it, in order.
+//------------------------------------------------------------------------------
+pub fn load_merged_yaml(
+    base: &Path,
+    profile: Option<&str>,
+    overlays: &[PathBuf],
+) -> Result<Value, String> {
+    let mut merged = if base.exists() {
+        read_yaml_file(base)?
+    } else {
+        Value::Mapping(Mapping::new())
+    };
+    if let Some(profile) = profile {
+        apply_profile(&mut merged, profile)?;
+    }
+    for overlay in overlays {
+        deep_merge(&mut merged, read_yaml_file(overlay)?);
+    }
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn yaml(s: &str) -> Value {
+        serde_yaml::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_deep_merge() {
+        let mut base = yaml(r#"
+docker_image_name: app:latest
+gpus: all
+shm_size: 8g
+env:
+  A: "1"
+  B: "2"
+volumes:
+  - host_path: /data
+    container_path: /data
+"#);
+        deep_merge(&mut base, yaml(r#"
+gpus: [1]
+shm_size: ~
+env:
+  B: "3"
+  C: "4"
+volumes:
+  - host_path: /mnt/fast/data
+    container_path: /data
+"#));
+
+        assert_eq!(base, yaml(r#"
+docker_image_name: app:latest
+gpus: [1]
+env:
+  A: "1"
+  B: "3"
+  C: "4"
+volumes:
+  - host_path: /mnt/fast/data
+    container_path: /data
+"#));
+    }
+
+    #[test]
+    fn test_load_merged_yaml_with_override_file() {
+        let temp = TempDir::new().unwrap();
+        let base = temp.path().join("run_configuration.yml");
+        fs::write(&base, "docker_image_name: app\ngpus: all\n").unwrap();
+        fs::write(temp.path().join(OVERRIDE_FILE_NAME), "gpus: device=1\n").unwrap();
+        let extra = temp.path().join("laptop.yml");
+        fs::write(&extra, "shm_size: 2g\n").unwrap();
+
+        let overlays = overlay_paths(temp.path(), std::slice::from_ref(&extra)).unwrap();
+        assert_eq!(overlays.len(), 2);
+
+        let merged = load_merged_yaml(&base, None, &overlays).unwrap();
+        assert_eq!(merged, yaml("docker_image_name: app\ngpus: device=1\nshm_size: 2g\n"));
+
+        assert!(overlay_paths(temp.path(), &[temp.path().join("missing.yml")]).is_err());
+    }
+
+    #[test]
+    fn test_apply_profile() {
+        let base = yaml(r#"
+docker_image_name: app
+shm_size: 8g
+env:
+  LOG_LEVEL: info
+profiles:
+  debug:
+    env:
+      LOG_LEVEL: debug
+    command: [bash]
+  prod:
+    shm_size: 64g
+"#);
+
+        let mut debug = base.clone();
+        apply_profile(&mut debug, "debug").unwrap();
+        assert_eq!(debug["env"]["LOG_LEVEL"], yaml("debug"));
+        assert_eq!(debug["command"], yaml("[bash]"));
+        assert_eq!(debug["shm_size"], yaml("8g"));
+
+        let mut prod = base.clone();
+        apply_profile(&mut prod, "prod").unwrap();
+        assert_eq!(prod["shm_size"], yaml("64g"));
+
+        let err = apply_profile(&mut base.clone(), "staging").unwrap_err();
+        assert_eq!(err, "Unknown profile 'staging' (available: debug, prod)");
+    }
+}
@@ -1,12 +1,28 @@
 //! Run configuration — merged from docker_runner's richer RunConfiguration.
-//! Supports: gpus, shm_size, ports, volumes, env, ipc, command.
+//! Supports: gpus, nvidia_visible_devices, gpu_memory_fraction, shm_size,
+//! ports, volumes, devices, env, env_file, ipc, pid, privileged, user, workdir,
+//! mount_cwd, hostname, extra_hosts, dns, read_only, init, runtime, rootless,
+//! engine, verify_digest, networks, ensure_networks, container_name,
+//! name_collision, healthcheck, secrets, extra_args, command, profiles.
 //! Only fields present in the file are used; nothing is assumed by default
 //! beyond the required `docker_image_name`.
+//!
+//! String values may reference host environment variables as `${VAR}` or
+//! `${VAR:-default}` (see `interpolation`), so secrets stay out of the YAML,
+//! and the file's own `vars:` as `{{ name }}` (see `variables`).
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use super::interpolation::{from_str_interpolated, from_value_with_vars};
+use super::overlay::load_merged_yaml;
+use super::schema::RUN_CONFIGURATION_SCHEMA;
+use super::validation::{check_schema, check_schema_files};
+use crate::run_docker::container_name::NameCollisionPolicy;
+use crate::run_docker::engine::ContainerEngine;
+use crate::run_docker::networks::NetworkSpec;
 
 //------------------------------------------------------------------------------
 /// Path on the host machine / path inside the container (for -v).
@@ -42,6 +58,147 @@ impl PortMapping {
     }
 }
 
+//------------------------------------------------------------------------------
+/// Host device to expose inside the container (for --device).
+/// Parsed from `host[:container[:permissions]]`, e.g. `/dev/video0` or
+/// `/dev/snd:/dev/snd:rw`. Permissions are any combination of r, w, m.
+//------------------------------------------------------------------------------
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct DeviceMapping {
+    /// Device path on the host machine
+    pub host_path: String,
+    /// Device path inside the container (defaults to host_path)
+    pub container_path: Option<String>,
+    /// cgroup permissions (defaults to rwm)
+    pub permissions: Option<String>,
+}
+
+impl DeviceMapping {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = spec.trim().split(':').collect();
+        if parts.len() > 3 || parts.iter().any(|p| p.is_empty()) {
+            return Err(format!(
+                "Invalid device '{}': expected host[:container[:permissions]]",
+                spec));
+        }
+
+        let mut device = DeviceMapping {
+            host_path: parts[0].to_string(),
+            ..Default::default()
+        };
+
+        // `host:rw` is valid docker syntax too: a lone permissions suffix.
+        let is_permissions = |p: &str| p.chars().all(|c| matches!(c, 'r' | 'w' | 'm'));
+        match parts.len() {
+            2 if is_permissions(parts[1]) => {
+                device.permissions = Some(parts[1].to_string());
+            }
+            2 => device.container_path = Some(parts[1].to_string()),
+            3 => {
+                if !is_permissions(parts[2]) {
+                    return Err(format!(
+                        "Invalid device permissions '{}' in '{}': use r, w, m",
+                        parts[2], spec));
+                }
+                device.container_path = Some(parts[1].to_string());
+                device.permissions = Some(parts[2].to_string());
+            }
+            _ => {}
+        }
+
+        if !device.host_path.starts_with('/') {
+            return Err(format!(
+                "Invalid device '{}': host path must be absolute", spec));
+        }
+
+        Ok(device)
+    }
+
+    pub fn into_device_mapping(self) -> String {
+        let container = self.container_path.unwrap_or_else(
+            || self.host_path.clone());
+        match self.permissions {
+            Some(perms) => format!("{}:{}:{}", self.host_path, container, perms),
+            None => format!("{}:{}", self.host_path, container),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+/// Container healthcheck, rendered as `--health-*` flags. Durations use
+/// docker syntax (`30s`, `1m`).
+//------------------------------------------------------------------------------
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct HealthCheck {
+    /// Shell command run inside the container; exit 0 = healthy
+    pub test: String,
+    #[serde(default)]
+    pub interval: Option<String>,
+    #[serde(default)]
+    pub timeout: Option<String>,
+    #[serde(default)]
+    pub start_period: Option<String>,
+    #[serde(default)]
+    pub retries: Option<u32>,
+}
+
+impl HealthCheck {
+    /// `--health-*` arguments for docker run.
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = vec!["--health-cmd".to_string(), self.test.clone()];
+        let durations = [
+            ("--health-interval", &self.interval),
+            ("--health-timeout", &self.timeout),
+            ("--health-start-period", &self.start_period),
+        ];
+        for (flag, value) in durations {
+            if let Some(value) = value {
+                args.push(flag.to_string());
+                args.push(value.clone());
+            }
+        }
+        if let Some(retries) = self.retries {
+            args.push("--health-retries".to_string());
+            args.push(retries.to_string());
+        }
+        args
+    }
+}
+
+//------------------------------------------------------------------------------
+/// A secret mounted read-only into the container at `target`, sourced from a
+/// host `file` or a host `env` variable (written to a 0400 temp file that is
+/// removed when the run ends). Unlike `-e`, the value does not show up in
+/// `docker inspect`.
+//------------------------------------------------------------------------------
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct SecretMount {
+    /// Host file holding the secret (supports ~)
+    #[serde(default)]
+    pub file: Option<String>,
+    /// Host environment variable holding the secret
+    #[serde(default)]
+    pub env: Option<String>,
+    /// Absolute path inside the container, e.g. /run/secrets/hf_token
+    pub target: String,
+}
+
+impl SecretMount {
+    /// Check that exactly one source is set and the target is absolute.
+    pub fn validate(&self) -> Result<(), String> {
+        match (&self.file, &self.env) {
+            (Some(_), None) | (None, Some(_)) => {}
+            _ => return Err(format!(
+                "Secret '{}' must set exactly one of 'file' or 'env'", self.target)),
+        }
+        if !self.target.trim().starts_with('/') {
+            return Err(format!(
+                "Secret target '{}' must be an absolute path", self.target));
+        }
+        Ok(())
+    }
+}
+
 //------------------------------------------------------------------------------
 /// Command after the image: either a single string (split on whitespace)
 /// or a list of strings. Omitted = use image CMD.
@@ -68,6 +225,86 @@ impl CommandOption {
     }
 }
 
+/// A GPU in a device list: an index, or an ID such as `0:1` (MIG) or a UUID.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum GpuId {
+    Index(u32),
+    Id(String),
+}
+
+impl GpuId {
+    fn into_string(self) -> String {
+        match self {
+            GpuId::Index(i) => i.to_string(),
+            GpuId::Id(id) => id.trim().to_string(),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+/// Deserialize `gpus` from a string (`all`, `device=1`, `device=0:1`, `auto`,
+/// ...), a count, or a list of device IDs (indices, `GPU:MIG` pairs, UUIDs). A
+/// list becomes `"device=0,1"` - the quotes are needed so docker doesn't split
+/// the value on the comma.
+//------------------------------------------------------------------------------
+fn deserialize_gpus<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum GpusOption {
+        Spec(String),
+        Count(u32),
+        Devices(Vec<GpuId>),
+    }
+
+    Ok(match Option::<GpusOption>::deserialize(deserializer)? {
+        None => None,
+        Some(GpusOption::Spec(s)) => Some(s),
+        Some(GpusOption::Count(n)) => Some(n.to_string()),
+        Some(GpusOption::Devices(devices)) if devices.is_empty() => None,
+        Some(GpusOption::Devices(devices)) => {
+            let ids: Vec<String> = devices.into_iter().map(GpuId::into_string).collect();
+            if ids.len() == 1 {
+                Some(format!("device={}", ids[0]))
+            } else {
+                Some(format!("\"device={}\"", ids.join(",")))
+            }
+        }
+    })
+}
+
+//------------------------------------------------------------------------------
+/// Deserialize `nvidia_visible_devices` from a string (`all`, `0,1`, a MIG
+/// UUID, ...), a single index, or a list of device IDs, joined with commas.
+//------------------------------------------------------------------------------
+fn deserialize_visible_devices<'de, D>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum VisibleDevices {
+        One(GpuId),
+        List(Vec<GpuId>),
+    }
+
+    Ok(match Option::<VisibleDevices>::deserialize(deserializer)? {
+        None => None,
+        Some(VisibleDevices::One(id)) => Some(id.into_string()),
+        Some(VisibleDevices::List(ids)) if ids.is_empty() => None,
+        Some(VisibleDevices::List(ids)) => Some(ids
+            .into_iter()
+            .map(GpuId::into_string)
+            .collect::<Vec<_>>()
+            .join(",")),
+    })
+}
+
 /// Env: map (key: value) or list of "KEY=value" strings.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -99,16 +336,38 @@ impl EnvOption {
 
 //------------------------------------------------------------------------------
 /// Richer run configuration (from docker_runner) used when loading from YAML.
-/// Supports: gpus, shm_size, ports, volumes, env, ipc, command.
+/// Supports: gpus, nvidia_visible_devices, gpu_memory_fraction, shm_size,
+/// ports, volumes, devices, env, env_file, ipc, pid, privileged, user, workdir,
+/// mount_cwd, hostname, extra_hosts, dns, read_only, init, runtime, rootless,
+/// engine, verify_digest, networks, ensure_networks, container_name,
+/// name_collision, healthcheck, secrets, extra_args, command, profiles.
 //------------------------------------------------------------------------------
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct RunConfiguration {
     /// Docker image name (required).
     pub docker_image_name: String,
 
-    #[serde(default)]
+    /// GPUs (docker run --gpus): `all`, a count, `device=N`, a MIG device
+    /// (`device=0:1`), a list of device IDs, or `auto` to pick an idle GPU
+    /// via nvidia-smi.
+    #[serde(default, deserialize_with = "deserialize_gpus")]
     pub gpus: Option<String>,
 
+    /// NVIDIA_VISIBLE_DEVICES for the container (e.g. with `runtime:
+    /// nvidia`): `all`, device IDs, or MIG UUIDs, as a string or list.
+    #[serde(default, deserialize_with = "deserialize_visible_devices")]
+    pub nvidia_visible_devices: Option<String>,
+
+    /// Share of GPU memory the workload should use, in (0, 1]; exported as
+    /// GPU_MEMORY_FRACTION and XLA_PYTHON_CLIENT_MEM_FRACTION.
+    #[serde(default)]
+    pub gpu_memory_fraction: Option<f64>,
+
+    /// Used-memory threshold (MiB) under which `gpus: auto` treats a GPU as
+    /// idle (default 1024).
+    #[serde(default)]
+    pub gpu_memory_threshold_mib: Option<u64>,
+
     #[serde(default)]
     pub shm_size: Option<String>,
 
@@ -120,15 +379,124 @@ pub struct RunConfiguration {
     #[serde(default)]
     pub volumes: Option<Vec<VolumeMount>>,
 
+    /// Devices (docker run --device), as `host[:container[:permissions]]`.
+    #[serde(default)]
+    pub devices: Option<Vec<String>>,
+
     #[serde(default)]
     pub env: Option<EnvOption>,
 
+    /// Files of KEY=value lines (docker run --env-file). Supports ~; relative
+    /// paths are resolved by docker against the build directory.
+    #[serde(default)]
+    pub env_file: Option<Vec<String>>,
+
     #[serde(default)]
     pub ipc: Option<String>,
 
+    /// PID namespace (docker run --pid), e.g. `host` to see and profile host
+    /// processes (perf, nsys).
+    #[serde(default)]
+    pub pid: Option<String>,
+
+    /// Give the container extended privileges (docker run --privileged).
+    #[serde(default)]
+    pub privileged: bool,
+
+    /// User to run as (docker run --user): `uid[:gid]`, a user name, or
+    /// `current` for the invoking user's uid:gid.
+    #[serde(default)]
+    pub user: Option<String>,
+
+    /// Working directory inside the container (docker run --workdir).
+    #[serde(default)]
+    pub workdir: Option<String>,
+
+    /// Bind-mount the invoking directory at this container path (e.g.
+    /// `/workspace`) and make it the working directory unless `workdir` is
+    /// set.
+    #[serde(default)]
+    pub mount_cwd: Option<String>,
+
+    /// Container hostname (docker run --hostname).
+    #[serde(default)]
+    pub hostname: Option<String>,
+
+    /// Extra /etc/hosts entries as `host:ip` (docker run --add-host).
+    #[serde(default)]
+    pub extra_hosts: Option<Vec<String>>,
+
+    /// DNS servers (docker run --dns).
+    #[serde(default)]
+    pub dns: Option<Vec<String>>,
+
+    /// Mount the container's root filesystem read-only (docker run --read-only).
+    #[serde(default)]
+    pub read_only: bool,
+
+    /// Run an init process as PID 1 to reap zombies and forward signals
+    /// (docker run --init).
+    #[serde(default)]
+    pub init: bool,
+
+    /// OCI runtime (docker run --runtime), e.g. `nvidia`, `runc`, or a custom
+    /// runtime registered with the daemon.
+    #[serde(default)]
+    pub runtime: Option<String>,
+
+    /// Whether the daemon is rootless Docker. Unset = detect automatically.
+    #[serde(default)]
+    pub rootless: Option<bool>,
+
+    /// Container engine: `docker` (default) or `podman`.
+    #[serde(default)]
+    pub engine: Option<ContainerEngine>,
+
+    /// Check the local image against the `@sha256:` digest in
+    /// docker_image_name before running.
+    #[serde(default)]
+    pub verify_digest: bool,
+
     /// Optional command and args after the image.
     #[serde(default)]
     pub command: Option<CommandOption>,
+
+    /// Networks to connect to (--network): names, or mappings with name,
+    /// driver, subnet, gateway used when creating them.
+    #[serde(default)]
+    pub networks: Option<Vec<NetworkSpec>>,
+
+    /// Create missing user-defined networks before running.
+    #[serde(default)]
+    pub ensure_networks: bool,
+
+    /// Container name (--name); may be a template such as
+    /// `{image}-{profile}-{date}` (see `run_docker::container_name`).
+    #[serde(default)]
+    pub container_name: Option<String>,
+
+    /// What to do when a container with that name exists: `fail`
+    /// (default), `replace`, or `suffix`.
+    #[serde(default)]
+    pub name_collision: Option<NameCollisionPolicy>,
+
+    /// Container healthcheck (see `HealthCheck`); used by `run --wait`.
+    #[serde(default)]
+    pub healthcheck: Option<HealthCheck>,
+
+    /// Secrets mounted read-only as files (see `SecretMount`).
+    #[serde(default)]
+    pub secrets: Option<Vec<SecretMount>>,
+
+    /// Raw arguments passed to docker run verbatim, just before the image;
+    /// an escape hatch for flags this tool doesn't model.
+    #[serde(default)]
+    pub extra_args: Option<Vec<String>>,
+
+    /// Named sets of fields (e.g. `dev`, `debug`) merged over the rest of
+    /// the file when selected with `--profile`.
+    #[serde(default)]
+    pub profiles: Option<HashMap<String, serde_yaml::Value>>,
 }
 
 impl RunConfiguration {
@@ -138,15 +506,67 @@ impl RunConfiguration {
     pub fn load_from_path<P: AsRef<Path>>(path: P) -> Result<Self, String> {
         let content = fs::read_to_string(path.as_ref())
             .map_err(|e| format!("Failed to read config: {}", e))?;
-        let configuration: RunConfiguration = serde_yaml::from_str(&content)
-            .map_err(|e| format!("Failed to parse YAML: {}", e))?;
+        check_schema(path.as_ref(), &content, RUN_CONFIGURATION_SCHEMA)?;
+        let configuration: RunConfiguration = from_str_interpolated(&content)?;
+        configuration.validate()
+    }
+
+    /// Load from a YAML file with the selected profile and overlays
+    /// deep-merged over it (see `configuration::overlay`), and `vars`
+    /// overriding the file's `vars:`. The base file may be absent if overlays
+    /// provide everything.
+    pub fn load_with_overlays<P: AsRef<Path>>(
+        path: P,
+        profile: Option<&str>,
+        overlays: &[PathBuf],
+        vars: &[(String, String)],
+    ) -> Result<Self, String> {
+        check_schema_files(&[path.as_ref()], RUN_CONFIGURATION_SCHEMA)?;
+        check_schema_files(overlays, RUN_CONFIGURATION_SCHEMA)?;
+        let merged = load_merged_yaml(path.as_ref(), profile, overlays)?;
+        let configuration: RunConfiguration = from_value_with_vars(merged, vars)?;
+        configuration.validate()
+    }
+
+    /// Check fields serde can't: non-empty image name, devices, user,
+    /// extra_hosts, healthcheck, secrets.
+    pub(crate) fn validate(self) -> Result<Self, String> {
+        let configuration = self;
         if configuration.docker_image_name.trim().is_empty() {
             return Err(
                 "Configuration must set 'docker_image_name' (non-empty)".to_string());
         }
+        configuration.device_mappings()?;
+        if let Some(ref healthcheck) = configuration.healthcheck
+            && healthcheck.test.trim().is_empty()
+        {
+            return Err("healthcheck.test must not be empty".to_string());
+        }
+        for secret in configuration.secrets.iter().flatten() {
+            secret.validate()?;
+        }
+        if let Some(ref user) = configuration.user {
+            resolve_user(user)?;
+        }
+        for host in configuration.extra_hosts.iter().flatten() {
+            match host.split_once(':') {
+                Some((name, ip)) if !name.is_empty() && !ip.is_empty() => {}
+                _ => return Err(format!(
+                    "Invalid extra_hosts entry '{}': expected host:ip", host)),
+            }
+        }
         Ok(configuration)
     }
 
+    /// Parse `devices` into DeviceMappings (empty if unset).
+    pub fn device_mappings(&self) -> Result<Vec<DeviceMapping>, String> {
+        self.devices
+            .iter()
+            .flatten()
+            .map(|spec| DeviceMapping::parse(spec))
+            .collect()
+    }
+
     /// Load from a directory (looks for run_configuration.yml there).
     pub fn load_from_directory<P: AsRef<Path>>(dir: P) -> Result<Self, String> {
         let path = dir.as_ref().join(Self::DEFAULT_FILENAME);
@@ -197,13 +617,28 @@ impl RunDockerConfiguration {
         let content = fs::read_to_string(&path)
             .map_err(|e| format!("Failed to read configuration file: {}", e))?;
 
-        // Parse YAML
-        let data: RunDockerConfigurationData = serde_yaml::from_str(&content)
-            .map_err(|e| format!("Failed to parse YAML: {}", e))?;
+        check_schema(&path, &content, RUN_CONFIGURATION_SCHEMA)?;
+
+        // Parse YAML, interpolating ${VAR} references
+        let data: RunDockerConfigurationData = from_str_interpolated(&content)?;
 
         Ok(data)
     }
 
+    /// Load run configuration data with the selected profile and overlays
+    /// deep-merged over it, and `vars` overriding the file's `vars:`.
+    pub fn load_data_with_overlays<P: AsRef<Path>>(
+        file_path: P,
+        profile: Option<&str>,
+        overlays: &[PathBuf],
+        vars: &[(String, String)],
+    ) -> Result<RunDockerConfigurationData, String> {
+        check_schema_files(&[file_path.as_ref()], RUN_CONFIGURATION_SCHEMA)?;
+        check_schema_files(overlays, RUN_CONFIGURATION_SCHEMA)?;
+        let merged = load_merged_yaml(file_path.as_ref(), profile, overlays)?;
+        from_value_with_vars(merged, vars)
+    }
+
     /// Load from a specific directory (looks for run_configuration.yml there)
     pub fn load_from_directory<P: AsRef<Path>>(
         directory: P,
@@ -213,16 +648,45 @@ impl RunDockerConfiguration {
     }
 }
 
+/// Resolve a `user` spec into a value for `docker run --user`.
+/// `current` becomes the invoking user's `uid:gid`; anything else must be
+/// `user[:group]` and is passed through.
+pub fn resolve_user(spec: &str) -> Result<String, String> {
+    let spec = spec.trim();
+    if spec == "current" {
+        return Ok(current_user_spec());
+    }
+
+    let parts: Vec<&str> = spec.split(':').collect();
+    if spec.is_empty() || parts.len() > 2 || parts.iter().any(|p| p.is_empty()) {
+        return Err(format!(
+            "Invalid user '{}': expected uid[:gid], name[:group], or 'current'",
+            spec));
+    }
+    Ok(spec.to_string())
+}
+
+#[cfg(unix)]
+fn current_user_spec() -> String {
+    use nix::unistd::{getgid, getuid};
+    format!("{}:{}", getuid().as_raw(), getgid().as_raw())
+}
+
+#[cfg(not(unix))]
+fn current_user_spec() -> String {
+    "1000:1000".to_string()
+}
+
 /// Expand leading ~ in path with home directory.
 pub fn expand_tilde(path: &str) -> String {
     if path.starts_with("~/") {
         if let Some(home) = std::env::var_os("HOME") {
             return home.to_string_lossy().to_string() + &path[1..];
         }
-    } else if path == "~" {
-        if let Some(home) = std::env::var_os("HOME") {
-            return home.to_string_lossy().to_string();
-        }
+    } else if path == "~"
+        && let Some(home) = std::env::var_os("HOME")
+    {
+        return home.to_string_lossy().to_string();
     }
     path.to_string()
 }
@@ -275,6 +739,90 @@ ports:
         assert!(config.ports.is_empty());
     }
 
+    #[test]
+    fn test_parse_device_mapping() {
+        let device = DeviceMapping::parse("/dev/video0").unwrap();
+        assert_eq!(device.host_path, "/dev/video0");
+        assert_eq!(device.clone().into_device_mapping(), "/dev/video0:/dev/video0");
+
+        let device = DeviceMapping::parse("/dev/snd:/dev/audio:rw").unwrap();
+        assert_eq!(device.container_path.as_deref(), Some("/dev/audio"));
+        assert_eq!(device.permissions.as_deref(), Some("rw"));
+        assert_eq!(device.into_device_mapping(), "/dev/snd:/dev/audio:rw");
+
+        let device = DeviceMapping::parse("/dev/fuse:rwm").unwrap();
+        assert_eq!(device.container_path, None);
+        assert_eq!(device.into_device_mapping(), "/dev/fuse:/dev/fuse:rwm");
+
+        assert!(DeviceMapping::parse("dev/video0").is_err());
+        assert!(DeviceMapping::parse("/dev/a:/dev/b:rx").is_err());
+        assert!(DeviceMapping::parse("/dev/a::rw").is_err());
+        assert!(DeviceMapping::parse("/a:/b:r:w").is_err());
+    }
+
+    #[test]
+    fn test_resolve_user() {
+        assert_eq!(resolve_user("1000:1000").unwrap(), "1000:1000");
+        assert_eq!(resolve_user(" builder ").unwrap(), "builder");
+        assert!(resolve_user("").is_err());
+        assert!(resolve_user("1000:").is_err());
+        assert!(resolve_user("1:2:3").is_err());
+
+        let current = resolve_user("current").unwrap();
+        let (uid, gid) = current.split_once(':').unwrap();
+        assert_eq!(uid, nix::unistd::getuid().as_raw().to_string());
+        assert_eq!(gid, nix::unistd::getgid().as_raw().to_string());
+    }
+
+    #[test]
+    fn test_load_from_path_interpolates_env() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("run_configuration.yml");
+
+        // PATH is set in any test environment; the other variable is not.
+        let yaml_content = r#"
+docker_image_name: "${DOCKER_BUILDER_TEST_UNSET:-fallback}:latest"
+env:
+  HOST_PATH: "${PATH}"
+env_file:
+  - ~/.secrets/hf.env
+  - local.env
+"#;
+        fs::write(&config_path, yaml_content).unwrap();
+
+        let config = RunConfiguration::load_from_path(&config_path).unwrap();
+        assert_eq!(config.docker_image_name, "fallback:latest");
+        let env = config.env.unwrap().into_env_pairs();
+        assert_eq!(env[0].1, std::env::var("PATH").unwrap());
+        assert_eq!(config.env_file.unwrap().len(), 2);
+
+        fs::write(
+            &config_path,
+            "docker_image_name: x\nenv:\n  T: ${DOCKER_BUILDER_TEST_UNSET}\n").unwrap();
+        let err = RunConfiguration::load_from_path(&config_path).unwrap_err();
+        assert!(err.contains("DOCKER_BUILDER_TEST_UNSET"));
+    }
+
+    #[test]
+    fn test_parse_gpus_variants() {
+        let parse = |gpus: &str| -> Option<String> {
+            let yaml = format!("docker_image_name: x\n{}", gpus);
+            serde_yaml::from_str::<RunConfiguration>(&yaml).unwrap().gpus
+        };
+        assert_eq!(parse("gpus: all").as_deref(), Some("all"));
+        assert_eq!(parse("gpus: auto").as_deref(), Some("auto"));
+        assert_eq!(parse("gpus: 2").as_deref(), Some("2"));
+        assert_eq!(parse("gpus: [1]").as_deref(), Some("device=1"));
+        assert_eq!(parse("gpus: [0, 2]").as_deref(), Some("\"device=0,2\""));
+        assert_eq!(parse("gpus: []"), None);
+        assert_eq!(parse("gpus: device=0:1").as_deref(), Some("device=0:1"));
+        assert_eq!(
+            parse("gpus: [\"0:0\", \"0:1\"]").as_deref(),
+            Some("\"device=0:0,0:1\""));
+        assert_eq!(parse("gpus: [0, \"1:2\"]").as_deref(), Some("\"device=0,1:2\""));
+        assert_eq!(parse(""), None);
+    }
+
     /// Test parsing the richer RunConfiguration (from docker_runner).
     #[test]
     fn test_parse_run_configuration_yaml() {
@@ -291,6 +839,21 @@ volumes:
     container_path: /models
   - host_path: ~/.cache/huggingface
     container_path: /root/.cache/huggingface
+devices:
+  - /dev/video0
+  - /dev/snd:/dev/snd:rw
+workdir: /workspace
+hostname: sglang
+extra_hosts:
+  - "registry.local:10.0.0.5"
+  - "host.docker.internal:host-gateway"
+dns:
+  - 1.1.1.1
+read_only: true
+init: true
+runtime: nvidia
+rootless: false
+engine: podman
 command:
   - python3
   - -m
@@ -321,6 +884,21 @@ command:
         assert_eq!(volumes[1].host_path, "~/.cache/huggingface");
         assert_eq!(volumes[1].container_path, "/root/.cache/huggingface");
 
+        assert_eq!(config.workdir.as_deref(), Some("/workspace"));
+        assert_eq!(config.hostname.as_deref(), Some("sglang"));
+        assert_eq!(config.extra_hosts.as_ref().unwrap().len(), 2);
+        assert_eq!(config.dns.as_deref(), Some(&["1.1.1.1".to_string()][..]));
+        assert!(config.read_only);
+        assert!(config.init);
+        assert_eq!(config.runtime.as_deref(), Some("nvidia"));
+        assert_eq!(config.rootless, Some(false));
+        assert_eq!(config.engine, Some(ContainerEngine::Podman));
+
+        let devices = config.device_mappings().unwrap();
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[0].host_path, "/dev/video0");
+        assert_eq!(devices[1].permissions.as_deref(), Some("rw"));
+
         let cmd = config.command.as_ref().unwrap();
         let args = cmd.clone().into_vec();
         assert_eq!(args[0], "python3");
@@ -333,4 +911,24 @@ command:
         assert!(args.contains(&"--port".to_string()));
         assert!(args.contains(&"30000".to_string()));
     }
+
+    #[test]
+    fn test_healthcheck_to_args() {
+        let healthcheck: HealthCheck = serde_yaml::from_str(r#"
+test: curl -f http://localhost:30000/health || exit 1
+interval: 10s
+start_period: 2m
+retries: 5
+"#).unwrap();
+        assert_eq!(healthcheck.to_args(), vec![
+            "--health-cmd",
+            "curl -f http://localhost:30000/health || exit 1",
+            "--health-interval",
+            "10s",
+            "--health-start-period",
+            "2m",
+            "--health-retries",
+            "5",
+        ]);
+    }
 }
@@ -0,0 +1,396 @@
+//! JSON Schemas for build_configuration.yml and run_configuration.yml, and a
+//! validator for the subset of JSON Schema they use.
+//!
+//! The schemas ship in `schemas/` (point an editor's YAML language server at
+//! them for completion) and are compiled in. Supported keywords: `type`,
+//! `enum`, `properties`, `required`, `additionalProperties`, `items`,
+//! `anyOf`, `$ref` (local), `minimum`, `maximum`, `exclusiveMinimum`.
+//! Annotations such as `description` are ignored.
+
+use serde_json::Value as Json;
+use serde_yaml::Value;
+use std::fmt;
+
+pub const BUILD_CONFIGURATION_SCHEMA: &str =
+    include_str!("../../schemas/build_configuration.schema.json");
+pub const RUN_CONFIGURATION_SCHEMA: &str =
+    include_str!("../../schemas/run_configuration.schema.json");
+
+//------------------------------------------------------------------------------
+/// One step into a YAML document: a mapping key or a list index.
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+//------------------------------------------------------------------------------
+/// A schema violation at `path` (empty for the document root).
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaError {
+    pub path: Vec<PathSegment>,
+    /// The offending key for unknown-key errors (not part of `path`, which
+    /// names the enclosing mapping).
+    pub key: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            return f.write_str(&self.message);
+        }
+        write!(f, "{}: {}", format_path(&self.path), self.message)
+    }
+}
+
+//------------------------------------------------------------------------------
+/// `volumes[0].host_path` style rendering of a path.
+//------------------------------------------------------------------------------
+pub fn format_path(path: &[PathSegment]) -> String {
+    let mut out = String::new();
+    for segment in path {
+        match segment {
+            PathSegment::Key(key) if out.is_empty() => out.push_str(key),
+            PathSegment::Key(key) => {
+                out.push('.');
+                out.push_str(key);
+            }
+            PathSegment::Index(i) => out.push_str(&format!("[{}]", i)),
+        }
+    }
+    out
+}
+
+//------------------------------------------------------------------------------
+/// A known key within edit distance 2 of `key`, for typo hints.
+//------------------------------------------------------------------------------
+pub fn closest_match<'k, I>(key: &str, known: I) -> Option<&'k str>
+where
+    I: IntoIterator<Item = &'k str>,
+{
+    known
+        .into_iter()
+        .map(|k| (edit_distance(key, k), k))
+        .filter(|(d, _)| *d <= 2)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, k)| k)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut curr = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            curr.push((prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1));
+        }
+        prev = curr;
+    }
+    prev[b.len()]
+}
+
+/// JSON Schema type name of a YAML value.
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Sequence(_) => "array",
+        Value::Mapping(_) => "object",
+        Value::Tagged(tagged) => type_name(&tagged.value),
+    }
+}
+
+fn matches_type(value: &Value, expected: &str) -> bool {
+    let actual = type_name(value);
+    actual == expected || (expected == "number" && actual == "integer")
+}
+
+/// Names in a `type` keyword (a string or a list of strings).
+fn type_names(types: &Json) -> Vec<&str> {
+    match types {
+        Json::String(t) => vec![t.as_str()],
+        Json::Array(ts) => ts.iter().filter_map(Json::as_str).collect(),
+        _ => vec![],
+    }
+}
+
+/// Resolve a local `$ref` (`#` or `#/$defs/name`).
+fn resolve_ref<'s>(root: &'s Json, reference: &str) -> Option<&'s Json> {
+    let pointer = reference.strip_prefix('#')?;
+    root.pointer(pointer)
+}
+
+fn describe(schema: &Json, root: &Json) -> String {
+    if let Some(reference) = schema.get("$ref").and_then(Json::as_str) {
+        return resolve_ref(root, reference).map_or_else(
+            || reference.to_string(),
+            |s| describe(s, root));
+    }
+    if let Some(types) = schema.get("type") {
+        return type_names(types).join(" or ");
+    }
+    if let Some(values) = schema.get("enum").and_then(Json::as_array) {
+        return format!("one of {}", enum_list(values));
+    }
+    "a valid value".to_string()
+}
+
+fn enum_list(values: &[Json]) -> String {
+    values
+        .iter()
+        .filter(|v| !v.is_null())
+        .map(|v| v.as_str().map_or_else(|| v.to_string(), String::from))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+struct Validator<'s> {
+    root: &'s Json,
+    errors: Vec<SchemaError>,
+}
+
+impl<'s> Validator<'s> {
+    fn error(&mut self, path: &[PathSegment], message: String) {
+        self.errors.push(SchemaError { path: path.to_vec(), key: None, message });
+    }
+
+    fn check(&mut self, schema: &'s Json, value: &Value, path: &mut Vec<PathSegment>) {
+        if let Value::Tagged(tagged) = value {
+            return self.check(schema, &tagged.value, path);
+        }
+
+        if let Some(reference) = schema.get("$ref").and_then(Json::as_str) {
+            match resolve_ref(self.root, reference) {
+                Some(target) => self.check(target, value, path),
+                None => self.error(path, format!("Schema has unresolvable $ref '{}'", reference)),
+            }
+            return;
+        }
+
+        if let Some(branches) = schema.get("anyOf").and_then(Json::as_array) {
+            self.check_any_of(branches, value, path);
+            return;
+        }
+
+        if let Some(types) = schema.get("type") {
+            let names = type_names(types);
+            if !names.iter().any(|t| matches_type(value, t)) {
+                self.error(path, format!(
+                    "expected {}, found {}", names.join(" or "), type_name(value)));
+                return;
+            }
+        }
+
+        if let Some(values) = schema.get("enum").and_then(Json::as_array) {
+            let json = serde_json::to_value(value).unwrap_or(Json::Null);
+            if !values.contains(&json) {
+                self.error(path, format!(
+                    "expected one of {}, found {}", enum_list(values), json));
+                return;
+            }
+        }
+
+        match value {
+            Value::Number(n) => self.check_range(schema, n.as_f64().unwrap_or_default(), path),
+            Value::Mapping(map) => self.check_object(schema, map, path),
+            Value::Sequence(items) => {
+                if let Some(item_schema) = schema.get("items") {
+                    for (i, item) in items.iter().enumerate() {
+                        path.push(PathSegment::Index(i));
+                        self.check(item_schema, item, path);
+                        path.pop();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Accept the first matching branch. Otherwise report the errors of the
+    /// single branch whose type fits (most precise), or a summary.
+    fn check_any_of(&mut self, branches: &'s [Json], value: &Value, path: &[PathSegment]) {
+        let mut fitting = Vec::new();
+        for branch in branches {
+            let mut sub = Validator { root: self.root, errors: Vec::new() };
+            sub.check(branch, value, &mut path.to_vec());
+            if sub.errors.is_empty() {
+                return;
+            }
+            let type_fits = sub.errors.iter().all(|e| e.path.len() > path.len()
+                || !e.message.starts_with("expected "));
+            if type_fits {
+                fitting.push(sub.errors);
+            }
+        }
+
+        if fitting.len() == 1 {
+            self.errors.extend(fitting.pop().unwrap_or_default());
+            return;
+        }
+        let expected: Vec<String> = branches.iter().map(|b| describe(b, self.root)).collect();
+        self.error(path, format!(
+            "expected {}, found {}", expected.join(" or "), type_name(value)));
+    }
+
+    fn check_range(&mut self, schema: &Json, n: f64, path: &[PathSegment]) {
+        let bound = |key: &str| schema.get(key).and_then(Json::as_f64);
+        if let Some(min) = bound("minimum")
+            && n < min
+        {
+            self.error(path, format!("{} is less than the minimum {}", n, min));
+        }
+        if let Some(min) = bound("exclusiveMinimum")
+            && n <= min
+        {
+            self.error(path, format!("{} must be greater than {}", n, min));
+        }
+        if let Some(max) = bound("maximum")
+            && n > max
+        {
+            self.error(path, format!("{} is greater than the maximum {}", n, max));
+        }
+    }
+
+    fn check_object(
+        &mut self,
+        schema: &'s Json,
+        map: &serde_yaml::Mapping,
+        path: &mut Vec<PathSegment>,
+    ) {
+        let properties = schema.get("properties").and_then(Json::as_object);
+
+        for required in schema.get("required").and_then(Json::as_array).into_iter().flatten() {
+            if let Some(key) = required.as_str()
+                && !map.contains_key(key)
+            {
+                self.error(path, format!("Missing required key '{}'", key));
+            }
+        }
+
+        for (key, value) in map {
+            let Some(key) = key.as_str() else {
+                self.error(path, "Keys must be strings".to_string());
+                continue;
+            };
+            let property = properties.and_then(|p| p.get(key));
+            let schema = match (property, schema.get("additionalProperties")) {
+                (Some(property), _) => property,
+                (None, Some(Json::Bool(false))) => {
+                    let known = properties.into_iter().flat_map(|p| p.keys().map(String::as_str));
+                    let hint = closest_match(key, known)
+                        .map(|k| format!(" (did you mean '{}'?)", k))
+                        .unwrap_or_default();
+                    self.errors.push(SchemaError {
+                        path: path.clone(),
+                        key: Some(key.to_string()),
+                        message: format!("Unknown key '{}'{}", key, hint),
+                    });
+                    continue;
+                }
+                (None, Some(additional)) if additional.is_object() => additional,
+                (None, _) => continue,
+            };
+            path.push(PathSegment::Key(key.to_string()));
+            self.check(schema, value, path);
+            path.pop();
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+/// Validate `value` against `schema` (JSON text), returning every violation.
+//------------------------------------------------------------------------------
+pub fn validate_value(schema: &str, value: &Value) -> Result<Vec<SchemaError>, String> {
+    let root: Json = serde_json::from_str(schema)
+        .map_err(|e| format!("Invalid JSON Schema: {}", e))?;
+    let mut validator = Validator { root: &root, errors: Vec::new() };
+    validator.check(&root, value, &mut Vec::new());
+    Ok(validator.errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(schema: &str, yaml: &str) -> Vec<String> {
+        let value: Value = serde_yaml::from_str(yaml).unwrap();
+        validate_value(schema, &value)
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_run_schema() {
+        assert!(errors(RUN_CONFIGURATION_SCHEMA, r#"
+docker_image_name: app:latest
+gpus: [0, "0:1"]
+volumes:
+  - host_path: /data
+    container_path: /data
+env: {A: "1"}
+networks: [kb-net, {name: other, driver: bridge}]
+engine: podman
+healthcheck: {test: "curl -f localhost", retries: 3}
+profiles:
+  dev:
+    shm_size: 2g
+"#).is_empty());
+
+        assert_eq!(errors(RUN_CONFIGURATION_SCHEMA, r#"
+docker_image_name: app:latest
+volums: []
+ports:
+  - host_port: "8080"
+    container_port: 70000
+engine: containerd
+read_only: "yes"
+healthcheck: {interval: 5s}
+profiles:
+  dev:
+    comand: bash
+"#), vec![
+            "Unknown key 'volums' (did you mean 'volumes'?)",
+            "ports[0].host_port: expected integer, found string",
+            "ports[0].container_port: 70000 is greater than the maximum 65535",
+            "engine: expected one of docker, podman, found \"containerd\"",
+            "read_only: expected boolean, found string",
+            "healthcheck: Missing required key 'test'",
+            "profiles.dev: Unknown key 'comand' (did you mean 'command'?)",
+        ]);
+    }
+
+    #[test]
+    fn test_build_schema() {
+        assert_eq!(errors(BUILD_CONFIGURATION_SCHEMA, r#"
+docker_image_name: app
+build_args: {CUDA: 12, EXTRAS: [a]}
+cache_from: [1]
+dockerfile_components:
+  - label: base
+"#), vec![
+            "Missing required key 'base_image'",
+            "build_args.EXTRAS: expected string or number or boolean or null, found array",
+            "cache_from[0]: expected string, found integer",
+            "dockerfile_components[0]: Missing required key 'path'",
+        ]);
+    }
+
+    #[test]
+    fn test_format_path() {
+        let path = [
+            PathSegment::Key("volumes".to_string()),
+            PathSegment::Index(2),
+            PathSegment::Key("host_path".to_string()),
+        ];
+        assert_eq!(format_path(&path), "volumes[2].host_path");
+    }
+}
@@ -0,0 +1,211 @@
+//! Stack configuration — several services started together.
+//!
+//! stack_configuration.yml lists services, each a RunConfiguration plus
+//! `depends_on`, and networks shared by all of them. Services start in
+//! dependency order and stop in reverse. For example:
+//!
+//! ```yaml
+//! name: kb
+//! networks: [kb-net]
+//! services:
+//!   postgres:
+//!     docker_image_name: pgvector/pgvector:pg16
+//!     healthcheck:
+//!       test: pg_isready -U postgres
+//!   app:
+//!     docker_image_name: kb-app:latest
+//!     depends_on: [postgres]
+//! ```
+//!
+//! Services can't use `secrets:` yet; pass credentials through `env:` or
+//! `env_file:`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use super::interpolation::from_str_interpolated;
+use super::run_docker_configuration::RunConfiguration;
+use crate::run_docker::engine::ContainerEngine;
+use crate::run_docker::networks::NetworkSpec;
+
+//------------------------------------------------------------------------------
+/// One service: a run configuration plus the services it starts after.
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct StackService {
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+
+    #[serde(flatten)]
+    pub run: RunConfiguration,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct StackConfiguration {
+    /// Stack name; prefixes container names (`<name>-<service>`). Defaults
+    /// to the directory name.
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Container engine for all services (default: docker).
+    #[serde(default)]
+    pub engine: Option<ContainerEngine>,
+
+    /// Networks every service joins; created if missing.
+    #[serde(default)]
+    pub networks: Vec<NetworkSpec>,
+
+    /// Services by name; BTreeMap keeps start order stable among services
+    /// that don't depend on each other.
+    pub services: BTreeMap<String, StackService>,
+}
+
+impl StackConfiguration {
+    pub const DEFAULT_FILE_NAME: &'static str = "stack_configuration.yml";
+
+    /// Load from a YAML file, interpolating `${VAR}` and validating
+    /// services and dependencies.
+    pub fn load_from_path<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+        let mut configuration: StackConfiguration = from_str_interpolated(&content)?;
+
+        if configuration.services.is_empty() {
+            return Err(format!("'{}' defines no services", path.display()));
+        }
+        for (name, service) in &mut configuration.services {
+            if service.run.secrets.as_ref().is_some_and(|s| !s.is_empty()) {
+                return Err(format!(
+                    "Service '{}': secrets are not supported in {}; use env or env_file",
+                    name, Self::DEFAULT_FILE_NAME));
+            }
+            service.run = std::mem::take(&mut service.run)
+                .validate()
+                .map_err(|e| format!("Service '{}': {}", name, e))?;
+        }
+        configuration.start_order()?;
+        Ok(configuration)
+    }
+
+    /// Load stack_configuration.yml from a directory.
+    pub fn load_from_directory<P: AsRef<Path>>(dir: P) -> Result<Self, String> {
+        Self::load_from_path(dir.as_ref().join(Self::DEFAULT_FILE_NAME))
+    }
+
+    //--------------------------------------------------------------------------
+    /// Service names in dependency order (dependencies first). Errors on
+    /// unknown dependencies and cycles.
+    //--------------------------------------------------------------------------
+    pub fn start_order(&self) -> Result<Vec<String>, String> {
+        let mut remaining: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (name, service) in &self.services {
+            for dependency in &service.depends_on {
+                if !self.services.contains_key(dependency) {
+                    return Err(format!(
+                        "Service '{}' depends on unknown service '{}'",
+                        name, dependency));
+                }
+            }
+            remaining.insert(
+                name.as_str(),
+                service.depends_on.iter().map(String::as_str).collect());
+        }
+
+        let mut order: Vec<String> = Vec::new();
+        while !remaining.is_empty() {
+            let mut ready: Vec<&str> = remaining
+                .iter()
+                .filter(|(_, deps)| deps.iter().all(|d| order.iter().any(|o| o == d)))
+                .map(|(name, _)| *name)
+                .collect();
+            if ready.is_empty() {
+                let mut cycle: Vec<&str> = remaining.keys().copied().collect();
+                cycle.sort();
+                return Err(format!(
+                    "Dependency cycle among services: {}", cycle.join(", ")));
+            }
+            ready.sort();
+            for name in ready {
+                remaining.remove(name);
+                order.push(name.to_string());
+            }
+        }
+        Ok(order)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_stack_configuration() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(StackConfiguration::DEFAULT_FILE_NAME);
+        fs::write(&path, r#"
+name: kb
+networks: [kb-net]
+services:
+  app:
+    docker_image_name: kb-app:latest
+    depends_on: [postgres, embedder]
+  embedder:
+    docker_image_name: embedder:latest
+    gpus: [0]
+    depends_on: [postgres]
+  postgres:
+    docker_image_name: pgvector/pgvector:pg16
+    env:
+      POSTGRES_PASSWORD: postgres
+"#).unwrap();
+
+        let stack = StackConfiguration::load_from_path(&path).unwrap();
+        assert_eq!(stack.name.as_deref(), Some("kb"));
+        assert_eq!(stack.networks[0].name(), "kb-net");
+        assert_eq!(stack.services["embedder"].run.gpus.as_deref(), Some("device=0"));
+        assert_eq!(stack.start_order().unwrap(), vec!["postgres", "embedder", "app"]);
+    }
+
+    #[test]
+    fn test_service_secrets_are_rejected() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(StackConfiguration::DEFAULT_FILE_NAME);
+        fs::write(&path, r#"
+services:
+  app:
+    docker_image_name: kb-app:latest
+    secrets:
+      - file: ./db_password.txt
+        target: db_password
+"#).unwrap();
+
+        let err = StackConfiguration::load_from_path(&path).unwrap_err();
+        assert!(err.contains("Service 'app': secrets are not supported"), "{}", err);
+    }
+
+    #[test]
+    fn test_start_order_errors() {
+        let service = |deps: &[&str]| StackService {
+            depends_on: deps.iter().map(|d| d.to_string()).collect(),
+            run: RunConfiguration {
+                docker_image_name: "img".to_string(),
+                ..Default::default()
+            },
+        };
+
+        let mut stack = StackConfiguration::default();
+        stack.services.insert("a".to_string(), service(&["b"]));
+        stack.services.insert("b".to_string(), service(&["a"]));
+        stack.services.insert("c".to_string(), service(&[]));
+        assert_eq!(
+            stack.start_order().unwrap_err(),
+            "Dependency cycle among services: a, b");
+
+        stack.services.insert("a".to_string(), service(&["missing"]));
+        assert!(stack.start_order().unwrap_err().contains("unknown service 'missing'"));
+    }
+}
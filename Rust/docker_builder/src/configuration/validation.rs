@@ -0,0 +1,1158 @@
+//! Validation of build_configuration.yml and run_configuration.yml.
+//!
+//! Unlike loading, which stops at serde's first (often opaque) error,
+//! validation collects every problem it can find and points at the offending
+//! line: unknown keys, empty image names, invalid or duplicate port mappings,
+//! missing host paths, and malformed env, device, GPU, and user entries.
+
+use serde::Serialize;
+use serde_yaml::{Mapping, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::run_docker::digest::{split_digest, validate_digest};
+use crate::run_docker::gpu_selection::{
+    memory_fraction_env, validate_gpu_id, validate_gpus_spec};
+use super::build_docker_configuration::{
+    BuildDockerConfiguration, BuildDockerConfigurationData, DockerfileComponent};
+use super::interpolation::interpolate_env;
+use super::overlay::PROFILES_KEY;
+use super::schema::{closest_match, validate_value, PathSegment};
+use super::variables::{collect_vars, substitute_in_value, VARS_KEY};
+use super::run_docker_configuration::{
+    expand_tilde, resolve_user, DeviceMapping, PortMapping, RunConfiguration,
+    RunDockerConfiguration, RunDockerConfigurationData, SecretMount, VolumeMount};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+//------------------------------------------------------------------------------
+/// A single problem found in a configuration file.
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationIssue {
+    pub file: PathBuf,
+    /// 1-based line number, if the problem could be located
+    pub line: Option<usize>,
+    /// 1-based column number (only for YAML syntax/type errors)
+    pub column: Option<usize>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.file.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+            if let Some(column) = self.column {
+                write!(f, ":{}", column)?;
+            }
+        }
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, ": {}: {}", severity, self.message)
+    }
+}
+
+//------------------------------------------------------------------------------
+/// Maps YAML keys and list items back to line numbers in the source text.
+/// Handles block-style YAML, which is what these files use; anything it can't
+/// locate falls back to the enclosing key's line.
+//------------------------------------------------------------------------------
+struct LineIndex<'a> {
+    lines: Vec<&'a str>,
+}
+
+impl<'a> LineIndex<'a> {
+    fn new(content: &'a str) -> Self {
+        Self { lines: content.lines().collect() }
+    }
+
+    fn starts_with_key(text: &str, key: &str) -> bool {
+        let text = text.trim_start_matches('"').trim_start_matches('\'');
+        text.strip_prefix(key).is_some_and(|rest| {
+            let rest = rest.trim_start_matches('"').trim_start_matches('\'');
+            rest.starts_with(':')
+        })
+    }
+
+    /// Line (1-based) of a top-level `key:`.
+    fn top_level_key(&self, key: &str) -> Option<usize> {
+        self.lines
+            .iter()
+            .position(|l| Self::starts_with_key(l, key))
+            .map(|i| i + 1)
+    }
+
+    /// Zero-based line range of the block under a top-level key.
+    fn section(&self, key: &str) -> Option<(usize, usize)> {
+        let start = self.top_level_key(key)?;
+        let end = self.lines[start..]
+            .iter()
+            .position(|l| {
+                !l.is_empty()
+                    && !l.starts_with(char::is_whitespace)
+                    && !l.starts_with('#')
+                    && !l.starts_with('-')
+            })
+            .map_or(self.lines.len(), |i| start + i);
+        Some((start, end))
+    }
+
+    /// Line of the nth `- ` item under a top-level key.
+    fn item(&self, key: &str, n: usize) -> Option<usize> {
+        let (start, end) = self.section(key)?;
+        self.lines[start..end]
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| l.trim_start().starts_with('-'))
+            .nth(n)
+            .map(|(i, _)| start + i + 1)
+    }
+
+    /// Line of `field:` within the nth item under a top-level key.
+    fn item_field(&self, key: &str, n: usize, field: &str) -> Option<usize> {
+        let item_line = self.item(key, n)?;
+        let (_, end) = self.section(key)?;
+        let item_end = self.item(key, n + 1).map_or(end, |l| l - 1);
+        (item_line - 1..item_end)
+            .find(|&i| {
+                let text = self.lines[i].trim_start().trim_start_matches('-').trim_start();
+                Self::starts_with_key(text, field)
+            })
+            .map(|i| i + 1)
+    }
+
+    /// Line of a nested `field:` anywhere under a top-level key.
+    fn nested_key(&self, key: &str, field: &str) -> Option<usize> {
+        let (start, end) = self.section(key)?;
+        (start..end)
+            .find(|&i| Self::starts_with_key(self.lines[i].trim_start(), field))
+            .map(|i| i + 1)
+    }
+
+    /// Indentation of a line's content, counting a leading `- ` as indent so
+    /// the first key of a list item lines up with the rest. None for blank
+    /// and comment lines.
+    fn content_indent(line: &str) -> Option<usize> {
+        let leading = line.len() - line.trim_start().len();
+        let rest = &line[leading..];
+        if rest.is_empty() || rest.starts_with('#') {
+            return None;
+        }
+        match rest.strip_prefix('-') {
+            Some(item) if item.is_empty() || item.starts_with(' ') => {
+                let item_body = item.trim_start();
+                Some(line.len() - item_body.len())
+            }
+            _ => Some(leading),
+        }
+    }
+
+    /// End (exclusive) of the block that starts after line `from`, i.e. the
+    /// next content line whose `indent` is at most `max`.
+    fn block_end<F>(&self, from: usize, end: usize, max: usize, indent: F) -> usize
+    where
+        F: Fn(&str) -> Option<usize>,
+    {
+        (from + 1..end)
+            .find(|&i| indent(self.lines[i]).is_some_and(|n| n <= max))
+            .unwrap_or(end)
+    }
+
+    /// Line and column (1-based) of the value at `path` in block-style YAML,
+    /// or of `key` inside it when given. Falls back to the deepest enclosing
+    /// node that could be found (e.g. for flow-style `{...}` values).
+    fn locate(&self, path: &[PathSegment], key: Option<&str>) -> Option<(usize, usize)> {
+        let key = key.map(|k| PathSegment::Key(k.to_string()));
+        let (mut start, mut end) = (0, self.lines.len());
+        let mut found = None;
+
+        for segment in path.iter().chain(key.as_ref()) {
+            match segment {
+                PathSegment::Key(key) => {
+                    let indent = (start..end)
+                        .filter_map(|i| Self::content_indent(self.lines[i]))
+                        .min()?;
+                    let Some(line) = (start..end).find(|&i| {
+                        Self::content_indent(self.lines[i]) == Some(indent)
+                            && Self::starts_with_key(&self.lines[i][indent..], key)
+                    }) else {
+                        break;
+                    };
+                    found = Some((line + 1, indent + 1));
+                    end = self.block_end(line, end, indent, Self::content_indent);
+                    start = line + 1;
+                }
+                PathSegment::Index(n) => {
+                    let dash_indent = |l: &str| {
+                        let text = l.trim_start();
+                        (!text.is_empty() && !text.starts_with('#'))
+                            .then_some(l.len() - text.len())
+                    };
+                    let is_item = |l: &str| l.trim_start().starts_with('-');
+                    let Some(indent) = (start..end)
+                        .filter(|&i| is_item(self.lines[i]))
+                        .filter_map(|i| dash_indent(self.lines[i]))
+                        .min()
+                    else {
+                        break;
+                    };
+                    let Some(line) = (start..end)
+                        .filter(|&i| is_item(self.lines[i])
+                            && dash_indent(self.lines[i]) == Some(indent))
+                        .nth(*n)
+                    else {
+                        break;
+                    };
+                    found = Some((line + 1, indent + 1));
+                    end = self.block_end(line, end, indent, dash_indent);
+                    start = line;
+                }
+            }
+        }
+        found
+    }
+}
+
+//------------------------------------------------------------------------------
+/// Collects issues for one file.
+//------------------------------------------------------------------------------
+struct Report<'a> {
+    file: PathBuf,
+    index: LineIndex<'a>,
+    issues: Vec<ValidationIssue>,
+}
+
+impl<'a> Report<'a> {
+    fn new(file: &Path, content: &'a str) -> Self {
+        Self {
+            file: file.to_path_buf(),
+            index: LineIndex::new(content),
+            issues: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, severity: Severity, line: Option<usize>, message: String) {
+        self.issues.push(ValidationIssue {
+            file: self.file.clone(),
+            line,
+            column: None,
+            severity,
+            message,
+        });
+    }
+
+    fn error(&mut self, line: Option<usize>, message: String) {
+        self.push(Severity::Error, line, message);
+    }
+
+    fn warning(&mut self, line: Option<usize>, message: String) {
+        self.push(Severity::Warning, line, message);
+    }
+
+    fn yaml_error(&mut self, error: &serde_yaml::Error) {
+        let location = error.location();
+        self.issues.push(ValidationIssue {
+            file: self.file.clone(),
+            line: location.as_ref().map(|l| l.line()),
+            column: location.as_ref().map(|l| l.column()),
+            severity: Severity::Error,
+            message: strip_location(&error.to_string()),
+        });
+    }
+
+    /// Report keys of `map` not in `known`; `line_of` locates each key.
+    fn unknown_keys<F>(&mut self, map: &Mapping, known: &[String], what: &str, line_of: F)
+    where
+        F: Fn(&LineIndex, &str) -> Option<usize>,
+    {
+        for key in map.keys() {
+            let Some(key) = key.as_str() else {
+                self.error(None, format!("Non-string key in {}", what));
+                continue;
+            };
+            if !known.iter().any(|k| k == key) {
+                let line = line_of(&self.index, key);
+                let hint = closest_match(key, known.iter().map(String::as_str))
+                    .map(|k| format!(" (did you mean '{}'?)", k))
+                    .unwrap_or_default();
+                self.error(line, format!("Unknown key '{}' in {}{}", key, what, hint));
+            }
+        }
+    }
+}
+
+/// serde_yaml appends " at line X column Y"; the location is reported separately.
+fn strip_location(message: &str) -> String {
+    match message.rfind(" at line ") {
+        Some(idx) => message[..idx].to_string(),
+        None => message.to_string(),
+    }
+}
+
+//------------------------------------------------------------------------------
+/// Field names of a struct, taken from its default serialization so the list
+/// stays in sync with the struct definition.
+//------------------------------------------------------------------------------
+fn known_keys<T: Serialize + Default>() -> Vec<String> {
+    match serde_yaml::to_value(T::default()) {
+        Ok(Value::Mapping(map)) => map
+            .keys()
+            .filter_map(|k| k.as_str().map(String::from))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn is_valid_env_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Read and parse a YAML file into a top-level mapping, reporting problems.
+fn load_mapping<'a>(report: &mut Report<'a>, content: &'a str) -> Option<Mapping> {
+    match serde_yaml::from_str::<Value>(content) {
+        Ok(Value::Mapping(map)) => Some(resolve_vars(report, map)),
+        Ok(Value::Null) => {
+            report.error(None, "File is empty".to_string());
+            None
+        }
+        Ok(_) => {
+            report.error(Some(1), "Top level must be a mapping of keys".to_string());
+            None
+        }
+        Err(e) => {
+            report.yaml_error(&e);
+            None
+        }
+    }
+}
+
+/// Substitute `{{ var }}` so later checks see real values, reporting
+/// unresolvable references per key. `vars` itself is removed.
+fn resolve_vars(report: &mut Report, mut map: Mapping) -> Mapping {
+    let vars = match collect_vars(&Value::Mapping(map.clone()), &[]) {
+        Ok(vars) => vars,
+        Err(e) => {
+            let line = report.index.top_level_key(VARS_KEY);
+            report.error(line, e);
+            map.remove(VARS_KEY);
+            return map;
+        }
+    };
+    map.remove(VARS_KEY);
+
+    for (key, value) in map.iter_mut() {
+        let Some(key) = key.as_str() else {
+            continue;
+        };
+        if key == PROFILES_KEY {
+            continue;
+        }
+        let mut substituted = value.clone();
+        match substitute_in_value(&mut substituted, &vars) {
+            Ok(()) => *value = substituted,
+            Err(e) => {
+                let line = report.index.top_level_key(key);
+                report.error(line, format!("{}: {}", key, e));
+            }
+        }
+    }
+    map
+}
+
+//------------------------------------------------------------------------------
+/// Check a configuration file's content against a JSON Schema (see
+/// `configuration::schema`), for use when loading. Every violation is
+/// reported with its line and column. Content that isn't a YAML mapping is
+/// left for the loader to report.
+//------------------------------------------------------------------------------
+pub fn check_schema(file: &Path, content: &str, schema: &str) -> Result<(), String> {
+    let value = match serde_yaml::from_str::<Value>(content) {
+        Ok(value @ Value::Mapping(_)) => value,
+        _ => return Ok(()),
+    };
+    let errors = validate_value(schema, &value)?;
+    if errors.is_empty() {
+        return Ok(());
+    }
+
+    let index = LineIndex::new(content);
+    let issues: Vec<String> = errors
+        .iter()
+        .map(|error| {
+            let location = index.locate(&error.path, error.key.as_deref());
+            ValidationIssue {
+                file: file.to_path_buf(),
+                line: location.map(|(line, _)| line),
+                column: location.map(|(_, column)| column),
+                severity: Severity::Error,
+                message: error.to_string(),
+            }
+            .to_string()
+        })
+        .collect();
+    Err(format!("Invalid configuration:\n  {}", issues.join("\n  ")))
+}
+
+//------------------------------------------------------------------------------
+/// `check_schema` on each of `files` that exists (base files may be absent
+/// when overlays provide everything).
+//------------------------------------------------------------------------------
+pub fn check_schema_files<P: AsRef<Path>>(files: &[P], schema: &str) -> Result<(), String> {
+    for file in files {
+        let file = file.as_ref();
+        if !file.exists() {
+            continue;
+        }
+        let content = fs::read_to_string(file)
+            .map_err(|e| format!("Failed to read '{}': {}", file.display(), e))?;
+        check_schema(file, &content, schema)?;
+    }
+    Ok(())
+}
+
+//------------------------------------------------------------------------------
+/// Validate a build_configuration.yml file.
+//------------------------------------------------------------------------------
+pub fn validate_build_configuration(path: &Path) -> Vec<ValidationIssue> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            return vec![ValidationIssue {
+                file: path.to_path_buf(),
+                line: None,
+                column: None,
+                severity: Severity::Error,
+                message: format!("Failed to read file: {}", e),
+            }];
+        }
+    };
+
+    let mut report = Report::new(path, &content);
+    let Some(map) = load_mapping(&mut report, &content) else {
+        return report.issues;
+    };
+
+    // build_args is skipped when empty, so it's missing from known_keys().
+    let mut known = known_keys::<BuildDockerConfigurationData>();
+    known.push("build_args".to_string());
+    report.unknown_keys(&map, &known, "build configuration", |index, key| {
+        index.top_level_key(key)
+    });
+
+    for key in ["docker_image_name", "base_image"] {
+        match map.get(key) {
+            None => report.error(None, format!("Missing required key '{}'", key)),
+            Some(Value::String(s)) if s.trim().is_empty() => {
+                let line = report.index.top_level_key(key);
+                report.error(line, format!("'{}' must not be empty", key));
+            }
+            Some(Value::String(s)) => check_image_digest(&mut report, key, s),
+            _ => {}
+        }
+    }
+
+    check_string_values(&mut report, &map);
+
+    if let Some(Value::Mapping(args)) = map.get("build_args") {
+        for key in args.keys() {
+            let key = key.as_str().unwrap_or_default();
+            if !is_valid_env_key(key) {
+                let line = report.index.nested_key("build_args", key);
+                report.error(line, format!("Invalid build arg name '{}'", key));
+            }
+        }
+    }
+
+    let config_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let component_keys = known_keys::<DockerfileComponent>();
+    if let Some(Value::Sequence(components)) = map.get("dockerfile_components") {
+        for (i, component) in components.iter().enumerate() {
+            let line = report.index.item("dockerfile_components", i);
+            let Value::Mapping(component) = component else {
+                report.error(line, format!(
+                    "dockerfile_components[{}] must have 'label' and 'path'", i));
+                continue;
+            };
+            report.unknown_keys(
+                component,
+                &component_keys,
+                &format!("dockerfile_components[{}]", i),
+                |index, key| index.item_field("dockerfile_components", i, key));
+
+            match component.get("path").and_then(Value::as_str) {
+                Some(p) => {
+                    let resolved = config_dir.join(p);
+                    if !resolved.exists() {
+                        let line = report.index
+                            .item_field("dockerfile_components", i, "path")
+                            .or(line);
+                        report.error(line, format!(
+                            "Dockerfile component path does not exist: {}",
+                            resolved.display()));
+                    }
+                }
+                None => report.error(line, format!(
+                    "dockerfile_components[{}] is missing 'path'", i)),
+            }
+        }
+    }
+
+    // Type errors (e.g. a list where a string is expected), with location.
+    if let Err(e) = serde_yaml::from_str::<BuildDockerConfigurationData>(&content) {
+        report.yaml_error(&e);
+    }
+
+    report.issues
+}
+
+//------------------------------------------------------------------------------
+/// Validate a run_configuration.yml file (richer or legacy format).
+//------------------------------------------------------------------------------
+pub fn validate_run_configuration(path: &Path) -> Vec<ValidationIssue> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            return vec![ValidationIssue {
+                file: path.to_path_buf(),
+                line: None,
+                column: None,
+                severity: Severity::Error,
+                message: format!("Failed to read file: {}", e),
+            }];
+        }
+    };
+
+    let mut report = Report::new(path, &content);
+    let Some(map) = load_mapping(&mut report, &content) else {
+        return report.issues;
+    };
+
+    // Same rule as run_docker: docker_image_name selects the richer format.
+    let is_rich = map.contains_key("docker_image_name");
+    let (known, what) = if is_rich {
+        (known_keys::<RunConfiguration>(), "run configuration")
+    } else {
+        (known_keys::<RunDockerConfigurationData>(),
+         "legacy run configuration (add docker_image_name for the full format)")
+    };
+    report.unknown_keys(&map, &known, what, |index, key| index.top_level_key(key));
+
+    if let Some(Value::String(name)) = map.get("docker_image_name") {
+        if name.trim().is_empty() {
+            let line = report.index.top_level_key("docker_image_name");
+            report.error(line, "'docker_image_name' must not be empty".to_string());
+        }
+        check_image_digest(&mut report, "docker_image_name", name);
+    }
+
+    check_string_values(&mut report, &map);
+    check_ports(&mut report, &map);
+    check_volumes(&mut report, &map);
+    if is_rich {
+        check_env(&mut report, &map);
+        check_rich_fields(&mut report, &map);
+        check_profiles(&mut report, &map, &known);
+    }
+
+    // Type errors, with location.
+    let typed = if is_rich {
+        serde_yaml::from_str::<RunConfiguration>(&content).map(|_| ())
+    } else {
+        serde_yaml::from_str::<RunDockerConfigurationData>(&content).map(|_| ())
+    };
+    if let Err(e) = typed {
+        report.yaml_error(&e);
+    }
+
+    report.issues
+}
+
+/// Report a malformed `@sha256:` digest in an image name.
+fn check_image_digest(report: &mut Report, key: &str, image_name: &str) {
+    if let Some(digest) = split_digest(image_name).1
+        && let Err(e) = validate_digest(digest)
+    {
+        let line = report.index.top_level_key(key);
+        report.error(line, e);
+    }
+}
+
+/// Report unresolvable `${VAR}` references in top-level string values.
+fn check_string_values(report: &mut Report, map: &Mapping) {
+    fn walk(value: &Value, found: &mut Vec<String>) {
+        match value {
+            Value::String(s) => {
+                if let Err(e) = interpolate_env(s) {
+                    found.push(e);
+                }
+            }
+            Value::Sequence(items) => items.iter().for_each(|v| walk(v, found)),
+            Value::Mapping(m) => m.values().for_each(|v| walk(v, found)),
+            _ => {}
+        }
+    }
+
+    for (key, value) in map {
+        let mut found = Vec::new();
+        walk(value, &mut found);
+        let line = key.as_str().and_then(|k| report.index.top_level_key(k));
+        for message in found {
+            report.error(line, message);
+        }
+    }
+}
+
+fn check_ports(report: &mut Report, map: &Mapping) {
+    let Some(Value::Sequence(ports)) = map.get("ports") else {
+        return;
+    };
+
+    let mut seen: HashMap<u64, usize> = HashMap::new();
+    for (i, port) in ports.iter().enumerate() {
+        let line = report.index.item("ports", i);
+        let Value::Mapping(port) = port else {
+            report.error(line, format!(
+                "ports[{}] must be a mapping with host_port and container_port", i));
+            continue;
+        };
+
+        report.unknown_keys(
+            port,
+            &known_keys::<PortMapping>(),
+            &format!("ports[{}]", i),
+            |index, key| index.item_field("ports", i, key));
+
+        for field in ["host_port", "container_port"] {
+            let field_line = report.index.item_field("ports", i, field).or(line);
+            match port.get(field).and_then(Value::as_u64) {
+                Some(n) if (1..=65535).contains(&n) => {
+                    if field == "host_port"
+                        && let Some(first) = seen.insert(n, i)
+                    {
+                        report.error(field_line, format!(
+                            "Duplicate host_port {} (also used by ports[{}])", n, first));
+                    }
+                }
+                Some(n) => report.error(field_line, format!(
+                    "ports[{}].{} {} is out of range (1-65535)", i, field, n)),
+                None if port.contains_key(field) => report.error(field_line, format!(
+                    "ports[{}].{} must be a port number", i, field)),
+                None => report.error(line, format!(
+                    "ports[{}] is missing '{}'", i, field)),
+            }
+        }
+    }
+}
+
+fn check_volumes(report: &mut Report, map: &Mapping) {
+    let Some(Value::Sequence(volumes)) = map.get("volumes") else {
+        return;
+    };
+
+    for (i, volume) in volumes.iter().enumerate() {
+        let line = report.index.item("volumes", i);
+        let Value::Mapping(volume) = volume else {
+            report.error(line, format!(
+                "volumes[{}] must be a mapping with host_path and container_path", i));
+            continue;
+        };
+
+        report.unknown_keys(
+            volume,
+            &known_keys::<VolumeMount>(),
+            &format!("volumes[{}]", i),
+            |index, key| index.item_field("volumes", i, key));
+
+        for field in ["host_path", "container_path"] {
+            match volume.get(field).and_then(Value::as_str) {
+                Some(p) if !p.trim().is_empty() => {}
+                _ => report.error(line, format!(
+                    "volumes[{}] is missing '{}'", i, field)),
+            }
+        }
+
+        if let Some(container_path) = volume.get("container_path").and_then(Value::as_str)
+            && !container_path.trim().is_empty()
+            && !container_path.trim().starts_with('/')
+        {
+            let field_line = report.index.item_field("volumes", i, "container_path");
+            report.error(field_line.or(line), format!(
+                "volumes[{}].container_path '{}' must be absolute", i, container_path));
+        }
+
+        if let Some(host_path) = volume.get("host_path").and_then(Value::as_str)
+            && let Ok(host_path) = interpolate_env(host_path.trim())
+            && !host_path.is_empty()
+        {
+            let expanded = expand_tilde(&host_path);
+            if !Path::new(&expanded).exists() {
+                let field_line = report.index.item_field("volumes", i, "host_path");
+                report.warning(field_line.or(line), format!(
+                    "volumes[{}].host_path does not exist: {} (docker will create it \
+                     owned by root)",
+                    i, expanded));
+            }
+        }
+    }
+}
+
+fn check_env(report: &mut Report, map: &Mapping) {
+    match map.get("env") {
+        Some(Value::Mapping(env)) => {
+            for key in env.keys() {
+                let key = key.as_str().unwrap_or_default();
+                if !is_valid_env_key(key) {
+                    let line = report.index.nested_key("env", key);
+                    report.error(line, format!("Invalid env variable name '{}'", key));
+                }
+            }
+        }
+        Some(Value::Sequence(env)) => {
+            for (i, entry) in env.iter().enumerate() {
+                let line = report.index.item("env", i);
+                match entry.as_str().and_then(|s| s.split_once('=')) {
+                    Some((key, _)) if is_valid_env_key(key.trim()) => {}
+                    Some((key, _)) => report.error(line, format!(
+                        "Invalid env variable name '{}' in env[{}]", key, i)),
+                    None => report.error(line, format!(
+                        "env[{}] must be KEY=value, got {:?}",
+                        i,
+                        entry.as_str().unwrap_or("<not a string>"))),
+                }
+            }
+        }
+        _ => {}
+    }
+
+    for (i, env_file) in map
+        .get("env_file")
+        .and_then(Value::as_sequence)
+        .into_iter()
+        .flatten()
+        .enumerate()
+    {
+        if let Some(env_file) = env_file.as_str()
+            && let Ok(env_file) = interpolate_env(env_file.trim())
+        {
+            let expanded = expand_tilde(&env_file);
+            let resolved = report.file.parent()
+                .map_or_else(|| PathBuf::from(&expanded), |dir| dir.join(&expanded));
+            if !resolved.exists() {
+                let line = report.index.item("env_file", i);
+                report.error(line, format!("env_file does not exist: {}", expanded));
+            }
+        }
+    }
+}
+
+fn check_rich_fields(report: &mut Report, map: &Mapping) {
+    for (i, device) in map
+        .get("devices")
+        .and_then(Value::as_sequence)
+        .into_iter()
+        .flatten()
+        .enumerate()
+    {
+        if let Some(spec) = device.as_str()
+            && let Err(e) = DeviceMapping::parse(spec)
+        {
+            let line = report.index.item("devices", i);
+            report.error(line, e);
+        }
+    }
+
+    for (i, host) in map
+        .get("extra_hosts")
+        .and_then(Value::as_sequence)
+        .into_iter()
+        .flatten()
+        .enumerate()
+    {
+        let valid = host
+            .as_str()
+            .and_then(|h| h.split_once(':'))
+            .is_some_and(|(name, ip)| !name.is_empty() && !ip.is_empty());
+        if !valid {
+            let line = report.index.item("extra_hosts", i);
+            report.error(line, format!("extra_hosts[{}] must be host:ip", i));
+        }
+    }
+
+    for (i, secret) in map
+        .get("secrets")
+        .and_then(Value::as_sequence)
+        .into_iter()
+        .flatten()
+        .enumerate()
+    {
+        let result = serde_yaml::from_value::<SecretMount>(secret.clone())
+            .map_err(|e| format!("secrets[{}]: {}", i, e))
+            .and_then(|secret| secret.validate());
+        if let Err(e) = result {
+            let line = report.index.item("secrets", i);
+            report.error(line, e);
+        }
+    }
+
+    if let Some(user) = map.get("user").and_then(Value::as_str)
+        && let Err(e) = resolve_user(user)
+    {
+        let line = report.index.top_level_key("user");
+        report.error(line, e);
+    }
+
+    if let Some(path) = map.get("mount_cwd").and_then(Value::as_str)
+        && !path.trim().is_empty()
+        && !path.trim().starts_with('/')
+    {
+        let line = report.index.top_level_key("mount_cwd");
+        report.error(line, format!("mount_cwd '{}' must be absolute", path));
+    }
+
+    check_gpus(report, map);
+    check_namespaces(report, map);
+}
+
+/// `ipc` and `pid` modes docker accepts.
+fn check_namespaces(report: &mut Report, map: &Mapping) {
+    let modes: [(&str, &[&str]); 2] = [
+        ("ipc", &["none", "private", "shareable", "host"]),
+        ("pid", &["host"]),
+    ];
+    for (key, allowed) in modes {
+        let Some(mode) = map.get(key).and_then(Value::as_str) else {
+            continue;
+        };
+        let mode = mode.trim();
+        let valid = mode.is_empty()
+            || allowed.contains(&mode)
+            || mode.strip_prefix("container:").is_some_and(|c| !c.is_empty());
+        if !valid {
+            let line = report.index.top_level_key(key);
+            report.error(line, format!(
+                "Invalid {} '{}': expected {} or container:<name>",
+                key, mode, allowed.join(", ")));
+        }
+    }
+}
+
+/// GPU device IDs (including MIG) and gpu_memory_fraction range.
+fn check_gpus(report: &mut Report, map: &Mapping) {
+    if let Some(spec) = map.get("gpus").and_then(Value::as_str)
+        && let Err(e) = validate_gpus_spec(spec)
+    {
+        let line = report.index.top_level_key("gpus");
+        report.error(line, e);
+    }
+
+    for key in ["gpus", "nvidia_visible_devices"] {
+        for (i, id) in map
+            .get(key)
+            .and_then(Value::as_sequence)
+            .into_iter()
+            .flatten()
+            .enumerate()
+        {
+            if let Some(id) = id.as_str()
+                && let Err(e) = validate_gpu_id(id)
+            {
+                let line = report.index.item(key, i);
+                report.error(line, format!("{}[{}]: {}", key, i, e));
+            }
+        }
+    }
+
+    if let Some(devices) = map.get("nvidia_visible_devices").and_then(Value::as_str)
+        && !matches!(devices.trim(), "all" | "none" | "void")
+        && let Err(e) = devices.split(',').try_for_each(validate_gpu_id)
+    {
+        let line = report.index.top_level_key("nvidia_visible_devices");
+        report.error(line, e);
+    }
+
+    if let Some(fraction) = map.get("gpu_memory_fraction").and_then(Value::as_f64)
+        && let Err(e) = memory_fraction_env(fraction)
+    {
+        let line = report.index.top_level_key("gpu_memory_fraction");
+        report.error(line, e);
+    }
+}
+
+/// Each profile must be a mapping of run configuration keys.
+fn check_profiles(report: &mut Report, map: &Mapping, known: &[String]) {
+    let Some(Value::Mapping(profiles)) = map.get(PROFILES_KEY) else {
+        return;
+    };
+
+    for (name, profile) in profiles {
+        let name = name.as_str().unwrap_or_default();
+        match profile {
+            Value::Mapping(fields) => report.unknown_keys(
+                fields,
+                known,
+                &format!("profile '{}'", name),
+                |index, key| index.nested_key(PROFILES_KEY, key)),
+            Value::Null => {}
+            _ => {
+                let line = report.index.nested_key(PROFILES_KEY, name);
+                report.error(line, format!(
+                    "Profile '{}' must be a mapping of configuration keys", name));
+            }
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+/// Validate both configuration files in a build directory.
+/// A missing run_configuration.yml is fine (defaults are used).
+//------------------------------------------------------------------------------
+pub fn validate_build_dir(build_dir: &Path) -> Result<Vec<ValidationIssue>, String> {
+    if !build_dir.is_dir() {
+        return Err(format!(
+            "Build directory does not exist: {}", build_dir.display()));
+    }
+
+    let mut issues = Vec::new();
+
+    let build_file = build_dir.join(BuildDockerConfiguration::DEFAULT_FILE_NAME);
+    if build_file.exists() {
+        issues.extend(validate_build_configuration(&build_file));
+    } else {
+        issues.push(ValidationIssue {
+            file: build_file,
+            line: None,
+            column: None,
+            severity: Severity::Error,
+            message: "File not found".to_string(),
+        });
+    }
+
+    let run_file = build_dir.join(RunDockerConfiguration::DEFAULT_FILE_NAME);
+    if run_file.exists() {
+        issues.extend(validate_run_configuration(&run_file));
+    }
+
+    Ok(issues)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn messages(issues: &[ValidationIssue]) -> Vec<String> {
+        issues.iter().map(|i| i.to_string()).collect()
+    }
+
+    #[test]
+    fn test_validate_build_configuration() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("Dockerfile.base"), "FROM x").unwrap();
+        let path = temp.path().join("build_configuration.yml");
+        fs::write(&path, r#"
+docker_image_name: ""
+base_image: ubuntu:24.04
+build_arg:
+  CUDA: "12.4"
+dockerfile_components:
+  - label: base
+    path: Dockerfile.base
+  - label: missing
+    path: Dockerfile.missing
+"#).unwrap();
+
+        let issues = validate_build_configuration(&path);
+        let text = messages(&issues).join("\n");
+        assert_eq!(issues.len(), 3, "{}", text);
+        assert!(text.contains(":2: error: 'docker_image_name' must not be empty"));
+        assert!(text.contains(
+            ":4: error: Unknown key 'build_arg' in build configuration \
+             (did you mean 'build_args'?)"));
+        assert!(text.contains(":10: error: Dockerfile component path does not exist"));
+    }
+
+    #[test]
+    fn test_validate_run_configuration() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("run_configuration.yml");
+        fs::write(&path, r#"
+docker_image_name: my-image:latest
+shm_szie: 16g
+ports:
+  - host_port: 8080
+    container_port: 80
+  - host_port: 8080
+    container_port: 81
+  - host_port: 0
+    container_port: 82
+volumes:
+  - host_path: /definitely/not/a/real/path
+    container_path: data
+env:
+  - GOOD=1
+  - NOEQUALS
+devices:
+  - video0
+secrets:
+  - env: HF_TOKEN
+    file: ~/.hf_token
+    target: /run/secrets/hf_token
+profiles:
+  debug:
+    comand: bash
+gpus:
+  - "0:1"
+  - "0:x"
+gpu_memory_fraction: 1.5
+pid: shared
+mount_cwd: workspace
+"#).unwrap();
+
+        let issues = validate_run_configuration(&path);
+        let text = messages(&issues).join("\n");
+        assert!(text.contains(
+            ":3: error: Unknown key 'shm_szie' in run configuration \
+             (did you mean 'shm_size'?)"), "{}", text);
+        assert!(text.contains(
+            ":7: error: Duplicate host_port 8080 (also used by ports[0])"), "{}", text);
+        assert!(text.contains(":9: error: ports[2].host_port 0 is out of range"));
+        assert!(text.contains(":13: error: volumes[0].container_path 'data' must be absolute"));
+        assert!(text.contains(":12: warning: volumes[0].host_path does not exist"));
+        assert!(text.contains(":16: error: env[1] must be KEY=value"));
+        assert!(text.contains(":18: error: Invalid device 'video0'"));
+        assert!(text.contains(
+            ":20: error: Secret '/run/secrets/hf_token' must set exactly one of"), "{}", text);
+        assert!(text.contains(
+            ":25: error: Unknown key 'comand' in profile 'debug' \
+             (did you mean 'command'?)"), "{}", text);
+        assert!(text.contains(":28: error: gpus[1]: Invalid GPU device '0:x'"), "{}", text);
+        assert!(text.contains(
+            ":29: error: gpu_memory_fraction must be in (0, 1]"), "{}", text);
+        assert!(text.contains(
+            ":30: error: Invalid pid 'shared': expected host or container:<name>"),
+            "{}", text);
+        assert!(text.contains(":31: error: mount_cwd 'workspace' must be absolute"), "{}", text);
+    }
+
+    #[test]
+    fn test_validate_reports_yaml_type_errors_with_location() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("run_configuration.yml");
+        fs::write(&path, "docker_image_name: x\nread_only: [1]\n").unwrap();
+
+        let issues = validate_run_configuration(&path);
+        assert_eq!(issues.len(), 1, "{:?}", issues);
+        assert_eq!(issues[0].line, Some(2));
+        assert!(issues[0].column.is_some());
+        assert!(issues[0].message.contains("read_only"));
+    }
+
+    #[test]
+    fn test_validate_build_dir() {
+        let temp = TempDir::new().unwrap();
+        fs::write(
+            temp.path().join("build_configuration.yml"),
+            "docker_image_name: ok:1\nbase_image: ubuntu:24.04\n").unwrap();
+        fs::write(
+            temp.path().join("run_configuration.yml"),
+            "ports:\n  - host_port: 8080\n    container_port: 80\n").unwrap();
+
+        let issues = validate_build_dir(temp.path()).unwrap();
+        assert!(issues.is_empty(), "{:?}", messages(&issues));
+        assert!(validate_build_dir(&temp.path().join("nope")).is_err());
+    }
+
+    #[test]
+    fn test_validate_resolves_vars() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("run_configuration.yml");
+        fs::write(&path, format!(r#"
+vars:
+  data_dir: {}
+docker_image_name: app:latest
+volumes:
+  - host_path: "{{{{ data_dir }}}}"
+    container_path: /data
+workdir: "{{{{ work_dir }}}}"
+"#, temp.path().display())).unwrap();
+
+        let issues = validate_run_configuration(&path);
+        let text = messages(&issues).join("\n");
+        assert_eq!(issues.len(), 1, "{}", text);
+        assert!(text.contains(
+            ":8: error: workdir: Unknown variable '{{ work_dir }}' (defined: data_dir)"),
+            "{}", text);
+    }
+
+    fn schema_keys(schema: &str, pointer: &str) -> Vec<String> {
+        let schema: serde_json::Value = serde_json::from_str(schema).unwrap();
+        let mut keys: Vec<String> = schema
+            .pointer(pointer)
+            .and_then(serde_json::Value::as_object)
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    fn sorted(mut keys: Vec<String>, extra: &[&str]) -> Vec<String> {
+        keys.extend(extra.iter().map(|k| k.to_string()));
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn test_schemas_match_structs() {
+        use super::super::schema::{BUILD_CONFIGURATION_SCHEMA, RUN_CONFIGURATION_SCHEMA};
+
+        assert_eq!(
+            schema_keys(RUN_CONFIGURATION_SCHEMA, "/properties"),
+            sorted(known_keys::<RunConfiguration>(), &[VARS_KEY]));
+        assert_eq!(
+            schema_keys(BUILD_CONFIGURATION_SCHEMA, "/properties"),
+            sorted(known_keys::<BuildDockerConfigurationData>(), &["build_args", VARS_KEY]));
+        assert_eq!(
+            schema_keys(BUILD_CONFIGURATION_SCHEMA, "/$defs/dockerfile_component/properties"),
+            sorted(known_keys::<DockerfileComponent>(), &[]));
+        assert_eq!(
+            schema_keys(RUN_CONFIGURATION_SCHEMA, "/$defs/volume_mount/properties"),
+            sorted(known_keys::<VolumeMount>(), &[]));
+    }
+
+    #[test]
+    fn test_check_schema_reports_line_and_column() {
+        use super::super::schema::RUN_CONFIGURATION_SCHEMA;
+
+        let path = Path::new("run_configuration.yml");
+        let content = r#"docker_image_name: app:latest
+volums:
+  - host_path: /data
+    container_path: /data
+ports:
+  - host_port: 8080
+    container_port: 80
+  - container_port: 81
+    host_port: "8081"
+"#;
+        let error = check_schema(path, content, RUN_CONFIGURATION_SCHEMA).unwrap_err();
+        assert_eq!(error, "Invalid configuration:\n  \
+            run_configuration.yml:2:1: error: Unknown key 'volums' (did you mean 'volumes'?)\n  \
+            run_configuration.yml:9:5: error: ports[1].host_port: expected integer, found string");
+
+        assert!(check_schema(path, "docker_image_name: app\n", RUN_CONFIGURATION_SCHEMA).is_ok());
+    }
+}
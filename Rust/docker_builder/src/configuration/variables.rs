@@ -0,0 +1,260 @@
+//! `vars:` - named values defined once at the top of a configuration file and
+//! referenced elsewhere in it as `{{ name }}`.
+//!
+//! ```yaml
+//! vars:
+//!   model_dir: /mnt/models
+//!   llm: "{{ model_dir }}/Qwen2.5-7B"
+//! volumes:
+//!   - host_path: "{{ llm }}"
+//!     container_path: /models/llm
+//! ```
+//!
+//! Values may reference other vars and host env vars (`${VAR}`, interpolated
+//! first). `--var name=value` on the command line overrides or adds a var.
+//! Only `{{ identifier }}` is substituted, so Go templates such as
+//! `{{.State.Health}}` in commands pass through. The `vars` key is removed
+//! before the file is deserialized, and `profiles` are left as written.
+
+use serde_yaml::Value;
+use std::collections::HashMap;
+
+use super::overlay::PROFILES_KEY;
+
+/// Top-level key holding variables.
+pub const VARS_KEY: &str = "vars";
+
+fn unknown_variable(name: &str, mut defined: Vec<&str>) -> String {
+    defined.sort();
+    format!(
+        "Unknown variable '{{{{ {} }}}}' (defined: {})",
+        name,
+        if defined.is_empty() { "none".to_string() } else { defined.join(", ") })
+}
+
+fn is_valid_var_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+//------------------------------------------------------------------------------
+/// Parse a `--var name=value` argument.
+//------------------------------------------------------------------------------
+pub fn parse_var_override(arg: &str) -> Result<(String, String), String> {
+    let (name, value) = arg.split_once('=').ok_or_else(|| format!(
+        "Invalid --var '{}': expected name=value", arg))?;
+    let name = name.trim();
+    if !is_valid_var_name(name) {
+        return Err(format!("Invalid variable name '{}' in --var", name));
+    }
+    Ok((name.to_string(), value.to_string()))
+}
+
+//------------------------------------------------------------------------------
+/// Replace each `{{ name }}` in `input` with `lookup(name)`. Anything else
+/// between braces is kept verbatim.
+//------------------------------------------------------------------------------
+pub fn substitute_vars_with<F>(input: &str, mut lookup: F) -> Result<String, String>
+where
+    F: FnMut(&str) -> Result<String, String>,
+{
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        let name = after[..end].trim();
+        out.push_str(&rest[..start]);
+        if is_valid_var_name(name) {
+            out.push_str(&lookup(name)?);
+        } else {
+            out.push_str(&rest[start..start + 2 + end + 2]);
+        }
+        rest = &after[end + 2..];
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Resolve one var, following references to other vars.
+fn resolve_var(
+    name: &str,
+    raw: &HashMap<String, String>,
+    resolved: &mut HashMap<String, String>,
+    stack: &mut Vec<String>,
+) -> Result<String, String> {
+    if let Some(value) = resolved.get(name) {
+        return Ok(value.clone());
+    }
+    if stack.iter().any(|n| n == name) {
+        stack.push(name.to_string());
+        return Err(format!("Variables reference each other in a cycle: {}", stack.join(" -> ")));
+    }
+    let Some(template) = raw.get(name) else {
+        return Err(unknown_variable(name, raw.keys().map(String::as_str).collect()));
+    };
+
+    stack.push(name.to_string());
+    let value = substitute_vars_with(template, |n| resolve_var(n, raw, resolved, stack))?;
+    stack.pop();
+    resolved.insert(name.to_string(), value.clone());
+    Ok(value)
+}
+
+//------------------------------------------------------------------------------
+/// Fully resolved vars from the top-level `vars:` mapping of `value`, with
+/// `overrides` replacing or adding entries.
+//------------------------------------------------------------------------------
+pub fn collect_vars(
+    value: &Value,
+    overrides: &[(String, String)],
+) -> Result<HashMap<String, String>, String> {
+    let mut raw = HashMap::new();
+    match value.get(VARS_KEY) {
+        None | Some(Value::Null) => {}
+        Some(Value::Mapping(vars)) => {
+            for (name, var) in vars {
+                let name = name.as_str().filter(|n| is_valid_var_name(n)).ok_or_else(|| format!(
+                    "Invalid variable name {:?} in '{}'", name, VARS_KEY))?;
+                let var = match var {
+                    Value::String(s) => s.clone(),
+                    Value::Number(n) => n.to_string(),
+                    Value::Bool(b) => b.to_string(),
+                    _ => return Err(format!(
+                        "{}.{} must be a string, number, or boolean", VARS_KEY, name)),
+                };
+                raw.insert(name.to_string(), var);
+            }
+        }
+        Some(_) => return Err(format!("'{}' must be a mapping of names to values", VARS_KEY)),
+    }
+    for (name, var) in overrides {
+        raw.insert(name.clone(), var.clone());
+    }
+
+    let mut resolved = HashMap::new();
+    let mut names: Vec<&String> = raw.keys().collect();
+    names.sort();
+    for name in names {
+        resolve_var(name, &raw, &mut resolved, &mut Vec::new())?;
+    }
+    Ok(resolved)
+}
+
+//------------------------------------------------------------------------------
+/// Substitute resolved `vars` in every string scalar of `value`.
+//------------------------------------------------------------------------------
+pub fn substitute_in_value(value: &mut Value, vars: &HashMap<String, String>) -> Result<(), String> {
+    let lookup = |name: &str| vars.get(name).cloned().ok_or_else(|| {
+        unknown_variable(name, vars.keys().map(String::as_str).collect())
+    });
+    match value {
+        Value::String(s) => *s = substitute_vars_with(s, lookup)?,
+        Value::Sequence(items) => {
+            for item in items {
+                substitute_in_value(item, vars)?;
+            }
+        }
+        Value::Mapping(map) => {
+            for (_, v) in map.iter_mut() {
+                substitute_in_value(v, vars)?;
+            }
+        }
+        Value::Tagged(tagged) => substitute_in_value(&mut tagged.value, vars)?,
+        Value::Null | Value::Bool(_) | Value::Number(_) => {}
+    }
+    Ok(())
+}
+
+//------------------------------------------------------------------------------
+/// Resolve `vars:` (plus `overrides`), remove it from `value`, and substitute
+/// `{{ name }}` in all other top-level values except `profiles`.
+//------------------------------------------------------------------------------
+pub fn apply_vars(value: &mut Value, overrides: &[(String, String)]) -> Result<(), String> {
+    let vars = collect_vars(value, overrides)?;
+    let Value::Mapping(map) = value else {
+        return Ok(());
+    };
+    map.remove(VARS_KEY);
+
+    for (key, entry) in map.iter_mut() {
+        if key.as_str() == Some(PROFILES_KEY) {
+            continue;
+        }
+        substitute_in_value(entry, &vars).map_err(|e| match key.as_str() {
+            Some(k) => format!("{}: {}", k, e),
+            None => e,
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yaml(s: &str) -> Value {
+        serde_yaml::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_apply_vars() {
+        let mut value = yaml(r#"
+vars:
+  model_dir: /mnt/models
+  llm: "{{ model_dir }}/qwen"
+  port: 8080
+volumes:
+  - host_path: "{{llm}}"
+    container_path: /models
+command: ["serve", "--port", "{{ port }}", "{{.State}}"]
+profiles:
+  dev:
+    env:
+      X: "{{ only_in_dev }}"
+"#);
+        apply_vars(&mut value, &[]).unwrap();
+        assert_eq!(value, yaml(r#"
+volumes:
+  - host_path: /mnt/models/qwen
+    container_path: /models
+command: ["serve", "--port", "8080", "{{.State}}"]
+profiles:
+  dev:
+    env:
+      X: "{{ only_in_dev }}"
+"#));
+
+        let mut value = yaml("vars:\n  model_dir: /mnt/models\nworkdir: '{{ model_dir }}'\n");
+        let overrides = [("model_dir".to_string(), "/data/models".to_string())];
+        apply_vars(&mut value, &overrides).unwrap();
+        assert_eq!(value, yaml("workdir: /data/models\n"));
+    }
+
+    #[test]
+    fn test_apply_vars_errors() {
+        let err = apply_vars(&mut yaml("workdir: '{{ missing }}'\n"), &[]).unwrap_err();
+        assert!(err.contains("workdir: Unknown variable '{{ missing }}'"), "{}", err);
+
+        let err = apply_vars(
+            &mut yaml("vars:\n  a: '{{ b }}'\n  b: '{{ a }}'\n"), &[]).unwrap_err();
+        assert!(err.contains("cycle: a -> b -> a"), "{}", err);
+
+        assert!(apply_vars(&mut yaml("vars: [1]\n"), &[]).is_err());
+        assert!(apply_vars(&mut yaml("vars:\n  a: [1]\n"), &[]).is_err());
+    }
+
+    #[test]
+    fn test_parse_var_override() {
+        assert_eq!(
+            parse_var_override("model_dir=/mnt/a=b").unwrap(),
+            ("model_dir".to_string(), "/mnt/a=b".to_string()));
+        assert!(parse_var_override("novalue").is_err());
+        assert!(parse_var_override("1bad=x").is_err());
+    }
+}
@@ -1,3 +1,6 @@
 pub mod build_docker;
 pub mod configuration;
 pub mod run_docker;
+pub mod stack;
+pub mod stats;
+pub mod systemd;
@@ -1,12 +1,38 @@
 //! Universal Docker builder and runner
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
+use std::time::Duration;
 
+use docker_builder::configuration::variables::parse_var_override;
+use docker_builder::run_docker::container_name::{
+    apply_collision_policy,
+    NameCollisionPolicy};
+use docker_builder::run_docker::engine::ContainerEngine;
+use docker_builder::run_docker::exec_shell::{
+    open_shell,
+    ShellArgs,
+    DEFAULT_SHELL};
+use docker_builder::run_docker::lock_file::{
+    effective_configuration,
+    host_env_placeholders,
+    render_lock_file,
+    write_lock_file};
+use docker_builder::run_docker::networks::{ensure_networks, NetworkSpec};
+use docker_builder::run_docker::port_check::{
+    check_port_conflicts,
+    published_host_ports};
 use docker_builder::run_docker::run_docker::{
-    build_run_command_from_args,
+    execute_detached_run_command,
     execute_docker_run_command,
+    resolve_run_command,
     RunDockerArgs};
+use docker_builder::run_docker::wait::{
+    wait_for_ready,
+    DEFAULT_WAIT_TIMEOUT_SECS};
+use docker_builder::stack::{stack_down, stack_up, StackArgs};
+use docker_builder::stats::{show_stats, StatsArgs};
+use docker_builder::systemd::{generate_unit, RestartPolicy, SystemdArgs};
 
 #[derive(Parser, Debug)]
 #[command(name = "docker_builder")]
@@ -28,6 +54,11 @@ enum Commands {
 
         #[arg(long)]
         network_host: bool,
+
+        /// Override or add a `vars:` entry in build_configuration.yml;
+        /// repeatable
+        #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_var_override)]
+        vars: Vec<(String, String)>,
     },
 
     /// Run a Docker container
@@ -64,22 +95,216 @@ enum Commands {
         #[arg(long)]
         no_gpu: bool,
 
-        /// Enable GUI support (X11 forwarding)
+        /// Enable GUI support (Wayland socket and/or X11 forwarding)
         #[arg(long)]
         gui: bool,
 
         /// Enable audio support (PulseAudio + ALSA)
         #[arg(long)]
         audio: bool,
+
+        /// Run as this user: uid[:gid], a user name, or `current` for the
+        /// invoking user (overrides `user` in run_configuration.yml)
+        #[arg(long)]
+        user: Option<String>,
+
+        /// IPC namespace, e.g. host (overrides `ipc` in run_configuration.yml)
+        #[arg(long)]
+        ipc: Option<String>,
+
+        /// PID namespace, e.g. host for profiling host processes (overrides
+        /// `pid` in run_configuration.yml)
+        #[arg(long)]
+        pid: Option<String>,
+
+        /// Give the container extended privileges (--privileged)
+        #[arg(long)]
+        privileged: bool,
+
+        /// Bind-mount the current directory into the container and work
+        /// there (default /workspace; overrides `mount_cwd` in
+        /// run_configuration.yml)
+        #[arg(long, value_name = "PATH", num_args = 0..=1,
+              default_missing_value = "/workspace")]
+        mount_cwd: Option<String>,
+
+        /// Container engine: docker or podman (overrides `engine` in
+        /// run_configuration.yml)
+        #[arg(long)]
+        engine: Option<ContainerEngine>,
+
+        /// Pull the image if it is missing locally (registry references only)
+        #[arg(long)]
+        pull: bool,
+
+        /// Profile from the `profiles:` section of run_configuration.yml to
+        /// merge over the base configuration
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Container name; may be a template using {image}, {tag},
+        /// {profile}, {user}, {date}, {time}
+        #[arg(long)]
+        name: Option<String>,
+
+        /// When a container with the name exists: fail, replace (remove the
+        /// old one), or suffix (append -2, -3, ...)
+        #[arg(long)]
+        on_name_collision: Option<NameCollisionPolicy>,
+
+        /// YAML file deep-merged over run_configuration.yml (after
+        /// run_configuration.override.yml); repeatable
+        #[arg(long = "config-overlay", value_name = "PATH")]
+        config_overlays: Vec<PathBuf>,
+
+        /// Override or add a `vars:` entry in the configuration files;
+        /// repeatable
+        #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_var_override)]
+        vars: Vec<(String, String)>,
+
+        /// With --detached, wait until the container is healthy (or its
+        /// published ports accept connections); dump logs on failure
+        #[arg(long, requires = "detached")]
+        wait: bool,
+
+        /// Seconds to wait with --wait
+        #[arg(long, default_value_t = DEFAULT_WAIT_TIMEOUT_SECS)]
+        wait_timeout: u64,
+
+        /// Print the shell-escaped command without executing it
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Print the effective configuration (YAML, overlays, profile,
+        /// variables and CLI flags resolved) without executing
+        #[arg(long, conflicts_with = "dry_run")]
+        show_effective_config: bool,
+
+        /// Write the effective configuration to run_configuration.lock.yml;
+        /// env values read from the host are kept as `${VAR}`
+        #[arg(long, conflicts_with = "show_effective_config")]
+        lock: bool,
+
+        /// Output format for the resolved command: text or json (argv plus
+        /// resolved configuration)
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+
+        /// Raw docker run arguments passed through verbatim before the image,
+        /// e.g. `-- --cap-add SYS_PTRACE`
+        #[arg(last = true, value_name = "DOCKER_ARGS")]
+        extra_args: Vec<String>,
+    },
+
+    /// Open a shell in the running container for a build directory, or in a
+    /// fresh one with the same mounts and GPUs
+    Shell {
+        /// Directory containing build_configuration.yml and
+        /// run_configuration.yml
+        build_dir: PathBuf,
+
+        /// Shell to exec or use as entrypoint
+        #[arg(long, default_value = DEFAULT_SHELL)]
+        shell: String,
+
+        /// Start a fresh container even if one is running
+        #[arg(long)]
+        new: bool,
+
+        /// Container engine: docker or podman
+        #[arg(long)]
+        engine: Option<ContainerEngine>,
+
+        /// Profile from the `profiles:` section of run_configuration.yml
+        #[arg(long)]
+        profile: Option<String>,
+    },
+
+    /// Start the services in stack_configuration.yml in dependency order
+    Up {
+        /// Directory containing stack_configuration.yml
+        dir: PathBuf,
+
+        /// Print the run commands without executing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Stop and remove the services in stack_configuration.yml
+    Down {
+        /// Directory containing stack_configuration.yml
+        dir: PathBuf,
+
+        /// Print the commands without executing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Print a systemd service unit that runs the configured container
+    Systemd {
+        /// Directory containing build_configuration.yml and
+        /// run_configuration.yml
+        build_dir: PathBuf,
+
+        /// Service and container name
+        #[arg(long)]
+        name: String,
+
+        /// Restart policy: no, always, on-failure, or on-abnormal
+        #[arg(long, default_value = "on-failure")]
+        restart: RestartPolicy,
+
+        /// Unit to start first (adds After= and Requires=); repeatable
+        #[arg(long, value_name = "UNIT")]
+        after: Vec<String>,
+
+        /// Profile from the `profiles:` section of run_configuration.yml
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Container engine: docker or podman
+        #[arg(long)]
+        engine: Option<ContainerEngine>,
+    },
+
+    /// Show CPU, memory and GPU usage of running containers started by
+    /// docker_builder
+    Stats {
+        /// Container engine: docker or podman
+        #[arg(long, default_value = "docker")]
+        engine: ContainerEngine,
+
+        /// Refresh every two seconds until interrupted
+        #[arg(long)]
+        watch: bool,
+    },
+
+    /// Tag and push a built image (registry, additional_tags,
+    /// tag_with_git_sha in build_configuration.yml)
+    Push {
+        /// Path to the build directory
+        build_dir: PathBuf,
+    },
+
+    /// Check build_configuration.yml and run_configuration.yml for errors
+    Validate {
+        /// Path to the build directory
+        build_dir: PathBuf,
     },
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
 fn main() -> Result<(), String> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Build { build_dir, no_cache, network_host } => {
-            build_docker_image(build_dir, no_cache, network_host)
+        Commands::Build { build_dir, no_cache, network_host, vars } => {
+            build_docker_image(build_dir, no_cache, network_host, vars)
         }
         Commands::Run {
             build_dir,
@@ -91,19 +316,68 @@ fn main() -> Result<(), String> {
             no_gpu,
             gui,
             audio,
+            user,
+            ipc,
+            pid,
+            privileged,
+            mount_cwd,
+            engine,
+            pull,
+            profile,
+            name,
+            on_name_collision,
+            config_overlays,
+            vars,
+            wait,
+            wait_timeout,
+            dry_run,
+            show_effective_config,
+            lock,
+            output,
+            extra_args,
         } => {
-            let interactive = !no_interactive;
-            run_docker_container(
+            let args = RunDockerArgs {
                 build_dir,
                 gpu_id,
-                interactive,
+                interactive: !no_interactive,
                 detached,
                 entrypoint,
                 network_host,
                 no_gpu,
                 gui,
-                audio)
+                audio,
+                user,
+                ipc,
+                pid,
+                privileged,
+                mount_cwd,
+                engine,
+                pull,
+                profile,
+                name,
+                name_collision: on_name_collision,
+                config_overlays,
+                vars,
+                extra_args,
+            };
+            let wait_timeout = wait.then(|| Duration::from_secs(wait_timeout));
+            if show_effective_config {
+                return show_effective_configuration(&args);
+            }
+            run_docker_container(args, dry_run, lock, output, wait_timeout)
+        }
+        Commands::Shell { build_dir, shell, new, engine, profile } => {
+            open_shell(&ShellArgs { build_dir, shell, fresh: new, engine, profile })
+        }
+        Commands::Up { dir, dry_run } => stack_up(&StackArgs { dir, dry_run }),
+        Commands::Down { dir, dry_run } => stack_down(&StackArgs { dir, dry_run }),
+        Commands::Systemd { build_dir, name, restart, after, profile, engine } => {
+            let args = SystemdArgs { build_dir, name, restart, after, profile, engine };
+            print_systemd_unit(&args)
         }
+        Commands::Stats { engine, watch } => show_stats(&StatsArgs { engine, watch }),
+        Commands::Push { build_dir } => push_docker_image(build_dir),
+        Commands::Validate { build_dir } => validate_configuration(build_dir),
     }
 }
 
@@ -111,6 +385,7 @@ fn build_docker_image(
     build_dir: PathBuf,
     no_cache: bool,
     network_host: bool,
+    vars: Vec<(String, String)>,
 ) -> Result<(), String> {
     use docker_builder::build_docker::build_docker::{
         BuildDockerArgs,
@@ -121,6 +396,7 @@ fn build_docker_image(
         build_dir,
         no_cache,
         network_host,
+        vars,
     };
 
     let image_name = build_docker_image_from_args(&args)?;
@@ -130,39 +406,136 @@ fn build_docker_image(
     Ok(())
 }
 
+fn push_docker_image(build_dir: PathBuf) -> Result<(), String> {
+    use docker_builder::build_docker::push_docker::{
+        PushDockerArgs,
+        push_docker_image_from_args,
+    };
+
+    let references = push_docker_image_from_args(&PushDockerArgs { build_dir })?;
+
+    println!("\n✓ Push complete:");
+    for reference in references {
+        println!("  {}", reference);
+    }
+
+    Ok(())
+}
+
 fn run_docker_container(
-    build_dir: PathBuf,
-    gpu_id: Option<u32>,
-    interactive: bool,
-    detached: bool,
-    entrypoint: Option<String>,
-    network_host: bool,
-    no_gpu: bool,
-    gui: bool,
-    audio: bool,
+    args: RunDockerArgs,
+    dry_run: bool,
+    lock: bool,
+    output: OutputFormat,
+    wait_timeout: Option<Duration>,
 ) -> Result<(), String> {
+    let mut resolved = resolve_run_command(&args)?;
 
-    let args = RunDockerArgs {
-        build_dir: build_dir.clone(),
-        gpu_id,
-        interactive,
-        detached,
-        entrypoint,
-        network_host,
-        no_gpu,
-        gui,
-        audio,
-    };
+    match output {
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(&resolved)
+                .map_err(|e| format!("Failed to serialize command: {}", e))?;
+            println!("{}", json);
+        }
+        OutputFormat::Text if dry_run => {
+            println!("{}", resolved.command_line);
+        }
+        OutputFormat::Text => {
+            println!("\n==> Docker run command:");
+            println!("    {}", resolved.command_line);
+            println!("\n==> Image: {}", resolved.docker_image_name);
+        }
+    }
+
+    if lock {
+        let lock_file = render_lock_file(
+            &effective_configuration(&args, &resolved),
+            &resolved.plan,
+            &host_env_placeholders(&args, &resolved)?)?;
+        let lock_path = write_lock_file(&resolved.build_dir, &lock_file)?;
+        eprintln!("    Effective configuration written to {}", lock_path.display());
+    }
 
-    let (docker_cmd, docker_image_name) = build_run_command_from_args(
-        &args
-    )?;
+    if dry_run {
+        return Ok(());
+    }
+
+    resolved.ensure_image(args.pull)?;
+    resolved.prepare_secrets(args.detached)?;
+
+    // Create missing networks the command actually connects to
+    if let Some(rc) = &resolved.run_configuration
+        && rc.ensure_networks
+    {
+        let networks: Vec<NetworkSpec> = rc.networks
+            .iter()
+            .flatten()
+            .filter(|n| resolved.argv
+                .windows(2)
+                .any(|w| w[0] == "--network" && w[1] == n.name()))
+            .cloned()
+            .collect();
+        ensure_networks(resolved.engine, &networks)?;
+    }
+
+    // Replacing a container may free its ports, so handle names first
+    if let Some(name) = &resolved.container_name {
+        apply_collision_policy(resolved.engine, name, resolved.name_collision)?;
+    }
+    check_port_conflicts(resolved.engine, &resolved.argv)?;
+
+    if let Some(timeout) = wait_timeout {
+        let container_id = execute_detached_run_command(
+            &resolved.argv, &resolved.build_dir)?;
+        let ports = published_host_ports(&resolved.argv);
+        return wait_for_ready(resolved.engine, &container_id, &ports, timeout);
+    }
 
-    println!("\n==> Docker run command:");
-    println!("    {}", docker_cmd.join(" "));
-    println!("\n==> Image: {}", docker_image_name);
+    execute_docker_run_command(&resolved.argv, &resolved.build_dir)?;
 
-    execute_docker_run_command(&docker_cmd, &build_dir)?;
+    Ok(())
+}
+
+fn show_effective_configuration(args: &RunDockerArgs) -> Result<(), String> {
+    let resolved = resolve_run_command(args)?;
+    let lock_file = render_lock_file(
+        &effective_configuration(args, &resolved),
+        &resolved.plan,
+        &host_env_placeholders(args, &resolved)?)?;
+    print!("{}", lock_file);
+    Ok(())
+}
+
+fn print_systemd_unit(args: &SystemdArgs) -> Result<(), String> {
+    let unit = generate_unit(args)?;
+    print!("{}", unit);
+
+    eprintln!("\n==> Install with:");
+    eprintln!(
+        "    docker_builder systemd ... > /etc/systemd/system/{}.service",
+        args.name);
+    eprintln!("    systemctl daemon-reload && systemctl enable --now {}", args.name);
+    Ok(())
+}
+
+fn validate_configuration(build_dir: PathBuf) -> Result<(), String> {
+    use docker_builder::configuration::validation::{
+        validate_build_dir,
+        Severity};
+
+    let issues = validate_build_dir(&build_dir)?;
+    for issue in &issues {
+        println!("{}", issue);
+    }
+
+    let errors = issues
+        .iter()
+        .filter(|issue| issue.severity == Severity::Error)
+        .count();
+    if errors > 0 {
+        return Err(format!("{} error(s) in {}", errors, build_dir.display()));
+    }
 
+    println!("✓ Configuration is valid: {}", build_dir.display());
     Ok(())
 }
@@ -1,2 +1,16 @@
 pub mod build_docker_run_command;
+pub mod container_name;
+pub mod digest;
+pub mod engine;
+pub mod exec_shell;
+pub mod gpu_selection;
+pub mod lock_file;
+pub mod networks;
+pub mod port_check;
+pub mod rootless;
+pub mod run_plan;
+pub mod secrets;
+pub mod shell;
+pub mod wait;
+#[allow(clippy::module_inception)]
 pub mod run_docker;
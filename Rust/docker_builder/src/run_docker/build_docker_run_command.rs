@@ -1,89 +1,181 @@
 //! Build docker run argv from configuration.
 //!
 //! Supports two modes:
-//! 1. YAML-driven: RunConfiguration fields (gpus, shm_size, env, ipc, command)
-//!    from run_configuration.yml (richer docker_runner-style).
+//! 1. YAML-driven: RunConfiguration fields (gpus, shm_size, devices, env,
+//!    env_file, ipc, pid, privileged, user, workdir, mount_cwd, hostname,
+//!    extra_hosts, dns, read_only, init, runtime, command) from
+//!    run_configuration.yml (richer docker_runner-style), plus extra_args
+//!    passed through verbatim.
 //! 2. CLI-driven: BuildDockerRunCommandConfiguration struct (legacy CLI flags).
 //!
-//! CLI flags override YAML where both exist.
+//! CLI flags override YAML where both exist. The argv targets docker unless
+//! the engine is podman (see `engine`).
+//!
+//! Each builder has a `*_plan` variant returning the typed `RunPlan`; the
+//! `Vec<String>` builders are that plan flattened with `to_argv`.
 
 use crate::configuration::run_docker_configuration::{
-    expand_tilde, RunConfiguration, RunDockerConfigurationData,
+    expand_tilde, resolve_user, DeviceMapping, RunConfiguration,
+    RunDockerConfigurationData, VolumeMount,
 };
 use std::path::Path;
 
+use super::container_name::{render_name_template, NameContext};
+use super::engine::ContainerEngine;
+use super::gpu_selection::{
+    memory_fraction_env, resolve_gpus, DEFAULT_GPU_MEMORY_THRESHOLD_MIB};
+use super::run_plan::RunPlan;
+
 //------------------------------------------------------------------------------
 /// Build docker run argv from a richer RunConfiguration (YAML-driven).
-/// Produces: ["docker", "run", ...options..., image, ...command...].
+/// Produces: ["docker", "run", ...options..., image, ...command...]
+/// (or "podman" when `engine: podman`).
 //------------------------------------------------------------------------------
 pub fn build_run_args_from_yaml(
     configuration: &RunConfiguration,
 ) -> Result<Vec<String>, String> {
+    Ok(build_run_plan_from_yaml(configuration)?.to_argv())
+}
+
+//------------------------------------------------------------------------------
+/// `build_run_args_from_yaml` as a typed RunPlan.
+//------------------------------------------------------------------------------
+pub fn build_run_plan_from_yaml(
+    configuration: &RunConfiguration,
+) -> Result<RunPlan, String> {
     if configuration.docker_image_name.trim().is_empty() {
         return Err("Configuration 'docker_image_name' is empty".to_string());
     }
 
-    let mut args = vec!["docker".to_string(), "run".to_string()];
+    let engine = configuration.engine.unwrap_or_default();
+    let mut plan = RunPlan::new(engine, configuration.docker_image_name.trim());
 
-    if let Some(ref g) = configuration.gpus {
-        if !g.is_empty() {
-            args.push("--gpus".to_string());
-            args.push(g.clone());
-        }
+    if let Some(ref g) = configuration.gpus
+        && !g.is_empty()
+    {
+        let spec = resolve_gpus(g, gpu_memory_threshold(configuration))?;
+        plan.flag_pairs(&engine.gpu_args(&spec));
     }
 
-    if let Some(ref s) = configuration.shm_size {
-        if !s.is_empty() {
-            args.push("--shm-size".to_string());
-            args.push(s.clone());
-        }
+    if let Some(ref s) = configuration.shm_size
+        && !s.is_empty()
+    {
+        plan.flag("--shm-size", s.clone());
+    }
+
+    for network in configuration.networks.iter().flatten() {
+        plan.flag("--network", network.name().to_string());
     }
 
     if let Some(ref port_list) = configuration.ports {
         for port_map in port_list {
-            args.push("-p".to_string());
-            args.push(
-                format!(
-                    "{}:{}", port_map.host_port, port_map.container_port));
+            plan.port(port_map.host_port, port_map.container_port);
         }
     }
 
     if let Some(ref vol_list) = configuration.volumes {
         for volume in vol_list {
             let host_exp = expand_tilde(volume.host_path.trim());
-            args.push("-v".to_string());
-            args.push(format!("{}:{}", host_exp, volume.container_path.trim()));
+            plan.mount(host_exp, volume.container_path.trim(), None);
         }
     }
 
+    add_cwd_mount(
+        &mut plan,
+        configuration.mount_cwd.as_deref(),
+        has_workdir(configuration))?;
+
+    for device in configuration.device_mappings()? {
+        plan.flag("--device", device.into_device_mapping());
+    }
+
+    add_env_files(&mut plan, configuration);
+
     if let Some(ref e) = configuration.env {
         for (k, v) in e.clone().into_env_pairs() {
             if !k.is_empty() {
-                args.push("-e".to_string());
-                args.push(format!("{}={}", k, v));
+                plan.env(k, v);
             }
         }
     }
 
-    if let Some(ref i) = configuration.ipc {
-        if !i.is_empty() {
-            args.push("--ipc".to_string());
-            args.push(i.clone());
-        }
+    add_gpu_env(&mut plan, configuration)?;
+
+    if let Some(ref i) = configuration.ipc
+        && !i.is_empty()
+    {
+        plan.flag("--ipc", i.clone());
     }
 
-    args.push(configuration.docker_image_name.trim().to_string());
+    if let Some(ref p) = configuration.pid
+        && !p.is_empty()
+    {
+        plan.flag("--pid", p.clone());
+    }
+
+    if configuration.privileged {
+        plan.switch("--privileged");
+    }
+
+    if let Some(ref u) = configuration.user {
+        plan.flag("--user", resolve_user(u)?);
+    }
+
+    add_container_options(&mut plan, configuration);
+
+    if let Some(ref r) = configuration.runtime
+        && !r.is_empty()
+    {
+        plan.flag("--runtime", r.clone());
+    }
+
+    if let Some(ref template) = configuration.container_name {
+        let context = NameContext::now(&configuration.docker_image_name, None);
+        plan.flag("--name", render_name_template(template, &context)?);
+    }
+
+    add_extra_args(&mut plan, Some(configuration), &[]);
 
     if let Some(ref cmd) = configuration.command {
         let parts = cmd.clone().into_vec();
         for p in parts {
             if !p.is_empty() {
-                args.push(p);
+                plan.command.push(p);
             }
         }
     }
 
-    Ok(args)
+    Ok(plan)
+}
+
+//------------------------------------------------------------------------------
+/// Add NVIDIA_VISIBLE_DEVICES and the gpu_memory_fraction env vars from YAML.
+/// Not used by the no-GPU builder.
+//------------------------------------------------------------------------------
+fn add_gpu_env(
+    plan: &mut RunPlan,
+    yaml_cfg: &RunConfiguration,
+) -> Result<(), String> {
+    let mut env = vec![];
+    if let Some(ref devices) = yaml_cfg.nvidia_visible_devices
+        && !devices.is_empty()
+    {
+        env.push(("NVIDIA_VISIBLE_DEVICES".to_string(), devices.clone()));
+    }
+    if let Some(fraction) = yaml_cfg.gpu_memory_fraction {
+        env.extend(memory_fraction_env(fraction)?);
+    }
+
+    for (k, v) in env {
+        plan.env(k, v);
+    }
+    Ok(())
+}
+
+fn gpu_memory_threshold(configuration: &RunConfiguration) -> u64 {
+    configuration
+        .gpu_memory_threshold_mib
+        .unwrap_or(DEFAULT_GPU_MEMORY_THRESHOLD_MIB)
 }
 
 //------------------------------------------------------------------------------
@@ -119,7 +211,7 @@ pub struct BuildDockerRunCommandConfiguration {
     /// Custom container name (--name)
     pub container_name: Option<String>,
 
-    /// Enable GUI support (X11 forwarding)
+    /// Enable GUI support (Wayland socket and/or X11 forwarding)
     pub enable_gui: bool,
 
     /// Enable audio support (PulseAudio)
@@ -128,6 +220,45 @@ pub struct BuildDockerRunCommandConfiguration {
     /// Additional environment variables
     pub env_vars: Vec<(String, String)>,
 
+    /// Additional devices (--device), appended after those from YAML
+    pub devices: Vec<DeviceMapping>,
+
+    /// User spec (--user): `uid[:gid]`, name, or `current`. Overrides YAML.
+    pub user: Option<String>,
+
+    /// OCI runtime (--runtime). Overrides YAML.
+    pub runtime: Option<String>,
+
+    /// IPC namespace (--ipc), e.g. `host`. Overrides YAML.
+    pub ipc: Option<String>,
+
+    /// PID namespace (--pid), e.g. `host`. Overrides YAML.
+    pub pid: Option<String>,
+
+    /// Run privileged (--privileged); also enabled by YAML `privileged`.
+    pub privileged: bool,
+
+    /// Container labels (--label), as `key=value`
+    pub labels: Vec<String>,
+
+    /// Raw docker run arguments (`run -- ...`), appended after YAML
+    /// `extra_args`, just before the image.
+    pub extra_args: Vec<String>,
+
+    /// Container path to bind-mount the current directory at, also used as
+    /// the workdir unless YAML sets `workdir`. Overrides YAML `mount_cwd`.
+    pub mount_cwd: Option<String>,
+
+    /// Daemon is rootless Docker: `current` user maps to container root and
+    /// GUI/audio sockets are only reachable as container root.
+    pub rootless: bool,
+
+    /// Container engine (binary name, GPU syntax)
+    pub engine: ContainerEngine,
+
+    /// Prepared secret files, mounted read-only (see `secrets`)
+    pub secret_mounts: Vec<VolumeMount>,
+
     /// Richer YAML run configuration (gpus, shm_size, env, ipc, command).
     /// When set, its fields are merged in; CLI args override where both exist.
     pub yaml_run_config: Option<RunConfiguration>,
@@ -149,26 +280,340 @@ impl Default for BuildDockerRunCommandConfiguration {
             enable_gui: false,
             enable_audio: false,
             env_vars: vec![],
+            devices: vec![],
+            user: None,
+            runtime: None,
+            ipc: None,
+            pid: None,
+            privileged: false,
+            labels: vec![],
+            extra_args: vec![],
+            mount_cwd: None,
+            rootless: false,
+            engine: ContainerEngine::Docker,
+            secret_mounts: vec![],
             yaml_run_config: None,
         }
     }
 }
 
+/// XDG_RUNTIME_DIR inside the container; matches where audio mounts PulseAudio.
+const CONTAINER_RUNTIME_DIR: &str = "/run/user/1000";
+
+//------------------------------------------------------------------------------
+/// Add GUI support to docker run command: the Wayland socket when the host
+/// session is Wayland, plus X11 (for XWayland or plain X11 hosts).
+//------------------------------------------------------------------------------
+fn add_gui_support(plan: &mut RunPlan) {
+    add_gui_support_with(
+        plan,
+        |name| std::env::var(name).ok(),
+        |path| path.exists());
+}
+
+//------------------------------------------------------------------------------
+/// `add_gui_support` with injectable env lookup and socket check (for tests).
+///
+/// A Wayland session is detected from WAYLAND_DISPLAY (a socket name under
+/// XDG_RUNTIME_DIR, or an absolute path). Its socket is mounted under the
+/// container's XDG_RUNTIME_DIR. X11 is added unless the session is Wayland
+/// without XWayland (no DISPLAY).
+//------------------------------------------------------------------------------
+fn add_gui_support_with(
+    plan: &mut RunPlan,
+    lookup: impl Fn(&str) -> Option<String>,
+    socket_exists: impl Fn(&Path) -> bool,
+) {
+    let lookup = |name: &str| lookup(name).filter(|value| !value.is_empty());
+
+    let wayland_socket = lookup("WAYLAND_DISPLAY").and_then(|display| {
+        let display = Path::new(&display);
+        let socket = if display.is_absolute() {
+            display.to_path_buf()
+        } else {
+            Path::new(&lookup("XDG_RUNTIME_DIR")?).join(display)
+        };
+        socket_exists(&socket).then_some(socket)
+    });
+
+    if let Some(socket) = &wayland_socket {
+        let name = socket
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        plan.env("XDG_RUNTIME_DIR", CONTAINER_RUNTIME_DIR);
+        plan.env("WAYLAND_DISPLAY", &name);
+        plan.env("XDG_SESSION_TYPE", "wayland");
+        plan.mount(
+            socket.display().to_string(),
+            format!("{}/{}", CONTAINER_RUNTIME_DIR, name),
+            None);
+    }
+
+    let display = lookup("DISPLAY");
+    if wayland_socket.is_some() && display.is_none() {
+        return;
+    }
+
+    let display = display.unwrap_or_else(|| ":0".to_string());
+    plan.env("DISPLAY", display);
+    plan.mount("/tmp/.X11-unix", "/tmp/.X11-unix", Some("rw"));
+}
+
+//------------------------------------------------------------------------------
+/// Collect devices from YAML (if any) followed by builder-supplied devices.
+//------------------------------------------------------------------------------
+fn collect_devices(
+    configuration: &BuildDockerRunCommandConfiguration,
+) -> Result<Vec<DeviceMapping>, String> {
+    let mut devices = match configuration.yaml_run_config {
+        Some(ref yaml_cfg) => yaml_cfg.device_mappings()?,
+        None => vec![],
+    };
+    devices.extend(configuration.devices.iter().cloned());
+    Ok(devices)
+}
+
+//------------------------------------------------------------------------------
+/// Add --workdir, --hostname, --add-host, --dns, --read-only, --init, and
+/// --health-* from YAML.
+//------------------------------------------------------------------------------
+fn add_container_options(plan: &mut RunPlan, yaml_cfg: &RunConfiguration) {
+    if let Some(ref w) = yaml_cfg.workdir
+        && !w.is_empty()
+    {
+        plan.flag("--workdir", w.clone());
+    }
+
+    if let Some(ref h) = yaml_cfg.hostname
+        && !h.is_empty()
+    {
+        plan.flag("--hostname", h.clone());
+    }
+
+    for host in yaml_cfg.extra_hosts.iter().flatten() {
+        plan.flag("--add-host", host.trim().to_string());
+    }
+
+    for server in yaml_cfg.dns.iter().flatten() {
+        plan.flag("--dns", server.trim().to_string());
+    }
+
+    if yaml_cfg.read_only {
+        plan.switch("--read-only");
+    }
+
+    if yaml_cfg.init {
+        plan.switch("--init");
+    }
+
+    if let Some(ref healthcheck) = yaml_cfg.healthcheck {
+        plan.flag_pairs(&healthcheck.to_args());
+    }
+}
+
+//------------------------------------------------------------------------------
+/// Add --env-file for each YAML `env_file` entry. Files come before -e flags
+/// so explicit `env` values take precedence.
+//------------------------------------------------------------------------------
+fn add_env_files(plan: &mut RunPlan, yaml_cfg: &RunConfiguration) {
+    for env_file in yaml_cfg.env_file.iter().flatten() {
+        let env_file = env_file.trim();
+        if !env_file.is_empty() {
+            plan.flag("--env-file", expand_tilde(env_file));
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+/// Add --user from the builder field, falling back to YAML `user`.
+///
+/// Under rootless Docker, container root already is the invoking user on the
+/// host, so `current` maps to `0:0`; any other uid maps to a subordinate uid
+/// that cannot reach the host's X11/PulseAudio sockets, which is warned about.
+/// Rootless Podman keeps the host uid instead, via `--userns keep-id`.
+//------------------------------------------------------------------------------
+fn add_user(
+    plan: &mut RunPlan,
+    configuration: &BuildDockerRunCommandConfiguration,
+) -> Result<(), String> {
+    let user = configuration.user.as_ref().or(configuration
+        .yaml_run_config
+        .as_ref()
+        .and_then(|yaml_cfg| yaml_cfg.user.as_ref()));
+    let Some(user) = user else {
+        return Ok(());
+    };
+
+    let is_current = user.trim() == "current";
+    let resolved = match configuration.engine {
+        ContainerEngine::Docker if configuration.rootless && is_current => {
+            "0:0".to_string()
+        }
+        ContainerEngine::Podman if configuration.rootless && is_current => {
+            plan.flag("--userns", "keep-id".to_string());
+            resolve_user(user)?
+        }
+        _ => resolve_user(user)?,
+    };
+
+    let is_root = resolved == "0" || resolved.starts_with("0:") || resolved == "root";
+    if configuration.rootless
+        && !is_root
+        && !(configuration.engine == ContainerEngine::Podman && is_current)
+        && (configuration.enable_gui || configuration.enable_audio)
+    {
+        eprintln!(
+            "⚠ Warning: rootless Docker maps user '{}' to a subordinate uid; \
+             GUI/audio sockets will likely be inaccessible (use user: current)",
+            resolved);
+    }
+
+    plan.flag("--user", resolved);
+    Ok(())
+}
+
+//------------------------------------------------------------------------------
+/// Add --ipc and --pid from the builder fields, falling back to YAML, and
+/// --privileged when either the builder or YAML asks for it.
+//------------------------------------------------------------------------------
+fn add_namespace_options(
+    plan: &mut RunPlan,
+    configuration: &BuildDockerRunCommandConfiguration,
+) {
+    let yaml_cfg = configuration.yaml_run_config.as_ref();
+
+    let ipc = configuration.ipc.as_ref().or(
+        yaml_cfg.and_then(|yaml_cfg| yaml_cfg.ipc.as_ref()));
+    if let Some(ipc) = ipc
+        && !ipc.is_empty()
+    {
+        plan.flag("--ipc", ipc.clone());
+    }
+
+    let pid = configuration.pid.as_ref().or(
+        yaml_cfg.and_then(|yaml_cfg| yaml_cfg.pid.as_ref()));
+    if let Some(pid) = pid
+        && !pid.is_empty()
+    {
+        plan.flag("--pid", pid.clone());
+    }
+
+    if configuration.privileged || yaml_cfg.is_some_and(|yaml_cfg| yaml_cfg.privileged) {
+        plan.switch("--privileged");
+    }
+}
+
+fn has_workdir(yaml_cfg: &RunConfiguration) -> bool {
+    yaml_cfg.workdir.as_ref().is_some_and(|w| !w.is_empty())
+}
+
+//------------------------------------------------------------------------------
+/// Bind-mount the current directory at `mount_cwd` and make it the workdir
+/// unless `has_workdir` (YAML `workdir` wins).
+//------------------------------------------------------------------------------
+fn add_cwd_mount(
+    plan: &mut RunPlan,
+    mount_cwd: Option<&str>,
+    has_workdir: bool,
+) -> Result<(), String> {
+    if mount_cwd.is_none_or(|target| target.trim().is_empty()) {
+        return Ok(());
+    }
+    let cwd = std::env::current_dir()
+        .map_err(|e| format!("Failed to get current directory for mount_cwd: {}", e))?;
+    add_cwd_mount_with(plan, mount_cwd, has_workdir, &cwd)
+}
+
+//------------------------------------------------------------------------------
+/// `add_cwd_mount` with the host directory given (for tests).
+//------------------------------------------------------------------------------
+fn add_cwd_mount_with(
+    plan: &mut RunPlan,
+    mount_cwd: Option<&str>,
+    has_workdir: bool,
+    cwd: &Path,
+) -> Result<(), String> {
+    let Some(target) = mount_cwd.map(str::trim).filter(|t| !t.is_empty()) else {
+        return Ok(());
+    };
+    if !target.starts_with('/') {
+        return Err(format!("mount_cwd '{}' must be an absolute container path", target));
+    }
+    plan.mount(cwd.display().to_string(), target, None);
+    if !has_workdir {
+        plan.flag("--workdir", target.to_string());
+    }
+    Ok(())
+}
+
+//------------------------------------------------------------------------------
+/// `add_cwd_mount` from the builder field, falling back to YAML `mount_cwd`.
+//------------------------------------------------------------------------------
+fn add_builder_cwd_mount(
+    plan: &mut RunPlan,
+    configuration: &BuildDockerRunCommandConfiguration,
+) -> Result<(), String> {
+    let yaml_cfg = configuration.yaml_run_config.as_ref();
+    let mount_cwd = configuration.mount_cwd.as_deref().or(
+        yaml_cfg.and_then(|yaml_cfg| yaml_cfg.mount_cwd.as_deref()));
+    add_cwd_mount(plan, mount_cwd, yaml_cfg.is_some_and(has_workdir))
+}
+
+//------------------------------------------------------------------------------
+/// Append YAML `extra_args` then `cli_args` verbatim (they go just before the
+/// image, so they can't be mistaken for the container command).
+//------------------------------------------------------------------------------
+fn add_extra_args(
+    plan: &mut RunPlan,
+    yaml_cfg: Option<&RunConfiguration>,
+    cli_args: &[String],
+) {
+    let yaml_args = yaml_cfg.and_then(|yaml_cfg| yaml_cfg.extra_args.as_ref());
+    plan.raw(yaml_args.into_iter().flatten().chain(cli_args).cloned().collect());
+}
+
+//------------------------------------------------------------------------------
+/// Add --runtime from the builder field, falling back to YAML `runtime`.
+//------------------------------------------------------------------------------
+fn add_runtime(
+    plan: &mut RunPlan,
+    configuration: &BuildDockerRunCommandConfiguration,
+) {
+    let runtime = configuration.runtime.as_ref().or(configuration
+        .yaml_run_config
+        .as_ref()
+        .and_then(|yaml_cfg| yaml_cfg.runtime.as_ref()));
+    if let Some(runtime) = runtime
+        && !runtime.is_empty()
+    {
+        plan.flag("--runtime", runtime.clone());
+    }
+}
+
+//------------------------------------------------------------------------------
+/// Add a read-only -v for each prepared secret.
+//------------------------------------------------------------------------------
+fn add_secret_mounts(plan: &mut RunPlan, mounts: &[VolumeMount]) {
+    for mount in mounts {
+        plan.mount(&mount.host_path, &mount.container_path, Some("ro"));
+    }
+}
+
 //------------------------------------------------------------------------------
-/// Add X11 GUI support to docker run command.
+/// Add --device flags for each device mapping.
 //------------------------------------------------------------------------------
-fn add_gui_support(cmd: &mut Vec<String>) {
-    let display = std::env::var("DISPLAY").unwrap_or_else(|_| ":0".to_string());
-    cmd.push("-e".to_string());
-    cmd.push(format!("DISPLAY={}", display));
-    cmd.push("-v".to_string());
-    cmd.push("/tmp/.X11-unix:/tmp/.X11-unix:rw".to_string());
+fn add_devices(plan: &mut RunPlan, devices: &[DeviceMapping]) {
+    for device in devices {
+        plan.flag("--device", device.clone().into_device_mapping());
+    }
 }
 
 //------------------------------------------------------------------------------
 /// Add audio support (PulseAudio) to docker run command.
+/// `devices` are the already-mapped devices; /dev/snd is only added if absent.
 //------------------------------------------------------------------------------
-fn add_audio_support(cmd: &mut Vec<String>) {
+fn add_audio_support(plan: &mut RunPlan, devices: &[DeviceMapping]) {
     #[cfg(unix)]
     let user_id = {
         use nix::unistd::getuid;
@@ -183,14 +628,11 @@ fn add_audio_support(cmd: &mut Vec<String>) {
 
     // Check if PulseAudio socket exists
     if Path::new(&pulse_native).exists() {
-        cmd.push("-v".to_string());
-        cmd.push(format!("{}:/run/user/1000/pulse:ro", pulse_socket));
+        plan.mount(&pulse_socket, "/run/user/1000/pulse", Some("ro"));
 
-        cmd.push("-e".to_string());
-        cmd.push("PULSE_SERVER=unix:/run/user/1000/pulse/native".to_string());
+        plan.env("PULSE_SERVER", "unix:/run/user/1000/pulse/native");
 
-        cmd.push("-e".to_string());
-        cmd.push("PULSE_RUNTIME_PATH=/run/user/1000/pulse".to_string());
+        plan.env("PULSE_RUNTIME_PATH", "/run/user/1000/pulse");
 
         let cookie_paths = [
             format!(
@@ -203,10 +645,7 @@ fn add_audio_support(cmd: &mut Vec<String>) {
         let mut cookie_mounted = false;
         for cookie_path in &cookie_paths {
             if std::path::Path::new(cookie_path).exists() {
-                cmd.push("-v".to_string());
-                cmd.push(format!(
-                    "{}:/run/user/1000/pulse-cookie:ro",
-                    cookie_path));
+                plan.mount(cookie_path, "/run/user/1000/pulse-cookie", Some("ro"));
                 cookie_mounted = true;
                 break;
             }
@@ -215,261 +654,280 @@ fn add_audio_support(cmd: &mut Vec<String>) {
         if !cookie_mounted {
             let pulse_cookie_in_socket = format!("{}/cookie", pulse_socket);
             if std::path::Path::new(&pulse_cookie_in_socket).exists() {
-                cmd.push("-v".to_string());
-                cmd.push(format!(
-                    "{}:/run/user/1000/pulse-cookie:ro",
-                    pulse_cookie_in_socket));
+                plan.mount(
+                    pulse_cookie_in_socket, "/run/user/1000/pulse-cookie", Some("ro"));
             }
         }
     }
 
-    // Always add ALSA device as fallback
-    cmd.push("--device".to_string());
-    cmd.push("/dev/snd".to_string());
+    // Add ALSA device as fallback unless it is already mapped
+    if !devices.iter().any(|d| d.host_path == "/dev/snd") {
+        plan.flag("--device", "/dev/snd".to_string());
+    }
 }
 
 pub fn build_docker_run_command(
     configuration: &BuildDockerRunCommandConfiguration,
 ) -> Result<Vec<String>, String> {
+    Ok(build_docker_run_plan(configuration)?.to_argv())
+}
+
+//------------------------------------------------------------------------------
+/// `build_docker_run_command` as a typed RunPlan.
+//------------------------------------------------------------------------------
+pub fn build_docker_run_plan(
+    configuration: &BuildDockerRunCommandConfiguration,
+) -> Result<RunPlan, String> {
     if configuration.docker_image_name.is_empty() {
         return Err("Docker image name is empty".to_string());
     }
 
-    let mut docker_run_cmd = vec!["docker".to_string(), "run".to_string()];
+    let engine = configuration.engine;
+    let mut plan = RunPlan::new(engine, &configuration.docker_image_name);
 
     // --- YAML-sourced fields (gpus, shm_size, ipc from yaml_run_config) ---
     // CLI gpu_id overrides YAML gpus when set.
     if let Some(ref yaml_cfg) = configuration.yaml_run_config {
         // gpus: CLI gpu_id takes precedence
-        if configuration.gpu_id.is_none() {
-            if let Some(ref g) = yaml_cfg.gpus {
-                if !g.is_empty() {
-                    docker_run_cmd.push("--gpus".to_string());
-                    docker_run_cmd.push(g.clone());
-                }
-            }
+        if configuration.gpu_id.is_none()
+            && let Some(ref g) = yaml_cfg.gpus
+            && !g.is_empty()
+        {
+            let spec = resolve_gpus(g, gpu_memory_threshold(yaml_cfg))?;
+            plan.flag_pairs(&engine.gpu_args(&spec));
         }
 
-        if let Some(ref s) = yaml_cfg.shm_size {
-            if !s.is_empty() {
-                docker_run_cmd.push("--shm-size".to_string());
-                docker_run_cmd.push(s.clone());
-            }
+        if let Some(ref s) = yaml_cfg.shm_size
+            && !s.is_empty()
+        {
+            plan.flag("--shm-size", s.clone());
         }
     }
 
     // CLI GPU support (overrides YAML)
     if let Some(gpu) = configuration.gpu_id {
-        docker_run_cmd.push("--gpus".to_string());
-        docker_run_cmd.push(format!("device={}", gpu));
+        plan.flag_pairs(&engine.gpu_args(&format!("device={}", gpu)));
     }
 
     if configuration.is_detached {
-        docker_run_cmd.push("-d".to_string());
+        plan.switch("-d");
     } else {
-        docker_run_cmd.push("--rm".to_string());
+        plan.switch("--rm");
     }
 
     if configuration.is_interactive {
-        docker_run_cmd.push("-it".to_string());
+        plan.switch("-it");
     }
 
     if configuration.use_host_network {
-        docker_run_cmd.push("--network".to_string());
-        docker_run_cmd.push("host".to_string());
+        plan.flag("--network", "host".to_string());
     }
 
     for network in &configuration.networks {
-        docker_run_cmd.push("--network".to_string());
-        docker_run_cmd.push(network.clone());
+        plan.flag("--network", network.clone());
     }
 
     // Ports: from legacy run_config
     for port_map in &configuration.run_config.ports {
-        docker_run_cmd.push("-p".to_string());
-        docker_run_cmd.push(
-            format!("{}:{}",
-            port_map.host_port,
-            port_map.container_port));
+        plan.port(port_map.host_port, port_map.container_port);
     }
     // Ports: from YAML run config (if set and not already in legacy)
-    if let Some(ref yaml_cfg) = configuration.yaml_run_config {
-        if configuration.run_config.ports.is_empty() {
-            if let Some(ref port_list) = yaml_cfg.ports {
-                for port_map in port_list {
-                    docker_run_cmd.push("-p".to_string());
-                    docker_run_cmd.push(
-                        format!("{}:{}", port_map.host_port, port_map.container_port));
-                }
-            }
+    if let Some(ref yaml_cfg) = configuration.yaml_run_config
+        && configuration.run_config.ports.is_empty()
+        && let Some(ref port_list) = yaml_cfg.ports
+    {
+        for port_map in port_list {
+            plan.port(port_map.host_port, port_map.container_port);
         }
     }
 
     // Volumes: from legacy run_config
     for volume in &configuration.run_config.volumes {
-        docker_run_cmd.push("-v".to_string());
-        docker_run_cmd.push(
-            format!("{}:{}",
-            volume.host_path,
-            volume.container_path));
+        plan.mount(&volume.host_path, &volume.container_path, None);
     }
     // Volumes: from YAML run config (if set and not already in legacy)
-    if let Some(ref yaml_cfg) = configuration.yaml_run_config {
-        if configuration.run_config.volumes.is_empty() {
-            if let Some(ref vol_list) = yaml_cfg.volumes {
-                for volume in vol_list {
-                    let host_exp = expand_tilde(volume.host_path.trim());
-                    docker_run_cmd.push("-v".to_string());
-                    docker_run_cmd.push(
-                        format!("{}:{}", host_exp, volume.container_path.trim()));
-                }
-            }
+    if let Some(ref yaml_cfg) = configuration.yaml_run_config
+        && configuration.run_config.volumes.is_empty()
+        && let Some(ref vol_list) = yaml_cfg.volumes
+    {
+        for volume in vol_list {
+            let host_exp = expand_tilde(volume.host_path.trim());
+            plan.mount(host_exp, volume.container_path.trim(), None);
         }
     }
 
+    add_builder_cwd_mount(&mut plan, configuration)?;
+    add_secret_mounts(&mut plan, &configuration.secret_mounts);
+
+    let devices = collect_devices(configuration)?;
+    add_devices(&mut plan, &devices);
+
     if configuration.enable_gui {
-        add_gui_support(&mut docker_run_cmd);
+        add_gui_support(&mut plan);
     }
     if configuration.enable_audio {
-        add_audio_support(&mut docker_run_cmd);
+        add_audio_support(&mut plan, &devices);
     }
 
-    // Env vars from YAML
+    // Env files and env vars from YAML
     if let Some(ref yaml_cfg) = configuration.yaml_run_config {
+        add_env_files(&mut plan, yaml_cfg);
+
         if let Some(ref e) = yaml_cfg.env {
             for (k, v) in e.clone().into_env_pairs() {
                 if !k.is_empty() {
-                    docker_run_cmd.push("-e".to_string());
-                    docker_run_cmd.push(format!("{}={}", k, v));
+                    plan.env(k, v);
                 }
             }
         }
+
+        add_gpu_env(&mut plan, yaml_cfg)?;
     }
 
     // Env vars from CLI
     for (key, value) in &configuration.env_vars {
-        docker_run_cmd.push("-e".to_string());
-        docker_run_cmd.push(format!("{}={}", key, value));
+        plan.env(key.clone(), value.clone());
     }
 
-    // IPC: YAML value (CLI doesn't have a dedicated ipc flag in the old builder)
+    add_namespace_options(&mut plan, configuration);
+    add_user(&mut plan, configuration)?;
+    add_runtime(&mut plan, configuration);
+
     if let Some(ref yaml_cfg) = configuration.yaml_run_config {
-        if let Some(ref i) = yaml_cfg.ipc {
-            if !i.is_empty() {
-                docker_run_cmd.push("--ipc".to_string());
-                docker_run_cmd.push(i.clone());
-            }
-        }
+        add_container_options(&mut plan, yaml_cfg);
     }
 
     if let Some(name) = &configuration.container_name {
-        docker_run_cmd.push("--name".to_string());
-        docker_run_cmd.push(name.clone());
+        plan.flag("--name", name.clone());
     }
 
     if let Some(entrypoint) = &configuration.entrypoint {
-        docker_run_cmd.push("--entrypoint".to_string());
-        docker_run_cmd.push(entrypoint.clone());
+        plan.flag("--entrypoint", entrypoint.clone());
     }
 
-    // Add image
-    docker_run_cmd.push(configuration.docker_image_name.to_string());
+    for label in &configuration.labels {
+        plan.flag("--label", label.clone());
+    }
+
+    add_extra_args(
+        &mut plan,
+        configuration.yaml_run_config.as_ref(),
+        &configuration.extra_args);
 
     // Command from YAML (after image)
-    if let Some(ref yaml_cfg) = configuration.yaml_run_config {
-        if let Some(ref cmd) = yaml_cfg.command {
-            let parts = cmd.clone().into_vec();
-            for p in parts {
-                if !p.is_empty() {
-                    docker_run_cmd.push(p);
-                }
+    if let Some(ref yaml_cfg) = configuration.yaml_run_config
+        && let Some(ref cmd) = yaml_cfg.command
+    {
+        let parts = cmd.clone().into_vec();
+        for p in parts {
+            if !p.is_empty() {
+                plan.command.push(p);
             }
         }
     }
 
-    Ok(docker_run_cmd)
+    Ok(plan)
 }
 
 pub fn build_docker_run_command_with_no_gpu(
     configuration: &BuildDockerRunCommandConfiguration,
 ) -> Result<Vec<String>, String> {
+    Ok(build_docker_run_plan_with_no_gpu(configuration)?.to_argv())
+}
+
+//------------------------------------------------------------------------------
+/// `build_docker_run_command_with_no_gpu` as a typed RunPlan.
+//------------------------------------------------------------------------------
+pub fn build_docker_run_plan_with_no_gpu(
+    configuration: &BuildDockerRunCommandConfiguration,
+) -> Result<RunPlan, String> {
     if configuration.docker_image_name.is_empty() {
         return Err("Docker image name is empty".to_string());
     }
 
-    let mut docker_run_cmd = vec!["docker".to_string(), "run".to_string()];
+    let mut plan = RunPlan::new(configuration.engine, &configuration.docker_image_name);
 
     // shm_size from YAML
-    if let Some(ref yaml_cfg) = configuration.yaml_run_config {
-        if let Some(ref s) = yaml_cfg.shm_size {
-            if !s.is_empty() {
-                docker_run_cmd.push("--shm-size".to_string());
-                docker_run_cmd.push(s.clone());
-            }
-        }
+    if let Some(ref yaml_cfg) = configuration.yaml_run_config
+        && let Some(ref s) = yaml_cfg.shm_size
+        && !s.is_empty()
+    {
+        plan.flag("--shm-size", s.clone());
     }
 
     if configuration.is_detached {
-        docker_run_cmd.push("-d".to_string());
+        plan.switch("-d");
     } else {
-        docker_run_cmd.push("--rm".to_string());
+        plan.switch("--rm");
     }
 
     if configuration.is_interactive {
-        docker_run_cmd.push("-it".to_string());
+        plan.switch("-it");
     }
 
     if configuration.use_host_network {
-        docker_run_cmd.push("--network".to_string());
-        docker_run_cmd.push("host".to_string());
+        plan.flag("--network", "host".to_string());
     }
 
     for network in &configuration.networks {
-        docker_run_cmd.push("--network".to_string());
-        docker_run_cmd.push(network.clone());
+        plan.flag("--network", network.clone());
     }
 
     for port_map in &configuration.run_config.ports {
-        docker_run_cmd.push("-p".to_string());
-        docker_run_cmd.push(
-            format!("{}:{}",
-            port_map.host_port,
-            port_map.container_port));
+        plan.port(port_map.host_port, port_map.container_port);
     }
 
     for volume in &configuration.run_config.volumes {
-        docker_run_cmd.push("-v".to_string());
-        docker_run_cmd.push(
-            format!("{}:{}",
-            volume.host_path,
-            volume.container_path));
+        plan.mount(&volume.host_path, &volume.container_path, None);
     }
 
+    add_builder_cwd_mount(&mut plan, configuration)?;
+    add_secret_mounts(&mut plan, &configuration.secret_mounts);
+
+    let devices = collect_devices(configuration)?;
+    add_devices(&mut plan, &devices);
+
     if configuration.enable_gui {
-        add_gui_support(&mut docker_run_cmd);
+        add_gui_support(&mut plan);
     }
     if configuration.enable_audio {
-        add_audio_support(&mut docker_run_cmd);
+        add_audio_support(&mut plan, &devices);
+    }
+
+    if let Some(ref yaml_cfg) = configuration.yaml_run_config {
+        add_env_files(&mut plan, yaml_cfg);
     }
 
     for (key, value) in &configuration.env_vars {
-        docker_run_cmd.push("-e".to_string());
-        docker_run_cmd.push(format!("{}={}", key, value));
+        plan.env(key.clone(), value.clone());
+    }
+
+    add_namespace_options(&mut plan, configuration);
+    add_user(&mut plan, configuration)?;
+    add_runtime(&mut plan, configuration);
+
+    if let Some(ref yaml_cfg) = configuration.yaml_run_config {
+        add_container_options(&mut plan, yaml_cfg);
     }
 
     if let Some(name) = &configuration.container_name {
-        docker_run_cmd.push("--name".to_string());
-        docker_run_cmd.push(name.clone());
+        plan.flag("--name", name.clone());
     }
 
     if let Some(entrypoint) = &configuration.entrypoint {
-        docker_run_cmd.push("--entrypoint".to_string());
-        docker_run_cmd.push(entrypoint.clone());
+        plan.flag("--entrypoint", entrypoint.clone());
     }
 
-    docker_run_cmd.push(configuration.docker_image_name.to_string());
+    for label in &configuration.labels {
+        plan.flag("--label", label.clone());
+    }
+
+    add_extra_args(
+        &mut plan,
+        configuration.yaml_run_config.as_ref(),
+        &configuration.extra_args);
 
-    Ok(docker_run_cmd)
+    Ok(plan)
 }
 
 #[cfg(test)]
@@ -477,6 +935,7 @@ mod tests {
     use super::*;
     use crate::configuration::run_docker_configuration::{
         PortMapping, VolumeMount};
+    use std::collections::HashMap;
 
     #[test]
     fn test_build_docker_run_command_with_gpu() {
@@ -542,6 +1001,190 @@ mod tests {
         assert_eq!(cmd.last().unwrap(), "test-no-gpu:latest");
     }
 
+    #[test]
+    fn test_build_docker_run_command_with_devices() {
+        let yaml_run_config = RunConfiguration {
+            docker_image_name: "cam:latest".to_string(),
+            devices: Some(vec![
+                "/dev/video0".to_string(),
+                "/dev/snd:/dev/snd:rw".to_string(),
+            ]),
+            ..Default::default()
+        };
+
+        let config = BuildDockerRunCommandConfiguration {
+            docker_image_name: "cam:latest".to_string(),
+            yaml_run_config: Some(yaml_run_config),
+            devices: vec![DeviceMapping::parse("/dev/fuse:rwm").unwrap()],
+            enable_audio: true,
+            ..Default::default()
+        };
+
+        for cmd in [
+            build_docker_run_command(&config).unwrap(),
+            build_docker_run_command_with_no_gpu(&config).unwrap(),
+        ] {
+            let devices: Vec<&String> = cmd
+                .windows(2)
+                .filter(|w| w[0] == "--device")
+                .map(|w| &w[1])
+                .collect();
+            assert_eq!(
+                devices,
+                vec![
+                    "/dev/video0:/dev/video0",
+                    "/dev/snd:/dev/snd:rw",
+                    "/dev/fuse:/dev/fuse:rwm",
+                ]);
+        }
+
+        let bad = BuildDockerRunCommandConfiguration {
+            docker_image_name: "cam:latest".to_string(),
+            yaml_run_config: Some(RunConfiguration {
+                docker_image_name: "cam:latest".to_string(),
+                devices: Some(vec!["video0".to_string()]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(build_docker_run_command(&bad).is_err());
+    }
+
+    #[test]
+    fn test_build_docker_run_command_with_user() {
+        let mut config = BuildDockerRunCommandConfiguration {
+            docker_image_name: "dev:latest".to_string(),
+            yaml_run_config: Some(RunConfiguration {
+                docker_image_name: "dev:latest".to_string(),
+                user: Some("1000:1000".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let cmd = build_docker_run_command(&config).unwrap();
+        let idx = cmd.iter().position(|s| s == "--user").unwrap();
+        assert_eq!(cmd[idx + 1], "1000:1000");
+
+        // Builder value overrides YAML; `current` resolves to uid:gid.
+        config.user = Some("current".to_string());
+        let cmd = build_docker_run_command_with_no_gpu(&config).unwrap();
+        let idx = cmd.iter().position(|s| s == "--user").unwrap();
+        assert_eq!(
+            cmd[idx + 1],
+            format!(
+                "{}:{}",
+                nix::unistd::getuid().as_raw(),
+                nix::unistd::getgid().as_raw()));
+        assert_eq!(cmd.iter().filter(|s| *s == "--user").count(), 1);
+    }
+
+    #[test]
+    fn test_build_docker_run_command_with_container_options() {
+        let yaml_run_config = RunConfiguration {
+            docker_image_name: "svc:latest".to_string(),
+            workdir: Some("/workspace".to_string()),
+            hostname: Some("svc".to_string()),
+            extra_hosts: Some(vec!["db:10.0.0.2".to_string()]),
+            dns: Some(vec!["1.1.1.1".to_string(), "8.8.8.8".to_string()]),
+            read_only: true,
+            init: true,
+            env_file: Some(vec!["svc.env".to_string()]),
+            ..Default::default()
+        };
+
+        let args = build_run_args_from_yaml(&yaml_run_config).unwrap();
+        let config = BuildDockerRunCommandConfiguration {
+            docker_image_name: "svc:latest".to_string(),
+            yaml_run_config: Some(yaml_run_config),
+            ..Default::default()
+        };
+
+        for cmd in [
+            args,
+            build_docker_run_command(&config).unwrap(),
+            build_docker_run_command_with_no_gpu(&config).unwrap(),
+        ] {
+            let joined = cmd.join(" ");
+            assert!(joined.contains("--workdir /workspace"));
+            assert!(joined.contains("--hostname svc"));
+            assert!(joined.contains("--add-host db:10.0.0.2"));
+            assert!(joined.contains("--dns 1.1.1.1 --dns 8.8.8.8"));
+            assert!(cmd.contains(&"--read-only".to_string()));
+            assert!(cmd.contains(&"--init".to_string()));
+            assert!(joined.contains("--env-file svc.env"));
+            assert_eq!(cmd.last().unwrap(), "svc:latest");
+        }
+    }
+
+    #[test]
+    fn test_build_docker_run_command_runtime_and_rootless() {
+        let mut config = BuildDockerRunCommandConfiguration {
+            docker_image_name: "gpu:latest".to_string(),
+            yaml_run_config: Some(RunConfiguration {
+                docker_image_name: "gpu:latest".to_string(),
+                runtime: Some("nvidia".to_string()),
+                ..Default::default()
+            }),
+            user: Some("current".to_string()),
+            rootless: true,
+            ..Default::default()
+        };
+
+        let cmd = build_docker_run_command(&config).unwrap();
+        assert!(cmd.join(" ").contains("--runtime nvidia"));
+        assert!(cmd.join(" ").contains("--user 0:0"));
+
+        config.runtime = Some("runc".to_string());
+        config.rootless = false;
+        let cmd = build_docker_run_command_with_no_gpu(&config).unwrap();
+        assert!(cmd.join(" ").contains("--runtime runc"));
+        assert!(cmd.join(" ").contains(&format!(
+            "--user {}:{}",
+            nix::unistd::getuid().as_raw(),
+            nix::unistd::getgid().as_raw())));
+        assert_eq!(cmd.iter().filter(|s| *s == "--runtime").count(), 1);
+    }
+
+    #[test]
+    fn test_build_docker_run_command_with_podman() {
+        let mut config = BuildDockerRunCommandConfiguration {
+            docker_image_name: "gpu:latest".to_string(),
+            yaml_run_config: Some(RunConfiguration {
+                docker_image_name: "gpu:latest".to_string(),
+                gpus: Some("\"device=0,1\"".to_string()),
+                ..Default::default()
+            }),
+            user: Some("current".to_string()),
+            rootless: true,
+            engine: ContainerEngine::Podman,
+            ..Default::default()
+        };
+
+        let cmd = build_docker_run_command(&config).unwrap();
+        assert_eq!(cmd[0], "podman");
+        assert!(!cmd.contains(&"--gpus".to_string()));
+        let joined = cmd.join(" ");
+        assert!(joined.contains(
+            "--device nvidia.com/gpu=0 --device nvidia.com/gpu=1"));
+        assert!(joined.contains("--userns keep-id --user "));
+
+        config.gpu_id = Some(3);
+        let cmd = build_docker_run_command(&config).unwrap();
+        assert!(cmd.join(" ").contains("--device nvidia.com/gpu=3"));
+        assert!(!cmd.join(" ").contains("nvidia.com/gpu=0"));
+
+        let yaml_cfg = RunConfiguration {
+            docker_image_name: "gpu:latest".to_string(),
+            gpus: Some("all".to_string()),
+            engine: Some(ContainerEngine::Podman),
+            ..Default::default()
+        };
+        let args = build_run_args_from_yaml(&yaml_cfg).unwrap();
+        assert_eq!(args[0], "podman");
+        assert!(args.join(" ").contains("--device nvidia.com/gpu=all"));
+    }
+
     #[test]
     fn test_build_run_args_from_yaml_full_config() {
         use crate::configuration::run_docker_configuration::{
@@ -575,6 +1218,7 @@ mod tests {
                 "--port".to_string(),
                 "30000".to_string(),
             ])),
+            ..Default::default()
         };
 
         let args = build_run_args_from_yaml(&config).expect(
@@ -601,4 +1245,160 @@ mod tests {
         assert!(args.contains(&"/models".to_string()));
         assert!(args.contains(&"30000".to_string()));
     }
+
+    #[test]
+    fn test_build_run_args_with_mig_and_memory_fraction() {
+        let config: RunConfiguration = serde_yaml::from_str(
+            "docker_image_name: embedder:latest\n\
+             gpus: [\"0:1\"]\n\
+             nvidia_visible_devices: [\"MIG-4f8e3a53\", \"MIG-9a1b2c3d\"]\n\
+             gpu_memory_fraction: 0.25\n").unwrap();
+
+        let joined = build_run_args_from_yaml(&config).unwrap().join(" ");
+        assert!(joined.contains("--gpus device=0:1"), "{}", joined);
+        assert!(joined.contains(
+            "-e NVIDIA_VISIBLE_DEVICES=MIG-4f8e3a53,MIG-9a1b2c3d"), "{}", joined);
+        assert!(joined.contains("-e GPU_MEMORY_FRACTION=0.25"), "{}", joined);
+        assert!(joined.contains("-e XLA_PYTHON_CLIENT_MEM_FRACTION=0.25"), "{}", joined);
+
+        let config = RunConfiguration {
+            gpu_memory_fraction: Some(2.0),
+            ..config
+        };
+        assert!(build_run_args_from_yaml(&config).is_err());
+    }
+
+    #[test]
+    fn test_gui_support_wayland_and_x11_fallback() {
+        let gui_args = |vars: &[(&str, &str)]| {
+            let vars: HashMap<String, String> = vars
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            let mut plan = RunPlan::new(ContainerEngine::Docker, "img");
+            add_gui_support_with(
+                &mut plan,
+                |name| vars.get(name).cloned(),
+                |path| path.starts_with("/run/user/1000"));
+            plan.option_args().join(" ")
+        };
+
+        // X11 only
+        assert_eq!(
+            gui_args(&[("DISPLAY", ":1")]),
+            "-e DISPLAY=:1 -v /tmp/.X11-unix:/tmp/.X11-unix:rw");
+
+        // Wayland without XWayland
+        assert_eq!(
+            gui_args(&[
+                ("WAYLAND_DISPLAY", "wayland-0"),
+                ("XDG_RUNTIME_DIR", "/run/user/1000"),
+            ]),
+            "-e XDG_RUNTIME_DIR=/run/user/1000 -e WAYLAND_DISPLAY=wayland-0 \
+             -e XDG_SESSION_TYPE=wayland \
+             -v /run/user/1000/wayland-0:/run/user/1000/wayland-0");
+
+        // Wayland with XWayland also gets X11
+        let args = gui_args(&[
+            ("WAYLAND_DISPLAY", "/run/user/1000/wayland-1"),
+            ("DISPLAY", ":0"),
+        ]);
+        assert!(args.contains("WAYLAND_DISPLAY=wayland-1"), "{}", args);
+        assert!(args.ends_with("-e DISPLAY=:0 -v /tmp/.X11-unix:/tmp/.X11-unix:rw"));
+
+        // Socket missing: fall back to X11
+        assert_eq!(
+            gui_args(&[("WAYLAND_DISPLAY", "wayland-0"), ("XDG_RUNTIME_DIR", "/tmp")]),
+            "-e DISPLAY=:0 -v /tmp/.X11-unix:/tmp/.X11-unix:rw");
+    }
+
+    #[test]
+    fn test_namespace_options_cli_overrides_yaml() {
+        let mut config = BuildDockerRunCommandConfiguration {
+            docker_image_name: "profiler:latest".to_string(),
+            yaml_run_config: Some(RunConfiguration {
+                docker_image_name: "profiler:latest".to_string(),
+                ipc: Some("private".to_string()),
+                pid: Some("host".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let joined = build_docker_run_command_with_no_gpu(&config).unwrap().join(" ");
+        assert!(joined.contains("--ipc private --pid host"), "{}", joined);
+        assert!(!joined.contains("--privileged"));
+
+        config.ipc = Some("host".to_string());
+        config.privileged = true;
+        let joined = build_docker_run_command(&config).unwrap().join(" ");
+        assert!(joined.contains("--ipc host --pid host --privileged"), "{}", joined);
+
+        let yaml: RunConfiguration = serde_yaml::from_str(
+            "docker_image_name: x\npid: host\nprivileged: true\n").unwrap();
+        let joined = build_run_args_from_yaml(&yaml).unwrap().join(" ");
+        assert!(joined.contains("--pid host --privileged"), "{}", joined);
+    }
+
+    #[test]
+    fn test_mount_cwd() {
+        let cwd = Path::new("/home/me/project");
+        let plan = || RunPlan::new(ContainerEngine::Docker, "img");
+        let mut cmd = plan();
+        add_cwd_mount_with(&mut cmd, Some("/workspace"), false, cwd).unwrap();
+        assert_eq!(
+            cmd.option_args().join(" "),
+            "-v /home/me/project:/workspace --workdir /workspace");
+
+        let mut cmd = plan();
+        add_cwd_mount_with(&mut cmd, Some("/src"), true, cwd).unwrap();
+        assert_eq!(cmd.option_args().join(" "), "-v /home/me/project:/src");
+
+        assert!(add_cwd_mount_with(&mut plan(), Some("workspace"), false, cwd).is_err());
+        let mut cmd = plan();
+        add_cwd_mount_with(&mut cmd, None, false, cwd).unwrap();
+        assert!(cmd.options.is_empty());
+
+        let config = BuildDockerRunCommandConfiguration {
+            docker_image_name: "dev:latest".to_string(),
+            mount_cwd: Some("/workspace".to_string()),
+            yaml_run_config: Some(RunConfiguration {
+                docker_image_name: "dev:latest".to_string(),
+                mount_cwd: Some("/ignored".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let cwd = std::env::current_dir().unwrap();
+        let joined = build_docker_run_command_with_no_gpu(&config).unwrap().join(" ");
+        assert!(joined.contains(&format!(
+            "-v {}:/workspace --workdir /workspace", cwd.display())), "{}", joined);
+        assert!(!joined.contains("/ignored"));
+    }
+
+    #[test]
+    fn test_extra_args_go_before_image() {
+        let yaml: RunConfiguration = serde_yaml::from_str(r#"
+docker_image_name: dbg:latest
+extra_args: ["--cap-add", "SYS_PTRACE"]
+command: [gdb]
+"#).unwrap();
+        let joined = build_run_args_from_yaml(&yaml).unwrap().join(" ");
+        assert!(joined.ends_with("--cap-add SYS_PTRACE dbg:latest gdb"), "{}", joined);
+
+        let config = BuildDockerRunCommandConfiguration {
+            docker_image_name: "dbg:latest".to_string(),
+            extra_args: vec!["--security-opt".to_string(), "seccomp=unconfined".to_string()],
+            yaml_run_config: Some(yaml),
+            ..Default::default()
+        };
+        let joined = build_docker_run_command(&config).unwrap().join(" ");
+        assert!(joined.ends_with(
+            "--cap-add SYS_PTRACE --security-opt seccomp=unconfined dbg:latest gdb"),
+            "{}", joined);
+        let joined = build_docker_run_command_with_no_gpu(&config).unwrap().join(" ");
+        assert!(joined.ends_with(
+            "--cap-add SYS_PTRACE --security-opt seccomp=unconfined dbg:latest"),
+            "{}", joined);
+    }
 }
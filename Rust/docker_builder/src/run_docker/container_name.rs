@@ -0,0 +1,283 @@
+//! Container names: templating and collision handling.
+//!
+//! Templates may use `{image}` (repository name without registry or tag),
+//! `{tag}`, `{profile}` (`default` when none is selected), `{user}`, `{date}`
+//! (YYYYMMDD, UTC) and `{time}` (HHMMSS, UTC). Characters docker doesn't
+//! allow in names are replaced with `-`.
+//!
+//! When a container with the name already exists, the policy decides:
+//! `fail` (default), `replace` (stop and remove the old one), or `suffix`
+//! (append `-2`, `-3`, ... until the name is free).
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::process::Command;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::engine::ContainerEngine;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum NameCollisionPolicy {
+    #[default]
+    Fail,
+    Replace,
+    Suffix,
+}
+
+impl fmt::Display for NameCollisionPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            NameCollisionPolicy::Fail => "fail",
+            NameCollisionPolicy::Replace => "replace",
+            NameCollisionPolicy::Suffix => "suffix",
+        })
+    }
+}
+
+impl FromStr for NameCollisionPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "fail" => Ok(NameCollisionPolicy::Fail),
+            "replace" => Ok(NameCollisionPolicy::Replace),
+            "suffix" => Ok(NameCollisionPolicy::Suffix),
+            other => Err(format!(
+                "Unknown name collision policy '{}': expected fail, replace, or suffix",
+                other)),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+/// Values available to name templates.
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone)]
+pub struct NameContext {
+    pub image_name: String,
+    pub profile: Option<String>,
+    pub user: String,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+impl NameContext {
+    /// Context for the current user and time.
+    pub fn now(image_name: &str, profile: Option<&str>) -> Self {
+        Self {
+            image_name: image_name.to_string(),
+            profile: profile.map(String::from),
+            user: std::env::var("USER").unwrap_or_else(|_| "user".to_string()),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// (year, month, day) for days since 1970-01-01 (proleptic Gregorian).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Replace characters docker rejects in container names with `-`.
+fn sanitize_name(name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-') { c } else { '-' })
+        .collect();
+    sanitized.trim_start_matches(['_', '.', '-']).to_string()
+}
+
+//------------------------------------------------------------------------------
+/// Render a name template. Unknown placeholders are an error.
+//------------------------------------------------------------------------------
+pub fn render_name_template(template: &str, context: &NameContext) -> Result<String, String> {
+    let image = context.image_name.split('@').next().unwrap_or_default();
+    let last = image.rsplit('/').next().unwrap_or(image);
+    let (repository, tag) = last.split_once(':').unwrap_or((last, "latest"));
+
+    let secs = context.timestamp as i64;
+    let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+    let seconds_of_day = secs.rem_euclid(86_400);
+
+    let mut rendered = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let end = rest[start..].find('}').ok_or_else(|| format!(
+            "Unclosed '{{' in container name template '{}'", template))?;
+        let value = match &rest[start + 1..start + end] {
+            "image" => repository.to_string(),
+            "tag" => tag.to_string(),
+            "profile" => context.profile.clone().unwrap_or_else(|| "default".to_string()),
+            "user" => context.user.clone(),
+            "date" => format!("{:04}{:02}{:02}", year, month, day),
+            "time" => format!(
+                "{:02}{:02}{:02}",
+                seconds_of_day / 3600,
+                seconds_of_day % 3600 / 60,
+                seconds_of_day % 60),
+            other => return Err(format!(
+                "Unknown placeholder '{{{}}}' in container name template '{}'",
+                other, template)),
+        };
+        rendered.push_str(&value);
+        rest = &rest[start + end + 1..];
+    }
+    rendered.push_str(rest);
+
+    let name = sanitize_name(&rendered);
+    if name.is_empty() {
+        return Err(format!("Container name template '{}' renders empty", template));
+    }
+    Ok(name)
+}
+
+//------------------------------------------------------------------------------
+/// True if a container (running or stopped) with exactly this name exists.
+//------------------------------------------------------------------------------
+pub fn container_exists(engine: ContainerEngine, name: &str) -> bool {
+    Command::new(engine.binary())
+        .args([
+            "ps", "-a",
+            "--filter", &format!("name=^/?{}$", name),
+            "--format", "{{.Names}}",
+        ])
+        .output()
+        .is_ok_and(|out| {
+            String::from_utf8_lossy(&out.stdout)
+                .lines()
+                .any(|line| line.trim() == name)
+        })
+}
+
+//------------------------------------------------------------------------------
+/// First of `name`, `name-2`, `name-3`, ... for which `exists` is false.
+//------------------------------------------------------------------------------
+pub fn first_free_name<F>(name: &str, exists: F) -> String
+where
+    F: Fn(&str) -> bool,
+{
+    if !exists(name) {
+        return name.to_string();
+    }
+    (2..)
+        .map(|n| format!("{}-{}", name, n))
+        .find(|candidate| !exists(candidate))
+        .unwrap_or_else(|| name.to_string())
+}
+
+//------------------------------------------------------------------------------
+/// Resolve the final name without side effects: `suffix` picks a free name;
+/// `fail` and `replace` keep the name (see `apply_collision_policy`).
+//------------------------------------------------------------------------------
+pub fn resolve_container_name(
+    engine: ContainerEngine,
+    name: &str,
+    policy: NameCollisionPolicy,
+) -> String {
+    match policy {
+        NameCollisionPolicy::Suffix => first_free_name(name, |n| container_exists(engine, n)),
+        NameCollisionPolicy::Fail | NameCollisionPolicy::Replace => name.to_string(),
+    }
+}
+
+//------------------------------------------------------------------------------
+/// Just before running: fail on an existing container, or remove it for
+/// `replace`.
+//------------------------------------------------------------------------------
+pub fn apply_collision_policy(
+    engine: ContainerEngine,
+    name: &str,
+    policy: NameCollisionPolicy,
+) -> Result<(), String> {
+    if !container_exists(engine, name) {
+        return Ok(());
+    }
+
+    match policy {
+        NameCollisionPolicy::Fail => Err(format!(
+            "A container named '{}' already exists; remove it or set \
+             name_collision: replace|suffix (or --on-name-collision)",
+            name)),
+        NameCollisionPolicy::Replace => {
+            eprintln!("==> Replacing existing container '{}'", name);
+            let status = Command::new(engine.binary())
+                .args(["rm", "-f", name])
+                .stdout(std::process::Stdio::null())
+                .status()
+                .map_err(|e| format!("Failed to execute {} rm: {}", engine, e))?;
+            if !status.success() {
+                return Err(format!("Failed to remove existing container '{}'", name));
+            }
+            Ok(())
+        }
+        // Already made unique by resolve_container_name
+        NameCollisionPolicy::Suffix => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> NameContext {
+        NameContext {
+            image_name: "ghcr.io/org/sglang:cu130".to_string(),
+            profile: Some("dev".to_string()),
+            user: "ernest".to_string(),
+            // 2024-02-29 13:45:06 UTC
+            timestamp: 1_709_214_306,
+        }
+    }
+
+    #[test]
+    fn test_render_name_template() {
+        assert_eq!(
+            render_name_template("{image}-{profile}-{date}", &context()).unwrap(),
+            "sglang-dev-20240229");
+        assert_eq!(
+            render_name_template("{user}_{image}:{tag}@{time}", &context()).unwrap(),
+            "ernest_sglang-cu130-134506");
+        assert_eq!(render_name_template("fixed", &context()).unwrap(), "fixed");
+
+        let mut no_profile = context();
+        no_profile.profile = None;
+        assert_eq!(
+            render_name_template("{image}-{profile}", &no_profile).unwrap(),
+            "sglang-default");
+
+        assert!(render_name_template("{nope}", &context()).is_err());
+        assert!(render_name_template("{image", &context()).is_err());
+    }
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+    }
+
+    #[test]
+    fn test_first_free_name_and_policy_parsing() {
+        let taken = ["app", "app-2"];
+        assert_eq!(first_free_name("app", |n| taken.contains(&n)), "app-3");
+        assert_eq!(first_free_name("other", |n| taken.contains(&n)), "other");
+
+        assert_eq!("Replace".parse(), Ok(NameCollisionPolicy::Replace));
+        assert!("overwrite".parse::<NameCollisionPolicy>().is_err());
+    }
+}
@@ -0,0 +1,143 @@
+//! Image digest pinning (`name:tag@sha256:<hex>`) and verification.
+//!
+//! With a digest, docker runs exactly that image regardless of the tag. With
+//! `verify_digest: true` the local copy of the tag is also checked against
+//! the digest, so a `:latest` that was silently re-pulled or rebuilt is caught
+//! before the run instead of being noticed in the results.
+
+use std::process::Command;
+
+use super::engine::ContainerEngine;
+
+const SHA256_PREFIX: &str = "sha256:";
+
+//------------------------------------------------------------------------------
+/// Split `name[:tag]@sha256:<hex>` into the name part and the digest.
+//------------------------------------------------------------------------------
+pub fn split_digest(image_name: &str) -> (&str, Option<&str>) {
+    match image_name.split_once('@') {
+        Some((name, digest)) => (name, Some(digest)),
+        None => (image_name, None),
+    }
+}
+
+//------------------------------------------------------------------------------
+/// Check that a digest is `sha256:` followed by 64 lowercase hex characters.
+//------------------------------------------------------------------------------
+pub fn validate_digest(digest: &str) -> Result<(), String> {
+    let hex = digest.strip_prefix(SHA256_PREFIX).ok_or_else(|| format!(
+        "Invalid image digest '{}': expected sha256:<64 hex characters>", digest))?;
+    if hex.len() != 64 || !hex.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f')) {
+        return Err(format!(
+            "Invalid image digest '{}': expected sha256:<64 hex characters>", digest));
+    }
+    Ok(())
+}
+
+//------------------------------------------------------------------------------
+/// Digests from `docker image inspect` RepoDigests output
+/// (`["repo@sha256:...", ...]`), plus the image ID.
+//------------------------------------------------------------------------------
+pub fn parse_inspect_digests(repo_digests_json: &str, image_id: &str) -> Vec<String> {
+    let mut digests: Vec<String> = serde_json::from_str::<Option<Vec<String>>>(
+        repo_digests_json.trim())
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|d| split_digest(d).1.map(String::from))
+        .collect();
+    let image_id = image_id.trim();
+    if !image_id.is_empty() {
+        digests.push(image_id.to_string());
+    }
+    digests
+}
+
+/// Digests of a local image (RepoDigests and ID).
+fn local_image_digests(
+    engine: ContainerEngine,
+    image_name: &str,
+) -> Result<Vec<String>, String> {
+    let output = Command::new(engine.binary())
+        .args([
+            "image", "inspect",
+            "--format", "{{json .RepoDigests}}\n{{.Id}}",
+            image_name,
+        ])
+        .output()
+        .map_err(|e| format!("Failed to execute {} image inspect: {}", engine, e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "Image '{}' not found locally, cannot verify its digest: {}",
+            image_name,
+            String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines();
+    let repo_digests = lines.next().unwrap_or_default();
+    let image_id = lines.next().unwrap_or_default();
+    Ok(parse_inspect_digests(repo_digests, image_id))
+}
+
+//------------------------------------------------------------------------------
+/// Verify that the local image matches the pinned digest in `image_name`.
+/// For `name:tag@digest` the local `name:tag` is checked, which catches a tag
+/// that has moved; for `name@digest` the image must exist locally.
+//------------------------------------------------------------------------------
+pub fn verify_image_digest(
+    engine: ContainerEngine,
+    image_name: &str,
+) -> Result<(), String> {
+    let (name, digest) = split_digest(image_name);
+    let digest = digest.ok_or_else(|| format!(
+        "verify_digest is set but '{}' has no @sha256: digest", image_name))?;
+    validate_digest(digest)?;
+
+    let has_tag = name.rsplit('/').next().is_some_and(|last| last.contains(':'));
+    let local = if has_tag { name } else { image_name };
+
+    let digests = local_image_digests(engine, local)?;
+    if !digests.iter().any(|d| d == digest) {
+        return Err(format!(
+            "Digest mismatch for '{}': expected {}, local image has {}",
+            local,
+            digest,
+            if digests.is_empty() { "no digests".to_string() } else { digests.join(", ") }));
+    }
+
+    eprintln!("    ✓ Digest verified: {}", digest);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIGEST: &str =
+        "sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn test_split_and_validate_digest() {
+        let pinned = format!("ghcr.io/org/app:latest@{}", DIGEST);
+        assert_eq!(split_digest(&pinned), ("ghcr.io/org/app:latest", Some(DIGEST)));
+        assert_eq!(split_digest("app:latest"), ("app:latest", None));
+
+        assert!(validate_digest(DIGEST).is_ok());
+        assert!(validate_digest("sha256:abc").is_err());
+        assert!(validate_digest("md5:0123").is_err());
+        assert!(validate_digest(&DIGEST.to_uppercase()).is_err());
+    }
+
+    #[test]
+    fn test_parse_inspect_digests() {
+        let repo_digests = format!(r#"["ghcr.io/org/app@{}"]"#, DIGEST);
+        assert_eq!(
+            parse_inspect_digests(&repo_digests, "sha256:feed\n"),
+            vec![DIGEST.to_string(), "sha256:feed".to_string()]);
+        assert_eq!(parse_inspect_digests("[]", ""), Vec::<String>::new());
+        assert_eq!(parse_inspect_digests("null", "sha256:feed"), vec!["sha256:feed"]);
+    }
+}
@@ -0,0 +1,116 @@
+//! Container engine backends - docker (default) or podman.
+//!
+//! The run command is built with docker syntax; the engine decides the binary
+//! name and translates the pieces that differ. Podman exposes NVIDIA GPUs as
+//! CDI devices (`--device nvidia.com/gpu=N`) rather than `--gpus`.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// CDI device name prefix for NVIDIA GPUs (nvidia-container-toolkit).
+pub const NVIDIA_CDI_PREFIX: &str = "nvidia.com/gpu";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerEngine {
+    #[default]
+    Docker,
+    Podman,
+}
+
+impl ContainerEngine {
+    /// Name of the CLI binary.
+    pub fn binary(&self) -> &'static str {
+        match self {
+            ContainerEngine::Docker => "docker",
+            ContainerEngine::Podman => "podman",
+        }
+    }
+
+    //--------------------------------------------------------------------------
+    /// Translate a docker `--gpus` value (`all`, `N`, `device=0`,
+    /// `"device=0,1"`) into this engine's arguments.
+    //--------------------------------------------------------------------------
+    pub fn gpu_args(&self, spec: &str) -> Vec<String> {
+        match self {
+            ContainerEngine::Docker => vec!["--gpus".to_string(), spec.to_string()],
+            ContainerEngine::Podman => cdi_gpu_devices(spec)
+                .into_iter()
+                .flat_map(|device| ["--device".to_string(), device])
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for ContainerEngine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.binary())
+    }
+}
+
+impl FromStr for ContainerEngine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "docker" => Ok(ContainerEngine::Docker),
+            "podman" => Ok(ContainerEngine::Podman),
+            other => Err(format!(
+                "Unknown engine '{}': expected 'docker' or 'podman'", other)),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+/// CDI device names for a docker `--gpus` value. A count N selects GPUs 0..N,
+/// since CDI has no notion of "any N GPUs".
+//------------------------------------------------------------------------------
+fn cdi_gpu_devices(spec: &str) -> Vec<String> {
+    let spec = spec.trim().trim_matches('"');
+    if spec == "all" {
+        return vec![format!("{}=all", NVIDIA_CDI_PREFIX)];
+    }
+    if let Ok(count) = spec.parse::<u32>() {
+        return (0..count)
+            .map(|i| format!("{}={}", NVIDIA_CDI_PREFIX, i))
+            .collect();
+    }
+    spec.strip_prefix("device=")
+        .unwrap_or(spec)
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| format!("{}={}", NVIDIA_CDI_PREFIX, id))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gpu_args_per_engine() {
+        assert_eq!(
+            ContainerEngine::Docker.gpu_args("\"device=0,1\""),
+            vec!["--gpus", "\"device=0,1\""]);
+
+        let podman = ContainerEngine::Podman;
+        assert_eq!(podman.gpu_args("all"), vec!["--device", "nvidia.com/gpu=all"]);
+        assert_eq!(podman.gpu_args("device=1"), vec!["--device", "nvidia.com/gpu=1"]);
+        assert_eq!(
+            podman.gpu_args("\"device=0,2\""),
+            vec!["--device", "nvidia.com/gpu=0", "--device", "nvidia.com/gpu=2"]);
+        assert_eq!(
+            podman.gpu_args("2"),
+            vec!["--device", "nvidia.com/gpu=0", "--device", "nvidia.com/gpu=1"]);
+    }
+
+    #[test]
+    fn test_parse_engine() {
+        assert_eq!("podman".parse::<ContainerEngine>(), Ok(ContainerEngine::Podman));
+        assert_eq!(" Docker ".parse::<ContainerEngine>(), Ok(ContainerEngine::Docker));
+        assert!("containerd".parse::<ContainerEngine>().is_err());
+        assert_eq!(ContainerEngine::default().binary(), "docker");
+    }
+}
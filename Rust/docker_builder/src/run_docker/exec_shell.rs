@@ -0,0 +1,172 @@
+//! `docker_builder shell` - a shell in the container for a build directory.
+//!
+//! If a container from the configured image is running, exec into it
+//! (preferring the configured container name). Otherwise start a fresh
+//! interactive container with the same mounts, GPUs, and env as `run`, but
+//! with the shell as entrypoint and no `--name`, so it never collides with
+//! the long-running container.
+
+use std::path::PathBuf;
+use std::process::Command;
+
+use super::engine::ContainerEngine;
+use super::run_docker::{
+    execute_docker_run_command, resolve_run_command, RunDockerArgs};
+
+/// Default shell to exec or use as entrypoint.
+pub const DEFAULT_SHELL: &str = "bash";
+
+/// Arguments from CLI for `shell`
+#[derive(Debug, Clone)]
+pub struct ShellArgs {
+    pub build_dir: PathBuf,
+    /// Shell to run, e.g. bash or sh
+    pub shell: String,
+    /// Always start a fresh container, even if one is running
+    pub fresh: bool,
+    pub engine: Option<ContainerEngine>,
+    pub profile: Option<String>,
+}
+
+//------------------------------------------------------------------------------
+/// Pick a container from `ps --format '{{.ID}}\t{{.Names}}'` output: the one
+/// named `preferred` if present, else the first (most recently created).
+//------------------------------------------------------------------------------
+pub fn pick_running_container(ps_output: &str, preferred: Option<&str>) -> Option<String> {
+    let containers: Vec<(&str, &str)> = ps_output
+        .lines()
+        .filter_map(|line| {
+            let (id, name) = line.trim().split_once('\t')?;
+            Some((id.trim(), name.trim()))
+        })
+        .filter(|(id, _)| !id.is_empty())
+        .collect();
+
+    containers
+        .iter()
+        .find(|(_, name)| Some(*name) == preferred)
+        .or_else(|| containers.first())
+        .map(|(id, _)| id.to_string())
+}
+
+/// Running containers started from `image`.
+fn running_containers(engine: ContainerEngine, image: &str) -> Result<String, String> {
+    let output = Command::new(engine.binary())
+        .args([
+            "ps",
+            "--filter", &format!("ancestor={}", image),
+            "--format", "{{.ID}}\t{{.Names}}",
+        ])
+        .output()
+        .map_err(|e| format!("Failed to execute {} ps: {}", engine, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} ps failed: {}",
+            engine,
+            String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+//------------------------------------------------------------------------------
+/// Drop `option` and its value from argv (e.g. `--name x`).
+//------------------------------------------------------------------------------
+pub fn remove_option(argv: &[String], option: &str) -> Vec<String> {
+    let mut result = Vec::with_capacity(argv.len());
+    let mut args = argv.iter();
+    while let Some(arg) = args.next() {
+        if arg == option {
+            args.next();
+        } else {
+            result.push(arg.clone());
+        }
+    }
+    result
+}
+
+//------------------------------------------------------------------------------
+/// Exec a shell in the running container for `args.build_dir`, or start a
+/// fresh interactive one.
+//------------------------------------------------------------------------------
+pub fn open_shell(args: &ShellArgs) -> Result<(), String> {
+    let run_args = RunDockerArgs {
+        build_dir: args.build_dir.clone(),
+        gpu_id: None,
+        interactive: true,
+        detached: false,
+        entrypoint: Some(args.shell.clone()),
+        network_host: false,
+        no_gpu: false,
+        gui: false,
+        audio: false,
+        user: None,
+        ipc: None,
+        pid: None,
+        privileged: false,
+        mount_cwd: None,
+        engine: args.engine,
+        pull: false,
+        profile: args.profile.clone(),
+        name: None,
+        name_collision: None,
+        config_overlays: vec![],
+        vars: vec![],
+        extra_args: vec![],
+    };
+    let mut resolved = resolve_run_command(&run_args)?;
+    let engine = resolved.engine;
+
+    if !args.fresh {
+        let running = running_containers(engine, &resolved.docker_image_name)?;
+        if let Some(container) = pick_running_container(
+            &running, resolved.container_name.as_deref())
+        {
+            println!("==> {} exec -it {} {}", engine, container, args.shell);
+            let status = Command::new(engine.binary())
+                .args(["exec", "-it", &container, &args.shell])
+                .status()
+                .map_err(|e| format!("Failed to execute {} exec: {}", engine, e))?;
+            if !status.success() && status.code() != Some(127) {
+                return Err(format!(
+                    "{} exec failed with exit code: {}",
+                    engine,
+                    status.code().unwrap_or(-1)));
+            }
+            return Ok(());
+        }
+        println!(
+            "==> No running container for {}; starting a fresh one",
+            resolved.docker_image_name);
+    }
+
+    resolved.ensure_image(false)?;
+    resolved.prepare_secrets(false)?;
+    let argv = remove_option(&resolved.argv, "--name");
+    execute_docker_run_command(&argv, &resolved.build_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_running_container() {
+        let ps = "a1b2c3\tkb-app\nd4e5f6\tserene_turing\n";
+        assert_eq!(pick_running_container(ps, None).as_deref(), Some("a1b2c3"));
+        assert_eq!(
+            pick_running_container(ps, Some("serene_turing")).as_deref(),
+            Some("d4e5f6"));
+        assert_eq!(pick_running_container(ps, Some("other")).as_deref(), Some("a1b2c3"));
+        assert_eq!(pick_running_container("", None), None);
+    }
+
+    #[test]
+    fn test_remove_option() {
+        let argv: Vec<String> = ["docker", "run", "--name", "kb", "-it", "img"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(remove_option(&argv, "--name"), ["docker", "run", "-it", "img"]);
+        assert_eq!(remove_option(&argv, "--gpus"), argv);
+    }
+}
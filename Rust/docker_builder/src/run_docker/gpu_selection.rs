@@ -0,0 +1,215 @@
+//! GPU selection for `gpus: auto` - pick an idle GPU via nvidia-smi.
+//!
+//! A GPU counts as idle when its used memory is at or below a threshold
+//! (MiB). Among idle GPUs the one with the least used memory wins, ties going
+//! to the lowest index.
+//!
+//! Also checks device IDs in `gpus`/`nvidia_visible_devices`, which may name
+//! MIG instances (`0:1` = GPU 0, MIG device 1, or a `MIG-<uuid>`), and builds
+//! the env vars for `gpu_memory_fraction` so several model servers can share
+//! one GPU.
+
+use std::process::Command;
+
+/// Default used-memory threshold (MiB) below which a GPU is considered idle.
+pub const DEFAULT_GPU_MEMORY_THRESHOLD_MIB: u64 = 1024;
+
+/// Env vars set from `gpu_memory_fraction`: a generic one for model servers
+/// to read, plus the one JAX/XLA honours directly.
+pub const GPU_MEMORY_FRACTION_ENV_VARS: [&str; 2] = [
+    "GPU_MEMORY_FRACTION",
+    "XLA_PYTHON_CLIENT_MEM_FRACTION",
+];
+
+//------------------------------------------------------------------------------
+/// Memory usage of one GPU as reported by nvidia-smi.
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpuMemory {
+    pub index: u32,
+    pub memory_used_mib: u64,
+    pub memory_total_mib: u64,
+}
+
+//------------------------------------------------------------------------------
+/// Parse `nvidia-smi --query-gpu=index,memory.used,memory.total
+/// --format=csv,noheader,nounits` output.
+//------------------------------------------------------------------------------
+pub fn parse_nvidia_smi_output(output: &str) -> Result<Vec<GpuMemory>, String> {
+    output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            if fields.len() != 3 {
+                return Err(format!("Unexpected nvidia-smi line: '{}'", line));
+            }
+            let parse = |s: &str| s.parse::<u64>().map_err(|e| format!(
+                "Invalid number '{}' in nvidia-smi line '{}': {}", s, line, e));
+            Ok(GpuMemory {
+                index: parse(fields[0])? as u32,
+                memory_used_mib: parse(fields[1])?,
+                memory_total_mib: parse(fields[2])?,
+            })
+        })
+        .collect()
+}
+
+//------------------------------------------------------------------------------
+/// Pick the idle GPU with the least used memory, if any.
+//------------------------------------------------------------------------------
+pub fn select_idle_gpu(gpus: &[GpuMemory], threshold_mib: u64) -> Option<u32> {
+    gpus.iter()
+        .filter(|gpu| gpu.memory_used_mib <= threshold_mib)
+        .min_by_key(|gpu| (gpu.memory_used_mib, gpu.index))
+        .map(|gpu| gpu.index)
+}
+
+//------------------------------------------------------------------------------
+/// Query nvidia-smi for per-GPU memory usage.
+//------------------------------------------------------------------------------
+pub fn query_gpu_memory() -> Result<Vec<GpuMemory>, String> {
+    let output = Command::new("nvidia-smi")
+        .args([
+            "--query-gpu=index,memory.used,memory.total",
+            "--format=csv,noheader,nounits",
+        ])
+        .output()
+        .map_err(|e| format!("Failed to run nvidia-smi for gpus: auto: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "nvidia-smi failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    parse_nvidia_smi_output(&String::from_utf8_lossy(&output.stdout))
+}
+
+//------------------------------------------------------------------------------
+/// Resolve a `gpus` value into the argument for `docker run --gpus`.
+/// `auto` selects an idle GPU via nvidia-smi; other values pass through.
+//------------------------------------------------------------------------------
+pub fn resolve_gpus(spec: &str, threshold_mib: u64) -> Result<String, String> {
+    if spec.trim() != "auto" {
+        return Ok(spec.to_string());
+    }
+
+    let gpus = query_gpu_memory()?;
+    let index = select_idle_gpu(&gpus, threshold_mib).ok_or_else(|| format!(
+        "gpus: auto found no idle GPU (used memory <= {} MiB among {} GPU(s))",
+        threshold_mib, gpus.len()))?;
+    eprintln!("    gpus: auto selected GPU {}", index);
+    Ok(format!("device={}", index))
+}
+
+//------------------------------------------------------------------------------
+/// Check one GPU device ID: an index (`0`), a MIG device on a GPU (`0:1`), or
+/// a GPU/MIG UUID (`GPU-...`, `MIG-...`) as listed by `nvidia-smi -L`.
+//------------------------------------------------------------------------------
+pub fn validate_gpu_id(id: &str) -> Result<(), String> {
+    let id = id.trim();
+    let is_index = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    let is_uuid = |s: &str| {
+        s.len() > 4 && s[4..].chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '/'))
+    };
+
+    let valid = match id.split_once(':') {
+        Some((gpu, mig)) => is_index(gpu) && is_index(mig),
+        None => is_index(id)
+            || ((id.starts_with("GPU-") || id.starts_with("MIG-")) && is_uuid(id)),
+    };
+    if !valid {
+        return Err(format!(
+            "Invalid GPU device '{}': expected an index, GPU:MIG (e.g. 0:1), \
+             or a GPU-/MIG- UUID",
+            id));
+    }
+    Ok(())
+}
+
+//------------------------------------------------------------------------------
+/// Check a `gpus` value: `all`, `auto`, a count, or `device=` with one or more
+/// comma-separated device IDs (optionally in double quotes).
+//------------------------------------------------------------------------------
+pub fn validate_gpus_spec(spec: &str) -> Result<(), String> {
+    let spec = spec.trim().trim_matches('"');
+    if spec == "all" || spec == "auto" || spec.parse::<u32>().is_ok() {
+        return Ok(());
+    }
+    let Some(ids) = spec.strip_prefix("device=") else {
+        return Err(format!(
+            "Invalid gpus '{}': expected all, auto, a count, or device=ID[,ID...]",
+            spec));
+    };
+    ids.split(',').try_for_each(validate_gpu_id)
+}
+
+//------------------------------------------------------------------------------
+/// Env vars for `gpu_memory_fraction`, which must be in (0, 1].
+//------------------------------------------------------------------------------
+pub fn memory_fraction_env(fraction: f64) -> Result<Vec<(String, String)>, String> {
+    if !(fraction > 0.0 && fraction <= 1.0) {
+        return Err(format!(
+            "gpu_memory_fraction must be in (0, 1], got {}", fraction));
+    }
+    Ok(GPU_MEMORY_FRACTION_ENV_VARS
+        .iter()
+        .map(|name| (name.to_string(), fraction.to_string()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_select_idle_gpu() {
+        let output = "0, 23000, 24576\n1, 310, 24576\n2, 5, 24576\n3, 5, 24576\n";
+        let gpus = parse_nvidia_smi_output(output).unwrap();
+        assert_eq!(gpus.len(), 4);
+        assert_eq!(gpus[1], GpuMemory {
+            index: 1,
+            memory_used_mib: 310,
+            memory_total_mib: 24576,
+        });
+
+        assert_eq!(select_idle_gpu(&gpus, DEFAULT_GPU_MEMORY_THRESHOLD_MIB), Some(2));
+        assert_eq!(select_idle_gpu(&gpus[..2], 500), Some(1));
+        assert_eq!(select_idle_gpu(&gpus[..1], 500), None);
+
+        assert!(parse_nvidia_smi_output("0, N/A, 24576").is_err());
+        assert!(parse_nvidia_smi_output("garbage").is_err());
+    }
+
+    #[test]
+    fn test_resolve_gpus_passes_through_explicit_values() {
+        assert_eq!(resolve_gpus("all", 0).unwrap(), "all");
+        assert_eq!(resolve_gpus("\"device=0,1\"", 0).unwrap(), "\"device=0,1\"");
+    }
+
+    #[test]
+    fn test_validate_mig_and_gpu_specs() {
+        for id in ["0", "3:1", "MIG-4f8e3a53-2c0d-5b5f-9c3e-5d6a1b2c3d4e",
+                   "GPU-5f0c2a41-6e59-11ec-90d6-0242ac120003", "MIG-GPU-5f0c/1/0"] {
+            assert!(validate_gpu_id(id).is_ok(), "{}", id);
+        }
+        for id in ["", "x", "0:", ":1", "0:1:2", "MIG-", "gpu-0"] {
+            assert!(validate_gpu_id(id).is_err(), "{}", id);
+        }
+
+        assert!(validate_gpus_spec("all").is_ok());
+        assert!(validate_gpus_spec("2").is_ok());
+        assert!(validate_gpus_spec("device=0:1").is_ok());
+        assert!(validate_gpus_spec("\"device=0:0,0:1\"").is_ok());
+        assert!(validate_gpus_spec("device=0,bad").is_err());
+        assert!(validate_gpus_spec("some").is_err());
+
+        assert_eq!(memory_fraction_env(0.5).unwrap(), vec![
+            ("GPU_MEMORY_FRACTION".to_string(), "0.5".to_string()),
+            ("XLA_PYTHON_CLIENT_MEM_FRACTION".to_string(), "0.5".to_string()),
+        ]);
+        assert!(memory_fraction_env(0.0).is_err());
+        assert!(memory_fraction_env(1.5).is_err());
+    }
+}
@@ -0,0 +1,325 @@
+//! Effective run configuration - run_configuration.yml after overlays,
+//! profile, `${VAR}` / `{{ var }}` substitution and CLI flags, written to
+//! run_configuration.lock.yml by `run --lock` so the exact configuration can
+//! be reviewed and committed.
+//!
+//! Env values read from the host environment are written as their `${VAR}`
+//! placeholders, in the configuration and in the recorded command, so tokens
+//! passed that way never reach the file. Keep other credentials in
+//! `secrets:`, which records only where a secret comes from.
+
+use serde_yaml::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::configuration::overlay::{load_merged_yaml, overlay_paths};
+use crate::configuration::run_docker_configuration::{EnvOption, RunConfiguration};
+use crate::run_docker::networks::NetworkSpec;
+use super::run_docker::{ResolvedRunCommand, RunDockerArgs};
+use super::run_plan::{RunOption, RunPlan};
+use super::shell::join_command;
+
+pub const LOCK_FILE_NAME: &str = "run_configuration.lock.yml";
+
+//------------------------------------------------------------------------------
+/// The run configuration as resolved for `resolved`, with CLI overrides
+/// folded in. Legacy (volumes/ports only) files are expressed in the richer
+/// format. Flags with no YAML equivalent (-it, -d, --entrypoint, --gui,
+/// --audio) only show in the command recorded by `render_lock_file`.
+//------------------------------------------------------------------------------
+pub fn effective_configuration(
+    args: &RunDockerArgs,
+    resolved: &ResolvedRunCommand,
+) -> RunConfiguration {
+    let mut configuration = resolved.run_configuration.clone().unwrap_or_else(|| {
+        let legacy = &resolved.legacy_run_configuration;
+        RunConfiguration {
+            volumes: (!legacy.volumes.is_empty()).then(|| legacy.volumes.clone()),
+            ports: (!legacy.ports.is_empty()).then(|| legacy.ports.clone()),
+            ..Default::default()
+        }
+    });
+
+    configuration.docker_image_name = resolved.docker_image_name.clone();
+    configuration.engine = Some(resolved.engine);
+    configuration.rootless = Some(resolved.rootless);
+    configuration.container_name = resolved.container_name.clone();
+    configuration.name_collision = Some(resolved.name_collision);
+    // Already merged in
+    configuration.profiles = None;
+
+    if args.no_gpu {
+        configuration.gpus = None;
+        configuration.nvidia_visible_devices = None;
+        configuration.gpu_memory_fraction = None;
+    } else if let Some(gpu_id) = args.gpu_id {
+        configuration.gpus = Some(format!("device={}", gpu_id));
+    }
+
+    if args.network_host {
+        configuration.networks = Some(vec![NetworkSpec::Name("host".to_string())]);
+    }
+
+    if args.user.is_some() {
+        configuration.user = args.user.clone();
+    }
+    if args.ipc.is_some() {
+        configuration.ipc = args.ipc.clone();
+    }
+    if args.pid.is_some() {
+        configuration.pid = args.pid.clone();
+    }
+    configuration.privileged |= args.privileged;
+    if args.mount_cwd.is_some() {
+        configuration.mount_cwd = args.mount_cwd.clone();
+    }
+    if !args.extra_args.is_empty() {
+        configuration.extra_args
+            .get_or_insert_with(Vec::new)
+            .extend(args.extra_args.iter().cloned());
+    }
+
+    configuration
+}
+
+//------------------------------------------------------------------------------
+/// Env entries whose value reads the host environment, as written before
+/// interpolation (e.g. `API_TOKEN: ${API_TOKEN}`), keyed by env name.
+//------------------------------------------------------------------------------
+pub fn host_env_placeholders(
+    args: &RunDockerArgs,
+    resolved: &ResolvedRunCommand,
+) -> Result<HashMap<String, String>, String> {
+    if resolved.run_configuration.is_none() {
+        return Ok(HashMap::new());
+    }
+    let overlays = overlay_paths(&resolved.build_dir, &args.config_overlays)?;
+    let merged = load_merged_yaml(
+        &resolved.build_dir.join("run_configuration.yml"),
+        resolved.profile.as_deref(),
+        &overlays)?;
+    let Some(env) = merged.get("env") else {
+        return Ok(HashMap::new());
+    };
+    let env: EnvOption = serde_yaml::from_value(env.clone())
+        .map_err(|e| format!("Invalid env in run configuration: {}", e))?;
+    Ok(env
+        .into_env_pairs()
+        .into_iter()
+        .filter(|(_, value)| value.contains("${"))
+        .collect())
+}
+
+/// Put the placeholders back in place of the resolved env values.
+fn redact_env(value: &mut Value, placeholders: &HashMap<String, String>) {
+    match value.get_mut("env") {
+        Some(Value::Mapping(env)) => {
+            for (key, value) in env.iter_mut() {
+                if let Some(placeholder) = key.as_str().and_then(|k| placeholders.get(k)) {
+                    *value = Value::String(placeholder.clone());
+                }
+            }
+        }
+        Some(Value::Sequence(env)) => {
+            for entry in env.iter_mut() {
+                let Some((key, _)) = entry.as_str().and_then(|e| e.split_once('=')) else {
+                    continue;
+                };
+                if let Some(placeholder) = placeholders.get(key.trim()) {
+                    *entry = Value::String(format!("{}={}", key.trim(), placeholder));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The command for `plan` with host environment values as placeholders.
+fn redacted_command_line(plan: &RunPlan, placeholders: &HashMap<String, String>) -> String {
+    let mut plan = plan.clone();
+    for option in &mut plan.options {
+        if let RunOption::Env { key, value } = option
+            && let Some(placeholder) = placeholders.get(key.as_str())
+        {
+            *value = placeholder.clone();
+        }
+    }
+    join_command(&plan.to_argv())
+}
+
+/// Drop null entries from mappings so unset options don't clutter the file.
+fn remove_nulls(value: &mut Value) {
+    match value {
+        Value::Mapping(map) => {
+            map.retain(|_, v| !v.is_null());
+            map.values_mut().for_each(remove_nulls);
+        }
+        Value::Sequence(items) => items.iter_mut().for_each(remove_nulls),
+        _ => {}
+    }
+}
+
+//------------------------------------------------------------------------------
+/// YAML for the lock file: a header with the command for `plan`, then the
+/// configuration with unset options omitted. Env values listed in
+/// `placeholders` (see `host_env_placeholders`) are written unresolved.
+//------------------------------------------------------------------------------
+pub fn render_lock_file(
+    configuration: &RunConfiguration,
+    plan: &RunPlan,
+    placeholders: &HashMap<String, String>,
+) -> Result<String, String> {
+    let mut value = serde_yaml::to_value(configuration)
+        .map_err(|e| format!("Failed to serialize run configuration: {}", e))?;
+    remove_nulls(&mut value);
+    redact_env(&mut value, placeholders);
+    let command_line = redacted_command_line(plan, placeholders);
+    let yaml = serde_yaml::to_string(&value)
+        .map_err(|e| format!("Failed to serialize run configuration: {}", e))?;
+
+    Ok(format!(
+        "# Generated by docker_builder run; do not edit.\n\
+         # Effective configuration after overlays, profile, variables and CLI flags.\n\
+         # Command: {}\n{}",
+        command_line, yaml))
+}
+
+//------------------------------------------------------------------------------
+/// Write `contents` to run_configuration.lock.yml in `build_dir`.
+//------------------------------------------------------------------------------
+pub fn write_lock_file(build_dir: &Path, contents: &str) -> Result<PathBuf, String> {
+    let path = build_dir.join(LOCK_FILE_NAME);
+    fs::write(&path, contents)
+        .map_err(|e| format!("Failed to write '{}': {}", path.display(), e))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::run_docker_configuration::RunDockerConfigurationData;
+    use crate::configuration::schema::RUN_CONFIGURATION_SCHEMA;
+    use crate::configuration::validation::check_schema;
+    use crate::run_docker::container_name::NameCollisionPolicy;
+    use crate::run_docker::engine::ContainerEngine;
+    use crate::run_docker::run_docker::resolve_run_command;
+    use crate::run_docker::secrets::PreparedSecrets;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    fn args() -> RunDockerArgs {
+        RunDockerArgs {
+            build_dir: PathBuf::from("."),
+            gpu_id: Some(1),
+            interactive: true,
+            detached: false,
+            entrypoint: None,
+            network_host: false,
+            no_gpu: false,
+            gui: false,
+            audio: false,
+            user: Some("1000:1000".to_string()),
+            ipc: None,
+            pid: None,
+            privileged: true,
+            mount_cwd: None,
+            engine: None,
+            pull: false,
+            profile: Some("dev".to_string()),
+            name: None,
+            name_collision: None,
+            config_overlays: vec![],
+            vars: vec![],
+            extra_args: vec![],
+        }
+    }
+
+    #[test]
+    fn test_effective_configuration_and_render() {
+        let run_configuration: RunConfiguration = serde_yaml::from_str(r#"
+docker_image_name: app:latest
+gpus: all
+ipc: host
+shm_size: 8g
+env:
+  API_TOKEN: s3cret
+  MODE: dev
+"#).unwrap();
+        let mut plan = RunPlan::new(ContainerEngine::Docker, "app:latest");
+        plan.env("API_TOKEN", "s3cret");
+        plan.env("MODE", "dev");
+        let resolved = ResolvedRunCommand {
+            argv: vec![],
+            plan,
+            command_line: "docker run --rm app:latest".to_string(),
+            docker_image_name: "app:latest".to_string(),
+            engine: ContainerEngine::Docker,
+            rootless: false,
+            profile: Some("dev".to_string()),
+            container_name: Some("app-dev".to_string()),
+            name_collision: NameCollisionPolicy::default(),
+            build_dir: PathBuf::from("."),
+            run_configuration: Some(run_configuration),
+            legacy_run_configuration: RunDockerConfigurationData::default(),
+            secret_mounts: vec![],
+            secrets: Arc::new(PreparedSecrets::default()),
+        };
+
+        let effective = effective_configuration(&args(), &resolved);
+        assert_eq!(effective.gpus.as_deref(), Some("device=1"));
+        assert_eq!(effective.ipc.as_deref(), Some("host"));
+        assert_eq!(effective.user.as_deref(), Some("1000:1000"));
+        assert!(effective.privileged);
+
+        let placeholders =
+            HashMap::from([("API_TOKEN".to_string(), "${API_TOKEN}".to_string())]);
+        let rendered = render_lock_file(&effective, &resolved.plan, &placeholders).unwrap();
+        assert!(rendered.starts_with("# Generated by docker_builder run"));
+        assert!(rendered.contains(
+            "# Command: docker run -e 'API_TOKEN=${API_TOKEN}' -e MODE=dev app:latest\n"),
+            "{}", rendered);
+        assert!(!rendered.contains("s3cret"), "{}", rendered);
+        assert!(rendered.contains("API_TOKEN: ${API_TOKEN}\n"), "{}", rendered);
+        assert!(rendered.contains("MODE: dev\n"), "{}", rendered);
+        assert!(rendered.contains("\ncontainer_name: app-dev\n"), "{}", rendered);
+        assert!(rendered.contains("\nshm_size: 8g\n"));
+        assert!(!rendered.contains("null"), "{}", rendered);
+
+        // The lock file is itself a valid run configuration
+        check_schema(Path::new(LOCK_FILE_NAME), &rendered, RUN_CONFIGURATION_SCHEMA).unwrap();
+        let reloaded: RunConfiguration = serde_yaml::from_str(&rendered).unwrap();
+        assert_eq!(reloaded.gpus.as_deref(), Some("device=1"));
+        assert_eq!(reloaded.engine, Some(ContainerEngine::Docker));
+    }
+
+    #[test]
+    fn test_host_env_placeholders() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("build_configuration.yml"), r#"
+docker_image_name: app:latest
+base_image: ubuntu:22.04
+dockerfile_components: []
+"#).unwrap();
+        fs::write(dir.path().join("run_configuration.yml"), r#"
+docker_image_name: app:latest
+env:
+  - HOME_DIR=${HOME}
+  - MODE=dev
+"#).unwrap();
+        let mut args = args();
+        args.build_dir = dir.path().to_path_buf();
+        args.profile = None;
+        let resolved = resolve_run_command(&args).unwrap();
+        assert!(!resolved.plan.env_vars().any(|(_, v)| v == "${HOME}"));
+
+        let placeholders = host_env_placeholders(&args, &resolved).unwrap();
+        assert_eq!(placeholders.len(), 1);
+        assert_eq!(placeholders["HOME_DIR"], "${HOME}");
+
+        let rendered = render_lock_file(
+            &effective_configuration(&args, &resolved), &resolved.plan, &placeholders).unwrap();
+        assert!(rendered.contains("-e 'HOME_DIR=${HOME}'"), "{}", rendered);
+        assert!(rendered.contains("- HOME_DIR=${HOME}\n"), "{}", rendered);
+    }
+}
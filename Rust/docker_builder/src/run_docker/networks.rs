@@ -0,0 +1,136 @@
+//! User-defined networks: `networks` in run_configuration.yml, and creating
+//! missing ones when `ensure_networks: true`.
+
+use serde::{Deserialize, Serialize};
+use std::process::{Command, Stdio};
+
+use super::engine::ContainerEngine;
+
+/// Networks provided by the engine itself; never created.
+const BUILTIN_NETWORKS: &[&str] = &["bridge", "host", "none", "default"];
+
+//------------------------------------------------------------------------------
+/// A network to connect to: a name, or a name with settings used when the
+/// network has to be created.
+//------------------------------------------------------------------------------
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum NetworkSpec {
+    Name(String),
+    Detailed {
+        name: String,
+        /// Driver for creation (default: bridge)
+        #[serde(default)]
+        driver: Option<String>,
+        /// Subnet in CIDR form, e.g. 172.28.0.0/16
+        #[serde(default)]
+        subnet: Option<String>,
+        #[serde(default)]
+        gateway: Option<String>,
+    },
+}
+
+impl NetworkSpec {
+    pub fn name(&self) -> &str {
+        match self {
+            NetworkSpec::Name(name) => name.trim(),
+            NetworkSpec::Detailed { name, .. } => name.trim(),
+        }
+    }
+
+    /// True for engine-provided networks and `container:<id>` modes.
+    pub fn is_builtin(&self) -> bool {
+        let name = self.name();
+        BUILTIN_NETWORKS.contains(&name) || name.starts_with("container:")
+    }
+
+    //--------------------------------------------------------------------------
+    /// `network create` arguments for this network.
+    //--------------------------------------------------------------------------
+    pub fn create_args(&self) -> Vec<String> {
+        let mut args = vec!["network".to_string(), "create".to_string()];
+        let (driver, subnet, gateway) = match self {
+            NetworkSpec::Name(_) => (None, None, None),
+            NetworkSpec::Detailed { driver, subnet, gateway, .. } => {
+                (driver.as_ref(), subnet.as_ref(), gateway.as_ref())
+            }
+        };
+        args.push("--driver".to_string());
+        args.push(driver.map_or("bridge", |d| d.as_str()).to_string());
+        if let Some(subnet) = subnet {
+            args.push("--subnet".to_string());
+            args.push(subnet.clone());
+        }
+        if let Some(gateway) = gateway {
+            args.push("--gateway".to_string());
+            args.push(gateway.clone());
+        }
+        args.push(self.name().to_string());
+        args
+    }
+}
+
+/// True if the network exists.
+fn network_exists(engine: ContainerEngine, name: &str) -> bool {
+    Command::new(engine.binary())
+        .args(["network", "inspect", name])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+//------------------------------------------------------------------------------
+/// Create any missing user-defined networks in `networks`.
+//------------------------------------------------------------------------------
+pub fn ensure_networks(
+    engine: ContainerEngine,
+    networks: &[NetworkSpec],
+) -> Result<(), String> {
+    for network in networks.iter().filter(|n| !n.is_builtin()) {
+        if network_exists(engine, network.name()) {
+            continue;
+        }
+
+        eprintln!("==> Creating network '{}'", network.name());
+        let output = Command::new(engine.binary())
+            .args(network.create_args())
+            .output()
+            .map_err(|e| format!("Failed to execute {} network create: {}", engine, e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "Failed to create network '{}': {}",
+                network.name(),
+                String::from_utf8_lossy(&output.stderr).trim()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_network_specs() {
+        let networks: Vec<NetworkSpec> = serde_yaml::from_str(r#"
+- bridge
+- backend
+- name: llm-net
+  subnet: 172.28.0.0/16
+  gateway: 172.28.0.1
+"#).unwrap();
+
+        assert!(networks[0].is_builtin());
+        assert!(!networks[1].is_builtin());
+        assert_eq!(networks[1].create_args(), vec![
+            "network", "create", "--driver", "bridge", "backend"]);
+        assert_eq!(networks[2].name(), "llm-net");
+        assert_eq!(networks[2].create_args(), vec![
+            "network", "create", "--driver", "bridge",
+            "--subnet", "172.28.0.0/16",
+            "--gateway", "172.28.0.1",
+            "llm-net"]);
+        assert!(NetworkSpec::Name("container:abc".to_string()).is_builtin());
+    }
+}
@@ -0,0 +1,288 @@
+//! Host port conflict detection before `docker run`.
+//!
+//! Docker only reports a taken host port after creating the container, with
+//! a terse bind error. Checking up front lets the tool name the port and the
+//! process (or managed container) holding it.
+
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::net::{TcpListener, UdpSocket};
+use std::process::Command;
+
+use super::engine::ContainerEngine;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    Tcp,
+    Udp,
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Protocol::Tcp => "tcp",
+            Protocol::Udp => "udp",
+        })
+    }
+}
+
+//------------------------------------------------------------------------------
+/// A host port published with `-p`.
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublishedPort {
+    /// Host address to bind, if given (`127.0.0.1:8080:80`)
+    pub host_ip: Option<String>,
+    pub host_port: u16,
+    pub protocol: Protocol,
+}
+
+//------------------------------------------------------------------------------
+/// Parse a `-p` value: `[ip:]host_port:container_port[/proto]`. Specs
+/// without a fixed host port (`80`, `8000-8010:80` ranges) return None.
+//------------------------------------------------------------------------------
+pub fn parse_publish_spec(spec: &str) -> Option<PublishedPort> {
+    let (ports, protocol) = match spec.rsplit_once('/') {
+        Some((ports, "udp")) => (ports, Protocol::Udp),
+        Some((ports, _)) => (ports, Protocol::Tcp),
+        None => (spec, Protocol::Tcp),
+    };
+
+    let parts: Vec<&str> = ports.rsplitn(3, ':').collect();
+    let (host_port, host_ip) = match parts.as_slice() {
+        [_container, host] => (*host, None),
+        [_container, host, ip] => (*host, Some(ip.trim_matches(['[', ']']).to_string())),
+        _ => return None,
+    };
+
+    Some(PublishedPort {
+        host_ip: host_ip.filter(|ip| !ip.is_empty()),
+        host_port: host_port.parse().ok().filter(|&p| p != 0)?,
+        protocol,
+    })
+}
+
+//------------------------------------------------------------------------------
+/// Host ports published by a run argv (`-p`/`--publish`). Empty when the
+/// container uses host networking, where published ports are ignored.
+//------------------------------------------------------------------------------
+pub fn published_host_ports(argv: &[String]) -> Vec<PublishedPort> {
+    let host_network = argv
+        .windows(2)
+        .any(|w| w[0] == "--network" && w[1] == "host")
+        || argv.iter().any(|a| a == "--network=host");
+    if host_network {
+        return vec![];
+    }
+
+    argv.windows(2)
+        .filter(|w| w[0] == "-p" || w[0] == "--publish")
+        .filter_map(|w| parse_publish_spec(&w[1]))
+        .collect()
+}
+
+/// True if something else holds the port. Other bind failures (a privileged
+/// port without root, an address this host doesn't have) are left for the
+/// engine to report, since docker binds with its own privileges.
+fn is_port_in_use(port: &PublishedPort) -> bool {
+    let ip = port.host_ip.as_deref().unwrap_or("0.0.0.0");
+    let result = match port.protocol {
+        Protocol::Tcp => TcpListener::bind((ip, port.host_port)).map(drop),
+        Protocol::Udp => UdpSocket::bind((ip, port.host_port)).map(drop),
+    };
+    matches!(result, Err(e) if e.kind() == ErrorKind::AddrInUse)
+}
+
+//------------------------------------------------------------------------------
+/// Socket inodes bound to `port` in /proc/net/{tcp,udp}[6] content. For TCP
+/// only listening sockets (state 0A) count.
+//------------------------------------------------------------------------------
+pub fn parse_proc_net_inodes(content: &str, port: u16, protocol: Protocol) -> Vec<u64> {
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let local_port = fields.get(1)?.rsplit_once(':')?.1;
+            let state = fields.get(3)?;
+            let inode = fields.get(9)?;
+            let bound = u16::from_str_radix(local_port, 16).ok()? == port;
+            let listening = protocol == Protocol::Udp || *state == "0A";
+            (bound && listening).then(|| inode.parse().ok()).flatten()
+        })
+        .filter(|&inode| inode != 0)
+        .collect()
+}
+
+/// Process (pid, name) owning one of `inodes`, by scanning /proc/*/fd.
+fn find_socket_owner(inodes: &[u64]) -> Option<(u32, String)> {
+    let targets: Vec<String> = inodes.iter().map(|i| format!("socket:[{}]", i)).collect();
+    for entry in fs::read_dir("/proc").ok()?.flatten() {
+        let Ok(pid) = entry.file_name().to_string_lossy().parse::<u32>() else {
+            continue;
+        };
+        let Ok(fds) = fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        let owns = fds.flatten().any(|fd| {
+            fs::read_link(fd.path())
+                .is_ok_and(|link| targets.iter().any(|t| link.as_os_str() == t.as_str()))
+        });
+        if owns {
+            let name = fs::read_to_string(entry.path().join("comm"))
+                .map(|c| c.trim().to_string())
+                .unwrap_or_else(|_| "unknown".to_string());
+            return Some((pid, name));
+        }
+    }
+    None
+}
+
+/// Process holding a host port, if it can be determined.
+fn find_port_owner(port: &PublishedPort) -> Option<(u32, String)> {
+    let tables: &[&str] = match port.protocol {
+        Protocol::Tcp => &["/proc/net/tcp", "/proc/net/tcp6"],
+        Protocol::Udp => &["/proc/net/udp", "/proc/net/udp6"],
+    };
+    let inodes: Vec<u64> = tables
+        .iter()
+        .filter_map(|t| fs::read_to_string(t).ok())
+        .flat_map(|c| parse_proc_net_inodes(&c, port.host_port, port.protocol))
+        .collect();
+    if inodes.is_empty() {
+        return None;
+    }
+    find_socket_owner(&inodes)
+}
+
+/// Name of a running container publishing `port`, if any.
+fn find_container_publishing(engine: ContainerEngine, port: u16) -> Option<String> {
+    let output = Command::new(engine.binary())
+        .args([
+            "ps",
+            "--filter", &format!("publish={}", port),
+            "--format", "{{.Names}}",
+        ])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+//------------------------------------------------------------------------------
+/// Describe who holds `port`: a container, a process, or unknown.
+//------------------------------------------------------------------------------
+fn describe_conflict(engine: ContainerEngine, port: &PublishedPort) -> String {
+    let address = match &port.host_ip {
+        Some(ip) => format!("{}:{}", ip, port.host_port),
+        None => port.host_port.to_string(),
+    };
+    let holder = if let Some(container) = find_container_publishing(engine, port.host_port) {
+        format!("container '{}'", container)
+    } else if let Some((pid, name)) = find_port_owner(port) {
+        format!("{} (pid {})", name, pid)
+    } else {
+        "another process".to_string()
+    };
+    format!("host port {}/{} is already in use by {}", address, port.protocol, holder)
+}
+
+//------------------------------------------------------------------------------
+/// Fail if any host port published by `argv` is already bound.
+//------------------------------------------------------------------------------
+pub fn check_port_conflicts(engine: ContainerEngine, argv: &[String]) -> Result<(), String> {
+    let conflicts: Vec<String> = published_host_ports(argv)
+        .iter()
+        .filter(|port| is_port_in_use(port))
+        .map(|port| describe_conflict(engine, port))
+        .collect();
+
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "Port conflict{}:\n  {}\nFree the port(s) or change ports in run_configuration.yml.",
+        if conflicts.len() > 1 { "s" } else { "" },
+        conflicts.join("\n  ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_publish_spec() {
+        assert_eq!(parse_publish_spec("8080:80"), Some(PublishedPort {
+            host_ip: None,
+            host_port: 8080,
+            protocol: Protocol::Tcp,
+        }));
+        assert_eq!(parse_publish_spec("127.0.0.1:8080:80/udp"), Some(PublishedPort {
+            host_ip: Some("127.0.0.1".to_string()),
+            host_port: 8080,
+            protocol: Protocol::Udp,
+        }));
+        assert_eq!(parse_publish_spec("[::1]:9000:9000").unwrap().host_ip.as_deref(), Some("::1"));
+        assert_eq!(parse_publish_spec("80"), None);
+        assert_eq!(parse_publish_spec("8000-8010:80"), None);
+
+        let ports = published_host_ports(&argv(&["docker", "run", "-p", "8080:80", "img"]));
+        assert_eq!(ports.len(), 1);
+        let host = argv(&["docker", "run", "--network", "host", "-p", "8080:80", "img"]);
+        assert!(published_host_ports(&host).is_empty());
+    }
+
+    #[test]
+    fn test_parse_proc_net_inodes() {
+        let content = "\
+  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 4242 1 0
+   1: 0100007F:1F90 0100007F:C350 01 00000000:00000000 00:00000000 00000000  1000        0 4343 1 0
+   2: 00000000:0016 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 99 1 0
+";
+        assert_eq!(parse_proc_net_inodes(content, 8080, Protocol::Tcp), vec![4242]);
+        assert_eq!(parse_proc_net_inodes(content, 22, Protocol::Tcp), vec![99]);
+        assert!(parse_proc_net_inodes(content, 9999, Protocol::Tcp).is_empty());
+    }
+
+    #[test]
+    fn test_check_port_conflicts_names_the_process() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let spec = format!("127.0.0.1:{}:80", port);
+        let err = check_port_conflicts(
+            ContainerEngine::Docker,
+            &argv(&["docker", "run", "-p", &spec, "img"])).unwrap_err();
+        assert!(err.contains(&format!("127.0.0.1:{}/tcp is already in use by", port)), "{}", err);
+        assert!(err.contains(&format!("(pid {})", std::process::id())), "{}", err);
+
+        drop(listener);
+        assert!(check_port_conflicts(
+            ContainerEngine::Docker,
+            &argv(&["docker", "run", "-p", &spec, "img"])).is_ok());
+    }
+
+    #[test]
+    fn test_only_addr_in_use_is_a_conflict() {
+        // Not an address of this host: bind fails, but nothing holds the port
+        let port = PublishedPort {
+            host_ip: Some("192.0.2.1".to_string()),
+            host_port: 8080,
+            protocol: Protocol::Tcp,
+        };
+        assert!(TcpListener::bind(("192.0.2.1", 8080)).is_err());
+        assert!(!is_port_in_use(&port));
+    }
+}
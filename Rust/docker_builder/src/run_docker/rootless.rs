@@ -0,0 +1,98 @@
+//! Rootless Docker/Podman detection.
+//!
+//! Under rootless Docker the daemon runs as the invoking user and container
+//! uid 0 maps to that user on the host, while other container uids map into
+//! the user's subordinate range. That changes how bind mounts are owned and
+//! which container user can reach host sockets (X11, PulseAudio). Rootless
+//! Podman behaves the same way, but can keep the host uid with
+//! `--userns keep-id`.
+
+use std::process::Command;
+
+use super::engine::ContainerEngine;
+
+//------------------------------------------------------------------------------
+/// True if DOCKER_HOST points at a per-user daemon socket
+/// (e.g. `unix:///run/user/1000/docker.sock`).
+//------------------------------------------------------------------------------
+pub fn docker_host_is_rootless(docker_host: &str) -> bool {
+    docker_host.contains("/run/user/")
+}
+
+//------------------------------------------------------------------------------
+/// True if `docker info` SecurityOptions output includes `name=rootless`.
+//------------------------------------------------------------------------------
+pub fn security_options_indicate_rootless(security_options: &str) -> bool {
+    security_options.contains("name=rootless")
+}
+
+//------------------------------------------------------------------------------
+/// Detect whether the docker CLI talks to a rootless daemon.
+/// Checks DOCKER_HOST first, then asks the daemon; false if unreachable.
+//------------------------------------------------------------------------------
+pub fn detect_rootless_docker() -> bool {
+    if let Ok(docker_host) = std::env::var("DOCKER_HOST")
+        && docker_host_is_rootless(&docker_host)
+    {
+        return true;
+    }
+
+    Command::new("docker")
+        .args(["info", "--format", "{{json .SecurityOptions}}"])
+        .output()
+        .map(|out| {
+            out.status.success()
+                && security_options_indicate_rootless(
+                    &String::from_utf8_lossy(&out.stdout))
+        })
+        .unwrap_or(false)
+}
+
+//------------------------------------------------------------------------------
+/// Detect whether Podman runs rootless. Checks CONTAINER_HOST (podman remote)
+/// first, then `podman info`; falls back to "not running as root".
+//------------------------------------------------------------------------------
+pub fn detect_rootless_podman() -> bool {
+    if let Ok(container_host) = std::env::var("CONTAINER_HOST")
+        && docker_host_is_rootless(&container_host)
+    {
+        return true;
+    }
+
+    let output = Command::new("podman")
+        .args(["info", "--format", "{{.Host.Security.Rootless}}"])
+        .output();
+    match output {
+        Ok(out) if out.status.success() => {
+            String::from_utf8_lossy(&out.stdout).trim() == "true"
+        }
+        _ => !nix::unistd::getuid().is_root(),
+    }
+}
+
+//------------------------------------------------------------------------------
+/// Detect rootless mode for the given engine.
+//------------------------------------------------------------------------------
+pub fn detect_rootless(engine: ContainerEngine) -> bool {
+    match engine {
+        ContainerEngine::Docker => detect_rootless_docker(),
+        ContainerEngine::Podman => detect_rootless_podman(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rootless_indicators() {
+        assert!(docker_host_is_rootless("unix:///run/user/1000/docker.sock"));
+        assert!(!docker_host_is_rootless("unix:///var/run/docker.sock"));
+        assert!(!docker_host_is_rootless("tcp://10.0.0.2:2376"));
+
+        assert!(security_options_indicate_rootless(
+            r#"["name=seccomp,profile=builtin","name=rootless","name=cgroupns"]"#));
+        assert!(!security_options_indicate_rootless(
+            r#"["name=apparmor","name=seccomp,profile=builtin"]"#));
+    }
+}
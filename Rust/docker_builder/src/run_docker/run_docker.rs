@@ -2,15 +2,31 @@
 
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::Arc;
+
+use serde::Serialize;
 
 use crate::configuration::build_docker_configuration::BuildDockerConfiguration;
+use crate::configuration::overlay::{load_merged_yaml, overlay_paths};
 use crate::configuration::run_docker_configuration::{
-    RunConfiguration, RunDockerConfiguration};
+    RunConfiguration, RunDockerConfiguration, RunDockerConfigurationData, VolumeMount};
+use super::container_name::{
+    render_name_template, resolve_container_name, NameCollisionPolicy,
+    NameContext};
+use super::digest::{split_digest, validate_digest, verify_image_digest};
+use super::engine::ContainerEngine;
+use super::rootless::detect_rootless;
+use super::secrets::{plan_secrets, prepare_secrets, PreparedSecrets};
+use super::shell::join_command;
 use super::build_docker_run_command::{
     BuildDockerRunCommandConfiguration,
-    build_docker_run_command,
-    build_docker_run_command_with_no_gpu,
+    build_docker_run_plan,
+    build_docker_run_plan_with_no_gpu,
 };
+use super::run_plan::{RunOption, RunPlan};
+
+/// Label on every container started by docker_builder (see `stats`).
+pub const MANAGED_BY_LABEL: &str = "managed-by=docker_builder";
 
 //------------------------------------------------------------------------------
 /// Arguments from CLI
@@ -26,28 +42,175 @@ pub struct RunDockerArgs {
     pub no_gpu: bool,
     pub gui: bool,
     pub audio: bool,
+    pub user: Option<String>,
+    /// IPC namespace (e.g. `host`); overrides `ipc` in YAML
+    pub ipc: Option<String>,
+    /// PID namespace (e.g. `host`); overrides `pid` in YAML
+    pub pid: Option<String>,
+    /// Run privileged (also enabled by `privileged: true` in YAML)
+    pub privileged: bool,
+    /// Mount the current directory at this path and work there; overrides
+    /// `mount_cwd` in YAML
+    pub mount_cwd: Option<String>,
+    /// Container engine; overrides `engine` in run_configuration.yml
+    pub engine: Option<ContainerEngine>,
+    /// Pull the image if it is missing locally and looks like a registry
+    /// reference
+    pub pull: bool,
+    /// Profile from the `profiles:` section of run_configuration.yml
+    pub profile: Option<String>,
+    /// Container name or name template; overrides `container_name` in YAML
+    pub name: Option<String>,
+    /// Collision policy; overrides `name_collision` in YAML
+    pub name_collision: Option<NameCollisionPolicy>,
+    /// Overlays deep-merged over run_configuration.yml, after
+    /// run_configuration.override.yml
+    pub config_overlays: Vec<PathBuf>,
+    /// `--var name=value` overrides for `vars:` in both YAML files
+    pub vars: Vec<(String, String)>,
+    /// Raw docker run arguments (after `--`), appended after YAML
+    /// `extra_args`
+    pub extra_args: Vec<String>,
+}
+
+//------------------------------------------------------------------------------
+/// A fully resolved run command plus the configuration it was built from,
+/// for `--dry-run` / `--output json`.
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedRunCommand {
+    /// Full argv, starting with the engine binary
+    pub argv: Vec<String>,
+    /// The argv before flattening: image, mounts, ports, env and flags
+    pub plan: RunPlan,
+    /// argv joined and shell-escaped, ready to paste into a shell
+    pub command_line: String,
+    pub docker_image_name: String,
+    pub engine: ContainerEngine,
+    pub rootless: bool,
+    /// Selected run configuration profile
+    pub profile: Option<String>,
+    /// Rendered container name, if any
+    pub container_name: Option<String>,
+    pub name_collision: NameCollisionPolicy,
+    /// Canonicalized build directory (working directory for the command)
+    pub build_dir: PathBuf,
+    /// run_configuration.yml in the richer format, if it parsed as such
+    pub run_configuration: Option<RunConfiguration>,
+    /// run_configuration.yml in the legacy format (volumes/ports only)
+    pub legacy_run_configuration: RunDockerConfigurationData,
+    /// Planned secret mounts; env-sourced ones have placeholder sources until
+    /// `prepare_secrets` is called
+    pub secret_mounts: Vec<VolumeMount>,
+    /// Secret files backing the argv's secret mounts, once prepared;
+    /// env-sourced files are removed when the last clone is dropped, so keep
+    /// this alive until the container exits.
+    #[serde(skip)]
+    pub secrets: Arc<PreparedSecrets>,
+}
+
+impl ResolvedRunCommand {
+    //--------------------------------------------------------------------------
+    /// Write env-sourced secrets to files and point the command's secret
+    /// mounts at them. Call right before running; with `keep` (detached
+    /// runs) the files outlive this value and are reported.
+    //--------------------------------------------------------------------------
+    pub fn prepare_secrets(&mut self, keep: bool) -> Result<(), String> {
+        let secrets = match self.run_configuration.as_ref().and_then(|rc| rc.secrets.as_ref()) {
+            Some(secrets) if !secrets.is_empty() => prepare_secrets(secrets, keep)?,
+            _ => return Ok(()),
+        };
+        if let Some(dir) = &secrets.kept_dir {
+            eprintln!(
+                "    Note: detached run keeps secret files in {}; remove \
+                 it after the container stops",
+                dir.display());
+        }
+
+        for (planned, prepared) in self.secret_mounts.iter().zip(&secrets.mounts) {
+            let mount = self.plan.options.iter_mut().find_map(|option| match option {
+                RunOption::Mount(mount)
+                    if mount.source == planned.host_path
+                        && mount.target == planned.container_path => Some(mount),
+                _ => None,
+            });
+            if let Some(mount) = mount {
+                mount.source = prepared.host_path.clone();
+            }
+        }
+        self.secret_mounts = secrets.mounts.clone();
+        self.argv = self.plan.to_argv();
+        self.command_line = join_command(&self.argv);
+        self.secrets = Arc::new(secrets);
+        Ok(())
+    }
+
+    //--------------------------------------------------------------------------
+    /// Make sure the image is available before running: warn if it is
+    /// missing, pull registry images with `pull` (or on confirmation), and
+    /// verify the pinned digest if `verify_digest` is set. Only call this on
+    /// the way to running - it may hit the network or prompt on stdin.
+    //--------------------------------------------------------------------------
+    pub fn ensure_image(&self, pull: bool) -> Result<(), String> {
+        let engine = self.engine;
+        let image = &self.docker_image_name;
+        if !check_image_exists(engine, image) {
+            eprintln!("\n⚠ Warning: image '{}' not found locally ({}).", image, engine);
+            if looks_like_registry_reference(image) {
+                if pull || confirm_pull(image) {
+                    pull_image(engine, image)?;
+                } else {
+                    eprintln!("  Pull it with --pull, or:");
+                    eprintln!("  {} pull {}\n", engine, image);
+                }
+            } else {
+                eprintln!("  You may need to build it first:");
+                eprintln!("  docker_builder build {}\n", self.build_dir.display());
+            }
+        }
+
+        if self.run_configuration.as_ref().is_some_and(|rc| rc.verify_digest) {
+            verify_image_digest(engine, image)?;
+        }
+        Ok(())
+    }
 }
 
 //------------------------------------------------------------------------------
 /// Load configs and build docker run command.
 ///
+/// Thin wrapper over `resolve_run_command` returning only the argv and image.
+//------------------------------------------------------------------------------
+pub fn build_run_command_from_args(
+    args: &RunDockerArgs,
+) -> Result<(Vec<String>, String), String> {
+    let resolved = resolve_run_command(args)?;
+    Ok((resolved.argv, resolved.docker_image_name))
+}
+
+//------------------------------------------------------------------------------
+/// Load configs and resolve the docker run command.
+///
 /// # Steps:
 /// 1. Load build_configuration.yml from build_dir (for docker_image_name)
 /// 2. Load run_configuration.yml from build_dir.
-///    Tries RunConfiguration (richer YAML format) first; falls back to legacy
-///    RunDockerConfiguration (volumes/ports only).
+///    Tries RunConfiguration (richer YAML format) first; files without
+///    `docker_image_name` fall back to legacy RunDockerConfiguration
+///    (volumes/ports only).
 /// 3. Populate BuildDockerRunCommandConfiguration from args + configs
 /// 4. Build docker run command (Vec<String>)
 ///
 /// CLI flags (gpu_id, interactive, etc.) override corresponding YAML fields.
+/// Progress is reported on stderr so stdout stays clean for `--output json`.
+/// Nothing is pulled or written here; see `ResolvedRunCommand::ensure_image`.
 ///
 /// # Returns
-/// * `Ok((Vec<String>, String))` - Command args and image name
+/// * `Ok(ResolvedRunCommand)` - Command args, image name, resolved config
 /// * `Err(String)` - Error loading configs or building command
 //------------------------------------------------------------------------------
-pub fn build_run_command_from_args(
+pub fn resolve_run_command(
     args: &RunDockerArgs,
-) -> Result<(Vec<String>, String), String> {
+) -> Result<ResolvedRunCommand, String> {
     // Resolve build directory
     let build_dir = args.build_dir
         .canonicalize()
@@ -55,7 +218,7 @@ pub fn build_run_command_from_args(
             "Invalid build directory '{}': {}",
             args.build_dir.display(), e))?;
 
-    println!("==> Loading configurations from: {}", build_dir.display());
+    eprintln!("==> Loading configurations from: {}", build_dir.display());
 
     // 1. Load build_configuration.yml (for docker_image_name)
     let config_file = build_dir.join("build_configuration.yml");
@@ -66,65 +229,155 @@ pub fn build_run_command_from_args(
         ));
     }
 
-    let build_config = BuildDockerConfiguration::load_data(Some(&config_file))?;
+    let build_config = BuildDockerConfiguration::load_data_with_vars(
+        Some(&config_file), &args.vars)?;
     let docker_image_name = build_config.docker_image_name.clone();
-
-    println!("    Docker image: {}", docker_image_name);
-
-    // Check if image exists
-    if !check_image_exists(&docker_image_name) {
-        eprintln!(
-            "\n⚠ Warning: Docker image '{}' not found locally.",
-            docker_image_name);
-        eprintln!("  You may need to build it first:");
-        eprintln!("  docker_builder build {}\n", build_dir.display());
+    if let Some(digest) = split_digest(&docker_image_name).1 {
+        validate_digest(digest)?;
     }
 
+    eprintln!("    Docker image: {}", docker_image_name);
+
     // 2. Load run_configuration.yml
     //    Try richer RunConfiguration first (has gpus, shm_size, env, ipc, command).
     //    If the file has `docker_image_name`, it parses as RunConfiguration.
     //    Otherwise fall back to legacy (volumes/ports only).
+    //    run_configuration.override.yml and --config-overlay files are
+    //    deep-merged over it first.
     let run_config_file = build_dir.join("run_configuration.yml");
+    let overlays = overlay_paths(&build_dir, &args.config_overlays)?;
 
-    let (yaml_run_config, legacy_run_config) = if run_config_file.exists() {
-        println!(
+    let has_run_config = run_config_file.exists() || !overlays.is_empty();
+
+    let (yaml_run_config, legacy_run_config) = if has_run_config {
+        eprintln!(
             "    Loading run configuration from: {}",
             run_config_file.display());
+        if let Some(profile) = &args.profile {
+            eprintln!("    Profile: {}", profile);
+        }
+        for overlay in &overlays {
+            eprintln!("    Applying overlay: {}", overlay.display());
+        }
 
         // Try richer format first
-        match RunConfiguration::load_from_path(&run_config_file) {
+        let profile = args.profile.as_deref();
+        match RunConfiguration::load_with_overlays(
+            &run_config_file, profile, &overlays, &args.vars)
+        {
             Ok(rc) => {
-                println!("    Run config: richer YAML format (docker_runner style)");
+                eprintln!("    Run config: richer YAML format (docker_runner style)");
                 (Some(rc), Default::default())
             }
-            Err(_) => {
+            Err(error) => {
+                // A file naming its image is in the richer format, so report
+                // why it did not load rather than run it as legacy
+                let merged = load_merged_yaml(&run_config_file, profile, &overlays)?;
+                if merged.get("docker_image_name").is_some() {
+                    return Err(format!(
+                        "Invalid run configuration {}: {}",
+                        run_config_file.display(), error));
+                }
                 // Fall back to legacy (volumes/ports only, no docker_image_name required)
-                let legacy = RunDockerConfiguration::load_data(
-                    Some(&run_config_file))?;
-                println!("    Run config: legacy format (volumes/ports only)");
-                println!("    Volumes: {}", legacy.volumes.len());
-                println!("    Ports: {}", legacy.ports.len());
+                let legacy = RunDockerConfiguration::load_data_with_overlays(
+                    &run_config_file, profile, &overlays, &args.vars)?;
+                eprintln!("    Run config: legacy format (volumes/ports only)");
+                eprintln!("    Volumes: {}", legacy.volumes.len());
+                eprintln!("    Ports: {}", legacy.ports.len());
                 (None, legacy)
             }
         }
+    } else if let Some(profile) = &args.profile {
+        return Err(format!(
+            "--profile {} given but {} does not exist",
+            profile, run_config_file.display()));
     } else {
-        println!(
+        eprintln!(
             "    Warning: Run configuration file not found (using defaults)");
         (None, Default::default())
     };
 
+    // Engine: CLI --engine, then YAML `engine`, then docker
+    let engine = args.engine
+        .or_else(|| yaml_run_config.as_ref().and_then(|rc| rc.engine))
+        .unwrap_or_default();
+    if engine != ContainerEngine::Docker {
+        eprintln!("    Engine: {}", engine);
+    }
+
+    // Rootless changes uid mapping; YAML `rootless` overrides detection
+    let rootless = yaml_run_config
+        .as_ref()
+        .and_then(|rc| rc.rootless)
+        .unwrap_or_else(|| detect_rootless(engine));
+    if rootless {
+        eprintln!("    Rootless {}: yes", engine);
+    }
+
+    // Container name: CLI --name, then YAML; templates are rendered, and
+    // `suffix` picks a free name now (fail/replace act just before running)
+    let name_collision = args.name_collision
+        .or_else(|| yaml_run_config.as_ref().and_then(|rc| rc.name_collision))
+        .unwrap_or_default();
+    let container_name = match args.name.as_ref().or(yaml_run_config
+        .as_ref()
+        .and_then(|rc| rc.container_name.as_ref()))
+    {
+        Some(template) => {
+            let context = NameContext::now(&docker_image_name, args.profile.as_deref());
+            let name = render_name_template(template, &context)?;
+            let name = resolve_container_name(engine, &name, name_collision);
+            eprintln!("    Container name: {}", name);
+            Some(name)
+        }
+        None => None,
+    };
+
+    // Secrets: only planned here; files are written by prepare_secrets
+    let secret_mounts = match yaml_run_config.as_ref().and_then(|rc| rc.secrets.as_ref()) {
+        Some(secrets) => plan_secrets(secrets)?,
+        None => vec![],
+    };
+    if !secret_mounts.is_empty() {
+        eprintln!("    Secrets: {}", secret_mounts.len());
+    }
+
     // 3. Populate BuildDockerRunCommandConfiguration
-    let mut docker_run_config = BuildDockerRunCommandConfiguration::default();
-    docker_run_config.docker_image_name = docker_image_name.clone();
-    docker_run_config.run_config = legacy_run_config;
-    docker_run_config.yaml_run_config = yaml_run_config;
-
-    // Set fields from CLI args
-    docker_run_config.is_interactive = args.interactive;
-    docker_run_config.is_detached = args.detached;
-    docker_run_config.use_host_network = args.network_host;
-    docker_run_config.enable_gui = args.gui;
-    docker_run_config.enable_audio = args.audio;
+    let mut docker_run_config = BuildDockerRunCommandConfiguration {
+        docker_image_name: docker_image_name.clone(),
+        run_config: legacy_run_config.clone(),
+        yaml_run_config: yaml_run_config.clone(),
+        // Set fields from CLI args
+        is_interactive: args.interactive,
+        is_detached: args.detached,
+        use_host_network: args.network_host,
+        enable_gui: args.gui,
+        enable_audio: args.audio,
+        user: args.user.clone(),
+        ipc: args.ipc.clone(),
+        pid: args.pid.clone(),
+        privileged: args.privileged,
+        mount_cwd: args.mount_cwd.clone(),
+        labels: vec![MANAGED_BY_LABEL.to_string()],
+        extra_args: args.extra_args.clone(),
+        rootless,
+        engine,
+        secret_mounts: secret_mounts.clone(),
+        container_name: container_name.clone(),
+        ..Default::default()
+    };
+
+    // Networks from YAML; host networking replaces them
+    if let Some(networks) = yaml_run_config.as_ref().and_then(|rc| rc.networks.as_ref()) {
+        if args.network_host {
+            eprintln!("    Note: --network-host given; ignoring YAML networks");
+        } else {
+            docker_run_config.networks = networks
+                .iter()
+                .map(|n| n.name().to_string())
+                .collect();
+        }
+    }
 
     if let Some(entrypoint) = &args.entrypoint {
         docker_run_config.entrypoint = Some(entrypoint.clone());
@@ -138,16 +391,32 @@ pub fn build_run_command_from_args(
     }
 
     // 4. Build docker run command
-    println!("\n==> Building docker run command...");
-    let docker_cmd = if args.no_gpu {
-        build_docker_run_command_with_no_gpu(&docker_run_config)?
+    eprintln!("\n==> Building docker run command...");
+    let plan = if args.no_gpu {
+        build_docker_run_plan_with_no_gpu(&docker_run_config)?
     } else {
-        build_docker_run_command(&docker_run_config)?
+        build_docker_run_plan(&docker_run_config)?
     };
-
-    println!("    Command ready ({} args)", docker_cmd.len());
-
-    Ok((docker_cmd, docker_image_name))
+    let docker_cmd = plan.to_argv();
+
+    eprintln!("    Command ready ({} args)", docker_cmd.len());
+
+    Ok(ResolvedRunCommand {
+        command_line: join_command(&docker_cmd),
+        argv: docker_cmd,
+        plan,
+        docker_image_name,
+        engine,
+        rootless,
+        profile: args.profile.clone(),
+        container_name,
+        name_collision,
+        build_dir,
+        run_configuration: yaml_run_config,
+        legacy_run_configuration: legacy_run_config,
+        secret_mounts,
+        secrets: Arc::default(),
+    })
 }
 
 //------------------------------------------------------------------------------
@@ -182,10 +451,38 @@ pub fn execute_docker_run_command(
     Ok(())
 }
 
-/// Check if a Docker image exists locally.
-pub fn check_image_exists(image_name: &str) -> bool {
-    let output = Command::new("docker")
-        .args(&["images", "-q", image_name])
+//------------------------------------------------------------------------------
+/// Execute a detached run command and return the new container's ID.
+//------------------------------------------------------------------------------
+pub fn execute_detached_run_command(
+    cmd: &[String],
+    working_dir: &Path,
+) -> Result<String, String> {
+    let output = Command::new(&cmd[0])
+        .args(&cmd[1..])
+        .current_dir(working_dir)
+        .stderr(std::process::Stdio::inherit())
+        .output()
+        .map_err(|e| format!("Failed to execute docker command: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "Docker run failed with exit code: {}",
+            output.status.code().unwrap_or(-1)));
+    }
+
+    let container_id = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if container_id.is_empty() {
+        return Err("Docker run did not print a container ID".to_string());
+    }
+    println!("Started container {}", container_id);
+    Ok(container_id)
+}
+
+/// Check if an image exists locally for the given engine.
+pub fn check_image_exists(engine: ContainerEngine, image_name: &str) -> bool {
+    let output = Command::new(engine.binary())
+        .args(["images", "-q", image_name])
         .output();
 
     match output {
@@ -194,6 +491,56 @@ pub fn check_image_exists(image_name: &str) -> bool {
     }
 }
 
+//------------------------------------------------------------------------------
+/// True if an image name points at a registry rather than a local build: it
+/// has a namespace or registry host (`org/app`, `ghcr.io/org/app`,
+/// `localhost:5000/app`) or a digest. Bare names like `my-app:latest` are
+/// assumed to come from `docker_builder build`.
+//------------------------------------------------------------------------------
+pub fn looks_like_registry_reference(image_name: &str) -> bool {
+    let image_name = image_name.trim();
+    !image_name.is_empty() && (image_name.contains('/') || image_name.contains('@'))
+}
+
+/// Ask on the terminal whether to pull; false when not interactive.
+fn confirm_pull(image_name: &str) -> bool {
+    use std::io::{BufRead, IsTerminal, Write};
+
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        return false;
+    }
+    eprint!("  Pull '{}' now? [y/N] ", image_name);
+    let _ = std::io::stderr().flush();
+
+    let mut answer = String::new();
+    if std::io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+//------------------------------------------------------------------------------
+/// Pull an image. Pull progress goes to stderr so stdout stays clean for
+/// `--output json`.
+//------------------------------------------------------------------------------
+pub fn pull_image(engine: ContainerEngine, image_name: &str) -> Result<(), String> {
+    eprintln!("==> {} pull {}", engine, image_name);
+    let status = Command::new(engine.binary())
+        .args(["pull", image_name])
+        .stdout(std::io::stderr())
+        .status()
+        .map_err(|e| format!("Failed to execute {} pull: {}", engine, e))?;
+
+    if !status.success() {
+        return Err(format!(
+            "{} pull {} failed with exit code: {}",
+            engine,
+            image_name,
+            status.code().unwrap_or(-1)));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -231,6 +578,19 @@ volumes:
             no_gpu: false,
             gui: true,
             audio: false,
+            user: None,
+            ipc: None,
+            pid: None,
+            privileged: false,
+            mount_cwd: None,
+            engine: None,
+            pull: false,
+            profile: None,
+            name: None,
+            name_collision: None,
+            config_overlays: vec![],
+            vars: vec![],
+            extra_args: vec![],
         };
 
         let result = build_run_command_from_args(&args);
@@ -256,6 +616,54 @@ volumes:
         assert_eq!(cmd.last().unwrap(), "test-image:latest");
     }
 
+    #[test]
+    fn test_resolve_run_command_serializes_to_json() {
+        let temp = TempDir::new().unwrap();
+        fs::write(
+            temp.path().join("build_configuration.yml"),
+            "docker_image_name: json-image:v1\nbase_image: ubuntu:24.04\n").unwrap();
+        fs::write(
+            temp.path().join("run_configuration.yml"),
+            "docker_image_name: json-image:v1\nenv:\n  MSG: hello world\n").unwrap();
+
+        let args = RunDockerArgs {
+            build_dir: temp.path().to_path_buf(),
+            gpu_id: None,
+            interactive: false,
+            detached: false,
+            entrypoint: None,
+            network_host: false,
+            no_gpu: true,
+            gui: false,
+            audio: false,
+            user: None,
+            ipc: None,
+            pid: None,
+            privileged: false,
+            mount_cwd: None,
+            engine: None,
+            pull: false,
+            profile: None,
+            name: None,
+            name_collision: None,
+            config_overlays: vec![],
+            vars: vec![],
+            extra_args: vec![],
+        };
+
+        let resolved = resolve_run_command(&args).unwrap();
+        assert_eq!(resolved.argv[0], "docker");
+        assert!(resolved.command_line.starts_with("docker run --rm"));
+        assert!(resolved.command_line.contains("--label managed-by=docker_builder json-image:v1"));
+        assert_eq!(resolved.build_dir, temp.path().canonicalize().unwrap());
+
+        let json: serde_json::Value = serde_json::to_value(&resolved).unwrap();
+        assert_eq!(json["docker_image_name"], "json-image:v1");
+        assert_eq!(json["engine"], "docker");
+        assert_eq!(json["argv"].as_array().unwrap().len(), resolved.argv.len());
+        assert_eq!(json["run_configuration"]["env"]["MSG"], "hello world");
+    }
+
     #[test]
     fn test_build_run_command_with_no_gpu_and_missing_run_config() {
         let temp = TempDir::new().unwrap();
@@ -279,6 +687,19 @@ dockerfile_components: []
             no_gpu: true,
             gui: false,
             audio: false,
+            user: None,
+            ipc: None,
+            pid: None,
+            privileged: false,
+            mount_cwd: None,
+            engine: None,
+            pull: false,
+            profile: None,
+            name: None,
+            name_collision: None,
+            config_overlays: vec![],
+            vars: vec![],
+            extra_args: vec![],
         };
 
         let result = build_run_command_from_args(&args);
@@ -346,6 +767,19 @@ command:
             no_gpu: false,
             gui: false,
             audio: false,
+            user: None,
+            ipc: None,
+            pid: None,
+            privileged: false,
+            mount_cwd: None,
+            engine: None,
+            pull: false,
+            profile: None,
+            name: None,
+            name_collision: None,
+            config_overlays: vec![],
+            vars: vec![],
+            extra_args: vec![],
         };
 
         let result = build_run_command_from_args(&args);
@@ -370,4 +804,165 @@ command:
         assert!(cmd.contains(&"python3".to_string()));
         assert!(cmd.contains(&"train.py".to_string()));
     }
+
+    #[test]
+    fn test_run_configuration_overlays_are_merged() {
+        let temp = TempDir::new().unwrap();
+        fs::write(
+            temp.path().join("build_configuration.yml"),
+            "docker_image_name: my-ml-image:latest\nbase_image: ubuntu:24.04\n").unwrap();
+        fs::write(temp.path().join("run_configuration.yml"), r#"
+docker_image_name: my-ml-image:latest
+gpus: all
+shm_size: 32g
+volumes:
+  - host_path: /data
+    container_path: /data
+"#).unwrap();
+        fs::write(temp.path().join("run_configuration.override.yml"), r#"
+gpus: [1]
+volumes:
+  - host_path: /mnt/fast/data
+    container_path: /data
+"#).unwrap();
+        let laptop = temp.path().join("laptop.yml");
+        fs::write(&laptop, "shm_size: 4g\n").unwrap();
+
+        let args = RunDockerArgs {
+            build_dir: temp.path().to_path_buf(),
+            gpu_id: None,
+            interactive: false,
+            detached: false,
+            entrypoint: None,
+            network_host: false,
+            no_gpu: false,
+            gui: false,
+            audio: false,
+            user: None,
+            ipc: None,
+            pid: None,
+            privileged: false,
+            mount_cwd: None,
+            engine: None,
+            pull: false,
+            profile: None,
+            name: None,
+            name_collision: None,
+            config_overlays: vec![laptop],
+            vars: vec![],
+            extra_args: vec![],
+        };
+
+        let (cmd, _) = build_run_command_from_args(&args).unwrap();
+        let joined = cmd.join(" ");
+        assert!(joined.contains("--gpus device=1"), "{}", joined);
+        assert!(joined.contains("--shm-size 4g"), "{}", joined);
+        assert!(joined.contains("-v /mnt/fast/data:/data"), "{}", joined);
+        assert!(!joined.contains("-v /data:/data"), "{}", joined);
+    }
+
+    #[test]
+    fn test_invalid_richer_run_config_is_an_error() {
+        let temp = TempDir::new().unwrap();
+        fs::write(
+            temp.path().join("build_configuration.yml"),
+            "docker_image_name: myimg:latest\nbase_image: ubuntu:24.04\n").unwrap();
+        fs::write(
+            temp.path().join("run_configuration.yml"),
+            "docker_image_name: myimg:latest\ngpus: all\nextra_hosts: [badentry]\n").unwrap();
+
+        let args = RunDockerArgs {
+            build_dir: temp.path().to_path_buf(),
+            gpu_id: None,
+            interactive: false,
+            detached: false,
+            entrypoint: None,
+            network_host: false,
+            no_gpu: false,
+            gui: false,
+            audio: false,
+            user: None,
+            ipc: None,
+            pid: None,
+            privileged: false,
+            mount_cwd: None,
+            engine: None,
+            pull: false,
+            profile: None,
+            name: None,
+            name_collision: None,
+            config_overlays: vec![],
+            vars: vec![],
+            extra_args: vec![],
+        };
+
+        // Not silently run as a legacy (volumes/ports only) configuration
+        let error = build_run_command_from_args(&args).unwrap_err();
+        assert!(error.contains("extra_hosts"), "{}", error);
+        assert!(error.contains("badentry"), "{}", error);
+    }
+
+    #[test]
+    fn test_secrets_are_written_only_when_prepared() {
+        let temp = TempDir::new().unwrap();
+        fs::write(
+            temp.path().join("build_configuration.yml"),
+            "docker_image_name: myimg:latest\nbase_image: ubuntu:24.04\n").unwrap();
+        fs::write(temp.path().join("run_configuration.yml"), r#"
+docker_image_name: myimg:latest
+secrets:
+  - env: HOME
+    target: /run/secrets/home
+"#).unwrap();
+
+        let args = RunDockerArgs {
+            build_dir: temp.path().to_path_buf(),
+            gpu_id: None,
+            interactive: false,
+            detached: false,
+            entrypoint: None,
+            network_host: false,
+            no_gpu: true,
+            gui: false,
+            audio: false,
+            user: None,
+            ipc: None,
+            pid: None,
+            privileged: false,
+            mount_cwd: None,
+            engine: None,
+            pull: false,
+            profile: None,
+            name: None,
+            name_collision: None,
+            config_overlays: vec![],
+            vars: vec![],
+            extra_args: vec![],
+        };
+
+        // Resolving (dry run, --output json) only plans the mount
+        let mut resolved = resolve_run_command(&args).unwrap();
+        assert!(resolved.command_line.contains("-v '<env:HOME>:/run/secrets/home:ro'"),
+            "{}", resolved.command_line);
+        assert!(resolved.secrets.mounts.is_empty());
+
+        resolved.prepare_secrets(false).unwrap();
+        let host_path = PathBuf::from(&resolved.secret_mounts[0].host_path);
+        assert_eq!(fs::read_to_string(&host_path).unwrap(), std::env::var("HOME").unwrap());
+        assert!(resolved.argv.contains(&format!("{}:/run/secrets/home:ro", host_path.display())));
+        assert!(!resolved.command_line.contains("<env:HOME>"));
+
+        drop(resolved);
+        assert!(!host_path.exists());
+    }
+
+    #[test]
+    fn test_looks_like_registry_reference() {
+        assert!(looks_like_registry_reference("nvidia/cuda:12.4.1-devel-ubuntu22.04"));
+        assert!(looks_like_registry_reference("ghcr.io/org/app:1.0"));
+        assert!(looks_like_registry_reference("localhost:5000/app"));
+        assert!(looks_like_registry_reference("app@sha256:abcd"));
+        assert!(!looks_like_registry_reference("my-app:latest"));
+        assert!(!looks_like_registry_reference(""));
+    }
 }
@@ -0,0 +1,478 @@
+//! Typed docker run plan - what the command builders produce before it is
+//! flattened into argv.
+//!
+//! Options keep the order the builders add them in, so `to_argv` matches the
+//! argv `run` executes. Library consumers can inspect or edit mounts, ports,
+//! env and flags here instead of re-parsing a `Vec<String>`.
+
+use serde::Serialize;
+use serde_yaml::{Mapping, Value};
+
+use super::engine::ContainerEngine;
+use super::shell::join_command;
+
+//------------------------------------------------------------------------------
+/// A bind mount or named volume (`-v source:target[:mode]`).
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Mount {
+    pub source: String,
+    pub target: String,
+    /// e.g. `ro` or `rw`
+    pub mode: Option<String>,
+}
+
+impl Mount {
+    /// The `-v` / compose volume string.
+    pub fn to_spec(&self) -> String {
+        match &self.mode {
+            Some(mode) => format!("{}:{}:{}", self.source, self.target, mode),
+            None => format!("{}:{}", self.source, self.target),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+/// One docker run option.
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunOption {
+    Mount(Mount),
+    Port { host: u16, container: u16 },
+    Env { key: String, value: String },
+    /// Any other option, e.g. `--shm-size 8g` or `--privileged`
+    Flag { name: String, value: Option<String> },
+    /// `extra_args` passed through verbatim
+    Raw(Vec<String>),
+}
+
+impl RunOption {
+    /// The option as docker run arguments.
+    pub fn to_args(&self) -> Vec<String> {
+        match self {
+            RunOption::Mount(mount) => vec!["-v".to_string(), mount.to_spec()],
+            RunOption::Port { host, container } => {
+                vec!["-p".to_string(), format!("{}:{}", host, container)]
+            }
+            RunOption::Env { key, value } => {
+                vec!["-e".to_string(), format!("{}={}", key, value)]
+            }
+            RunOption::Flag { name, value } => {
+                std::iter::once(name.clone()).chain(value.clone()).collect()
+            }
+            RunOption::Raw(args) => args.clone(),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+/// A resolved docker run: engine, options, image and container command.
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RunPlan {
+    pub engine: ContainerEngine,
+    pub options: Vec<RunOption>,
+    pub image: String,
+    /// Command (and arguments) run in the container, after the image
+    pub command: Vec<String>,
+}
+
+impl RunPlan {
+    pub fn new(engine: ContainerEngine, image: impl Into<String>) -> Self {
+        Self {
+            engine,
+            options: vec![],
+            image: image.into(),
+            command: vec![],
+        }
+    }
+
+    /// Add an option that takes a value, e.g. `--shm-size 8g`.
+    pub fn flag(&mut self, name: &str, value: impl Into<String>) {
+        self.options.push(RunOption::Flag {
+            name: name.to_string(),
+            value: Some(value.into()),
+        });
+    }
+
+    /// Add an option without a value, e.g. `--privileged`.
+    pub fn switch(&mut self, name: &str) {
+        self.options.push(RunOption::Flag { name: name.to_string(), value: None });
+    }
+
+    /// Add `name value` pairs, e.g. from `ContainerEngine::gpu_args`.
+    pub fn flag_pairs(&mut self, args: &[String]) {
+        for pair in args.chunks(2) {
+            match pair {
+                [name, value] => self.flag(name, value.clone()),
+                [name] => self.switch(name),
+                _ => {}
+            }
+        }
+    }
+
+    pub fn mount(&mut self, source: impl Into<String>, target: impl Into<String>, mode: Option<&str>) {
+        self.options.push(RunOption::Mount(Mount {
+            source: source.into(),
+            target: target.into(),
+            mode: mode.map(str::to_string),
+        }));
+    }
+
+    pub fn port(&mut self, host: u16, container: u16) {
+        self.options.push(RunOption::Port { host, container });
+    }
+
+    pub fn env(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.options.push(RunOption::Env { key: key.into(), value: value.into() });
+    }
+
+    /// Add arguments verbatim (no-op when empty).
+    pub fn raw(&mut self, args: Vec<String>) {
+        if !args.is_empty() {
+            self.options.push(RunOption::Raw(args));
+        }
+    }
+
+    pub fn mounts(&self) -> impl Iterator<Item = &Mount> {
+        self.options.iter().filter_map(|option| match option {
+            RunOption::Mount(mount) => Some(mount),
+            _ => None,
+        })
+    }
+
+    /// Published `(host, container)` ports.
+    pub fn ports(&self) -> impl Iterator<Item = (u16, u16)> + '_ {
+        self.options.iter().filter_map(|option| match option {
+            RunOption::Port { host, container } => Some((*host, *container)),
+            _ => None,
+        })
+    }
+
+    pub fn env_vars(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.options.iter().filter_map(|option| match option {
+            RunOption::Env { key, value } => Some((key.as_str(), value.as_str())),
+            _ => None,
+        })
+    }
+
+    /// Values of every `name` flag, in order (empty for switches).
+    pub fn flag_values(&self, name: &str) -> Vec<&str> {
+        self.options.iter().filter_map(|option| match option {
+            RunOption::Flag { name: n, value } if n == name => {
+                Some(value.as_deref().unwrap_or(""))
+            }
+            _ => None,
+        }).collect()
+    }
+
+    pub fn has_flag(&self, name: &str) -> bool {
+        self.options.iter().any(|option| {
+            matches!(option, RunOption::Flag { name: n, .. } if n == name)
+        })
+    }
+
+    /// Drop every `name` flag (e.g. `--name` before a throwaway run).
+    pub fn remove_flag(&mut self, name: &str) {
+        self.options.retain(|option| {
+            !matches!(option, RunOption::Flag { name: n, .. } if n == name)
+        });
+    }
+
+    /// The options as docker run arguments (between `run` and the image).
+    pub fn option_args(&self) -> Vec<String> {
+        self.options.iter().flat_map(RunOption::to_args).collect()
+    }
+
+    //--------------------------------------------------------------------------
+    /// Full argv: `<engine> run ...options... image ...command...`.
+    //--------------------------------------------------------------------------
+    pub fn to_argv(&self) -> Vec<String> {
+        let mut argv = vec![self.engine.binary().to_string(), "run".to_string()];
+        argv.extend(self.option_args());
+        argv.push(self.image.clone());
+        argv.extend(self.command.iter().cloned());
+        argv
+    }
+
+    /// argv joined and shell-escaped, ready to paste into a shell.
+    pub fn to_shell_string(&self) -> String {
+        join_command(&self.to_argv())
+    }
+
+    //--------------------------------------------------------------------------
+    /// The plan as one docker compose service (the value under
+    /// `services.<name>`). `--rm` and `-d` are dropped since compose manages
+    /// the container lifecycle; options compose cannot express, including raw
+    /// `extra_args`, are an error rather than silently lost.
+    //--------------------------------------------------------------------------
+    pub fn to_compose_service(&self) -> Result<Value, String> {
+        let mut service = Mapping::new();
+        let mut environment = Mapping::new();
+        let mut healthcheck = Mapping::new();
+        let mut networks = vec![];
+
+        service.insert("image".into(), self.image.clone().into());
+
+        for option in &self.options {
+            match option {
+                RunOption::Mount(mount) => push(&mut service, "volumes", mount.to_spec()),
+                RunOption::Port { host, container } => {
+                    push(&mut service, "ports", format!("{}:{}", host, container));
+                }
+                RunOption::Env { key, value } => {
+                    environment.insert(key.clone().into(), value.clone().into());
+                }
+                RunOption::Raw(args) => {
+                    return Err(format!(
+                        "extra_args '{}' have no compose equivalent",
+                        join_command(args)));
+                }
+                RunOption::Flag { name, value } => {
+                    let value = value.clone().unwrap_or_default();
+                    match name.as_str() {
+                        "--rm" | "-d" => {}
+                        "-it" => {
+                            service.insert("stdin_open".into(), true.into());
+                            service.insert("tty".into(), true.into());
+                        }
+                        "--privileged" | "--read-only" | "--init" => {
+                            let key = compose_key(name);
+                            service.insert(key.into(), true.into());
+                        }
+                        "--gpus" => set_gpu_reservation(&mut service, &value),
+                        "--network" => networks.push(value),
+                        "--device" | "--add-host" | "--dns" | "--env-file"
+                        | "--label" => {
+                            push(&mut service, compose_key(name), value);
+                        }
+                        "--shm-size" | "--ipc" | "--pid" | "--user" | "--workdir"
+                        | "--hostname" | "--runtime" | "--name" | "--entrypoint"
+                        | "--userns" => {
+                            service.insert(compose_key(name).into(), value.into());
+                        }
+                        "--health-cmd" => {
+                            healthcheck.insert(
+                                "test".into(),
+                                Value::Sequence(vec!["CMD-SHELL".into(), value.into()]));
+                        }
+                        "--health-interval" | "--health-timeout"
+                        | "--health-start-period" => {
+                            let key = compose_key(name);
+                            healthcheck.insert(key.into(), value.into());
+                        }
+                        "--health-retries" => {
+                            let retries = value.parse::<u64>().map_err(|_| format!(
+                                "--health-retries '{}' is not a number", value))?;
+                            healthcheck.insert("retries".into(), retries.into());
+                        }
+                        other => {
+                            return Err(format!(
+                                "docker run option '{}' has no compose equivalent",
+                                other));
+                        }
+                    }
+                }
+            }
+        }
+
+        if !environment.is_empty() {
+            service.insert("environment".into(), Value::Mapping(environment));
+        }
+        if !healthcheck.is_empty() {
+            service.insert("healthcheck".into(), Value::Mapping(healthcheck));
+        }
+        match networks.as_slice() {
+            [] => {}
+            [mode] if is_network_mode(mode) => {
+                service.insert("network_mode".into(), mode.clone().into());
+            }
+            _ => {
+                if let Some(mode) = networks.iter().find(|n| is_network_mode(n)) {
+                    return Err(format!(
+                        "network '{}' cannot be combined with other networks in compose",
+                        mode));
+                }
+                service.insert(
+                    "networks".into(),
+                    Value::Sequence(networks.into_iter().map(Value::from).collect()));
+            }
+        }
+        if !self.command.is_empty() {
+            service.insert(
+                "command".into(),
+                Value::Sequence(self.command.iter().cloned().map(Value::from).collect()));
+        }
+
+        Ok(Value::Mapping(service))
+    }
+}
+
+/// Compose key for a docker run option.
+fn compose_key(name: &str) -> &'static str {
+    match name {
+        "--privileged" => "privileged",
+        "--read-only" => "read_only",
+        "--init" => "init",
+        "--device" => "devices",
+        "--add-host" => "extra_hosts",
+        "--dns" => "dns",
+        "--env-file" => "env_file",
+        "--label" => "labels",
+        "--shm-size" => "shm_size",
+        "--ipc" => "ipc",
+        "--pid" => "pid",
+        "--user" => "user",
+        "--workdir" => "working_dir",
+        "--hostname" => "hostname",
+        "--runtime" => "runtime",
+        "--name" => "container_name",
+        "--entrypoint" => "entrypoint",
+        "--userns" => "userns_mode",
+        "--health-interval" => "interval",
+        "--health-timeout" => "timeout",
+        "--health-start-period" => "start_period",
+        _ => unreachable!("no compose key for {}", name),
+    }
+}
+
+/// Networks that map to compose `network_mode` rather than `networks`.
+fn is_network_mode(network: &str) -> bool {
+    matches!(network, "host" | "none" | "bridge") || network.starts_with("container:")
+}
+
+/// Append `value` to the sequence under `key`.
+fn push(service: &mut Mapping, key: &str, value: String) {
+    let entry = service
+        .entry(key.into())
+        .or_insert_with(|| Value::Sequence(vec![]));
+    if let Value::Sequence(items) = entry {
+        items.push(value.into());
+    }
+}
+
+//------------------------------------------------------------------------------
+/// `deploy.resources.reservations.devices` for a `--gpus` spec (`all`, a
+/// count, or `device=0,1`).
+//------------------------------------------------------------------------------
+fn set_gpu_reservation(service: &mut Mapping, spec: &str) {
+    let spec = spec.trim().trim_matches('"');
+    let mut device = Mapping::new();
+    device.insert("driver".into(), "nvidia".into());
+    if spec == "all" {
+        device.insert("count".into(), "all".into());
+    } else if let Ok(count) = spec.parse::<u64>() {
+        device.insert("count".into(), count.into());
+    } else {
+        let ids = spec.strip_prefix("device=").unwrap_or(spec)
+            .split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(Value::from)
+            .collect();
+        device.insert("device_ids".into(), Value::Sequence(ids));
+    }
+    device.insert("capabilities".into(), Value::Sequence(vec!["gpu".into()]));
+
+    let mut reservations = Mapping::new();
+    reservations.insert("devices".into(), Value::Sequence(vec![Value::Mapping(device)]));
+    let mut resources = Mapping::new();
+    resources.insert("reservations".into(), Value::Mapping(reservations));
+    let mut deploy = Mapping::new();
+    deploy.insert("resources".into(), Value::Mapping(resources));
+    service.insert("deploy".into(), Value::Mapping(deploy));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan() -> RunPlan {
+        let mut plan = RunPlan::new(ContainerEngine::Docker, "app:latest");
+        plan.flag_pairs(&ContainerEngine::Docker.gpu_args("device=0,1"));
+        plan.switch("--rm");
+        plan.switch("-it");
+        plan.flag("--network", "host");
+        plan.port(8080, 80);
+        plan.mount("/host/data", "/data", Some("ro"));
+        plan.env("MSG", "hello world");
+        plan.flag("--shm-size", "8g");
+        plan.flag("--health-cmd", "curl -f localhost");
+        plan.flag("--health-retries", "3");
+        plan.command = vec!["python".to_string(), "app.py".to_string()];
+        plan
+    }
+
+    #[test]
+    fn test_to_argv_and_shell_string() {
+        let plan = plan();
+        assert_eq!(plan.to_argv(), [
+            "docker", "run", "--gpus", "device=0,1", "--rm", "-it",
+            "--network", "host", "-p", "8080:80", "-v", "/host/data:/data:ro",
+            "-e", "MSG=hello world", "--shm-size", "8g",
+            "--health-cmd", "curl -f localhost", "--health-retries", "3",
+            "app:latest", "python", "app.py",
+        ]);
+        assert_eq!(
+            plan.to_shell_string(),
+            "docker run --gpus device=0,1 --rm -it --network host -p 8080:80 \
+             -v /host/data:/data:ro -e 'MSG=hello world' --shm-size 8g \
+             --health-cmd 'curl -f localhost' --health-retries 3 app:latest \
+             python app.py");
+    }
+
+    #[test]
+    fn test_accessors_and_remove_flag() {
+        let mut plan = plan();
+        assert_eq!(plan.ports().collect::<Vec<_>>(), [(8080, 80)]);
+        assert_eq!(plan.mounts().next().unwrap().target, "/data");
+        assert_eq!(plan.env_vars().collect::<Vec<_>>(), [("MSG", "hello world")]);
+        assert_eq!(plan.flag_values("--network"), ["host"]);
+        assert!(plan.has_flag("--rm"));
+
+        plan.remove_flag("--rm");
+        assert!(!plan.has_flag("--rm"));
+        assert_eq!(&plan.to_argv()[2..5], ["--gpus", "device=0,1", "-it"]);
+    }
+
+    #[test]
+    fn test_to_compose_service() {
+        let service = plan().to_compose_service().unwrap();
+        let expected: Value = serde_yaml::from_str(r#"
+image: app:latest
+deploy:
+  resources:
+    reservations:
+      devices:
+        - driver: nvidia
+          device_ids: ["0", "1"]
+          capabilities: [gpu]
+stdin_open: true
+tty: true
+ports: ["8080:80"]
+volumes: ["/host/data:/data:ro"]
+shm_size: 8g
+environment:
+  MSG: hello world
+healthcheck:
+  test: [CMD-SHELL, curl -f localhost]
+  retries: 3
+network_mode: host
+command: [python, app.py]
+"#).unwrap();
+        assert_eq!(service, expected);
+    }
+
+    #[test]
+    fn test_to_compose_service_rejects_unmapped_options() {
+        let mut plan = RunPlan::new(ContainerEngine::Docker, "app");
+        plan.raw(vec!["--cap-add".to_string(), "SYS_PTRACE".to_string()]);
+        let error = plan.to_compose_service().unwrap_err();
+        assert!(error.contains("--cap-add SYS_PTRACE"), "{}", error);
+
+        let mut plan = RunPlan::new(ContainerEngine::Docker, "app");
+        plan.flag("--network-alias", "web");
+        assert!(plan.to_compose_service().is_err());
+    }
+}
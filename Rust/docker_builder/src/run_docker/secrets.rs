@@ -0,0 +1,273 @@
+//! Materialize `secrets` from run_configuration.yml as read-only bind mounts.
+//!
+//! File secrets are mounted directly. Env secrets are written to 0400 files
+//! in a private (0700) temp directory - under $XDG_RUNTIME_DIR when set, so
+//! they live on tmpfs - which is removed when `PreparedSecrets` is dropped,
+//! i.e. after the container exits. Detached runs outlive the process, so
+//! their directory is kept and reported instead.
+//!
+//! Resolving a command only plans the mounts (`plan_secrets`); files are
+//! written by `prepare_secrets` right before the container runs, so dry runs
+//! never put secret values on disk.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+use tempfile::TempDir;
+
+use crate::configuration::run_docker_configuration::{
+    expand_tilde, SecretMount, VolumeMount};
+
+//------------------------------------------------------------------------------
+/// Secret files prepared for a run and the mounts that expose them.
+//------------------------------------------------------------------------------
+#[derive(Debug, Default)]
+pub struct PreparedSecrets {
+    /// Mounts for `-v host:target:ro`
+    pub mounts: Vec<VolumeMount>,
+    /// Temp directory for env-sourced secrets; removed on drop
+    temp_dir: Option<TempDir>,
+    /// Temp directory kept for a detached run (not removed)
+    pub kept_dir: Option<PathBuf>,
+}
+
+impl PreparedSecrets {
+    /// `-v` arguments for the secret mounts.
+    pub fn volume_args(&self) -> Vec<String> {
+        self.mounts
+            .iter()
+            .flat_map(|m| {
+                ["-v".to_string(), format!("{}:{}:ro", m.host_path, m.container_path)]
+            })
+            .collect()
+    }
+}
+
+/// Base directory for secret temp dirs: $XDG_RUNTIME_DIR if usable.
+fn secrets_base_dir() -> PathBuf {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_dir())
+        .unwrap_or_else(std::env::temp_dir)
+}
+
+/// Write `value` to a new 0400 file.
+fn write_secret_file(path: &Path, value: &str) -> Result<(), String> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o400)
+        .open(path)
+        .map_err(|e| format!("Failed to create secret file '{}': {}", path.display(), e))?;
+    file.write_all(value.as_bytes())
+        .map_err(|e| format!("Failed to write secret file '{}': {}", path.display(), e))
+}
+
+//------------------------------------------------------------------------------
+/// Host path shown for an env-sourced secret before it is prepared, e.g.
+/// `<env:HF_TOKEN>`.
+//------------------------------------------------------------------------------
+pub fn planned_secret_source(env: &str) -> String {
+    format!("<env:{}>", env.trim())
+}
+
+//------------------------------------------------------------------------------
+/// The mounts secrets will use, without reading env values or writing
+/// files: file secrets mount their file, env secrets a placeholder
+/// (`planned_secret_source`) that `prepare_secrets` replaces.
+//------------------------------------------------------------------------------
+pub fn plan_secrets(secrets: &[SecretMount]) -> Result<Vec<VolumeMount>, String> {
+    secrets
+        .iter()
+        .map(|secret| {
+            secret.validate()?;
+            let host_path = match &secret.file {
+                Some(file) => expand_tilde(file.trim()),
+                None => planned_secret_source(secret.env.as_deref().unwrap_or_default()),
+            };
+            Ok(VolumeMount {
+                host_path,
+                container_path: secret.target.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+//------------------------------------------------------------------------------
+/// Prepare secrets using `lookup` for env-sourced values. With `keep`, the
+/// temp directory survives the returned value (for detached containers).
+//------------------------------------------------------------------------------
+pub fn prepare_secrets_with<F>(
+    secrets: &[SecretMount],
+    keep: bool,
+    lookup: F,
+) -> Result<PreparedSecrets, String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut prepared = PreparedSecrets::default();
+
+    for (i, secret) in secrets.iter().enumerate() {
+        secret.validate()?;
+        let target = secret.target.trim().to_string();
+
+        let host_path = if let Some(file) = &secret.file {
+            let path = expand_tilde(file.trim());
+            if !Path::new(&path).is_file() {
+                return Err(format!(
+                    "Secret file for '{}' does not exist: {}", target, path));
+            }
+            path
+        } else {
+            let name = secret.env.as_deref().unwrap_or_default().trim();
+            let value = lookup(name).ok_or_else(|| format!(
+                "Secret '{}' reads environment variable {} which is not set",
+                target, name))?;
+
+            if prepared.temp_dir.is_none() {
+                let dir = tempfile::Builder::new()
+                    .prefix("docker_builder-secrets-")
+                    .permissions(fs::Permissions::from_mode(0o700))
+                    .tempdir_in(secrets_base_dir())
+                    .map_err(|e| format!("Failed to create secrets directory: {}", e))?;
+                prepared.temp_dir = Some(dir);
+            }
+            let dir = prepared.temp_dir.as_ref().map(TempDir::path).unwrap_or(Path::new("."));
+            let path = dir.join(format!("secret-{}", i));
+            write_secret_file(&path, &value)?;
+            path.to_string_lossy().into_owned()
+        };
+
+        prepared.mounts.push(VolumeMount {
+            host_path,
+            container_path: target,
+        });
+    }
+
+    if keep && let Some(dir) = prepared.temp_dir.take() {
+        prepared.kept_dir = Some(dir.keep());
+    }
+
+    Ok(prepared)
+}
+
+//------------------------------------------------------------------------------
+/// Prepare secrets, reading env-sourced values from the host environment.
+//------------------------------------------------------------------------------
+pub fn prepare_secrets(
+    secrets: &[SecretMount],
+    keep: bool,
+) -> Result<PreparedSecrets, String> {
+    prepare_secrets_with(secrets, keep, |name| std::env::var(name).ok())
+}
+
+//------------------------------------------------------------------------------
+/// Remove a kept secrets directory (e.g. after stopping a detached
+/// container).
+//------------------------------------------------------------------------------
+pub fn remove_secrets_dir(dir: &Path) -> Result<(), String> {
+    fs::remove_dir_all(dir)
+        .map_err(|e| format!("Failed to remove '{}': {}", dir.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        (name == "HF_TOKEN").then(|| "hf_abc".to_string())
+    }
+
+    #[test]
+    fn test_prepare_secrets_writes_private_files_and_cleans_up() {
+        let temp = TempDir::new().unwrap();
+        let token_file = temp.path().join("token");
+        fs::write(&token_file, "file-secret").unwrap();
+
+        let secrets = vec![
+            SecretMount {
+                env: Some("HF_TOKEN".to_string()),
+                target: "/run/secrets/hf_token".to_string(),
+                ..Default::default()
+            },
+            SecretMount {
+                file: Some(token_file.to_string_lossy().into_owned()),
+                target: "/run/secrets/other".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        let prepared = prepare_secrets_with(&secrets, false, lookup).unwrap();
+        let env_secret = PathBuf::from(&prepared.mounts[0].host_path);
+        assert_eq!(fs::read_to_string(&env_secret).unwrap(), "hf_abc");
+        assert_eq!(
+            fs::metadata(&env_secret).unwrap().permissions().mode() & 0o777,
+            0o400);
+        assert_eq!(
+            fs::metadata(env_secret.parent().unwrap()).unwrap().permissions().mode() & 0o777,
+            0o700);
+        assert_eq!(
+            prepared.volume_args()[3],
+            format!("{}:/run/secrets/other:ro", token_file.display()));
+
+        drop(prepared);
+        assert!(!env_secret.exists());
+    }
+
+    #[test]
+    fn test_plan_secrets_writes_nothing() {
+        let secrets = vec![
+            SecretMount {
+                env: Some("HF_TOKEN".to_string()),
+                target: "/run/secrets/hf_token".to_string(),
+                ..Default::default()
+            },
+            SecretMount {
+                file: Some("/etc/app/token".to_string()),
+                target: "/run/secrets/other".to_string(),
+                ..Default::default()
+            },
+        ];
+        let mounts = plan_secrets(&secrets).unwrap();
+        assert_eq!(mounts[0].host_path, "<env:HF_TOKEN>");
+        assert_eq!(mounts[0].container_path, "/run/secrets/hf_token");
+        assert_eq!(mounts[1].host_path, "/etc/app/token");
+
+        let relative = vec![SecretMount {
+            env: Some("HF_TOKEN".to_string()),
+            target: "secrets/x".to_string(),
+            ..Default::default()
+        }];
+        assert!(plan_secrets(&relative).is_err());
+    }
+
+    #[test]
+    fn test_prepare_secrets_keep_and_errors() {
+        let secrets = vec![SecretMount {
+            env: Some("HF_TOKEN".to_string()),
+            target: "/run/secrets/hf_token".to_string(),
+            ..Default::default()
+        }];
+        let prepared = prepare_secrets_with(&secrets, true, lookup).unwrap();
+        let kept = prepared.kept_dir.clone().unwrap();
+        drop(prepared);
+        assert!(kept.join("secret-0").exists());
+        remove_secrets_dir(&kept).unwrap();
+
+        let unset = vec![SecretMount {
+            env: Some("UNSET".to_string()),
+            target: "/run/secrets/x".to_string(),
+            ..Default::default()
+        }];
+        assert!(prepare_secrets_with(&unset, false, lookup).is_err());
+
+        let relative = vec![SecretMount {
+            env: Some("HF_TOKEN".to_string()),
+            target: "secrets/x".to_string(),
+            ..Default::default()
+        }];
+        assert!(prepare_secrets_with(&relative, false, lookup).is_err());
+    }
+}
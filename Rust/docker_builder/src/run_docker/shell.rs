@@ -0,0 +1,51 @@
+//! POSIX shell quoting for printing commands that can be pasted into a shell.
+
+//------------------------------------------------------------------------------
+/// Quote a single argument for a POSIX shell. Arguments made only of safe
+/// characters are returned unchanged; anything else is single-quoted.
+//------------------------------------------------------------------------------
+pub fn quote_arg(arg: &str) -> String {
+    let is_safe = |c: char| {
+        c.is_ascii_alphanumeric()
+            || matches!(c, '_' | '-' | '.' | '/' | ':' | '=' | '@' | '%' | '+' | ',')
+    };
+    if !arg.is_empty() && arg.chars().all(is_safe) {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+//------------------------------------------------------------------------------
+/// Join argv into a single shell-escaped command line.
+//------------------------------------------------------------------------------
+pub fn join_command(argv: &[String]) -> String {
+    argv.iter()
+        .map(|arg| quote_arg(arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_arg() {
+        assert_eq!(quote_arg("docker"), "docker");
+        assert_eq!(quote_arg("/host/data:/data:ro"), "/host/data:/data:ro");
+        assert_eq!(quote_arg("\"device=0,1\""), "'\"device=0,1\"'");
+        assert_eq!(quote_arg("it's"), r"'it'\''s'");
+        assert_eq!(quote_arg("a b"), "'a b'");
+        assert_eq!(quote_arg(""), "''");
+        assert_eq!(quote_arg("$HOME"), "'$HOME'");
+    }
+
+    #[test]
+    fn test_join_command() {
+        let argv: Vec<String> = ["docker", "run", "-e", "MSG=hello world", "img"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(join_command(&argv), "docker run -e 'MSG=hello world' img");
+    }
+}
@@ -0,0 +1,171 @@
+//! `run --detached --wait`: wait until a started container is ready.
+//!
+//! Readiness is the container's healthcheck status when it has one (from
+//! `healthcheck` in run_configuration.yml or the image's HEALTHCHECK);
+//! otherwise every published TCP port must accept connections. Without
+//! either, a container that is still running counts as ready. On failure the
+//! last log lines are printed to help diagnose.
+
+use std::net::{SocketAddr, TcpStream};
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::engine::ContainerEngine;
+use super::port_check::{PublishedPort, Protocol};
+
+/// Default time to wait for readiness.
+pub const DEFAULT_WAIT_TIMEOUT_SECS: u64 = 120;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+const LOG_TAIL_LINES: u32 = 50;
+
+//------------------------------------------------------------------------------
+/// Container state from `docker inspect`.
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerState {
+    /// created, running, exited, ...
+    pub status: String,
+    /// starting, healthy, unhealthy; None without a healthcheck
+    pub health: Option<String>,
+}
+
+//------------------------------------------------------------------------------
+/// Parse `{{.State.Status}} {{if .State.Health}}{{.State.Health.Status}}{{end}}`.
+//------------------------------------------------------------------------------
+pub fn parse_container_state(output: &str) -> Option<ContainerState> {
+    let mut fields = output.split_whitespace();
+    let status = fields.next()?.to_string();
+    let health = fields.next().map(String::from);
+    Some(ContainerState { status, health })
+}
+
+//------------------------------------------------------------------------------
+/// Current state of a container (by name or ID).
+//------------------------------------------------------------------------------
+pub fn inspect_state(engine: ContainerEngine, container: &str) -> Result<ContainerState, String> {
+    let output = Command::new(engine.binary())
+        .args([
+            "inspect",
+            "--format",
+            "{{.State.Status}} {{if .State.Health}}{{.State.Health.Status}}{{end}}",
+            container,
+        ])
+        .output()
+        .map_err(|e| format!("Failed to execute {} inspect: {}", engine, e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "{} inspect {} failed: {}",
+            engine,
+            container,
+            String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    parse_container_state(&String::from_utf8_lossy(&output.stdout)).ok_or_else(|| format!(
+        "Unexpected {} inspect output for {}", engine, container))
+}
+
+/// True if a TCP connection to the published port succeeds.
+fn port_accepts_connections(port: &PublishedPort) -> bool {
+    let host = match port.host_ip.as_deref() {
+        None | Some("0.0.0.0") => "127.0.0.1",
+        Some("::") => "::1",
+        Some(ip) => ip,
+    };
+    format!("{}:{}", host, port.host_port)
+        .parse::<SocketAddr>()
+        .or_else(|_| format!("[{}]:{}", host, port.host_port).parse())
+        .is_ok_and(|addr| {
+            TcpStream::connect_timeout(&addr, Duration::from_secs(1)).is_ok()
+        })
+}
+
+/// Print the container's last log lines to stderr.
+fn dump_logs(engine: ContainerEngine, container: &str) {
+    eprintln!("\n==> Last {} log lines of {}:", LOG_TAIL_LINES, container);
+    let _ = Command::new(engine.binary())
+        .args(["logs", "--tail", &LOG_TAIL_LINES.to_string(), container])
+        .stdout(std::io::stderr())
+        .status();
+}
+
+//------------------------------------------------------------------------------
+/// Poll until `container` is ready or `timeout` passes. TCP `ports` are
+/// checked when the container has no healthcheck.
+//------------------------------------------------------------------------------
+pub fn wait_for_ready(
+    engine: ContainerEngine,
+    container: &str,
+    ports: &[PublishedPort],
+    timeout: Duration,
+) -> Result<(), String> {
+    let tcp_ports: Vec<&PublishedPort> = ports
+        .iter()
+        .filter(|p| p.protocol == Protocol::Tcp)
+        .collect();
+    let deadline = Instant::now() + timeout;
+    eprintln!("==> Waiting up to {}s for {} to be ready...", timeout.as_secs(), container);
+
+    let failure = loop {
+        let state = inspect_state(engine, container)?;
+        if state.status != "running" && state.status != "created" {
+            break format!("container is {}", state.status);
+        }
+
+        let ready = match state.health.as_deref() {
+            Some("healthy") => true,
+            Some("unhealthy") => break "healthcheck reports unhealthy".to_string(),
+            Some(_) => false,
+            None => state.status == "running"
+                && tcp_ports.iter().all(|p| port_accepts_connections(p)),
+        };
+        if ready {
+            eprintln!("    ✓ {} is ready", container);
+            return Ok(());
+        }
+
+        if Instant::now() >= deadline {
+            break format!("not ready after {}s", timeout.as_secs());
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    dump_logs(engine, container);
+    Err(format!("Container {} failed to become ready: {}", container, failure))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_parse_container_state() {
+        assert_eq!(parse_container_state("running healthy\n"), Some(ContainerState {
+            status: "running".to_string(),
+            health: Some("healthy".to_string()),
+        }));
+        assert_eq!(parse_container_state("exited \n"), Some(ContainerState {
+            status: "exited".to_string(),
+            health: None,
+        }));
+        assert_eq!(parse_container_state(""), None);
+    }
+
+    #[test]
+    fn test_port_accepts_connections() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut port = PublishedPort {
+            host_ip: None,
+            host_port: listener.local_addr().unwrap().port(),
+            protocol: Protocol::Tcp,
+        };
+        assert!(port_accepts_connections(&port));
+
+        drop(listener);
+        port.host_ip = Some("127.0.0.1".to_string());
+        assert!(!port_accepts_connections(&port));
+    }
+}
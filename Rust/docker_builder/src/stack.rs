@@ -0,0 +1,243 @@
+//! `up` / `down` for stack_configuration.yml.
+//!
+//! Services run detached as `<stack>-<service>`, labelled with the stack and
+//! service names, on the stack's shared networks with the service name as a
+//! network alias (so `postgres` resolves from the app). `up` starts services
+//! in dependency order, waiting for a dependency to become healthy when it
+//! has a healthcheck; `down` stops and removes them in reverse order.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use crate::configuration::stack_configuration::{StackConfiguration, StackService};
+use crate::run_docker::build_docker_run_command::build_run_plan_from_yaml;
+use crate::run_docker::engine::ContainerEngine;
+use crate::run_docker::networks::{ensure_networks, NetworkSpec};
+use crate::run_docker::run_plan::RunPlan;
+use crate::run_docker::run_docker::{execute_detached_run_command, MANAGED_BY_LABEL};
+use crate::run_docker::shell::join_command;
+use crate::run_docker::wait::{inspect_state, wait_for_ready, DEFAULT_WAIT_TIMEOUT_SECS};
+
+/// Label carrying the stack name on service containers.
+pub const STACK_LABEL: &str = "docker_builder.stack";
+/// Label carrying the service name on service containers.
+pub const SERVICE_LABEL: &str = "docker_builder.service";
+
+/// Arguments from CLI for up/down
+#[derive(Debug, Clone)]
+pub struct StackArgs {
+    /// Directory containing stack_configuration.yml
+    pub dir: PathBuf,
+    /// Print commands without running them
+    pub dry_run: bool,
+}
+
+/// Stack name: `name` from the file, else the directory name.
+fn stack_name(configuration: &StackConfiguration, dir: &Path) -> String {
+    configuration.name.clone().unwrap_or_else(|| {
+        dir.file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "stack".to_string())
+    })
+}
+
+//------------------------------------------------------------------------------
+/// Container name for a service.
+//------------------------------------------------------------------------------
+pub fn service_container_name(stack: &str, service: &str) -> String {
+    format!("{}-{}", stack, service)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-') { c } else { '-' })
+        .collect()
+}
+
+//------------------------------------------------------------------------------
+/// Detached run argv for one service.
+//------------------------------------------------------------------------------
+pub fn build_service_command(
+    stack: &str,
+    engine: ContainerEngine,
+    shared_networks: &[NetworkSpec],
+    service_name: &str,
+    service: &StackService,
+) -> Result<Vec<String>, String> {
+    let mut run = service.run.clone();
+    run.engine = Some(engine);
+    run.container_name = Some(service_container_name(stack, service_name));
+
+    let mut networks = shared_networks.to_vec();
+    for network in run.networks.iter().flatten() {
+        if !networks.iter().any(|n| n.name() == network.name()) {
+            networks.push(network.clone());
+        }
+    }
+    let has_user_network = networks.iter().any(|n| !n.is_builtin());
+    run.networks = Some(networks);
+
+    let mut plan = build_run_plan_from_yaml(&run)?;
+
+    let mut options = RunPlan::new(engine, &plan.image);
+    options.switch("-d");
+    options.flag("--label", format!("{}={}", STACK_LABEL, stack));
+    options.flag("--label", format!("{}={}", SERVICE_LABEL, service_name));
+    options.flag("--label", MANAGED_BY_LABEL);
+    if has_user_network {
+        options.flag("--network-alias", service_name);
+    }
+    // Options go right after `<engine> run`
+    plan.options.splice(0..0, options.options);
+    Ok(plan.to_argv())
+}
+
+fn run_engine(engine: ContainerEngine, args: &[&str]) -> Result<(), String> {
+    let status = Command::new(engine.binary())
+        .args(args)
+        .stdout(Stdio::null())
+        .status()
+        .map_err(|e| format!("Failed to execute {} {}: {}", engine, args[0], e))?;
+    if !status.success() {
+        return Err(format!("{} {} failed", engine, args.join(" ")));
+    }
+    Ok(())
+}
+
+fn load(args: &StackArgs) -> Result<(StackConfiguration, String, ContainerEngine), String> {
+    let dir = args.dir
+        .canonicalize()
+        .map_err(|e| format!("Invalid stack directory '{}': {}", args.dir.display(), e))?;
+    let configuration = StackConfiguration::load_from_directory(&dir)?;
+    let name = stack_name(&configuration, &dir);
+    let engine = configuration.engine.unwrap_or_default();
+    Ok((configuration, name, engine))
+}
+
+//------------------------------------------------------------------------------
+/// Start all services in dependency order. Services already running are
+/// left alone; stopped ones are recreated.
+//------------------------------------------------------------------------------
+pub fn stack_up(args: &StackArgs) -> Result<(), String> {
+    let (configuration, stack, engine) = load(args)?;
+    let order = configuration.start_order()?;
+
+    if !args.dry_run {
+        ensure_networks(engine, &configuration.networks)?;
+    }
+
+    for service_name in &order {
+        let service = &configuration.services[service_name];
+        let container = service_container_name(&stack, service_name);
+        let argv = build_service_command(
+            &stack, engine, &configuration.networks, service_name, service)?;
+
+        if args.dry_run {
+            println!("{}", join_command(&argv));
+            continue;
+        }
+
+        println!("==> Starting {} ({})", service_name, container);
+        match inspect_state(engine, &container) {
+            Ok(state) if state.status == "running" => {
+                println!("    Already running");
+                continue;
+            }
+            Ok(_) => run_engine(engine, &["rm", &container])?,
+            Err(_) => {}
+        }
+
+        if service.run.ensure_networks {
+            let own: Vec<NetworkSpec> = service.run.networks.clone().unwrap_or_default();
+            ensure_networks(engine, &own)?;
+        }
+        execute_detached_run_command(&argv, &args.dir)?;
+
+        let is_dependency = configuration
+            .services
+            .values()
+            .any(|s| s.depends_on.contains(service_name));
+        if is_dependency && service.run.healthcheck.is_some() {
+            wait_for_ready(
+                engine,
+                &container,
+                &[],
+                Duration::from_secs(DEFAULT_WAIT_TIMEOUT_SECS))?;
+        }
+    }
+
+    if !args.dry_run {
+        println!("\n✓ Stack '{}' is up ({} services)", stack, order.len());
+    }
+    Ok(())
+}
+
+//------------------------------------------------------------------------------
+/// Stop and remove all services in reverse dependency order, then remove the
+/// shared networks.
+//------------------------------------------------------------------------------
+pub fn stack_down(args: &StackArgs) -> Result<(), String> {
+    let (configuration, stack, engine) = load(args)?;
+    let mut order = configuration.start_order()?;
+    order.reverse();
+
+    for service_name in &order {
+        let container = service_container_name(&stack, service_name);
+        if args.dry_run {
+            println!("{} stop {}", engine, container);
+            println!("{} rm {}", engine, container);
+            continue;
+        }
+        if inspect_state(engine, &container).is_err() {
+            continue;
+        }
+        println!("==> Stopping {} ({})", service_name, container);
+        run_engine(engine, &["stop", &container])?;
+        run_engine(engine, &["rm", &container])?;
+    }
+
+    for network in configuration.networks.iter().filter(|n| !n.is_builtin()) {
+        if args.dry_run {
+            println!("{} network rm {}", engine, network.name());
+        } else if run_engine(engine, &["network", "rm", network.name()]).is_err() {
+            eprintln!("    Warning: could not remove network '{}'", network.name());
+        }
+    }
+
+    if !args.dry_run {
+        println!("\n✓ Stack '{}' is down", stack);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::run_docker_configuration::RunConfiguration;
+
+    #[test]
+    fn test_build_service_command() {
+        let service = StackService {
+            depends_on: vec!["postgres".to_string()],
+            run: RunConfiguration {
+                docker_image_name: "kb-app:latest".to_string(),
+                command: Some(
+                    crate::configuration::run_docker_configuration::CommandOption::Single(
+                        "kb serve".to_string())),
+                ..Default::default()
+            },
+        };
+        let shared = vec![NetworkSpec::Name("kb-net".to_string())];
+
+        let argv = build_service_command(
+            "kb", ContainerEngine::Docker, &shared, "app", &service).unwrap();
+        assert_eq!(&argv[..3], ["docker", "run", "-d"]);
+        let joined = join_command(&argv);
+        assert!(joined.contains("--label docker_builder.stack=kb"), "{}", joined);
+        assert!(joined.contains("--label docker_builder.service=app"), "{}", joined);
+        assert!(joined.contains("--label managed-by=docker_builder"), "{}", joined);
+        assert!(joined.contains("--network-alias app"), "{}", joined);
+        assert!(joined.contains("--network kb-net"), "{}", joined);
+        assert!(joined.ends_with("--name kb-app kb-app:latest kb serve"), "{}", joined);
+
+        assert_eq!(service_container_name("my stack", "db"), "my-stack-db");
+    }
+}
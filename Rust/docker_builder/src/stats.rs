@@ -0,0 +1,441 @@
+//! `stats` - resource usage of running containers started by docker_builder.
+//!
+//! Containers are found by the managed-by label. CPU and memory come from
+//! `<engine> stats`; GPU memory and utilization come from nvidia-smi, whose
+//! host PIDs are mapped to containers through `/proc/<pid>/cgroup`. GPU
+//! columns show `-` when nvidia-smi is unavailable.
+
+use std::collections::HashMap;
+use std::fs;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use crate::run_docker::engine::ContainerEngine;
+use crate::run_docker::run_docker::MANAGED_BY_LABEL;
+
+/// Refresh interval for `stats --watch`.
+pub const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Arguments from CLI for stats
+#[derive(Debug, Clone)]
+pub struct StatsArgs {
+    pub engine: ContainerEngine,
+    /// Refresh every `WATCH_INTERVAL` until interrupted
+    pub watch: bool,
+}
+
+//------------------------------------------------------------------------------
+/// A running managed container (from `ps`).
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManagedContainer {
+    /// Full container ID
+    pub id: String,
+    pub name: String,
+    pub image: String,
+}
+
+//------------------------------------------------------------------------------
+/// CPU and memory for one container (from `stats --no-stream`).
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourceUsage {
+    pub cpu_percent: String,
+    /// e.g. `1.2GiB / 31.3GiB`
+    pub memory_usage: String,
+    pub memory_percent: String,
+}
+
+//------------------------------------------------------------------------------
+/// A process using a GPU (from nvidia-smi), identified by its host PID.
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpuProcess {
+    pub pid: u32,
+    pub gpu_index: u32,
+    pub used_memory_mib: u64,
+    /// SM utilization in percent, if nvidia-smi pmon reported it
+    pub sm_percent: Option<u32>,
+}
+
+//------------------------------------------------------------------------------
+/// GPU usage summed over a container's processes.
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GpuUsage {
+    pub gpu_indices: Vec<u32>,
+    pub used_memory_mib: u64,
+    pub sm_percent: Option<u32>,
+}
+
+//------------------------------------------------------------------------------
+/// Parse `ps --format '{{.ID}}\t{{.Names}}\t{{.Image}}'` output.
+//------------------------------------------------------------------------------
+pub fn parse_ps_output(output: &str) -> Vec<ManagedContainer> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t').map(str::trim);
+            let id = fields.next().filter(|id| !id.is_empty())?;
+            Some(ManagedContainer {
+                id: id.to_string(),
+                name: fields.next().unwrap_or_default().to_string(),
+                image: fields.next().unwrap_or_default().to_string(),
+            })
+        })
+        .collect()
+}
+
+//------------------------------------------------------------------------------
+/// Parse `stats --no-stream --format
+/// '{{.ID}}\t{{.CPUPerc}}\t{{.MemUsage}}\t{{.MemPerc}}'` output, keyed by the
+/// (possibly truncated) container ID.
+//------------------------------------------------------------------------------
+pub fn parse_stats_output(output: &str) -> HashMap<String, ResourceUsage> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').map(str::trim).collect();
+            if fields.len() != 4 || fields[0].is_empty() {
+                return None;
+            }
+            Some((fields[0].to_string(), ResourceUsage {
+                cpu_percent: fields[1].to_string(),
+                memory_usage: fields[2].to_string(),
+                memory_percent: fields[3].to_string(),
+            }))
+        })
+        .collect()
+}
+
+//------------------------------------------------------------------------------
+/// Parse `nvidia-smi --query-compute-apps=pid,gpu_uuid,used_memory
+/// --format=csv,noheader,nounits`, mapping GPU UUIDs to indices with
+/// `uuid_to_index`. Unparseable lines are skipped.
+//------------------------------------------------------------------------------
+pub fn parse_compute_apps(
+    output: &str,
+    uuid_to_index: &HashMap<String, u32>,
+) -> Vec<GpuProcess> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            if fields.len() != 3 {
+                return None;
+            }
+            Some(GpuProcess {
+                pid: fields[0].parse().ok()?,
+                gpu_index: *uuid_to_index.get(fields[1])?,
+                used_memory_mib: fields[2].parse().unwrap_or(0),
+                sm_percent: None,
+            })
+        })
+        .collect()
+}
+
+//------------------------------------------------------------------------------
+/// Parse `nvidia-smi --query-gpu=index,uuid --format=csv,noheader` into a
+/// UUID -> index map.
+//------------------------------------------------------------------------------
+pub fn parse_gpu_uuids(output: &str) -> HashMap<String, u32> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (index, uuid) = line.split_once(',')?;
+            Some((uuid.trim().to_string(), index.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+//------------------------------------------------------------------------------
+/// Parse `nvidia-smi pmon -c 1 -s u` into PID -> SM utilization. Idle
+/// processes (`-`) are omitted.
+//------------------------------------------------------------------------------
+pub fn parse_pmon_output(output: &str) -> HashMap<u32, u32> {
+    output
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let pid = fields.get(1)?.parse().ok()?;
+            let sm = fields.get(3)?.parse().ok()?;
+            Some((pid, sm))
+        })
+        .collect()
+}
+
+//------------------------------------------------------------------------------
+/// The 64-hex-digit container ID in a `/proc/<pid>/cgroup` file (docker's
+/// `docker-<id>.scope` or `/docker/<id>`, podman's `libpod-<id>.scope`).
+//------------------------------------------------------------------------------
+pub fn container_id_from_cgroup(cgroup: &str) -> Option<String> {
+    cgroup
+        .split(|c: char| !c.is_ascii_hexdigit())
+        .find(|token| token.len() == 64)
+        .map(str::to_string)
+}
+
+//------------------------------------------------------------------------------
+/// Sum GPU processes per container; `container_of` maps a host PID to its
+/// full container ID.
+//------------------------------------------------------------------------------
+pub fn gpu_usage_by_container<F>(
+    processes: &[GpuProcess],
+    container_of: F,
+) -> HashMap<String, GpuUsage>
+where
+    F: Fn(u32) -> Option<String>,
+{
+    let mut usage: HashMap<String, GpuUsage> = HashMap::new();
+    for process in processes {
+        let Some(container) = container_of(process.pid) else {
+            continue;
+        };
+        let entry = usage.entry(container).or_default();
+        if !entry.gpu_indices.contains(&process.gpu_index) {
+            entry.gpu_indices.push(process.gpu_index);
+            entry.gpu_indices.sort_unstable();
+        }
+        entry.used_memory_mib += process.used_memory_mib;
+        if let Some(sm) = process.sm_percent {
+            entry.sm_percent = Some(entry.sm_percent.unwrap_or(0) + sm);
+        }
+    }
+    usage
+}
+
+/// Usage for `id` from a map keyed by full or truncated IDs.
+fn lookup<'m, T>(map: &'m HashMap<String, T>, id: &str) -> Option<&'m T> {
+    map.iter()
+        .find(|(key, _)| !key.is_empty() && (id.starts_with(key.as_str()) || key.starts_with(id)))
+        .map(|(_, value)| value)
+}
+
+//------------------------------------------------------------------------------
+/// Render a table of containers with their CPU, memory and GPU usage.
+//------------------------------------------------------------------------------
+pub fn render_table(
+    containers: &[ManagedContainer],
+    resources: &HashMap<String, ResourceUsage>,
+    gpu: Option<&HashMap<String, GpuUsage>>,
+) -> String {
+    let header = ["NAME", "IMAGE", "CPU %", "MEM USAGE / LIMIT", "MEM %", "GPU", "GPU MEM", "GPU %"];
+    let mut rows: Vec<Vec<String>> = vec![header.iter().map(|h| h.to_string()).collect()];
+
+    for container in containers {
+        let resource = lookup(resources, &container.id);
+        let field = |f: fn(&ResourceUsage) -> &String| {
+            resource.map_or_else(|| "-".to_string(), |r| f(r).clone())
+        };
+        let (gpus, gpu_memory, gpu_percent) = match gpu {
+            None => ("-".to_string(), "-".to_string(), "-".to_string()),
+            Some(gpu) => match lookup(gpu, &container.id) {
+                None => ("none".to_string(), "0MiB".to_string(), "0%".to_string()),
+                Some(usage) => (
+                    usage.gpu_indices
+                        .iter()
+                        .map(u32::to_string)
+                        .collect::<Vec<_>>()
+                        .join(","),
+                    format!("{}MiB", usage.used_memory_mib),
+                    usage.sm_percent.map_or_else(|| "-".to_string(), |p| format!("{}%", p)),
+                ),
+            },
+        };
+        rows.push(vec![
+            container.name.clone(),
+            container.image.clone(),
+            field(|r| &r.cpu_percent),
+            field(|r| &r.memory_usage),
+            field(|r| &r.memory_percent),
+            gpus,
+            gpu_memory,
+            gpu_percent,
+        ]);
+    }
+
+    let widths: Vec<usize> = (0..header.len())
+        .map(|i| rows.iter().map(|row| row[i].chars().count()).max().unwrap_or(0))
+        .collect();
+    rows.iter()
+        .map(|row| {
+            let line: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect();
+            format!("{}\n", line.join("  ").trim_end())
+        })
+        .collect()
+}
+
+/// Run a command and return stdout, or an error naming `what`.
+fn command_stdout(program: &str, args: &[&str], what: &str) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to execute {}: {}", what, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} failed: {}", what, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+//------------------------------------------------------------------------------
+/// Running containers carrying the managed-by label.
+//------------------------------------------------------------------------------
+pub fn list_managed_containers(engine: ContainerEngine) -> Result<Vec<ManagedContainer>, String> {
+    let filter = format!("label={}", MANAGED_BY_LABEL);
+    let output = command_stdout(
+        engine.binary(),
+        &["ps", "--no-trunc", "--filter", &filter, "--format", "{{.ID}}\t{{.Names}}\t{{.Image}}"],
+        &format!("{} ps", engine))?;
+    Ok(parse_ps_output(&output))
+}
+
+/// CPU and memory snapshot for `containers`.
+fn query_resources(
+    engine: ContainerEngine,
+    containers: &[ManagedContainer],
+) -> Result<HashMap<String, ResourceUsage>, String> {
+    let mut args = vec![
+        "stats",
+        "--no-stream",
+        "--format",
+        "{{.ID}}\t{{.CPUPerc}}\t{{.MemUsage}}\t{{.MemPerc}}",
+    ];
+    args.extend(containers.iter().map(|c| c.id.as_str()));
+    let output = command_stdout(engine.binary(), &args, &format!("{} stats", engine))?;
+    Ok(parse_stats_output(&output))
+}
+
+/// GPU usage per full container ID, or None when nvidia-smi is unavailable.
+fn query_gpu_usage() -> Option<HashMap<String, GpuUsage>> {
+    let uuids = command_stdout(
+        "nvidia-smi", &["--query-gpu=index,uuid", "--format=csv,noheader"], "nvidia-smi").ok()?;
+    let apps = command_stdout(
+        "nvidia-smi",
+        &["--query-compute-apps=pid,gpu_uuid,used_memory", "--format=csv,noheader,nounits"],
+        "nvidia-smi").ok()?;
+    // pmon is best effort; older drivers or MIG setups may not support it
+    let sm = command_stdout("nvidia-smi", &["pmon", "-c", "1", "-s", "u"], "nvidia-smi pmon")
+        .map(|output| parse_pmon_output(&output))
+        .unwrap_or_default();
+
+    let mut processes = parse_compute_apps(&apps, &parse_gpu_uuids(&uuids));
+    for process in &mut processes {
+        process.sm_percent = sm.get(&process.pid).copied();
+    }
+    Some(gpu_usage_by_container(&processes, |pid| {
+        fs::read_to_string(format!("/proc/{}/cgroup", pid))
+            .ok()
+            .and_then(|cgroup| container_id_from_cgroup(&cgroup))
+    }))
+}
+
+/// One snapshot of the stats table.
+fn stats_snapshot(engine: ContainerEngine) -> Result<String, String> {
+    let containers = list_managed_containers(engine)?;
+    if containers.is_empty() {
+        return Ok("No running containers started by docker_builder\n".to_string());
+    }
+    let resources = query_resources(engine, &containers)?;
+    let gpu = query_gpu_usage();
+    Ok(render_table(&containers, &resources, gpu.as_ref()))
+}
+
+//------------------------------------------------------------------------------
+/// Print resource usage of managed containers, once or (with `watch`)
+/// refreshing until interrupted.
+//------------------------------------------------------------------------------
+pub fn show_stats(args: &StatsArgs) -> Result<(), String> {
+    if !args.watch {
+        print!("{}", stats_snapshot(args.engine)?);
+        return Ok(());
+    }
+    loop {
+        let table = stats_snapshot(args.engine)?;
+        // Clear the screen and move the cursor home
+        print!("\x1b[2J\x1b[H{}", table);
+        thread::sleep(WATCH_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID_A: &str = "3f4e1c2b9a8d7e6f5a4b3c2d1e0f9a8b7c6d5e4f3a2b1c0d9e8f7a6b5c4d3e2f";
+    const ID_B: &str = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn test_container_id_from_cgroup() {
+        let docker = format!("0::/system.slice/docker-{}.scope\n", ID_A);
+        assert_eq!(container_id_from_cgroup(&docker).as_deref(), Some(ID_A));
+        let v1 = format!("12:memory:/docker/{}\n", ID_B);
+        assert_eq!(container_id_from_cgroup(&v1).as_deref(), Some(ID_B));
+        assert_eq!(container_id_from_cgroup("0::/user.slice/session-2.scope\n"), None);
+    }
+
+    #[test]
+    fn test_gpu_usage_by_container() {
+        let uuids = parse_gpu_uuids("0, GPU-aaa\n1, GPU-bbb\n");
+        let mut processes = parse_compute_apps(
+            "100, GPU-aaa, 2048\n101, GPU-bbb, 1024\n200, GPU-aaa, 512\n300, GPU-aaa, 64\n",
+            &uuids);
+        let sm = parse_pmon_output(
+            "# gpu        pid  type    sm   mem   enc   dec   command\n\
+             # Idx          #   C/G     %     %     %     %   name\n\
+                 0        100     C    40    10     -     -   python\n\
+                 1        101     C     5     1     -     -   python\n\
+                 0        200     C     -     -     -     -   python\n");
+        for process in &mut processes {
+            process.sm_percent = sm.get(&process.pid).copied();
+        }
+
+        let usage = gpu_usage_by_container(&processes, |pid| match pid {
+            100 | 101 => Some(ID_A.to_string()),
+            200 => Some(ID_B.to_string()),
+            _ => None,
+        });
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage[ID_A], GpuUsage {
+            gpu_indices: vec![0, 1],
+            used_memory_mib: 3072,
+            sm_percent: Some(45),
+        });
+        assert_eq!(usage[ID_B], GpuUsage {
+            gpu_indices: vec![0],
+            used_memory_mib: 512,
+            sm_percent: None,
+        });
+    }
+
+    #[test]
+    fn test_render_table() {
+        let containers = parse_ps_output(&format!(
+            "{}\tllm-server\tvllm:latest\n{}\tnotebook\tjupyter:1\n", ID_A, ID_B));
+        let resources = parse_stats_output(
+            "3f4e1c2b9a8d\t153.20%\t10.5GiB / 62.7GiB\t16.75%\n");
+        let gpu = HashMap::from([(ID_A.to_string(), GpuUsage {
+            gpu_indices: vec![0],
+            used_memory_mib: 20480,
+            sm_percent: Some(87),
+        })]);
+
+        let table = render_table(&containers, &resources, Some(&gpu));
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("NAME        IMAGE        CPU %"), "{}", table);
+        assert!(lines[1].contains("153.20%  10.5GiB / 62.7GiB  16.75%  0     20480MiB  87%"),
+            "{}", table);
+        assert!(lines[2].starts_with("notebook    jupyter:1    -"), "{}", table);
+        assert!(lines[2].ends_with("none  0MiB      0%"), "{}", table);
+
+        let table = render_table(&containers, &resources, None);
+        assert!(table.lines().nth(1).unwrap().ends_with("16.75%  -    -        -"), "{}", table);
+    }
+}
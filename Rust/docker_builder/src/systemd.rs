@@ -0,0 +1,276 @@
+//! Generate a systemd service unit from a build directory's run configuration.
+//!
+//! ExecStart is the same argv `run` would use, in the foreground (no `-d`,
+//! no `-it`) with a fixed `--name`, so systemd supervises the container
+//! process directly; ExecStartPre removes a leftover container with that name
+//! and ExecStop stops it.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::run_docker::engine::ContainerEngine;
+use crate::run_docker::run_docker::{resolve_run_command, RunDockerArgs};
+
+//------------------------------------------------------------------------------
+/// systemd `Restart=` policy.
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RestartPolicy {
+    No,
+    Always,
+    #[default]
+    OnFailure,
+    OnAbnormal,
+}
+
+impl fmt::Display for RestartPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RestartPolicy::No => "no",
+            RestartPolicy::Always => "always",
+            RestartPolicy::OnFailure => "on-failure",
+            RestartPolicy::OnAbnormal => "on-abnormal",
+        })
+    }
+}
+
+impl FromStr for RestartPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "no" => Ok(RestartPolicy::No),
+            "always" => Ok(RestartPolicy::Always),
+            "on-failure" => Ok(RestartPolicy::OnFailure),
+            "on-abnormal" => Ok(RestartPolicy::OnAbnormal),
+            other => Err(format!(
+                "Unknown restart policy '{}': expected no, always, on-failure, \
+                 or on-abnormal",
+                other)),
+        }
+    }
+}
+
+/// Arguments from CLI for `systemd`
+#[derive(Debug, Clone)]
+pub struct SystemdArgs {
+    pub build_dir: PathBuf,
+    /// Service and container name
+    pub name: String,
+    pub restart: RestartPolicy,
+    /// Units this service needs started first (After= and Requires=)
+    pub after: Vec<String>,
+    pub profile: Option<String>,
+    pub engine: Option<ContainerEngine>,
+}
+
+//------------------------------------------------------------------------------
+/// Everything needed to render a unit file.
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone)]
+pub struct UnitSpec {
+    pub name: String,
+    pub description: String,
+    pub engine: ContainerEngine,
+    /// Absolute path of the engine binary
+    pub engine_path: String,
+    /// Run argv, starting with the engine binary name
+    pub argv: Vec<String>,
+    pub working_dir: PathBuf,
+    pub restart: RestartPolicy,
+    pub after: Vec<String>,
+}
+
+//------------------------------------------------------------------------------
+/// Quote an argument for a systemd Exec line: `%` and `$` are escaped so
+/// they are not expanded as specifiers or variables, and arguments with
+/// whitespace or quotes are double-quoted.
+//------------------------------------------------------------------------------
+pub fn systemd_quote(arg: &str) -> String {
+    let escaped = arg.replace('%', "%%").replace('$', "$$");
+    let needs_quotes = escaped.is_empty()
+        || escaped.chars().any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\' | ';'));
+    if !needs_quotes {
+        return escaped;
+    }
+    format!("\"{}\"", escaped.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn exec_line(engine_path: &str, args: &[String]) -> String {
+    std::iter::once(engine_path.to_string())
+        .chain(args.iter().map(|arg| systemd_quote(arg)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+//------------------------------------------------------------------------------
+/// Render the unit file text.
+//------------------------------------------------------------------------------
+pub fn render_unit(spec: &UnitSpec) -> String {
+    let mut after = vec!["network-online.target".to_string()];
+    let mut requires = vec![];
+    if spec.engine == ContainerEngine::Docker {
+        after.push("docker.service".to_string());
+        requires.push("docker.service".to_string());
+    }
+    after.extend(spec.after.iter().cloned());
+    requires.extend(spec.after.iter().cloned());
+
+    let mut unit = String::new();
+    unit.push_str("[Unit]\n");
+    unit.push_str(&format!("Description={}\n", spec.description));
+    unit.push_str("Wants=network-online.target\n");
+    unit.push_str(&format!("After={}\n", after.join(" ")));
+    if !requires.is_empty() {
+        unit.push_str(&format!("Requires={}\n", requires.join(" ")));
+    }
+
+    unit.push_str("\n[Service]\n");
+    unit.push_str("Type=simple\n");
+    unit.push_str(&format!("WorkingDirectory={}\n", spec.working_dir.display()));
+    // Image pulls can take a while
+    unit.push_str("TimeoutStartSec=0\n");
+    unit.push_str(&format!("Restart={}\n", spec.restart));
+    unit.push_str("RestartSec=5\n");
+    unit.push_str(&format!(
+        "ExecStartPre=-{}\n",
+        exec_line(&spec.engine_path, &["rm".to_string(), "-f".to_string(), spec.name.clone()])));
+    unit.push_str(&format!("ExecStart={}\n", exec_line(&spec.engine_path, &spec.argv[1..])));
+    unit.push_str(&format!(
+        "ExecStop={}\n",
+        exec_line(&spec.engine_path, &["stop".to_string(), spec.name.clone()])));
+
+    unit.push_str("\n[Install]\n");
+    unit.push_str("WantedBy=multi-user.target\n");
+    unit
+}
+
+/// Absolute path of `binary` on PATH, else /usr/bin/<binary>.
+fn find_binary(binary: &str) -> String {
+    std::env::var_os("PATH")
+        .iter()
+        .flat_map(std::env::split_paths)
+        .map(|dir| dir.join(binary))
+        .find(|path| path.is_file())
+        .unwrap_or_else(|| Path::new("/usr/bin").join(binary))
+        .display()
+        .to_string()
+}
+
+//------------------------------------------------------------------------------
+/// Resolve the run command for `args.build_dir` and render its unit file.
+//------------------------------------------------------------------------------
+pub fn generate_unit(args: &SystemdArgs) -> Result<String, String> {
+    let run_args = RunDockerArgs {
+        build_dir: args.build_dir.clone(),
+        gpu_id: None,
+        interactive: false,
+        detached: false,
+        entrypoint: None,
+        network_host: false,
+        no_gpu: false,
+        gui: false,
+        audio: false,
+        user: None,
+        ipc: None,
+        pid: None,
+        privileged: false,
+        mount_cwd: None,
+        engine: args.engine,
+        pull: false,
+        profile: args.profile.clone(),
+        name: Some(args.name.clone()),
+        name_collision: None,
+        config_overlays: vec![],
+        vars: vec![],
+        extra_args: vec![],
+    };
+    let resolved = resolve_run_command(&run_args)?;
+
+    // Env-sourced secrets are temp files that would be gone when the unit
+    // starts
+    let has_env_secrets = resolved.run_configuration
+        .as_ref()
+        .and_then(|rc| rc.secrets.as_ref())
+        .is_some_and(|secrets| secrets.iter().any(|s| s.env.is_some()));
+    if has_env_secrets {
+        return Err(
+            "secrets with `env:` can't be used in a systemd unit; use `file:` \
+             secrets instead".to_string());
+    }
+
+    let spec = UnitSpec {
+        name: args.name.clone(),
+        description: format!("{} ({})", args.name, resolved.docker_image_name),
+        engine: resolved.engine,
+        engine_path: find_binary(resolved.engine.binary()),
+        argv: resolved.argv.clone(),
+        working_dir: resolved.build_dir.clone(),
+        restart: args.restart,
+        after: args.after.clone(),
+    };
+    Ok(render_unit(&spec))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_systemd_quote() {
+        assert_eq!(systemd_quote("--gpus"), "--gpus");
+        assert_eq!(systemd_quote("\"device=0,1\""), "\"\\\"device=0,1\\\"\"");
+        assert_eq!(systemd_quote("MSG=hello world"), "\"MSG=hello world\"");
+        assert_eq!(systemd_quote("100%"), "100%%");
+        assert_eq!(systemd_quote("$HOME"), "$$HOME");
+        assert_eq!(systemd_quote(""), "\"\"");
+    }
+
+    #[test]
+    fn test_render_unit() {
+        let argv: Vec<String> = [
+            "docker", "run", "--rm", "--gpus", "all", "-e", "MSG=hi there",
+            "--name", "embedder", "embedder:latest",
+        ]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let spec = UnitSpec {
+            name: "embedder".to_string(),
+            description: "embedder (embedder:latest)".to_string(),
+            engine: ContainerEngine::Docker,
+            engine_path: "/usr/bin/docker".to_string(),
+            argv,
+            working_dir: PathBuf::from("/srv/embedder"),
+            restart: RestartPolicy::Always,
+            after: vec!["postgresql.service".to_string()],
+        };
+
+        let unit = render_unit(&spec);
+        assert!(unit.starts_with("[Unit]\nDescription=embedder (embedder:latest)\n"));
+        assert!(unit.contains(
+            "After=network-online.target docker.service postgresql.service\n"));
+        assert!(unit.contains("Requires=docker.service postgresql.service\n"));
+        assert!(unit.contains("WorkingDirectory=/srv/embedder\n"));
+        assert!(unit.contains("Restart=always\n"));
+        assert!(unit.contains("ExecStartPre=-/usr/bin/docker rm -f embedder\n"));
+        assert!(unit.contains(
+            "ExecStart=/usr/bin/docker run --rm --gpus all -e \"MSG=hi there\" \
+             --name embedder embedder:latest\n"));
+        assert!(unit.contains("ExecStop=/usr/bin/docker stop embedder\n"));
+        assert!(unit.ends_with("[Install]\nWantedBy=multi-user.target\n"));
+
+        let podman = UnitSpec { engine: ContainerEngine::Podman, after: vec![], ..spec };
+        let unit = render_unit(&podman);
+        assert!(unit.contains("After=network-online.target\n"));
+        assert!(!unit.contains("Requires="));
+    }
+
+    #[test]
+    fn test_parse_restart_policy() {
+        assert_eq!("always".parse::<RestartPolicy>(), Ok(RestartPolicy::Always));
+        assert_eq!(RestartPolicy::default().to_string(), "on-failure");
+        assert!("sometimes".parse::<RestartPolicy>().is_err());
+    }
+}
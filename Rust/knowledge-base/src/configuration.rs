@@ -8,6 +8,8 @@ use std::path::Path;
 
 pub use pg_toolkit::PgConfig;
 
+use crate::sql_statements::KnowledgeBaseSql;
+
 /// Return a `PgConfig` with knowledge-base defaults, reading from env vars:
 /// - `KB_HOST`     → default: "localhost"
 /// - `KB_PORT`     → default: 5432
@@ -33,6 +35,32 @@ pub fn config_from_env() -> PgConfig {
 }
 
 /// Load knowledge-base config from a YAML file.
-pub fn config_from_yaml(path: impl AsRef<Path>) -> Result<PgConfig> {
+pub fn config_from_yaml(path: impl AsRef<Path>) -> pg_toolkit::Result<PgConfig> {
     PgConfig::from_yaml(path)
 }
+
+/// Return the knowledge base table naming, reading from env vars:
+/// - `KB_SCHEMA`          → default: "public"
+/// - `KB_TABLE_PREFIX`    → default: "" (prepended to the default table names)
+/// - `KB_DOCUMENTS_TABLE` → default: "{prefix}knowledge_base_documents"
+/// - `KB_CHUNKS_TABLE`    → default: "{prefix}knowledge_base_chunks"
+///
+/// Returns `Err` if any of the names is not a valid identifier.
+pub fn sql_from_env() -> Result<KnowledgeBaseSql> {
+    let _ = dotenvy::dotenv();
+
+    let mut builder = KnowledgeBaseSql::builder();
+    if let Ok(schema) = std::env::var("KB_SCHEMA") {
+        builder = builder.schema(schema);
+    }
+    if let Ok(prefix) = std::env::var("KB_TABLE_PREFIX") {
+        builder = builder.table_prefix(prefix);
+    }
+    if let Ok(name) = std::env::var("KB_DOCUMENTS_TABLE") {
+        builder = builder.documents_table(name);
+    }
+    if let Ok(name) = std::env::var("KB_CHUNKS_TABLE") {
+        builder = builder.chunks_table(name);
+    }
+    builder.build()
+}
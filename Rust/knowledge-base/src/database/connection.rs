@@ -3,10 +3,15 @@
 use pg_toolkit::{PgConfig, create_pool};
 use sqlx::PgPool;
 
+use crate::sql_statements::KnowledgeBaseSql;
+
 pub use pg_toolkit::create_pool as create_pg_pool;
 
 /// Create a sqlx PgPool for the knowledge base database.
-pub async fn create_knowledge_base_pool(config: &PgConfig) -> Result<PgPool, sqlx::Error> {
+///
+/// Errors are classified (`pg_toolkit::Error`), e.g. `MissingDatabase` when
+/// the knowledge base database has not been created yet.
+pub async fn create_knowledge_base_pool(config: &PgConfig) -> pg_toolkit::Result<PgPool> {
     create_pool(config).await
 }
 
@@ -14,14 +19,29 @@ pub async fn create_knowledge_base_pool(config: &PgConfig) -> Result<PgPool, sql
 #[derive(Debug, Clone)]
 pub struct KnowledgeBaseDb {
     pub(crate) pool: PgPool,
+    pub(crate) sql: KnowledgeBaseSql,
 }
 
 impl KnowledgeBaseDb {
+    /// Wrap a pool using the default schema and table names.
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            sql: KnowledgeBaseSql::default(),
+        }
+    }
+
+    /// Use a custom schema / table naming (see `KnowledgeBaseSql::builder`).
+    pub fn with_sql(mut self, sql: KnowledgeBaseSql) -> Self {
+        self.sql = sql;
+        self
     }
 
     pub fn pool(&self) -> &PgPool {
         &self.pool
     }
+
+    pub fn sql(&self) -> &KnowledgeBaseSql {
+        &self.sql
+    }
 }
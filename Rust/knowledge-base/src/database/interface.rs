@@ -5,38 +5,46 @@ use sqlx::Row;
 
 use crate::database::connection::KnowledgeBaseDb;
 use crate::models::{Chunk, Document, InsertChunk, InsertDocument, SearchResult};
-use crate::sql_statements::KnowledgeBaseSql;
+use crate::sql_statements::DEFAULT_SCHEMA;
 
 impl KnowledgeBaseDb {
     /// Create the pgvector extension if it does not already exist.
     /// Delegates to pg_toolkit::admin for the generic extension creation logic.
     pub async fn create_extension(&self) -> Result<()> {
-        pg_toolkit::admin::create_extension(&self.pool, "vector").await
+        Ok(pg_toolkit::admin::create_extension(&self.pool, "vector").await?)
     }
 
-    /// Create the knowledge base tables and indexes (idempotent).
+    /// Create the knowledge base schema, tables, and indexes (idempotent).
     pub async fn create_tables(&self) -> Result<()> {
-        sqlx::query(KnowledgeBaseSql::CREATE_DOCUMENTS_TABLE)
+        // `public` always exists; skip it so no CREATE privilege on the database is needed.
+        if self.sql.schema() != DEFAULT_SCHEMA {
+            sqlx::query(&self.sql.create_schema())
+                .execute(&self.pool)
+                .await
+                .with_context(|| format!("Failed to create schema '{}'", self.sql.schema()))?;
+        }
+
+        sqlx::query(&self.sql.create_documents_table())
             .execute(&self.pool)
             .await
             .context("Failed to create documents table")?;
 
-        sqlx::query(KnowledgeBaseSql::CREATE_CHUNKS_TABLE)
+        sqlx::query(&self.sql.create_chunks_table())
             .execute(&self.pool)
             .await
             .context("Failed to create chunks table")?;
 
-        sqlx::query(KnowledgeBaseSql::CREATE_HNSW_INDEX)
+        sqlx::query(&self.sql.create_hnsw_index())
             .execute(&self.pool)
             .await
             .context("Failed to create HNSW index")?;
 
-        sqlx::query(KnowledgeBaseSql::CREATE_DOCUMENT_ID_INDEX)
+        sqlx::query(&self.sql.create_document_id_index())
             .execute(&self.pool)
             .await
             .context("Failed to create document_id index")?;
 
-        sqlx::query(KnowledgeBaseSql::CREATE_CHUNK_INDEX_INDEX)
+        sqlx::query(&self.sql.create_chunk_index_index())
             .execute(&self.pool)
             .await
             .context("Failed to create chunk_index index")?;
@@ -46,7 +54,7 @@ impl KnowledgeBaseDb {
 
     /// Return true if a document with the given content_hash already exists.
     pub async fn document_exists_by_hash(&self, hash: &str) -> Result<bool> {
-        let row = sqlx::query(KnowledgeBaseSql::GET_DOCUMENT_BY_HASH)
+        let row = sqlx::query(&self.sql.get_document_by_hash())
             .bind(hash)
             .fetch_optional(&self.pool)
             .await
@@ -56,7 +64,7 @@ impl KnowledgeBaseDb {
 
     /// Insert a document record and return its generated id.
     pub async fn insert_document(&self, doc: &InsertDocument) -> Result<i32> {
-        let row = sqlx::query(KnowledgeBaseSql::INSERT_DOCUMENT)
+        let row = sqlx::query(&self.sql.insert_document())
             .bind(&doc.title)
             .bind(&doc.source_path)
             .bind(&doc.source_type)
@@ -78,7 +86,7 @@ impl KnowledgeBaseDb {
             .as_ref()
             .map(|v| Vector::from(v.clone()));
 
-        let row = sqlx::query(KnowledgeBaseSql::INSERT_CHUNK)
+        let row = sqlx::query(&self.sql.insert_chunk())
             .bind(chunk.document_id)
             .bind(chunk.chunk_index)
             .bind(chunk.total_chunks)
@@ -93,9 +101,19 @@ impl KnowledgeBaseDb {
         Ok(id)
     }
 
+    /// Retrieve a document by content hash; returns None if not found.
+    pub async fn get_document_by_hash(&self, hash: &str) -> Result<Option<Document>> {
+        let doc = sqlx::query_as::<_, Document>(&self.sql.get_document_by_hash())
+            .bind(hash)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to get document by hash")?;
+        Ok(doc)
+    }
+
     /// Retrieve a document by primary key; returns None if not found.
     pub async fn get_document_by_id(&self, id: i32) -> Result<Option<Document>> {
-        let doc = sqlx::query_as::<_, Document>(KnowledgeBaseSql::GET_DOCUMENT_BY_ID)
+        let doc = sqlx::query_as::<_, Document>(&self.sql.get_document_by_id())
             .bind(id)
             .fetch_optional(&self.pool)
             .await
@@ -105,7 +123,7 @@ impl KnowledgeBaseDb {
 
     /// Retrieve all chunks for a document, ordered by chunk_index.
     pub async fn get_document_chunks(&self, doc_id: i32) -> Result<Vec<Chunk>> {
-        let chunks = sqlx::query_as::<_, Chunk>(KnowledgeBaseSql::GET_DOCUMENT_CHUNKS)
+        let chunks = sqlx::query_as::<_, Chunk>(&self.sql.get_document_chunks())
             .bind(doc_id)
             .fetch_all(&self.pool)
             .await
@@ -126,7 +144,7 @@ impl KnowledgeBaseDb {
     ) -> Result<Vec<SearchResult>> {
         let query_vec = Vector::from(embedding.to_vec());
 
-        let rows = sqlx::query(KnowledgeBaseSql::VECTOR_SIMILARITY_SEARCH)
+        let rows = sqlx::query(&self.sql.vector_similarity_search())
             .bind(query_vec)
             .bind(threshold)
             .bind(limit)
@@ -153,14 +171,36 @@ impl KnowledgeBaseDb {
         Ok(results)
     }
 
+    /// Find the stored chunk nearest to `embedding`.
+    ///
+    /// Returns `(document_id, similarity_score)`, or None if no chunk has an
+    /// embedding yet.
+    pub async fn nearest_chunk(&self, embedding: &[f32]) -> Result<Option<(i32, f64)>> {
+        let query_vec = Vector::from(embedding.to_vec());
+
+        let row = sqlx::query(&self.sql.nearest_chunk())
+            .bind(query_vec)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to find nearest chunk")?;
+
+        match row {
+            Some(row) => Ok(Some((
+                row.try_get("document_id")?,
+                row.try_get("similarity_score")?,
+            ))),
+            None => Ok(None),
+        }
+    }
+
     /// Drop the knowledge base tables (chunks first to satisfy the FK constraint).
     pub async fn drop_tables(&self) -> Result<()> {
-        sqlx::query("DROP TABLE IF EXISTS knowledge_base_chunks CASCADE;")
+        sqlx::query(&self.sql.drop_chunks_table())
             .execute(&self.pool)
             .await
             .context("Failed to drop chunks table")?;
 
-        sqlx::query("DROP TABLE IF EXISTS knowledge_base_documents CASCADE;")
+        sqlx::query(&self.sql.drop_documents_table())
             .execute(&self.pool)
             .await
             .context("Failed to drop documents table")?;
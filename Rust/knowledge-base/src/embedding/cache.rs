@@ -0,0 +1,121 @@
+//! In-process LRU cache for query embeddings.
+//!
+//! For short interactive queries the `/embed_query` round-trip dominates search
+//! latency, and users often repeat or refine the same query.  Entries are keyed
+//! by the normalized query text (trimmed, inner whitespace collapsed; case is
+//! preserved because the model is case-sensitive), bounded in number, and
+//! expire after a TTL.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+struct CacheEntry {
+    embedding: Vec<f32>,
+    inserted_at: Instant,
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    entries: HashMap<String, CacheEntry>,
+    /// Monotonic use counter; the entry with the smallest `last_used` is evicted.
+    clock: u64,
+}
+
+/// Bounded LRU cache with per-entry TTL, safe to share across tasks.
+#[derive(Debug)]
+pub struct QueryEmbeddingCache {
+    capacity: usize,
+    ttl: Duration,
+    state: Mutex<CacheState>,
+}
+
+impl QueryEmbeddingCache {
+    /// Create a cache holding at most `capacity` entries, each valid for `ttl`.
+    ///
+    /// # Panics
+    /// Panics if `capacity == 0`.
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        Self {
+            capacity,
+            ttl,
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    /// Normalize a query into its cache key.
+    pub fn normalize_query(query: &str) -> String {
+        query.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    /// Return the cached embedding for `query`, if present and not expired.
+    pub fn get(&self, query: &str) -> Option<Vec<f32>> {
+        let key = Self::normalize_query(query);
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        let expired = match state.entries.get(&key) {
+            Some(entry) => entry.inserted_at.elapsed() > self.ttl,
+            None => return None,
+        };
+        if expired {
+            state.entries.remove(&key);
+            return None;
+        }
+
+        state.clock += 1;
+        let clock = state.clock;
+        let entry = state.entries.get_mut(&key)?;
+        entry.last_used = clock;
+        Some(entry.embedding.clone())
+    }
+
+    /// Insert (or refresh) the embedding for `query`, evicting the least
+    /// recently used entry if the cache is full.
+    pub fn insert(&self, query: &str, embedding: Vec<f32>) {
+        let key = Self::normalize_query(query);
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        if !state.entries.contains_key(&key) && state.entries.len() >= self.capacity {
+            // Drop expired entries first; fall back to the least recently used one.
+            let ttl = self.ttl;
+            state.entries.retain(|_, e| e.inserted_at.elapsed() <= ttl);
+            if state.entries.len() >= self.capacity
+                && let Some(lru_key) = state
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, e)| e.last_used)
+                    .map(|(k, _)| k.clone())
+            {
+                state.entries.remove(&lru_key);
+            }
+        }
+
+        state.clock += 1;
+        let last_used = state.clock;
+        state.entries.insert(
+            key,
+            CacheEntry {
+                embedding,
+                inserted_at: Instant::now(),
+                last_used,
+            },
+        );
+    }
+
+    /// Number of entries currently held (including not-yet-evicted expired ones).
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all entries.
+    pub fn clear(&self) {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).entries.clear();
+    }
+}
@@ -27,12 +27,14 @@
 //! }
 //! ```
 
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::{Context, Result, bail};
 use reqwest::Client;
-use tracing::instrument;
+use tracing::{debug, instrument};
 
+use crate::embedding::cache::QueryEmbeddingCache;
 use crate::embedding::config::EmbeddingClientConfig;
 use crate::embedding::types::{
     EmbedQueryRequest, EmbedQueryResponse, EmbedRequest, EmbedResponse, HealthResponse,
@@ -41,11 +43,12 @@ use crate::embedding::types::{
 /// Async HTTP client for the embedding server.
 ///
 /// Create once, reuse across many calls — the underlying `reqwest::Client`
-/// maintains a connection pool.
+/// maintains a connection pool.  Clones share the query embedding cache.
 #[derive(Debug, Clone)]
 pub struct EmbeddingClient {
     embed_client: Client,
     health_client: Client,
+    query_cache: Option<Arc<QueryEmbeddingCache>>,
     config: EmbeddingClientConfig,
 }
 
@@ -65,9 +68,17 @@ impl EmbeddingClient {
             .build()
             .context("Failed to build health HTTP client")?;
 
+        let query_cache = (config.query_cache_capacity > 0).then(|| {
+            Arc::new(QueryEmbeddingCache::new(
+                config.query_cache_capacity,
+                Duration::from_secs(config.query_cache_ttl_secs),
+            ))
+        });
+
         Ok(Self {
             embed_client,
             health_client,
+            query_cache,
             config,
         })
     }
@@ -153,12 +164,20 @@ impl EmbeddingClient {
     /// The query is treated as a single-chunk document so it goes through
     /// the same contextual model.  Returns a 1024-dim L2-normalised vector
     /// ready for cosine similarity against stored chunk embeddings.
+    ///
+    /// Results are served from the in-process query cache when enabled
+    /// (`query_cache_capacity > 0`) and still fresh.
     #[instrument(skip(self, query), fields(query_len = query.len()))]
     pub async fn embed_query(&self, query: &str) -> Result<Vec<f32>> {
         if query.trim().is_empty() {
             bail!("embed_query: query must not be empty");
         }
 
+        if let Some(embedding) = self.query_cache.as_ref().and_then(|c| c.get(query)) {
+            debug!("embed_query: cache hit");
+            return Ok(embedding);
+        }
+
         let request = EmbedQueryRequest {
             query: query.to_string(),
         };
@@ -176,6 +195,10 @@ impl EmbeddingClient {
             .await
             .context("embed_query: failed to parse response JSON")?;
 
+        if let Some(cache) = &self.query_cache {
+            cache.insert(query, response.embedding.clone());
+        }
+
         Ok(response.embedding)
     }
 
@@ -200,6 +223,11 @@ impl EmbeddingClient {
         Ok(response)
     }
 
+    /// Return the query embedding cache, if enabled.
+    pub fn query_cache(&self) -> Option<&QueryEmbeddingCache> {
+        self.query_cache.as_deref()
+    }
+
     /// Return a reference to the active configuration.
     pub fn config(&self) -> &EmbeddingClientConfig {
         &self.config
@@ -19,6 +19,18 @@ pub const DEFAULT_EMBEDDING_SERVER_URL: &str = "http://127.0.0.1:8765";
 pub const DEFAULT_EMBED_TIMEOUT_SECS: u64 = 60;
 /// Default timeout for `/health` calls (seconds).
 pub const DEFAULT_HEALTH_TIMEOUT_SECS: u64 = 5;
+/// Default number of cached query embeddings (0 disables the cache).
+pub const DEFAULT_QUERY_CACHE_CAPACITY: usize = 256;
+/// Default lifetime of a cached query embedding (seconds).
+pub const DEFAULT_QUERY_CACHE_TTL_SECS: u64 = 600;
+
+fn default_query_cache_capacity() -> usize {
+    DEFAULT_QUERY_CACHE_CAPACITY
+}
+
+fn default_query_cache_ttl_secs() -> u64 {
+    DEFAULT_QUERY_CACHE_TTL_SECS
+}
 
 /// Configuration for the embedding HTTP client.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -32,6 +44,14 @@ pub struct EmbeddingClientConfig {
 
     /// Timeout in seconds for health-check requests.
     pub health_timeout_secs: u64,
+
+    /// Maximum number of query embeddings cached in-process; 0 disables the cache.
+    #[serde(default = "default_query_cache_capacity")]
+    pub query_cache_capacity: usize,
+
+    /// Lifetime in seconds of a cached query embedding.
+    #[serde(default = "default_query_cache_ttl_secs")]
+    pub query_cache_ttl_secs: u64,
 }
 
 impl EmbeddingClientConfig {
@@ -43,6 +63,8 @@ impl EmbeddingClientConfig {
     /// - `KB_EMBEDDING_SERVER_URL`
     /// - `KB_EMBED_TIMEOUT_SECS`
     /// - `KB_HEALTH_TIMEOUT_SECS`
+    /// - `KB_QUERY_CACHE_CAPACITY`
+    /// - `KB_QUERY_CACHE_TTL_SECS`
     pub fn from_env() -> Self {
         let _ = dotenvy::dotenv();
         Self {
@@ -56,6 +78,14 @@ impl EmbeddingClientConfig {
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(DEFAULT_HEALTH_TIMEOUT_SECS),
+            query_cache_capacity: std::env::var("KB_QUERY_CACHE_CAPACITY")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_QUERY_CACHE_CAPACITY),
+            query_cache_ttl_secs: std::env::var("KB_QUERY_CACHE_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_QUERY_CACHE_TTL_SECS),
         }
     }
 
@@ -66,6 +96,8 @@ impl EmbeddingClientConfig {
     /// server_url: "http://127.0.0.1:8765"
     /// embed_timeout_secs: 60
     /// health_timeout_secs: 5
+    /// query_cache_capacity: 256
+    /// query_cache_ttl_secs: 600
     /// ```
     pub fn from_yaml(path: impl AsRef<Path>) -> Result<Self> {
         let content = std::fs::read_to_string(&path)
@@ -91,6 +123,8 @@ impl Default for EmbeddingClientConfig {
             server_url: DEFAULT_EMBEDDING_SERVER_URL.to_string(),
             embed_timeout_secs: DEFAULT_EMBED_TIMEOUT_SECS,
             health_timeout_secs: DEFAULT_HEALTH_TIMEOUT_SECS,
+            query_cache_capacity: DEFAULT_QUERY_CACHE_CAPACITY,
+            query_cache_ttl_secs: DEFAULT_QUERY_CACHE_TTL_SECS,
         }
     }
 }
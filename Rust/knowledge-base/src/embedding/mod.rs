@@ -31,10 +31,12 @@
 //! # }
 //! ```
 
+pub mod cache;
 pub mod client;
 pub mod config;
 pub mod types;
 
+pub use cache::QueryEmbeddingCache;
 pub use client::EmbeddingClient;
 pub use config::EmbeddingClientConfig;
 pub use types::{EmbedQueryRequest, EmbedQueryResponse, EmbedRequest, EmbedResponse, HealthResponse};
@@ -0,0 +1,147 @@
+//! Content filters invoked on document text before it is stored or embedded.
+//!
+//! A `ContentFilter` receives the full document text and returns the
+//! (possibly rewritten) text along with what it found.  Filters are chained
+//! in the order they were added to the pipeline; findings are recorded in the
+//! document metadata under `"content_filter"` and returned in `IngestResult`.
+//!
+//! `RegexRedactor` is the built-in filter: it detects email addresses, phone
+//! numbers, and common API key formats, and either scrubs them or only flags
+//! them, depending on its `RedactionMode`.
+
+use std::fmt::Debug;
+
+use regex::{NoExpand, Regex};
+use serde::{Deserialize, Serialize};
+
+/// Something a filter detected in the text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilterFinding {
+    /// Name of the filter that produced this finding.
+    pub filter: String,
+    /// Category of sensitive content (e.g. `"email"`).
+    pub kind: String,
+    /// Number of occurrences.
+    pub count: usize,
+}
+
+/// Output of a single filter pass.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterOutput {
+    /// Text to pass on to the next filter (unchanged if only flagging).
+    pub content: String,
+    pub findings: Vec<FilterFinding>,
+}
+
+/// A pluggable pre-storage content filter.
+pub trait ContentFilter: Debug + Send + Sync {
+    /// Short identifier used in findings and logs.
+    fn name(&self) -> &str;
+
+    /// Inspect `content` and return the text to keep plus any findings.
+    fn filter(&self, content: &str) -> FilterOutput;
+}
+
+/// Whether `RegexRedactor` rewrites matches or only reports them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactionMode {
+    /// Replace each match with `[REDACTED:<KIND>]`.
+    #[default]
+    Redact,
+    /// Leave the text untouched and only report findings.
+    Flag,
+}
+
+/// Regex-based detector for emails, phone numbers, and API keys.
+#[derive(Debug, Clone)]
+pub struct RegexRedactor {
+    mode: RedactionMode,
+    rules: Vec<(String, Regex)>,
+}
+
+impl RegexRedactor {
+    /// Create a redactor with the built-in email, phone, and API key rules.
+    pub fn new(mode: RedactionMode) -> Self {
+        let builtin = [
+            ("api_key", concat!(
+                r"\b(?:sk-[A-Za-z0-9_-]{20,}",
+                r"|AKIA[0-9A-Z]{16}",
+                r"|gh[pousr]_[A-Za-z0-9]{36,}",
+                r"|xox[abprs]-[A-Za-z0-9-]{10,}",
+                r"|hf_[A-Za-z0-9]{30,}",
+                r"|AIza[0-9A-Za-z_-]{35})\b",
+            )),
+            ("email", r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}\b"),
+            // A bare run of ten digits is more often an ID than a phone
+            // number, so require a country code, parentheses or a separator
+            ("phone", concat!(
+                r"(?:\+\d{1,3}[\s.-]?(?:\(\d{3}\)|\d{3})[\s.-]?\d{3}[\s.-]?\d{4}",
+                r"|\(\d{3}\)[\s.-]?\d{3}[\s.-]?\d{4}",
+                r"|\b\d{3}[\s.-]\d{3}[\s.-]?\d{4}",
+                r"|\b\d{3}[\s.-]?\d{3}[\s.-]\d{4})\b",
+            )),
+        ];
+
+        let rules = builtin
+            .iter()
+            .map(|(kind, pattern)| {
+                (
+                    kind.to_string(),
+                    Regex::new(pattern).expect("built-in redaction pattern is valid"),
+                )
+            })
+            .collect();
+
+        Self { mode, rules }
+    }
+
+    /// Add a custom rule; matches are reported under `kind`.
+    pub fn with_rule(mut self, kind: impl Into<String>, pattern: &str) -> Result<Self, regex::Error> {
+        self.rules.push((kind.into(), Regex::new(pattern)?));
+        Ok(self)
+    }
+
+    pub fn mode(&self) -> RedactionMode {
+        self.mode
+    }
+}
+
+impl Default for RegexRedactor {
+    fn default() -> Self {
+        Self::new(RedactionMode::default())
+    }
+}
+
+impl ContentFilter for RegexRedactor {
+    fn name(&self) -> &str {
+        "regex_redactor"
+    }
+
+    fn filter(&self, content: &str) -> FilterOutput {
+        let mut text = content.to_string();
+        let mut findings = Vec::new();
+
+        // Rules run in order, so earlier (more specific) rules win when redacting.
+        for (kind, pattern) in &self.rules {
+            let count = pattern.find_iter(&text).count();
+            if count == 0 {
+                continue;
+            }
+            findings.push(FilterFinding {
+                filter: self.name().to_string(),
+                kind: kind.clone(),
+                count,
+            });
+            if self.mode == RedactionMode::Redact {
+                let replacement = format!("[REDACTED:{}]", kind.to_uppercase());
+                text = pattern.replace_all(&text, NoExpand(&replacement)).into_owned();
+            }
+        }
+
+        FilterOutput {
+            content: text,
+            findings,
+        }
+    }
+}
@@ -1,6 +1,12 @@
+pub mod content_filter;
 pub mod file_ingester;
+pub mod near_duplicate;
 pub mod pipeline;
 pub mod text_chunker;
+pub mod text_normalizer;
+pub use content_filter::{ContentFilter, FilterFinding, RedactionMode, RegexRedactor};
 pub use file_ingester::*;
+pub use near_duplicate::{NearDuplicate, NearDuplicateAction, NearDuplicateConfig};
 pub use pipeline::IngestPipeline;
 pub use text_chunker::*;
+pub use text_normalizer::{TextNormalizer, TextNormalizerConfig, UnicodeForm};
@@ -0,0 +1,106 @@
+//! Near-duplicate detection based on chunk embedding similarity.
+//!
+//! Exact deduplication (by SHA-256 of the raw content) misses re-exports of the
+//! same paper, where whitespace, ligatures, or page furniture differ.  Here each
+//! new chunk is matched against its nearest stored chunk; the document whose
+//! chunks are nearest most often is the candidate, scored by the mean
+//! similarity of those matches over *all* new chunks.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// What to do when an incoming document is a probable near-duplicate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum NearDuplicateAction {
+    /// Do not ingest; report the existing document instead.
+    #[default]
+    Skip,
+    /// Ingest anyway, recording the existing document id in the metadata.
+    Link,
+}
+
+/// Configuration for near-duplicate detection at ingest time.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NearDuplicateConfig {
+    /// Minimum document-level similarity (0.0–1.0) to flag a near-duplicate.
+    pub threshold: f64,
+    /// Action to take when a near-duplicate is found.
+    #[serde(default)]
+    pub action: NearDuplicateAction,
+}
+
+impl NearDuplicateConfig {
+    pub fn new(threshold: f64, action: NearDuplicateAction) -> Self {
+        Self { threshold, action }
+    }
+}
+
+impl Default for NearDuplicateConfig {
+    fn default() -> Self {
+        Self::new(0.95, NearDuplicateAction::Skip)
+    }
+}
+
+/// An existing document that an incoming document closely resembles.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NearDuplicate {
+    /// Id of the existing document.
+    pub document_id: i32,
+    /// Document-level similarity score (0.0–1.0).
+    pub similarity: f64,
+}
+
+/// Score the best candidate document from per-chunk nearest neighbours.
+///
+/// `neighbours[i]` is the nearest stored chunk for new chunk `i`, as
+/// `(document_id, similarity)`, or `None` when the store is empty.  Chunks
+/// whose nearest neighbour belongs to a different document contribute zero,
+/// so a document only scores highly if it matches nearly every new chunk.
+///
+/// Returns `None` if there are no neighbours at all.
+pub fn score_near_duplicate(neighbours: &[Option<(i32, f64)>]) -> Option<NearDuplicate> {
+    if neighbours.is_empty() {
+        return None;
+    }
+
+    // document_id -> (matched chunks, similarity sum)
+    let mut per_document: HashMap<i32, (usize, f64)> = HashMap::new();
+    for (document_id, similarity) in neighbours.iter().flatten() {
+        let entry = per_document.entry(*document_id).or_insert((0, 0.0));
+        entry.0 += 1;
+        entry.1 += similarity;
+    }
+
+    per_document
+        .into_iter()
+        .max_by(|(id_a, (n_a, sum_a)), (id_b, (n_b, sum_b))| {
+            n_a.cmp(n_b)
+                .then(sum_a.total_cmp(sum_b))
+                // Prefer the older (lower id) document on a full tie.
+                .then(id_b.cmp(id_a))
+        })
+        .map(|(document_id, (_, sum))| NearDuplicate {
+            document_id,
+            similarity: sum / neighbours.len() as f64,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_near_duplicate() {
+        assert!(score_near_duplicate(&[]).is_none());
+        assert!(score_near_duplicate(&[None, None]).is_none());
+
+        // Three of four chunks match document 7; the fourth matches document 3.
+        let neighbours = [Some((7, 0.99)), Some((7, 0.97)), Some((3, 0.90)), Some((7, 0.98))];
+        let found = score_near_duplicate(&neighbours).expect("expected a candidate");
+        assert_eq!(found.document_id, 7);
+        // Non-matching chunks count as zero similarity.
+        assert!((found.similarity - (0.99 + 0.97 + 0.98) / 4.0).abs() < 1e-9);
+    }
+}
@@ -1,10 +1,11 @@
-//! Ingestion pipeline wiring FileIngester → TextChunker → EmbeddingClient → KnowledgeBaseDb.
+//! Ingestion pipeline wiring FileIngester → ContentFilter(s) → TextNormalizer → TextChunker →
+//! EmbeddingClient → KnowledgeBaseDb.
 //!
 //! # Example
 //!
 //! ```rust,no_run
 //! use knowledge_base::{
-//!     IngestPipeline, PgConfig, EmbeddingClientConfig,
+//!     ingestion::IngestPipeline, PgConfig, EmbeddingClientConfig,
 //! };
 //!
 //! #[tokio::main]
@@ -18,7 +19,7 @@
 //!     println!("Ingested doc {} with {} chunks", result.document_id, result.chunks_inserted);
 //!
 //!     // Search
-//!     let hits = pipeline.search("quantum field theory", 5).await?;
+//!     let hits = pipeline.search("quantum field theory", 5, None).await?;
 //!     for hit in hits {
 //!         println!("{:.3} – {}", hit.similarity_score, hit.content);
 //!     }
@@ -28,15 +29,22 @@
 //! ```
 
 use std::path::Path;
+use std::sync::Arc;
 
 use anyhow::{Context, Result, bail};
 use tracing::{info, instrument};
 
 use crate::database::connection::{KnowledgeBaseDb, create_knowledge_base_pool};
 use crate::embedding::{EmbeddingClient, EmbeddingClientConfig};
+use crate::ingestion::content_filter::{ContentFilter, FilterFinding};
 use crate::ingestion::file_ingester::{FileIngester, IngestedDocument};
+use crate::ingestion::near_duplicate::{
+    NearDuplicate, NearDuplicateAction, NearDuplicateConfig, score_near_duplicate,
+};
 use crate::ingestion::text_chunker::TextChunker;
+use crate::ingestion::text_normalizer::TextNormalizer;
 use crate::models::{InsertChunk, InsertDocument};
+use crate::sql_statements::KnowledgeBaseSql;
 use crate::PgConfig;
 
 /// Result of a successful ingestion.
@@ -48,6 +56,11 @@ pub struct IngestResult {
     pub chunks_inserted: usize,
     /// True if the document was already present (dedup by hash) and no new data was written.
     pub was_duplicate: bool,
+    /// Existing document this one closely resembles, if near-duplicate detection is enabled
+    /// and found a match. With `NearDuplicateAction::Skip`, `document_id` is the existing id.
+    pub near_duplicate: Option<NearDuplicate>,
+    /// Findings reported by the configured content filters (empty if none matched).
+    pub filter_findings: Vec<FilterFinding>,
 }
 
 /// Ingestion pipeline orchestrating file reading, chunking, embedding, and storage.
@@ -55,7 +68,10 @@ pub struct IngestResult {
 pub struct IngestPipeline {
     db: KnowledgeBaseDb,
     embedding_client: EmbeddingClient,
+    filters: Vec<Arc<dyn ContentFilter>>,
+    normalizer: TextNormalizer,
     chunker: TextChunker,
+    near_duplicate: Option<NearDuplicateConfig>,
 }
 
 impl IngestPipeline {
@@ -67,10 +83,20 @@ impl IngestPipeline {
     /// or table creation fails.
     #[instrument(skip(pg_config, embedding_config))]
     pub async fn new(pg_config: &PgConfig, embedding_config: EmbeddingClientConfig) -> Result<Self> {
+        Self::new_with_sql(pg_config, embedding_config, KnowledgeBaseSql::default()).await
+    }
+
+    /// Like `new`, but with a custom schema / table naming for the knowledge base tables.
+    #[instrument(skip(pg_config, embedding_config, sql))]
+    pub async fn new_with_sql(
+        pg_config: &PgConfig,
+        embedding_config: EmbeddingClientConfig,
+        sql: KnowledgeBaseSql,
+    ) -> Result<Self> {
         let pool = create_knowledge_base_pool(pg_config)
             .await
             .context("Failed to create database pool")?;
-        let db = KnowledgeBaseDb::new(pool);
+        let db = KnowledgeBaseDb::new(pool).with_sql(sql);
 
         // Ensure pgvector extension and tables exist
         db.create_extension()
@@ -88,16 +114,46 @@ impl IngestPipeline {
         Ok(Self {
             db,
             embedding_client,
+            filters: Vec::new(),
+            normalizer: TextNormalizer::default(),
             chunker: TextChunker::default(),
+            near_duplicate: None,
         })
     }
 
+    /// Add a content filter, run on document text before it is hashed, stored, or embedded.
+    ///
+    /// Filters run in the order they are added.
+    pub fn with_content_filter(mut self, filter: impl ContentFilter + 'static) -> Self {
+        self.filters.push(Arc::new(filter));
+        self
+    }
+
+    /// Replace the text normalizer applied before chunking.
+    ///
+    /// The default normalizer leaves text unchanged; see
+    /// `TextNormalizerConfig::pdf()` for the stages suited to PDF text.
+    pub fn with_text_normalizer(mut self, normalizer: TextNormalizer) -> Self {
+        self.normalizer = normalizer;
+        self
+    }
+
+    /// Enable near-duplicate detection by embedding similarity (disabled by default).
+    pub fn with_near_duplicate_detection(mut self, config: NearDuplicateConfig) -> Self {
+        self.near_duplicate = Some(config);
+        self
+    }
+
     /// Ingest a file from disk.
     ///
     /// # Deduplication
     ///
     /// If a document with the same content hash already exists, returns immediately
     /// with `was_duplicate: true` and the existing document id.
+    ///
+    /// With near-duplicate detection enabled, chunk embeddings are also compared
+    /// against stored ones; a match is reported in `near_duplicate` and either
+    /// skipped or linked according to the configured action.
     #[instrument(skip(self, path), fields(path = %path.display()))]
     pub async fn ingest_file(&self, path: &Path) -> Result<IngestResult> {
         let ingested = FileIngester::ingest_file(path)
@@ -129,45 +185,25 @@ impl IngestPipeline {
     /// Internal helper to ingest an already-parsed document.
     #[instrument(skip(self, ingested))]
     async fn ingest_ingested_document(&self, ingested: &IngestedDocument) -> Result<IngestResult> {
+        let (ingested, filter_findings) = self.apply_content_filters(ingested);
+        let ingested = &ingested;
         let content_hash = FileIngester::compute_sha256(&ingested.raw_content);
 
         // Deduplication check
-        if self.db.document_exists_by_hash(&content_hash).await? {
-            // Fetch existing document id for the return value
-            let existing = sqlx::query_as::<_, crate::models::Document>(
-                "SELECT id, title, source_path, source_type, raw_content, content_hash, metadata, ingested_at \
-                 FROM knowledge_base_documents WHERE content_hash = $1"
-            )
-            .bind(&content_hash)
-            .fetch_optional(self.db.pool())
-            .await
-            .context("Failed to fetch existing document by hash")?;
-
-            if let Some(doc) = existing {
-                info!(document_id = doc.id, "Document already exists (dedup)");
-                return Ok(IngestResult {
-                    document_id: doc.id,
-                    chunks_inserted: 0,
-                    was_duplicate: true,
-                });
-            }
+        if let Some(doc) = self.db.get_document_by_hash(&content_hash).await? {
+            info!(document_id = doc.id, "Document already exists (dedup)");
+            return Ok(IngestResult {
+                document_id: doc.id,
+                chunks_inserted: 0,
+                was_duplicate: true,
+                near_duplicate: None,
+                filter_findings,
+            });
         }
 
-        // Insert document
-        let insert_doc = InsertDocument {
-            title: Some(ingested.title.clone()),
-            source_path: Some(ingested.source_path.clone()),
-            source_type: Some(ingested.source_type.clone()),
-            raw_content: ingested.raw_content.clone(),
-            content_hash: content_hash.clone(),
-            metadata: ingested.metadata.clone(),
-        };
-
-        let document_id = self.db.insert_document(&insert_doc).await?;
-        info!(document_id, "Inserted document");
-
-        // Chunk the content
-        let chunks = self.chunker.chunk_text(&ingested.raw_content);
+        // Normalize and chunk the content (the stored raw_content and its hash stay untouched)
+        let normalized = self.normalizer.normalize(&ingested.raw_content);
+        let chunks = self.chunker.chunk_text(&normalized);
         if chunks.is_empty() {
             bail!("No chunks produced from document content");
         }
@@ -183,6 +219,58 @@ impl IngestPipeline {
             );
         }
 
+        // Near-duplicate check against existing chunk embeddings
+        let mut metadata = ingested.metadata.clone();
+        let near_duplicate = match self.near_duplicate {
+            Some(config) => self.find_near_duplicate(&chunk_embeddings, config.threshold).await?,
+            None => None,
+        };
+        if let (Some(found), Some(config)) = (near_duplicate, self.near_duplicate) {
+            match config.action {
+                NearDuplicateAction::Skip => {
+                    info!(
+                        document_id = found.document_id,
+                        similarity = found.similarity,
+                        "Probable near-duplicate, skipping"
+                    );
+                    return Ok(IngestResult {
+                        document_id: found.document_id,
+                        chunks_inserted: 0,
+                        was_duplicate: false,
+                        near_duplicate: Some(found),
+                        filter_findings,
+                    });
+                }
+                NearDuplicateAction::Link => {
+                    info!(
+                        document_id = found.document_id,
+                        similarity = found.similarity,
+                        "Probable near-duplicate, linking"
+                    );
+                    let mut object = match metadata {
+                        Some(serde_json::Value::Object(map)) => map,
+                        _ => serde_json::Map::new(),
+                    };
+                    object.insert("near_duplicate_of".to_string(), found.document_id.into());
+                    object.insert("near_duplicate_similarity".to_string(), found.similarity.into());
+                    metadata = Some(serde_json::Value::Object(object));
+                }
+            }
+        }
+
+        // Insert document
+        let insert_doc = InsertDocument {
+            title: Some(ingested.title.clone()),
+            source_path: Some(ingested.source_path.clone()),
+            source_type: Some(ingested.source_type.clone()),
+            raw_content: ingested.raw_content.clone(),
+            content_hash: content_hash.clone(),
+            metadata,
+        };
+
+        let document_id = self.db.insert_document(&insert_doc).await?;
+        info!(document_id, "Inserted document");
+
         // Insert chunks with embeddings
         let mut chunks_inserted = 0usize;
         for (idx, (chunk_text, embedding)) in chunks.iter().zip(chunk_embeddings.iter()).enumerate() {
@@ -209,9 +297,57 @@ impl IngestPipeline {
             document_id,
             chunks_inserted,
             was_duplicate: false,
+            near_duplicate,
+            filter_findings,
         })
     }
 
+    /// Run the content filters over the raw content, recording findings in the metadata.
+    fn apply_content_filters(
+        &self,
+        ingested: &IngestedDocument,
+    ) -> (IngestedDocument, Vec<FilterFinding>) {
+        let mut filtered = ingested.clone();
+        let mut findings = Vec::new();
+
+        for filter in &self.filters {
+            let output = filter.filter(&filtered.raw_content);
+            filtered.raw_content = output.content;
+            findings.extend(output.findings);
+        }
+
+        if !findings.is_empty() {
+            info!(n_findings = findings.len(), "Content filters reported findings");
+            let mut object = match filtered.metadata.take() {
+                Some(serde_json::Value::Object(map)) => map,
+                _ => serde_json::Map::new(),
+            };
+            object.insert(
+                "content_filter".to_string(),
+                serde_json::to_value(&findings).unwrap_or_default(),
+            );
+            filtered.metadata = Some(serde_json::Value::Object(object));
+        }
+
+        (filtered, findings)
+    }
+
+    /// Match each new chunk against its nearest stored chunk and score the best candidate.
+    ///
+    /// Returns the candidate only if its document-level similarity reaches `threshold`.
+    async fn find_near_duplicate(
+        &self,
+        chunk_embeddings: &[Vec<f32>],
+        threshold: f64,
+    ) -> Result<Option<NearDuplicate>> {
+        let mut neighbours = Vec::with_capacity(chunk_embeddings.len());
+        for embedding in chunk_embeddings {
+            neighbours.push(self.db.nearest_chunk(embedding).await?);
+        }
+
+        Ok(score_near_duplicate(&neighbours).filter(|found| found.similarity >= threshold))
+    }
+
     /// Search the knowledge
     /// Search the knowledge base for relevant chunks.
     ///
@@ -0,0 +1,254 @@
+//! Text normalization applied between `FileIngester` and `TextChunker`.
+//!
+//! Raw PDF text is full of extraction artifacts (ligatures, words split across
+//! line-end hyphens, running headers and page numbers, ragged whitespace) that
+//! would otherwise end up in chunk embeddings.  Each stage can be toggled
+//! independently and all are off by default, since header stripping and
+//! hyphen repair would damage Markdown, code and other plain text;
+//! `TextNormalizerConfig::pdf()` enables the stages suited to PDF text.
+//! Stages run in this order:
+//!
+//! 1. Unicode normalization (NFC or NFKC)
+//! 2. Header/footer stripping (short lines repeated across pages)
+//! 3. Hyphenation repair (`embed-\nding` → `embedding`)
+//! 4. Boilerplate removal (user-supplied regex patterns)
+//! 5. Whitespace collapsing
+//!
+//! Load order (first wins):
+//!   1. `TextNormalizerConfig::from_yaml(path)`
+//!   2. `TextNormalizerConfig::default()`
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+/// Lines longer than this are never treated as headers or footers.
+const MAX_HEADER_FOOTER_LEN: usize = 80;
+
+/// Unicode normalization form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnicodeForm {
+    /// Canonical composition.
+    Nfc,
+    /// Compatibility composition; also folds ligatures such as `ﬁ` into `fi`.
+    Nfkc,
+}
+
+/// Configuration for the text normalization stage.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextNormalizerConfig {
+    /// Unicode normalization form, or None to leave code points untouched.
+    pub unicode_form: Option<UnicodeForm>,
+
+    /// Collapse runs of spaces/tabs, trim line ends, and squeeze blank lines
+    /// so that at most one empty line separates paragraphs.
+    pub collapse_whitespace: bool,
+
+    /// Re-join words hyphenated across a line break.
+    pub repair_hyphenation: bool,
+
+    /// Remove short lines that repeat throughout the document (running
+    /// headers, footers, "Page N of M") and lines holding only a page number.
+    pub strip_headers_footers: bool,
+
+    /// Minimum number of occurrences for a line to count as a header/footer.
+    /// Digits are ignored when comparing lines.
+    pub min_header_footer_repeats: usize,
+
+    /// Regex patterns whose matches are removed (e.g. copyright notices).
+    pub boilerplate_patterns: Vec<String>,
+}
+
+impl TextNormalizerConfig {
+    /// Load configuration from a YAML file.
+    ///
+    /// Expected keys (all optional; missing stages stay off):
+    /// ```yaml
+    /// unicode_form: nfkc
+    /// collapse_whitespace: true
+    /// repair_hyphenation: true
+    /// strip_headers_footers: true
+    /// min_header_footer_repeats: 3
+    /// boilerplate_patterns:
+    ///   - "(?i)all rights reserved\\.?"
+    /// ```
+    pub fn from_yaml(path: impl AsRef<Path>) -> Result<Self> {
+        let content = std::fs::read_to_string(&path).with_context(|| {
+            format!("Failed to read text normalizer config: {:?}", path.as_ref())
+        })?;
+        let config: Self = serde_yaml::from_str(&content).with_context(|| {
+            format!("Failed to parse text normalizer config: {:?}", path.as_ref())
+        })?;
+        Ok(config)
+    }
+
+    /// The stages suited to text extracted from PDFs: NFKC, header/footer
+    /// stripping, hyphenation repair and whitespace collapsing.
+    pub fn pdf() -> Self {
+        Self {
+            unicode_form: Some(UnicodeForm::Nfkc),
+            collapse_whitespace: true,
+            repair_hyphenation: true,
+            strip_headers_footers: true,
+            ..Self::disabled()
+        }
+    }
+
+    /// A configuration with every stage switched off (the default).
+    pub fn disabled() -> Self {
+        Self {
+            unicode_form: None,
+            collapse_whitespace: false,
+            repair_hyphenation: false,
+            strip_headers_footers: false,
+            min_header_footer_repeats: 3,
+            boilerplate_patterns: Vec::new(),
+        }
+    }
+}
+
+impl Default for TextNormalizerConfig {
+    fn default() -> Self {
+        Self::disabled()
+    }
+}
+
+/// Applies the configured normalization stages to document text.
+#[derive(Debug, Clone)]
+pub struct TextNormalizer {
+    config: TextNormalizerConfig,
+    boilerplate: Vec<Regex>,
+    hyphenation: Regex,
+}
+
+impl TextNormalizer {
+    /// Create a normalizer, compiling the boilerplate patterns.
+    ///
+    /// Returns `Err` if any boilerplate pattern is not a valid regex.
+    pub fn new(config: TextNormalizerConfig) -> Result<Self> {
+        let boilerplate = config
+            .boilerplate_patterns
+            .iter()
+            .map(|p| {
+                Regex::new(p).with_context(|| format!("Invalid boilerplate pattern: {}", p))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // A letter, a hyphen at end of line, then a lowercase continuation.
+        let hyphenation = Regex::new(r"(\p{L})-[ \t]*\r?\n[ \t]*(\p{Ll})")
+            .expect("hyphenation pattern is valid");
+
+        Ok(Self {
+            config,
+            boilerplate,
+            hyphenation,
+        })
+    }
+
+    /// Normalize `text` according to the configuration.
+    pub fn normalize(&self, text: &str) -> String {
+        let mut text = match self.config.unicode_form {
+            Some(UnicodeForm::Nfc) => text.nfc().collect(),
+            Some(UnicodeForm::Nfkc) => text.nfkc().collect(),
+            None => text.to_string(),
+        };
+
+        if self.config.strip_headers_footers {
+            text = strip_headers_footers(&text, self.config.min_header_footer_repeats);
+        }
+
+        if self.config.repair_hyphenation {
+            text = self.hyphenation.replace_all(&text, "$1$2").into_owned();
+        }
+
+        for pattern in &self.boilerplate {
+            text = pattern.replace_all(&text, "").into_owned();
+        }
+
+        if self.config.collapse_whitespace {
+            text = collapse_whitespace(&text);
+        }
+
+        text
+    }
+
+    /// Return a reference to the active configuration.
+    pub fn config(&self) -> &TextNormalizerConfig {
+        &self.config
+    }
+}
+
+impl Default for TextNormalizer {
+    fn default() -> Self {
+        Self::new(TextNormalizerConfig::default()).expect("default config has no patterns")
+    }
+}
+
+/// Key used to compare candidate header/footer lines: digits are masked so
+/// "Page 3 of 10" and "Page 4 of 10" compare equal.
+fn header_footer_key(line: &str) -> String {
+    line.trim()
+        .chars()
+        .map(|c| if c.is_ascii_digit() { '#' } else { c })
+        .collect()
+}
+
+fn is_page_number(line: &str) -> bool {
+    let trimmed = line.trim();
+    !trimmed.is_empty() && trimmed.len() <= 4 && trimmed.chars().all(|c| c.is_ascii_digit())
+}
+
+fn strip_headers_footers(text: &str, min_repeats: usize) -> String {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if !trimmed.is_empty() && trimmed.len() <= MAX_HEADER_FOOTER_LEN {
+            *counts.entry(header_footer_key(trimmed)).or_insert(0) += 1;
+        }
+    }
+
+    text.lines()
+        .filter(|line| {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.len() > MAX_HEADER_FOOTER_LEN {
+                return true;
+            }
+            if is_page_number(trimmed) {
+                return false;
+            }
+            counts
+                .get(&header_footer_key(trimmed))
+                .is_none_or(|&n| n < min_repeats.max(2))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut blank_run = 0usize;
+
+    for line in text.lines() {
+        let collapsed = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if collapsed.is_empty() {
+            blank_run += 1;
+            continue;
+        }
+        if !out.is_empty() {
+            out.push('\n');
+            if blank_run > 0 {
+                out.push('\n');
+            }
+        }
+        out.push_str(&collapsed);
+        blank_run = 0;
+    }
+
+    out
+}
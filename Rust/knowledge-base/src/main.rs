@@ -3,20 +3,37 @@
 //! # Ingest a file
 //! cargo run --bin kb -- ingest /path/to/document.pdf
 //!
+//! # Ingest, skipping probable near-duplicates of existing documents
+//! cargo run --bin kb -- ingest /path/to/document.pdf --near-duplicate-threshold 0.95
+//!
+//! # Ingest with a custom text normalization config
+//! cargo run --bin kb -- ingest /path/to/document.pdf \
+//!     --normalizer-config Configurations/text_normalizer_configuration.yml
+//!
+//! # Ingest, scrubbing emails, phone numbers, and API keys first
+//! cargo run --bin kb -- ingest /path/to/document.pdf --pii redact
+//!
 //! # Search
 //! cargo run --bin kb -- search "quantum field theory" --limit 5
 //!
+//! # Search and write the hits to a markdown report
+//! cargo run --bin kb -- search "quantum field theory" --out report.md
+//!
 //! # Check embedding server health
 //! cargo run --bin kb -- health
 
 use std::path::PathBuf;
 
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use knowledge_base::{
-    configuration::config_from_env,
+    configuration::{config_from_env, sql_from_env},
     embedding::{EmbeddingClient, EmbeddingClientConfig},
-    ingestion::IngestPipeline,
+    ingestion::{
+        IngestPipeline, NearDuplicateAction, NearDuplicateConfig, RedactionMode, RegexRedactor,
+        TextNormalizer, TextNormalizerConfig,
+    },
+    report::render_markdown_report,
 };
 use tracing::{error, info};
 
@@ -34,6 +51,21 @@ enum Commands {
     Ingest {
         /// Path to the file to ingest
         path: PathBuf,
+        /// Flag documents whose chunks match existing ones at or above this
+        /// similarity (0.0–1.0) as probable near-duplicates (optional)
+        #[arg(long)]
+        near_duplicate_threshold: Option<f64>,
+        /// Ingest near-duplicates anyway, linking them to the existing document
+        /// in metadata instead of skipping them
+        #[arg(long, requires = "near_duplicate_threshold")]
+        link_near_duplicates: bool,
+        /// YAML file configuring text normalization before chunking (optional)
+        #[arg(long)]
+        normalizer_config: Option<PathBuf>,
+        /// Scan for emails, phone numbers, and API keys before storage:
+        /// `redact` scrubs them, `flag` only records them in metadata
+        #[arg(long, value_enum)]
+        pii: Option<PiiMode>,
     },
     /// Search the knowledge base
     Search {
@@ -45,24 +77,65 @@ enum Commands {
         /// Minimum similarity threshold (0.0–1.0, optional)
         #[arg(short, long)]
         threshold: Option<f32>,
+        /// Also write the results as a markdown report to this file (optional)
+        #[arg(short, long)]
+        out: Option<PathBuf>,
     },
     /// Check embedding server health
     Health,
 }
 
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum PiiMode {
+    Redact,
+    Flag,
+}
+
+impl From<PiiMode> for RedactionMode {
+    fn from(mode: PiiMode) -> Self {
+        match mode {
+            PiiMode::Redact => RedactionMode::Redact,
+            PiiMode::Flag => RedactionMode::Flag,
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Ingest { path } => ingest_file(path).await,
-        Commands::Search { query, limit, threshold } => search(query, limit, threshold).await,
+        Commands::Ingest {
+            path,
+            near_duplicate_threshold,
+            link_near_duplicates,
+            normalizer_config,
+            pii,
+        } => {
+            let near_duplicate = near_duplicate_threshold.map(|threshold| {
+                let action = if link_near_duplicates {
+                    NearDuplicateAction::Link
+                } else {
+                    NearDuplicateAction::Skip
+                };
+                NearDuplicateConfig::new(threshold, action)
+            });
+            ingest_file(path, near_duplicate, normalizer_config, pii).await
+        }
+        Commands::Search { query, limit, threshold, out } => {
+            search(query, limit, threshold, out).await
+        }
         Commands::Health => check_health().await,
     }
 }
 
-async fn ingest_file(path: PathBuf) -> Result<()> {
+async fn ingest_file(
+    path: PathBuf,
+    near_duplicate: Option<NearDuplicateConfig>,
+    normalizer_config: Option<PathBuf>,
+    pii: Option<PiiMode>,
+) -> Result<()> {
     if !path.exists() {
         anyhow::bail!("File not found: {}", path.display());
     }
@@ -71,17 +144,45 @@ async fn ingest_file(path: PathBuf) -> Result<()> {
     let embedding_config = EmbeddingClientConfig::from_env();
 
     info!("Initializing pipeline...");
-    let pipeline = IngestPipeline::new(&pg_config, embedding_config)
+    let sql = sql_from_env()?;
+    let mut pipeline = IngestPipeline::new_with_sql(&pg_config, embedding_config, sql)
         .await
         .context("Failed to initialize ingest pipeline")?;
+    if let Some(config) = near_duplicate {
+        pipeline = pipeline.with_near_duplicate_detection(config);
+    }
+    if let Some(config_path) = normalizer_config {
+        let config = TextNormalizerConfig::from_yaml(&config_path)?;
+        pipeline = pipeline.with_text_normalizer(TextNormalizer::new(config)?);
+    }
+    if let Some(mode) = pii {
+        pipeline = pipeline.with_content_filter(RegexRedactor::new(mode.into()));
+    }
 
     info!("Ingesting {}...", path.display());
     let result = pipeline.ingest_file(&path).await
         .with_context(|| format!("Failed to ingest {}", path.display()))?;
 
+    for finding in &result.filter_findings {
+        info!("{}: {} {} match(es)", finding.filter, finding.count, finding.kind);
+    }
+
     if result.was_duplicate {
         info!("Document already exists (duplicate). ID: {}", result.document_id);
+    } else if result.chunks_inserted == 0 && let Some(found) = result.near_duplicate {
+        info!(
+            "Probable near-duplicate of document {} ({:.1}% similar); not ingested",
+            found.document_id,
+            found.similarity * 100.0
+        );
     } else {
+        if let Some(found) = result.near_duplicate {
+            info!(
+                "Linked as probable near-duplicate of document {} ({:.1}% similar)",
+                found.document_id,
+                found.similarity * 100.0
+            );
+        }
         info!(
             "Ingested document {} with {} chunks",
             result.document_id,
@@ -92,12 +193,18 @@ async fn ingest_file(path: PathBuf) -> Result<()> {
     Ok(())
 }
 
-async fn search(query: String, limit: i64, threshold: Option<f32>) -> Result<()> {
+async fn search(
+    query: String,
+    limit: i64,
+    threshold: Option<f32>,
+    out: Option<PathBuf>,
+) -> Result<()> {
     let pg_config = config_from_env();
     let embedding_config = EmbeddingClientConfig::from_env();
 
     info!("Initializing pipeline...");
-    let pipeline = IngestPipeline::new(&pg_config, embedding_config)
+    let sql = sql_from_env()?;
+    let pipeline = IngestPipeline::new_with_sql(&pg_config, embedding_config, sql)
         .await
         .context("Failed to initialize ingest pipeline")?;
 
@@ -105,6 +212,13 @@ async fn search(query: String, limit: i64, threshold: Option<f32>) -> Result<()>
     let results = pipeline.search(&query, limit, threshold).await
         .context("Search failed")?;
 
+    if let Some(out_path) = out {
+        let report = render_markdown_report(&query, &results, chrono::Utc::now());
+        std::fs::write(&out_path, report)
+            .with_context(|| format!("Failed to write report to {}", out_path.display()))?;
+        info!("Wrote markdown report to {}", out_path.display());
+    }
+
     if results.is_empty() {
         println!("No results found.");
         return Ok(());
@@ -0,0 +1,117 @@
+//! Markdown rendering of search results, for sharing retrieval output.
+//!
+//! The report lists the query, when it was run, and for every hit its score,
+//! a link to the source file, and the full chunk text in a fenced block.
+
+use chrono::{DateTime, Utc};
+
+use crate::models::SearchResult;
+
+/// Render `results` for `query` as a markdown document.
+pub fn render_markdown_report(
+    query: &str,
+    results: &[SearchResult],
+    generated_at: DateTime<Utc>,
+) -> String {
+    let mut out = String::new();
+
+    out.push_str("# Knowledge base search report\n\n");
+    out.push_str(&format!("- **Query:** {}\n", escape_inline(query)));
+    out.push_str(&format!(
+        "- **Generated:** {}\n",
+        generated_at.format("%Y-%m-%d %H:%M:%S UTC")
+    ));
+    out.push_str(&format!("- **Results:** {}\n", results.len()));
+
+    if results.is_empty() {
+        out.push_str("\nNo results found.\n");
+        return out;
+    }
+
+    for (i, hit) in results.iter().enumerate() {
+        out.push_str(&format!(
+            "\n## {}. {}\n\n",
+            i + 1,
+            escape_inline(hit.title.as_deref().unwrap_or("(untitled)"))
+        ));
+        out.push_str(&format!(
+            "- **Score:** {:.4} ({:.1}%)\n",
+            hit.similarity_score,
+            hit.similarity_score * 100.0
+        ));
+        match hit.source_path.as_deref() {
+            Some(source) => out.push_str(&format!(
+                "- **Source:** [{}]({})\n",
+                escape_inline(source),
+                link_target(source)
+            )),
+            None => out.push_str("- **Source:** (unknown)\n"),
+        }
+        out.push_str(&format!(
+            "- **Chunk:** {}/{} (document {}, chunk id {})\n\n",
+            hit.chunk_index + 1,
+            hit.total_chunks,
+            hit.document_id,
+            hit.id
+        ));
+
+        let fence = fence_for(&hit.content);
+        out.push_str(&fence);
+        out.push_str("text\n");
+        out.push_str(hit.content.trim_end());
+        out.push('\n');
+        out.push_str(&fence);
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Escape characters that would otherwise be read as inline markdown.
+fn escape_inline(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\n' | '\r' => out.push(' '),
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Link target for a source path: URLs are kept as-is, local paths become
+/// `file://` links with spaces and parentheses percent-encoded.
+fn link_target(source: &str) -> String {
+    if source.contains("://") {
+        return source.replace(' ', "%20");
+    }
+    let path = source
+        .replace('%', "%25")
+        .replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29");
+    if path.starts_with('/') {
+        format!("file://{}", path)
+    } else {
+        path
+    }
+}
+
+/// A backtick fence longer than any backtick run inside `content`.
+fn fence_for(content: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in content.chars() {
+        if c == '`' {
+            run += 1;
+            longest = longest.max(run);
+        } else {
+            run = 0;
+        }
+    }
+    "`".repeat(longest.max(2) + 1)
+}
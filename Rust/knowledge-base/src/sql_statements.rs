@@ -1,14 +1,195 @@
 /// SQL statements for knowledge base operations.
 ///
 /// All queries use PostgreSQL $N positional parameters (sqlx convention).
-pub struct KnowledgeBaseSql;
+///
+/// Table names and the schema are configurable so several applications or
+/// environments can share one database.  The default instance uses the
+/// `public` schema and the `knowledge_base_documents` / `knowledge_base_chunks`
+/// tables; build others with `KnowledgeBaseSql::builder()`:
+///
+/// ```rust
+/// use knowledge_base::sql_statements::KnowledgeBaseSql;
+///
+/// let sql = KnowledgeBaseSql::builder()
+///     .schema("research")
+///     .table_prefix("staging_")
+///     .build()
+///     .unwrap();
+/// assert_eq!(sql.documents_table(), "\"research\".\"staging_knowledge_base_documents\"");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KnowledgeBaseSql {
+    schema: String,
+    documents_table: String,
+    chunks_table: String,
+    index_prefix: String,
+}
+
+/// Default schema for the knowledge base tables.
+pub const DEFAULT_SCHEMA: &str = "public";
+/// Default documents table name.
+pub const DEFAULT_DOCUMENTS_TABLE: &str = "knowledge_base_documents";
+/// Default chunks table name.
+pub const DEFAULT_CHUNKS_TABLE: &str = "knowledge_base_chunks";
+
+/// Builder for `KnowledgeBaseSql`; identifiers are validated in `build()`.
+#[derive(Debug, Clone, Default)]
+pub struct KnowledgeBaseSqlBuilder {
+    schema: Option<String>,
+    table_prefix: Option<String>,
+    documents_table: Option<String>,
+    chunks_table: Option<String>,
+}
+
+impl KnowledgeBaseSqlBuilder {
+    /// Schema holding the tables (default: `public`).
+    pub fn schema(mut self, schema: impl Into<String>) -> Self {
+        self.schema = Some(schema.into());
+        self
+    }
+
+    /// Prefix for the default table and index names, e.g. `staging_`.
+    pub fn table_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.table_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Full documents table name (takes precedence over the prefix).
+    pub fn documents_table(mut self, name: impl Into<String>) -> Self {
+        self.documents_table = Some(name.into());
+        self
+    }
+
+    /// Full chunks table name (takes precedence over the prefix).
+    pub fn chunks_table(mut self, name: impl Into<String>) -> Self {
+        self.chunks_table = Some(name.into());
+        self
+    }
+
+    /// Validate identifiers and build the statement set.
+    ///
+    /// Names must start with a letter or underscore, contain only ASCII
+    /// letters, digits, and underscores, and fit PostgreSQL's 63-byte limit.
+    pub fn build(self) -> anyhow::Result<KnowledgeBaseSql> {
+        let prefix = self.table_prefix.unwrap_or_default();
+        let schema = self.schema.unwrap_or_else(|| DEFAULT_SCHEMA.to_string());
+        let documents_table = self
+            .documents_table
+            .unwrap_or_else(|| format!("{}{}", prefix, DEFAULT_DOCUMENTS_TABLE));
+        let chunks_table = self
+            .chunks_table
+            .unwrap_or_else(|| format!("{}{}", prefix, DEFAULT_CHUNKS_TABLE));
+
+        // Index names are schema-scoped, so only the chunks table name needs
+        // to be folded in when it differs from the default.
+        let index_prefix = if chunks_table == format!("{}{}", prefix, DEFAULT_CHUNKS_TABLE) {
+            prefix.clone()
+        } else {
+            format!("{}_", chunks_table)
+        };
+
+        for (what, name) in [
+            ("schema", &schema),
+            ("documents table", &documents_table),
+            ("chunks table", &chunks_table),
+        ] {
+            validate_identifier(what, name)?;
+        }
+        if !prefix.is_empty() {
+            validate_identifier("table prefix", &prefix)?;
+        }
+        if documents_table == chunks_table {
+            anyhow::bail!("documents and chunks tables must have different names");
+        }
+        // Longest derived index name: {index_prefix}idx_kb_chunks_embedding_hnsw
+        validate_identifier(
+            "index name",
+            &format!("{}idx_kb_chunks_embedding_hnsw", index_prefix),
+        )?;
+
+        Ok(KnowledgeBaseSql {
+            schema,
+            documents_table,
+            chunks_table,
+            index_prefix,
+        })
+    }
+}
+
+fn validate_identifier(what: &str, name: &str) -> anyhow::Result<()> {
+    let mut chars = name.chars();
+    let valid_start = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_');
+    if !valid_start || !chars.all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        anyhow::bail!(
+            "Invalid {} '{}': use letters, digits, and underscores, starting with a letter or underscore",
+            what,
+            name
+        );
+    }
+    if name.len() > 63 {
+        anyhow::bail!("Invalid {} '{}': longer than 63 bytes", what, name);
+    }
+    Ok(())
+}
+
+impl Default for KnowledgeBaseSql {
+    fn default() -> Self {
+        Self {
+            schema: DEFAULT_SCHEMA.to_string(),
+            documents_table: DEFAULT_DOCUMENTS_TABLE.to_string(),
+            chunks_table: DEFAULT_CHUNKS_TABLE.to_string(),
+            index_prefix: String::new(),
+        }
+    }
+}
 
 impl KnowledgeBaseSql {
     pub const CREATE_VECTOR_EXTENSION: &'static str =
         "CREATE EXTENSION IF NOT EXISTS vector;";
 
-    pub const CREATE_DOCUMENTS_TABLE: &'static str = "
-        CREATE TABLE IF NOT EXISTS knowledge_base_documents (
+    pub fn builder() -> KnowledgeBaseSqlBuilder {
+        KnowledgeBaseSqlBuilder::default()
+    }
+
+    /// Unquoted schema name.
+    pub fn schema(&self) -> &str {
+        &self.schema
+    }
+
+    /// Unquoted documents table name (without schema).
+    pub fn documents_table_name(&self) -> &str {
+        &self.documents_table
+    }
+
+    /// Unquoted chunks table name (without schema).
+    pub fn chunks_table_name(&self) -> &str {
+        &self.chunks_table
+    }
+
+    /// Schema-qualified, quoted documents table.
+    pub fn documents_table(&self) -> String {
+        format!("\"{}\".\"{}\"", self.schema, self.documents_table)
+    }
+
+    /// Schema-qualified, quoted chunks table.
+    pub fn chunks_table(&self) -> String {
+        format!("\"{}\".\"{}\"", self.schema, self.chunks_table)
+    }
+
+    fn index_name(&self, suffix: &str) -> String {
+        format!("\"{}idx_kb_chunks_{}\"", self.index_prefix, suffix)
+    }
+
+    pub fn create_schema(&self) -> String {
+        format!("CREATE SCHEMA IF NOT EXISTS \"{}\";", self.schema)
+    }
+
+    pub fn create_documents_table(&self) -> String {
+        format!(
+            "
+        CREATE TABLE IF NOT EXISTS {} (
             id SERIAL PRIMARY KEY,
             title TEXT,
             source_path TEXT,
@@ -18,12 +199,17 @@ impl KnowledgeBaseSql {
             metadata JSONB,
             ingested_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
         );
-    ";
+    ",
+            self.documents_table()
+        )
+    }
 
-    pub const CREATE_CHUNKS_TABLE: &'static str = "
-        CREATE TABLE IF NOT EXISTS knowledge_base_chunks (
+    pub fn create_chunks_table(&self) -> String {
+        format!(
+            "
+        CREATE TABLE IF NOT EXISTS {} (
             id SERIAL PRIMARY KEY,
-            document_id INTEGER NOT NULL REFERENCES knowledge_base_documents(id) ON DELETE CASCADE,
+            document_id INTEGER NOT NULL REFERENCES {}(id) ON DELETE CASCADE,
             chunk_index INTEGER NOT NULL,
             total_chunks INTEGER NOT NULL,
             content TEXT NOT NULL,
@@ -31,78 +217,127 @@ impl KnowledgeBaseSql {
             embedding VECTOR(1024),
             created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
         );
-    ";
+    ",
+            self.chunks_table(),
+            self.documents_table()
+        )
+    }
 
     /// HNSW index on embedding + B-tree indexes on document_id and chunk_index.
     /// Executed as separate statements (sqlx does not support multi-statement in execute).
-    pub const CREATE_HNSW_INDEX: &'static str = "
-        CREATE INDEX IF NOT EXISTS idx_kb_chunks_embedding_hnsw
-        ON knowledge_base_chunks
+    pub fn create_hnsw_index(&self) -> String {
+        format!(
+            "
+        CREATE INDEX IF NOT EXISTS {}
+        ON {}
         USING hnsw (embedding vector_cosine_ops)
         WITH (m = 16, ef_construction = 64);
-    ";
+    ",
+            self.index_name("embedding_hnsw"),
+            self.chunks_table()
+        )
+    }
 
-    pub const CREATE_DOCUMENT_ID_INDEX: &'static str = "
-        CREATE INDEX IF NOT EXISTS idx_kb_chunks_document_id
-        ON knowledge_base_chunks(document_id);
-    ";
+    pub fn create_document_id_index(&self) -> String {
+        format!(
+            "
+        CREATE INDEX IF NOT EXISTS {}
+        ON {}(document_id);
+    ",
+            self.index_name("document_id"),
+            self.chunks_table()
+        )
+    }
 
-    pub const CREATE_CHUNK_INDEX_INDEX: &'static str = "
-        CREATE INDEX IF NOT EXISTS idx_kb_chunks_chunk_index
-        ON knowledge_base_chunks(chunk_index);
-    ";
+    pub fn create_chunk_index_index(&self) -> String {
+        format!(
+            "
+        CREATE INDEX IF NOT EXISTS {}
+        ON {}(chunk_index);
+    ",
+            self.index_name("chunk_index"),
+            self.chunks_table()
+        )
+    }
 
     /// Insert a document and return its id.
     /// Params: $1=title, $2=source_path, $3=source_type, $4=raw_content, $5=content_hash, $6=metadata
-    pub const INSERT_DOCUMENT: &'static str = "
-        INSERT INTO knowledge_base_documents (
+    pub fn insert_document(&self) -> String {
+        format!(
+            "
+        INSERT INTO {} (
             title, source_path, source_type, raw_content, content_hash, metadata
         ) VALUES (
             $1, $2, $3, $4, $5, $6
         ) RETURNING id;
-    ";
+    ",
+            self.documents_table()
+        )
+    }
 
     /// Insert a chunk and return its id.
     /// Params: $1=document_id, $2=chunk_index, $3=total_chunks, $4=content, $5=content_hash, $6=embedding
-    pub const INSERT_CHUNK: &'static str = "
-        INSERT INTO knowledge_base_chunks (
+    pub fn insert_chunk(&self) -> String {
+        format!(
+            "
+        INSERT INTO {} (
             document_id, chunk_index, total_chunks, content, content_hash, embedding
         ) VALUES (
             $1, $2, $3, $4, $5, $6
         ) RETURNING id;
-    ";
+    ",
+            self.chunks_table()
+        )
+    }
 
     /// Retrieve a document by its primary key.
     /// Params: $1=id
-    pub const GET_DOCUMENT_BY_ID: &'static str = "
+    pub fn get_document_by_id(&self) -> String {
+        format!(
+            "
         SELECT id, title, source_path, source_type, raw_content, content_hash,
                metadata, ingested_at
-        FROM knowledge_base_documents
+        FROM {}
         WHERE id = $1;
-    ";
+    ",
+            self.documents_table()
+        )
+    }
 
     /// Retrieve a document by its content hash (for deduplication).
     /// Params: $1=content_hash
-    pub const GET_DOCUMENT_BY_HASH: &'static str = "
+    pub fn get_document_by_hash(&self) -> String {
+        format!(
+            "
         SELECT id, title, source_path, source_type, raw_content, content_hash,
                metadata, ingested_at
-        FROM knowledge_base_documents
+        FROM {}
         WHERE content_hash = $1;
-    ";
+    ",
+            self.documents_table()
+        )
+    }
 
     /// Retrieve all chunks for a document, ordered by chunk_index.
     /// Params: $1=document_id
-    pub const GET_DOCUMENT_CHUNKS: &'static str = "
+    pub fn get_document_chunks(&self) -> String {
+        format!(
+            "
         SELECT id, document_id, chunk_index, total_chunks, content, content_hash,
                created_at
-        FROM knowledge_base_chunks
+        FROM {}
         WHERE document_id = $1
         ORDER BY chunk_index;
-    ";
+    ",
+            self.chunks_table()
+        )
+    }
 
     /// Cosine similarity search over chunk embeddings, joining document metadata.
     /// Params: $1=query_vector (pgvector::Vector), $2=similarity_threshold (f32 or NULL), $3=limit (i64)
-    pub const VECTOR_SIMILARITY_SEARCH: &'static str = "
+    pub fn vector_similarity_search(&self) -> String {
+        format!(
+            "
         SELECT
             c.id,
             c.document_id,
@@ -115,19 +350,53 @@ impl KnowledgeBaseSql {
             d.source_path,
             d.source_type,
             1.0 - (c.embedding <=> $1) AS similarity_score
-        FROM knowledge_base_chunks c
-        JOIN knowledge_base_documents d ON c.document_id = d.id
+        FROM {} c
+        JOIN {} d ON c.document_id = d.id
         WHERE ($2::float4 IS NULL OR (1.0 - (c.embedding <=> $1)) >= $2::float4)
         ORDER BY c.embedding <=> $1
         LIMIT $3;
-    ";
+    ",
+            self.chunks_table(),
+            self.documents_table()
+        )
+    }
 
-    /// Check whether a table exists in the current database.
+    /// Nearest stored chunk to a vector, for near-duplicate detection.
+    /// Params: $1=query_vector (pgvector::Vector)
+    pub fn nearest_chunk(&self) -> String {
+        format!(
+            "
+        SELECT
+            document_id,
+            1.0 - (embedding <=> $1) AS similarity_score
+        FROM {}
+        WHERE embedding IS NOT NULL
+        ORDER BY embedding <=> $1
+        LIMIT 1;
+    ",
+            self.chunks_table()
+        )
+    }
+
+    /// Check whether a table exists in the configured schema.
     /// Params: $1=table_name
-    pub const CHECK_TABLE_EXISTS: &'static str = "
+    pub fn check_table_exists(&self) -> String {
+        format!(
+            "
         SELECT table_name
         FROM information_schema.tables
-        WHERE table_schema = 'public'
+        WHERE table_schema = '{}'
           AND table_name = $1;
-    ";
+    ",
+            self.schema
+        )
+    }
+
+    pub fn drop_chunks_table(&self) -> String {
+        format!("DROP TABLE IF EXISTS {} CASCADE;", self.chunks_table())
+    }
+
+    pub fn drop_documents_table(&self) -> String {
+        format!("DROP TABLE IF EXISTS {} CASCADE;", self.documents_table())
+    }
 }
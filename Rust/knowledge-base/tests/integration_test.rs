@@ -13,8 +13,14 @@ mod tests {
     use knowledge_base::{
         configuration::PgConfig,
         database::connection::{create_knowledge_base_pool, KnowledgeBaseDb},
-        ingestion::{FileIngester, TextChunker},
-        models::{InsertChunk, InsertDocument},
+        embedding::QueryEmbeddingCache,
+        ingestion::{
+            ContentFilter, FileIngester, RedactionMode, RegexRedactor, TextChunker,
+            TextNormalizer, TextNormalizerConfig,
+        },
+        models::{InsertChunk, InsertDocument, SearchResult},
+        report::render_markdown_report,
+        sql_statements::KnowledgeBaseSql,
     };
 
     async fn setup_db() -> Option<KnowledgeBaseDb> {
@@ -168,4 +174,151 @@ mod tests {
             "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
         );
     }
+
+    #[tokio::test]
+    async fn test_text_normalizer_pdf_stages() {
+        let normalizer = TextNormalizer::new(TextNormalizerConfig::pdf()).expect("no patterns");
+        let text = "Journal of Tests\n\
+                    The e\u{FB03}cient embed-\nding   of  text.\n\
+                    12\n\
+                    Journal of Tests\n\n\n\n\
+                    Second paragraph.\n\
+                    Journal of Tests\n";
+        let normalized = normalizer.normalize(text);
+        assert_eq!(normalized, "The efficient embedding of text.\n\nSecond paragraph.");
+
+        // The default leaves text (e.g. Markdown with a repeated heading) alone
+        assert_eq!(TextNormalizer::default().normalize(text), text);
+    }
+
+    #[tokio::test]
+    async fn test_text_normalizer_boilerplate_and_disabled() {
+        let config = TextNormalizerConfig {
+            boilerplate_patterns: vec![r"(?i)all rights reserved\.?".to_string()],
+            ..TextNormalizerConfig::disabled()
+        };
+        let normalizer = TextNormalizer::new(config).expect("valid pattern");
+        assert_eq!(normalizer.normalize("Text. All Rights Reserved."), "Text. ");
+
+        let bad = TextNormalizerConfig {
+            boilerplate_patterns: vec!["(unclosed".to_string()],
+            ..TextNormalizerConfig::default()
+        };
+        assert!(TextNormalizer::new(bad).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_regex_redactor() {
+        let text = "Mail jane.doe@example.com or call (555) 123-4567. \
+                    Key: sk-abcdefghijklmnopqrstuvwx";
+
+        let redacted = RegexRedactor::new(RedactionMode::Redact).filter(text);
+        assert_eq!(
+            redacted.content,
+            "Mail [REDACTED:EMAIL] or call [REDACTED:PHONE]. Key: [REDACTED:API_KEY]"
+        );
+        let kinds: Vec<&str> = redacted.findings.iter().map(|f| f.kind.as_str()).collect();
+        assert_eq!(kinds, ["api_key", "email", "phone"]);
+
+        let flagged = RegexRedactor::new(RedactionMode::Flag).filter(text);
+        assert_eq!(flagged.content, text);
+        assert_eq!(flagged.findings.len(), 3);
+
+        let clean = RegexRedactor::default().filter("Nothing sensitive here.");
+        assert!(clean.findings.is_empty());
+
+        // Unformatted digit runs are IDs unless they carry a country code
+        let ids = RegexRedactor::default().filter("Order id 1234567890, ref 5551234567890.");
+        assert!(ids.findings.is_empty());
+        let phones = RegexRedactor::new(RedactionMode::Redact)
+            .filter("Call +1 5551234567, 555.123.4567 or 555 1234567.");
+        assert_eq!(
+            phones.content,
+            "Call [REDACTED:PHONE], [REDACTED:PHONE] or [REDACTED:PHONE]."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_knowledge_base_sql_builder() {
+        let default = KnowledgeBaseSql::builder().build().expect("default names are valid");
+        assert_eq!(default, KnowledgeBaseSql::default());
+        assert_eq!(default.documents_table(), "\"public\".\"knowledge_base_documents\"");
+        assert!(default.create_hnsw_index().contains("\"idx_kb_chunks_embedding_hnsw\""));
+
+        let staging = KnowledgeBaseSql::builder()
+            .schema("research")
+            .table_prefix("staging_")
+            .build()
+            .expect("valid names");
+        assert_eq!(staging.chunks_table(), "\"research\".\"staging_knowledge_base_chunks\"");
+        assert!(staging.create_hnsw_index().contains("\"staging_idx_kb_chunks_embedding_hnsw\""));
+        assert!(staging.create_chunks_table().contains(
+            "REFERENCES \"research\".\"staging_knowledge_base_documents\"(id)"
+        ));
+
+        for bad in [
+            KnowledgeBaseSql::builder().schema("bad-name"),
+            KnowledgeBaseSql::builder().table_prefix("x\"; DROP TABLE y; --"),
+            KnowledgeBaseSql::builder().documents_table("same").chunks_table("same"),
+        ] {
+            assert!(bad.build().is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_query_embedding_cache() {
+        let cache = QueryEmbeddingCache::new(2, std::time::Duration::from_secs(60));
+        assert!(cache.get("what is rust").is_none());
+
+        cache.insert("  what   is rust ", vec![1.0]);
+        assert_eq!(cache.get("what is rust"), Some(vec![1.0]));
+        assert!(cache.get("What is Rust").is_none());
+
+        // "what is rust" was used most recently, so "b" is evicted.
+        cache.insert("b", vec![2.0]);
+        cache.get("what is rust");
+        cache.insert("c", vec![3.0]);
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("b").is_none());
+        assert_eq!(cache.get("c"), Some(vec![3.0]));
+
+        let expiring = QueryEmbeddingCache::new(4, std::time::Duration::ZERO);
+        expiring.insert("q", vec![1.0]);
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        assert!(expiring.get("q").is_none());
+        assert!(expiring.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_render_markdown_report() {
+        let generated_at = chrono::DateTime::parse_from_rfc3339("2024-05-01T12:30:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let hit = SearchResult {
+            id: 7,
+            document_id: 3,
+            chunk_index: 1,
+            total_chunks: 4,
+            content: "Use ```rust``` fences.\nSecond line.".to_string(),
+            content_hash: "abc".to_string(),
+            created_at: None,
+            title: Some("notes_v2".to_string()),
+            source_path: Some("/data/my notes.md".to_string()),
+            source_type: Some("md".to_string()),
+            similarity_score: 0.9,
+        };
+
+        let report = render_markdown_report("vector *search*", &[hit], generated_at);
+        assert!(report.contains("- **Query:** vector \\*search\\*\n"));
+        assert!(report.contains("- **Generated:** 2024-05-01 12:30:00 UTC"));
+        assert!(report.contains("## 1. notes\\_v2"));
+        assert!(report.contains("- **Score:** 0.9000 (90.0%)"));
+        assert!(report.contains("(file:///data/my%20notes.md)"));
+        assert!(report.contains("- **Chunk:** 2/4 (document 3, chunk id 7)"));
+        assert!(report.contains("````text\nUse ```rust``` fences.\nSecond line.\n````\n"));
+
+        let empty = render_markdown_report("nothing", &[], generated_at);
+        assert!(empty.contains("- **Results:** 0"));
+        assert!(empty.contains("No results found."));
+    }
 }
@@ -1,133 +1,533 @@
 //! PostgreSQL administrative operations.
 //!
-//! Provides generic database lifecycle management: create/drop databases,
-//! create/check extensions. These operations are universal across all
-//! PostgreSQL-backed applications.
+//! Provides generic database lifecycle management: create/drop/rename databases,
+//! create/check extensions and schemas, refresh materialized views, truncate
+//! tables, create and detach partitions, manage row-level security and
+//! ownership, run SQL scripts. These operations are universal across
+//! all PostgreSQL-backed applications.
 //!
 //! Database creation and dropping require connecting to the system "postgres"
 //! database, so most functions here take a `&PgConfig` and create a temporary
 //! system connection internally.
 
-use anyhow::{Context, Result};
+use anyhow::{Context, anyhow, bail};
+use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
+use std::path::Path;
 
 use crate::config::PgConfig;
 use crate::connection::create_system_pool;
+use crate::error::Result;
+use crate::identifier::{quote_identifier, quote_literal, quote_qualified, validate_qualified};
+use crate::introspection::server_version;
+use crate::monitoring::terminate_connections;
+use crate::statement_tracing::{self, traced};
+
+/// Options for `truncate_table`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TruncateOptions {
+    /// Also truncate tables with foreign keys referencing this one.
+    pub cascade: bool,
+    /// Reset sequences owned by the table's columns (e.g. `SERIAL` ids).
+    pub restart_identity: bool,
+}
+
+/// Properties for `create_database_with`. Unset fields take the server's
+/// defaults (which mostly come from the template, `template1`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct CreateDatabaseOptions {
+    /// Role that will own the database.
+    pub owner: Option<String>,
+    /// Character set, e.g. `"UTF8"`.
+    pub encoding: Option<String>,
+    /// Collation and character classification locale, e.g. `"en_US.UTF-8"`
+    /// or `"C"` (PostgreSQL 13+). An encoding or locale different from the
+    /// template's needs `template: Some("template0")`.
+    pub locale: Option<String>,
+    /// Database to copy, e.g. `"template0"` or a database marked as a
+    /// template; it must have no open connections.
+    pub template: Option<String>,
+    /// Maximum concurrent connections; -1 means no limit.
+    pub connection_limit: Option<i32>,
+}
+
+/// Options for `drop_database_with`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DropDatabaseOptions {
+    /// Disconnect other sessions instead of failing while they are connected.
+    pub force: bool,
+}
+
+/// Statements a row-level security policy applies to.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum PolicyCommand {
+    #[default]
+    All,
+    Select,
+    Insert,
+    Update,
+    Delete,
+}
+
+impl PolicyCommand {
+    fn as_sql(self) -> &'static str {
+        match self {
+            PolicyCommand::All => "ALL",
+            PolicyCommand::Select => "SELECT",
+            PolicyCommand::Insert => "INSERT",
+            PolicyCommand::Update => "UPDATE",
+            PolicyCommand::Delete => "DELETE",
+        }
+    }
+}
+
+/// Definition of a policy for `create_policy`. `using` and `with_check` are
+/// SQL boolean expressions inserted verbatim, so they must not come from
+/// untrusted input.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct PolicyOptions {
+    pub command: PolicyCommand,
+    /// Combine with other policies by AND instead of OR. A table needs at
+    /// least one permissive policy for any row to be visible.
+    pub restrictive: bool,
+    /// Roles the policy applies to; empty means `PUBLIC`.
+    pub roles: Vec<String>,
+    /// Which existing rows are visible or affected, e.g.
+    /// `"tenant_id = current_setting('app.tenant_id')::int"`.
+    pub using: Option<String>,
+    /// Which new rows may be written; defaults to `using` on the server.
+    pub with_check: Option<String>,
+}
+
+/// A database on the server, as listed by `list_databases_detailed`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DatabaseInfo {
+    pub name: String,
+    pub owner: String,
+    /// Character set, e.g. `"UTF8"`.
+    pub encoding: String,
+    /// Collation locale, e.g. `"en_US.UTF-8"`.
+    pub collation: String,
+    /// On-disk size in bytes; `None` without `CONNECT` privilege on it.
+    pub size_bytes: Option<i64>,
+    /// Sessions currently connected to it.
+    pub connections: i64,
+    /// -1 means no limit.
+    pub connection_limit: i32,
+    pub is_template: bool,
+    pub allow_connections: bool,
+}
 
 /// Check whether a database exists.
+#[tracing::instrument(level = "debug", skip(config))]
 pub async fn database_exists(config: &PgConfig, database_name: &str) -> Result<bool> {
-    let pool = create_system_pool(config).await
-        .context("Failed to connect to system database")?;
+    traced(async {
+        let pool = create_system_pool(config).await
+            .context("Failed to connect to system database")?;
 
-    let exists: Option<i32> = sqlx::query_scalar(
-        "SELECT 1 FROM pg_database WHERE datname = $1"
-    )
-    .bind(database_name)
-    .fetch_optional(&pool)
-    .await
-    .context("Failed to query pg_database")?;
+        let exists: Option<i32> = sqlx::query_scalar(
+            "SELECT 1 FROM pg_database WHERE datname = $1"
+        )
+        .bind(database_name)
+        .fetch_optional(&pool)
+        .await
+        .context("Failed to query pg_database")?;
 
-    Ok(exists.is_some())
+        Ok(exists.is_some())
+    })
+    .await
 }
 
 /// Create a new database. No-ops if it already exists.
 ///
 /// Connects to the system "postgres" database to issue the CREATE DATABASE
 /// command, which cannot run inside a transaction.
+#[tracing::instrument(level = "debug", skip(config))]
 pub async fn create_database(config: &PgConfig, database_name: &str) -> Result<()> {
-    if database_exists(config, database_name).await? {
-        tracing::info!("Database '{}' already exists, skipping creation", database_name);
-        return Ok(());
-    }
+    traced(async {
+        create_database_with(config, database_name, &CreateDatabaseOptions::default()).await
+    })
+    .await
+}
 
-    let pool = create_system_pool(config).await
-        .context("Failed to connect to system database")?;
+/// Create a new database with the given owner, encoding, locale, template
+/// or connection limit. No-ops if it already exists, whatever its
+/// properties.
+#[tracing::instrument(level = "debug", skip(config))]
+pub async fn create_database_with(
+    config: &PgConfig,
+    database_name: &str,
+    options: &CreateDatabaseOptions,
+) -> Result<()> {
+    traced(async {
+        if database_exists(config, database_name).await? {
+            tracing::info!("Database '{}' already exists, skipping creation", database_name);
+            return Ok(());
+        }
 
-    // CREATE DATABASE cannot run inside a transaction block.
-    // sqlx does not support `execute` with parameters for DDL, so we format directly.
-    // Database names are validated to be alphanumeric+underscore before this point.
-    sqlx::query(&format!("CREATE DATABASE \"{}\"", database_name))
-        .execute(&pool)
-        .await
-        .with_context(|| format!("Failed to create database '{}'", database_name))?;
+        let pool = create_system_pool(config).await
+            .context("Failed to connect to system database")?;
 
-    tracing::info!("Created database '{}'", database_name);
-    Ok(())
+        // CREATE DATABASE cannot run inside a transaction block.
+        // sqlx does not support `execute` with parameters for DDL, so we format directly.
+        sqlx::query(&create_database_statement(database_name, options))
+            .execute(&pool)
+            .await
+            .with_context(|| format!("Failed to create database '{}'", database_name))?;
+
+        tracing::info!("Created database '{}' ({:?})", database_name, options);
+        Ok(())
+    })
+    .await
+}
+
+fn create_database_statement(database_name: &str, options: &CreateDatabaseOptions) -> String {
+    let mut sql = format!("CREATE DATABASE {}", quote_identifier(database_name));
+    if let Some(owner) = &options.owner {
+        sql.push_str(&format!(" OWNER {}", quote_identifier(owner)));
+    }
+    if let Some(template) = &options.template {
+        sql.push_str(&format!(" TEMPLATE {}", quote_identifier(template)));
+    }
+    if let Some(encoding) = &options.encoding {
+        sql.push_str(&format!(" ENCODING {}", quote_literal(encoding)));
+    }
+    if let Some(locale) = &options.locale {
+        sql.push_str(&format!(" LOCALE {}", quote_literal(locale)));
+    }
+    if let Some(limit) = options.connection_limit {
+        sql.push_str(&format!(" CONNECTION LIMIT {}", limit));
+    }
+    sql
 }
 
 /// Drop a database. No-ops if it does not exist.
 ///
 /// Terminates all existing connections to the database before dropping it,
 /// mirroring the behaviour of the Python PostgreSQLConnection.drop_database.
+#[tracing::instrument(level = "debug", skip(config))]
 pub async fn drop_database(config: &PgConfig, database_name: &str) -> Result<()> {
-    if !database_exists(config, database_name).await? {
-        tracing::info!("Database '{}' does not exist, skipping drop", database_name);
-        return Ok(());
-    }
+    traced(async {
+        drop_database_with(config, database_name, &DropDatabaseOptions { force: true }).await
+    })
+    .await
+}
 
-    let pool = create_system_pool(config).await
-        .context("Failed to connect to system database")?;
+/// Drop a database. No-ops if it does not exist.
+///
+/// With `force`, other sessions are disconnected first: by
+/// `DROP DATABASE ... WITH (FORCE)` on PostgreSQL 13+, or by terminating
+/// them before the drop on older servers. Without it, or if sessions cannot
+/// be cleared (e.g. they reconnect, or a prepared transaction is open), the
+/// error says how many sessions are still connected.
+#[tracing::instrument(level = "debug", skip(config))]
+pub async fn drop_database_with(
+    config: &PgConfig,
+    database_name: &str,
+    options: &DropDatabaseOptions,
+) -> Result<()> {
+    traced(async {
+        if !database_exists(config, database_name).await? {
+            tracing::info!("Database '{}' does not exist, skipping drop", database_name);
+            return Ok(());
+        }
+
+        let pool = create_system_pool(config).await
+            .context("Failed to connect to system database")?;
 
-    // Terminate all active connections to avoid "database is being accessed by other users"
-    sqlx::query(
-        "SELECT pg_terminate_backend(pid) FROM pg_stat_activity WHERE datname = $1"
-    )
-    .bind(database_name)
-    .execute(&pool)
+        let mut sql = format!("DROP DATABASE IF EXISTS {}", quote_identifier(database_name));
+        if options.force {
+            if server_version(&pool).await?.major >= 13 {
+                sql.push_str(" WITH (FORCE)");
+            } else {
+                // Terminate all active connections to avoid "database is being accessed by other users"
+                terminate_connections(&pool, database_name).await?;
+            }
+        }
+
+        if let Err(e) = sqlx::query(&sql).execute(&pool).await {
+            let in_use = e
+                .as_database_error()
+                .is_some_and(|d| d.code().as_deref() == Some("55006"));
+            if in_use {
+                let sessions: i64 = sqlx::query_scalar(
+                    "SELECT count(*) FROM pg_stat_activity WHERE datname = $1",
+                )
+                .bind(database_name)
+                .fetch_one(&pool)
+                .await
+                .unwrap_or_default();
+                return Err(anyhow::Error::new(e).context(format!(
+                    "Failed to drop database '{}': {} other session(s) still connected{}",
+                    database_name,
+                    sessions,
+                    if options.force { "" } else { " (drop with force to disconnect them)" }
+                )).into());
+            }
+            return Err(anyhow::Error::new(e)
+                .context(format!("Failed to drop database '{}'", database_name))
+                .into());
+        }
+
+        tracing::info!("Dropped database '{}' ({:?})", database_name, options);
+        Ok(())
+    })
     .await
-    .with_context(|| format!("Failed to terminate connections to '{}'", database_name))?;
+}
 
-    sqlx::query(&format!("DROP DATABASE IF EXISTS \"{}\"", database_name))
+/// Rename a database. No-ops if it was already renamed (`old` is gone and
+/// `new` exists).
+///
+/// Terminates connections to `old` first, since a database with active
+/// sessions cannot be renamed. Fails if both names exist or neither does.
+#[tracing::instrument(level = "debug", skip(config))]
+pub async fn rename_database(config: &PgConfig, old: &str, new: &str) -> Result<()> {
+    traced(async {
+        let old_exists = database_exists(config, old).await?;
+        let new_exists = database_exists(config, new).await?;
+        match (old_exists, new_exists) {
+            (false, true) => {
+                tracing::info!("Database '{}' already renamed to '{}', skipping", old, new);
+                return Ok(());
+            }
+            (false, false) => {
+                return Err(anyhow!("Cannot rename database '{}': it does not exist", old).into());
+            }
+            (true, true) => {
+                return Err(anyhow!("Cannot rename database '{}': '{}' already exists", old, new).into());
+            }
+            (true, false) => {}
+        }
+
+        let pool = create_system_pool(config).await
+            .context("Failed to connect to system database")?;
+
+        terminate_connections(&pool, old).await?;
+
+        sqlx::query(&format!(
+            "ALTER DATABASE {} RENAME TO {}", quote_identifier(old), quote_identifier(new)
+        ))
         .execute(&pool)
         .await
-        .with_context(|| format!("Failed to drop database '{}'", database_name))?;
+        .with_context(|| format!("Failed to rename database '{}' to '{}'", old, new))?;
 
-    tracing::info!("Dropped database '{}'", database_name);
-    Ok(())
+        tracing::info!("Renamed database '{}' to '{}'", old, new);
+        Ok(())
+    })
+    .await
 }
 
 /// Check whether a PostgreSQL extension is installed in the current database.
+#[tracing::instrument(level = "debug", skip(pool))]
 pub async fn extension_exists(pool: &PgPool, extension_name: &str) -> Result<bool> {
-    let exists: Option<i32> = sqlx::query_scalar(
-        "SELECT 1 FROM pg_extension WHERE extname = $1"
-    )
-    .bind(extension_name)
-    .fetch_optional(pool)
-    .await
-    .with_context(|| format!("Failed to query pg_extension for '{}'", extension_name))?;
+    traced(async {
+        let exists: Option<i32> = sqlx::query_scalar(
+            "SELECT 1 FROM pg_extension WHERE extname = $1"
+        )
+        .bind(extension_name)
+        .fetch_optional(pool)
+        .await
+        .with_context(|| format!("Failed to query pg_extension for '{}'", extension_name))?;
 
-    Ok(exists.is_some())
+        Ok(exists.is_some())
+    })
+    .await
 }
 
 /// Create a PostgreSQL extension if it does not already exist.
 ///
 /// Uses `CREATE EXTENSION IF NOT EXISTS` so this is safe to call repeatedly.
+#[tracing::instrument(level = "debug", skip(pool))]
 pub async fn create_extension(pool: &PgPool, extension_name: &str) -> Result<()> {
-    sqlx::query(&format!(
-        "CREATE EXTENSION IF NOT EXISTS \"{}\"",
-        extension_name
-    ))
-    .execute(pool)
+    traced(async {
+        sqlx::query(&format!(
+            "CREATE EXTENSION IF NOT EXISTS \"{}\"",
+            extension_name
+        ))
+        .execute(pool)
+        .await
+        .with_context(|| format!("Failed to create extension '{}'", extension_name))?;
+
+        tracing::info!("Extension '{}' is present", extension_name);
+        Ok(())
+    })
+    .await
+}
+
+/// Create a schema in the current database if it does not already exist.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn create_schema(pool: &PgPool, schema: &str) -> Result<()> {
+    traced(async {
+        sqlx::query(&format!("CREATE SCHEMA IF NOT EXISTS {}", quote_identifier(schema)))
+            .execute(pool)
+            .await
+            .with_context(|| format!("Failed to create schema '{}'", schema))?;
+
+        tracing::info!("Schema '{}' is present", schema);
+        Ok(())
+    })
     .await
-    .with_context(|| format!("Failed to create extension '{}'", extension_name))?;
+}
+
+/// Set the comment on a table, or on one of its columns when `column` is
+/// given. `table` is a name in the public schema or `schema.table`. Passing
+/// `None` as the comment removes it.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn set_comment(
+    pool: &PgPool,
+    table: &str,
+    column: Option<&str>,
+    comment: Option<&str>,
+) -> Result<()> {
+    traced(async {
+        let target = match column {
+            Some(column) => format!("COLUMN {}.{}", quote_qualified(table), quote_identifier(column)),
+            None => format!("TABLE {}", quote_qualified(table)),
+        };
+        let comment_sql = comment.map_or_else(|| "NULL".to_string(), quote_literal);
+        sqlx::query(&format!("COMMENT ON {} IS {}", target, comment_sql))
+            .execute(pool)
+            .await
+            .with_context(|| format!("Failed to set comment on {}", target))?;
 
-    tracing::info!("Extension '{}' is present", extension_name);
-    Ok(())
+        Ok(())
+    })
+    .await
+}
+
+/// Installed version of an extension in the current database, or `None` if
+/// it is not installed.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn extension_version(pool: &PgPool, extension_name: &str) -> Result<Option<String>> {
+    traced(async {
+        let version: Option<String> = sqlx::query_scalar(
+            "SELECT extversion FROM pg_extension WHERE extname = $1"
+        )
+        .bind(extension_name)
+        .fetch_optional(pool)
+        .await
+        .with_context(|| format!("Failed to query pg_extension for '{}'", extension_name))?;
+
+        Ok(version)
+    })
+    .await
+}
+
+/// Versions of an extension the server can install or update to, oldest
+/// first. Empty if the extension is not available on the server at all.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn available_extension_versions(pool: &PgPool, extension_name: &str) -> Result<Vec<String>> {
+    traced(async {
+        let mut versions: Vec<String> = sqlx::query_scalar(
+            "SELECT version FROM pg_available_extension_versions WHERE name = $1"
+        )
+        .bind(extension_name)
+        .fetch_all(pool)
+        .await
+        .with_context(|| format!("Failed to list available versions of '{}'", extension_name))?;
+
+        // Compare dotted parts numerically where possible, so 0.10.0 > 0.9.0.
+        versions.sort_by_cached_key(|version| {
+            version
+                .split('.')
+                .map(|part| part.parse::<u64>().map_err(|_| part.to_string()))
+                .collect::<Vec<_>>()
+        });
+        Ok(versions)
+    })
+    .await
+}
+
+/// Update an installed extension with `ALTER EXTENSION ... UPDATE`, to
+/// `to_version` or, if `None`, to the server's default version. Returns the
+/// version installed afterwards.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn update_extension(
+    pool: &PgPool,
+    extension_name: &str,
+    to_version: Option<&str>,
+) -> Result<String> {
+    traced(async {
+        let from = extension_version(pool, extension_name)
+            .await?
+            .with_context(|| format!("Extension '{}' is not installed", extension_name))?;
+
+        let target = to_version.map(|v| format!(" TO {}", quote_literal(v))).unwrap_or_default();
+        sqlx::query(&format!(
+            "ALTER EXTENSION {} UPDATE{}", quote_identifier(extension_name), target
+        ))
+        .execute(pool)
+        .await
+        .with_context(|| format!("Failed to update extension '{}'", extension_name))?;
+
+        let to = extension_version(pool, extension_name)
+            .await?
+            .with_context(|| format!("Extension '{}' disappeared during update", extension_name))?;
+        tracing::info!("Updated extension '{}' from {} to {}", extension_name, from, to);
+        Ok(to)
+    })
+    .await
 }
 
 /// List all non-template databases on the server.
+#[tracing::instrument(level = "debug", skip(config))]
 pub async fn list_databases(config: &PgConfig) -> Result<Vec<String>> {
-    let pool = create_system_pool(config).await
-        .context("Failed to connect to system database")?;
+    traced(async {
+        let pool = create_system_pool(config).await
+            .context("Failed to connect to system database")?;
 
-    let names: Vec<String> = sqlx::query_scalar(
-        "SELECT datname FROM pg_database WHERE datistemplate = false ORDER BY datname"
-    )
-    .fetch_all(&pool)
+        let names: Vec<String> = sqlx::query_scalar(
+            "SELECT datname FROM pg_database WHERE datistemplate = false ORDER BY datname"
+        )
+        .fetch_all(&pool)
+        .await
+        .context("Failed to list databases")?;
+
+        Ok(names)
+    })
     .await
-    .context("Failed to list databases")?;
+}
+
+/// List every database on the server, templates included, with owner,
+/// encoding, size and connection count, ordered by name.
+#[tracing::instrument(level = "debug", skip(config))]
+pub async fn list_databases_detailed(config: &PgConfig) -> Result<Vec<DatabaseInfo>> {
+    traced(async {
+        let pool = create_system_pool(config).await
+            .context("Failed to connect to system database")?;
+
+        let rows = sqlx::query_as::<_, (String, String, String, String, Option<i64>, i64, i32, bool, bool)>(
+            "SELECT d.datname::text, pg_get_userbyid(d.datdba)::text, \
+                    pg_encoding_to_char(d.encoding)::text, d.datcollate::text, \
+                    CASE WHEN has_database_privilege(d.oid, 'CONNECT') \
+                         THEN pg_database_size(d.oid) END, \
+                    (SELECT count(*) FROM pg_stat_activity a WHERE a.datid = d.oid), \
+                    d.datconnlimit, d.datistemplate, d.datallowconn \
+             FROM pg_database d \
+             ORDER BY d.datname",
+        )
+        .fetch_all(&pool)
+        .await
+        .context("Failed to list databases")?;
 
-    Ok(names)
+        Ok(rows
+            .into_iter()
+            .map(|(name, owner, encoding, collation, size_bytes, connections, connection_limit, is_template, allow_connections)| {
+                DatabaseInfo {
+                    name,
+                    owner,
+                    encoding,
+                    collation,
+                    size_bytes,
+                    connections,
+                    connection_limit,
+                    is_template,
+                    allow_connections,
+                }
+            })
+            .collect())
+    })
+    .await
 }
 
 /// Drop a PostgreSQL extension from the current database.
@@ -135,27 +535,560 @@ pub async fn list_databases(config: &PgConfig) -> Result<Vec<String>> {
 /// Uses `DROP EXTENSION IF EXISTS` so this is safe to call when the extension
 /// is already absent.  Pass `cascade = true` to also drop dependent objects
 /// (e.g. types and functions created by the extension).
+#[tracing::instrument(level = "debug", skip(pool))]
 pub async fn drop_extension(pool: &PgPool, extension_name: &str, cascade: bool) -> Result<()> {
-    let suffix = if cascade { " CASCADE" } else { "" };
-    sqlx::query(&format!(
-        "DROP EXTENSION IF EXISTS \"{}\"{}", extension_name, suffix
-    ))
-    .execute(pool)
-    .await
-    .with_context(|| format!("Failed to drop extension '{}'", extension_name))?;
+    traced(async {
+        let suffix = if cascade { " CASCADE" } else { "" };
+        sqlx::query(&format!(
+            "DROP EXTENSION IF EXISTS \"{}\"{}", extension_name, suffix
+        ))
+        .execute(pool)
+        .await
+        .with_context(|| format!("Failed to drop extension '{}'", extension_name))?;
 
-    tracing::info!("Dropped extension '{}' (cascade={})", extension_name, cascade);
-    Ok(())
+        tracing::info!("Dropped extension '{}' (cascade={})", extension_name, cascade);
+        Ok(())
+    })
+    .await
 }
 
 /// List all extensions installed in the current database.
+#[tracing::instrument(level = "debug", skip(pool))]
 pub async fn list_extensions(pool: &PgPool) -> Result<Vec<String>> {
-    let names: Vec<String> = sqlx::query_scalar(
-        "SELECT extname FROM pg_extension ORDER BY extname"
-    )
-    .fetch_all(pool)
+    traced(async {
+        let names: Vec<String> = sqlx::query_scalar(
+            "SELECT extname FROM pg_extension ORDER BY extname"
+        )
+        .fetch_all(pool)
+        .await
+        .context("Failed to list extensions")?;
+
+        Ok(names)
+    })
     .await
-    .context("Failed to list extensions")?;
+}
 
-    Ok(names)
+/// Refresh a materialized view, given as `name` (public schema) or
+/// `schema.name`. Fails if the view does not exist.
+///
+/// With `concurrently = true`, uses `REFRESH MATERIALIZED VIEW CONCURRENTLY`,
+/// which does not block readers but requires a unique index on the view and
+/// that it has already been populated.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn refresh_materialized_view(pool: &PgPool, name: &str, concurrently: bool) -> Result<()> {
+    traced(async {
+        let (schema, view) = name.split_once('.').unwrap_or(("public", name));
+        let exists: Option<i32> = sqlx::query_scalar(
+            "SELECT 1 FROM pg_matviews WHERE schemaname = $1 AND matviewname = $2"
+        )
+        .bind(schema)
+        .bind(view)
+        .fetch_optional(pool)
+        .await
+        .with_context(|| format!("Failed to query pg_matviews for '{}'", name))?;
+        if exists.is_none() {
+            return Err(anyhow!("Materialized view '{}' does not exist", name).into());
+        }
+
+        let mode = if concurrently { " CONCURRENTLY" } else { "" };
+        sqlx::query(&format!(
+            "REFRESH MATERIALIZED VIEW{} {}", mode, quote_qualified(name)
+        ))
+        .execute(pool)
+        .await
+        .with_context(|| format!("Failed to refresh materialized view '{}'", name))?;
+
+        tracing::info!("Refreshed materialized view '{}' (concurrently={})", name, concurrently);
+        Ok(())
+    })
+    .await
+}
+
+/// Remove all rows from a table, given as `name` (public schema) or
+/// `schema.name`. Fails on a malformed name or if the table is referenced by
+/// foreign keys and `options.cascade` is not set.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn truncate_table(pool: &PgPool, table: &str, options: &TruncateOptions) -> Result<()> {
+    traced(async {
+        let sql = truncate_statement(table, options)?;
+        sqlx::query(&sql)
+            .execute(pool)
+            .await
+            .with_context(|| format!("Failed to truncate table '{}'", table))?;
+
+        tracing::info!("Truncated table '{}' ({:?})", table, options);
+        Ok(())
+    })
+    .await
+}
+
+/// Create `name` as a range partition of `parent` covering `from`
+/// (inclusive) to `to` (exclusive), unless it already exists. Bounds are
+/// given as text, e.g. `"2024-01-01"`, and cast to the partition key type.
+/// Both names may be schema-qualified.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn create_range_partition(
+    pool: &PgPool,
+    parent: &str,
+    name: &str,
+    from: &str,
+    to: &str,
+) -> Result<()> {
+    traced(async {
+        validate_qualified(parent)?;
+        validate_qualified(name)?;
+
+        sqlx::query(&format!(
+            "CREATE TABLE IF NOT EXISTS {} PARTITION OF {} FOR VALUES FROM ({}) TO ({})",
+            quote_qualified(name),
+            quote_qualified(parent),
+            quote_literal(from),
+            quote_literal(to)
+        ))
+        .execute(pool)
+        .await
+        .with_context(|| format!("Failed to create partition '{}' of '{}'", name, parent))?;
+
+        tracing::info!("Partition '{}' of '{}' is present [{}, {})", name, parent, from, to);
+        Ok(())
+    })
+    .await
+}
+
+/// Detach partition `name` from `parent`, leaving it as a standalone table.
+/// With `concurrently`, other sessions are not blocked (not allowed inside
+/// a transaction or when `parent` has a default partition).
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn detach_partition(pool: &PgPool, parent: &str, name: &str, concurrently: bool) -> Result<()> {
+    traced(async {
+        validate_qualified(parent)?;
+        validate_qualified(name)?;
+
+        let mode = if concurrently { " CONCURRENTLY" } else { "" };
+        sqlx::query(&format!(
+            "ALTER TABLE {} DETACH PARTITION {}{}",
+            quote_qualified(parent),
+            quote_qualified(name),
+            mode
+        ))
+        .execute(pool)
+        .await
+        .with_context(|| format!("Failed to detach partition '{}' from '{}'", name, parent))?;
+
+        tracing::info!("Detached partition '{}' from '{}'", name, parent);
+        Ok(())
+    })
+    .await
+}
+
+/// Turn on row-level security for `table`: a table without policies then
+/// shows no rows to roles other than its owner and superusers.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn enable_row_level_security(pool: &PgPool, table: &str) -> Result<()> {
+    traced(async {
+        validate_qualified(table)?;
+
+        sqlx::query(&format!("ALTER TABLE {} ENABLE ROW LEVEL SECURITY", quote_qualified(table)))
+            .execute(pool)
+            .await
+            .with_context(|| format!("Failed to enable row-level security on '{}'", table))?;
+
+        tracing::info!("Enabled row-level security on '{}'", table);
+        Ok(())
+    })
+    .await
+}
+
+/// Turn off row-level security for `table`. Its policies are kept but not
+/// applied.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn disable_row_level_security(pool: &PgPool, table: &str) -> Result<()> {
+    traced(async {
+        validate_qualified(table)?;
+
+        sqlx::query(&format!("ALTER TABLE {} DISABLE ROW LEVEL SECURITY", quote_qualified(table)))
+            .execute(pool)
+            .await
+            .with_context(|| format!("Failed to disable row-level security on '{}'", table))?;
+
+        tracing::info!("Disabled row-level security on '{}'", table);
+        Ok(())
+    })
+    .await
+}
+
+/// Apply `table`'s policies to its owner too (`FORCE ROW LEVEL SECURITY`),
+/// or stop doing so. Superusers and `BYPASSRLS` roles are never subject to
+/// them.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn force_row_level_security(pool: &PgPool, table: &str, force: bool) -> Result<()> {
+    traced(async {
+        validate_qualified(table)?;
+
+        let action = if force { "FORCE" } else { "NO FORCE" };
+        sqlx::query(&format!(
+            "ALTER TABLE {} {} ROW LEVEL SECURITY",
+            quote_qualified(table),
+            action
+        ))
+        .execute(pool)
+        .await
+        .with_context(|| format!("Failed to set {} row-level security on '{}'", action, table))?;
+
+        tracing::info!("Set {} row-level security on '{}'", action, table);
+        Ok(())
+    })
+    .await
+}
+
+/// Create row-level security policy `name` on `table`. Policies take effect
+/// once row-level security is enabled on the table.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn create_policy(pool: &PgPool, table: &str, name: &str, options: &PolicyOptions) -> Result<()> {
+    traced(async {
+        let sql = create_policy_statement(table, name, options)?;
+        sqlx::query(&sql)
+            .execute(pool)
+            .await
+            .with_context(|| format!("Failed to create policy '{}' on '{}'", name, table))?;
+
+        tracing::info!("Created policy '{}' on '{}'", name, table);
+        Ok(())
+    })
+    .await
+}
+
+/// Drop policy `name` from `table`, if it exists.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn drop_policy(pool: &PgPool, table: &str, name: &str) -> Result<()> {
+    traced(async {
+        validate_qualified(table)?;
+
+        sqlx::query(&format!(
+            "DROP POLICY IF EXISTS {} ON {}",
+            quote_identifier(name),
+            quote_qualified(table)
+        ))
+        .execute(pool)
+        .await
+        .with_context(|| format!("Failed to drop policy '{}' on '{}'", name, table))?;
+
+        tracing::info!("Dropped policy '{}' on '{}'", name, table);
+        Ok(())
+    })
+    .await
+}
+
+fn create_policy_statement(table: &str, name: &str, options: &PolicyOptions) -> anyhow::Result<String> {
+    validate_qualified(table)?;
+    if name.is_empty() {
+        bail!("Policy name cannot be empty");
+    }
+
+    let kind = if options.restrictive { "RESTRICTIVE" } else { "PERMISSIVE" };
+    let roles = if options.roles.is_empty() {
+        "PUBLIC".to_string()
+    } else {
+        options.roles.iter().map(|r| quote_identifier(r)).collect::<Vec<_>>().join(", ")
+    };
+    let mut sql = format!(
+        "CREATE POLICY {} ON {} AS {} FOR {} TO {}",
+        quote_identifier(name),
+        quote_qualified(table),
+        kind,
+        options.command.as_sql(),
+        roles
+    );
+    if let Some(using) = &options.using {
+        sql.push_str(&format!(" USING ({})", using));
+    }
+    if let Some(with_check) = &options.with_check {
+        sql.push_str(&format!(" WITH CHECK ({})", with_check));
+    }
+    Ok(sql)
+}
+
+/// Make `role` the owner of `table` (`name` in the public schema or
+/// `schema.name`), along with the sequences owned by its columns.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn set_table_owner(pool: &PgPool, table: &str, role: &str) -> Result<()> {
+    traced(async {
+        validate_qualified(table)?;
+
+        sqlx::query(&format!(
+            "ALTER TABLE {} OWNER TO {}",
+            quote_qualified(table),
+            quote_identifier(role)
+        ))
+        .execute(pool)
+        .await
+        .with_context(|| format!("Failed to make '{}' the owner of '{}'", role, table))?;
+
+        tracing::info!("Table '{}' is now owned by '{}'", table, role);
+        Ok(())
+    })
+    .await
+}
+
+/// Transfer every object `from_role` owns in the current database, and its
+/// shared objects (databases, tablespaces), to `to_role`.
+///
+/// To decommission a role, run this in each database it owns objects in,
+/// then `DROP OWNED BY` there to revoke its remaining privileges, before
+/// `DROP ROLE`.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn reassign_owned(pool: &PgPool, from_role: &str, to_role: &str) -> Result<()> {
+    traced(async {
+        sqlx::query(&format!(
+            "REASSIGN OWNED BY {} TO {}",
+            quote_identifier(from_role),
+            quote_identifier(to_role)
+        ))
+        .execute(pool)
+        .await
+        .with_context(|| {
+            format!("Failed to reassign objects owned by '{}' to '{}'", from_role, to_role)
+        })?;
+
+        tracing::info!("Reassigned objects owned by '{}' to '{}'", from_role, to_role);
+        Ok(())
+    })
+    .await
+}
+
+/// Run the statements of a `.sql` file one at a time, in order, on a single
+/// connection (so `SET` and explicit `BEGIN`/`COMMIT` carry over). Returns
+/// the number of statements run.
+///
+/// Statements are split on `;`, except inside quotes, dollar-quoted bodies
+/// (`$$ ... $$`, `$fn$ ... $fn$`) and comments. SQL-standard function bodies
+/// (`BEGIN ATOMIC ... END`) are not recognized; use a dollar-quoted body.
+/// Statements are not wrapped in a transaction: on an error, those before it
+/// stay applied, and the error names the failing statement and its line.
+#[tracing::instrument(level = "debug", skip(pool, path), fields(path = ?path.as_ref(), rows_affected))]
+pub async fn execute_sql_file(pool: &PgPool, path: impl AsRef<Path>) -> Result<usize> {
+    traced(async {
+        let path = path.as_ref();
+        let script = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read SQL file: {:?}", path))?;
+        let statements = split_sql(&script)
+            .with_context(|| format!("Failed to parse SQL file: {:?}", path))?;
+
+        let mut conn = pool.acquire().await.context("Failed to acquire connection")?;
+        let mut rows_affected = 0;
+        for (index, statement) in statements.iter().enumerate() {
+            let result = sqlx::raw_sql(statement.sql).execute(&mut *conn).await.with_context(|| {
+                format!(
+                    "Statement {} (line {}) of {:?} failed: {}",
+                    index + 1,
+                    statement.line,
+                    path,
+                    statement.sql.lines().next().unwrap_or_default()
+                )
+            })?;
+            rows_affected += result.rows_affected();
+        }
+        statement_tracing::rows_affected(rows_affected);
+
+        tracing::info!("Executed {} statements from {:?}", statements.len(), path);
+        Ok(statements.len())
+    })
+    .await
+}
+
+/// One statement of a SQL script, without its `;`, and the line it starts on.
+#[derive(Debug, PartialEq)]
+struct ScriptStatement<'a> {
+    sql: &'a str,
+    line: usize,
+}
+
+/// Split a SQL script into statements. Comments before a statement are
+/// dropped; empty statements are skipped.
+fn split_sql(script: &str) -> anyhow::Result<Vec<ScriptStatement<'_>>> {
+    let bytes = script.as_bytes();
+    let is_ident = |b: u8| b.is_ascii_alphanumeric() || b == b'_' || b == b'$';
+    let newlines = |range: &[u8]| range.iter().filter(|&&b| b == b'\n').count();
+
+    let mut statements = vec![];
+    let mut start: Option<(usize, usize)> = None;
+    let mut line = 1;
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b == b'-' && bytes.get(i + 1) == Some(&b'-') {
+            // Line comment; the newline itself is handled below
+            i = script[i..].find('\n').map_or(bytes.len(), |n| i + n);
+            continue;
+        }
+        if b == b'/' && bytes.get(i + 1) == Some(&b'*') {
+            // Block comments nest in PostgreSQL
+            let (mut depth, mut j) = (1, i + 2);
+            while depth > 0 {
+                match (bytes.get(j), bytes.get(j + 1)) {
+                    (Some(b'/'), Some(b'*')) => (depth, j) = (depth + 1, j + 2),
+                    (Some(b'*'), Some(b'/')) => (depth, j) = (depth - 1, j + 2),
+                    (Some(_), _) => j += 1,
+                    (None, _) => bail!("Unterminated block comment starting on line {}", line),
+                }
+            }
+            line += newlines(&bytes[i..j]);
+            i = j;
+            continue;
+        }
+        if b == b';' {
+            if let Some((from, from_line)) = start.take() {
+                statements.push(ScriptStatement { sql: script[from..i].trim_end(), line: from_line });
+            }
+            i += 1;
+            continue;
+        }
+        if b.is_ascii_whitespace() {
+            line += usize::from(b == b'\n');
+            i += 1;
+            continue;
+        }
+
+        start.get_or_insert((i, line));
+        let end = match b {
+            b'\'' => {
+                let escapes = i > 0
+                    && matches!(bytes[i - 1], b'E' | b'e')
+                    && (i < 2 || !is_ident(bytes[i - 2]));
+                let mut j = i + 1;
+                loop {
+                    match bytes.get(j) {
+                        Some(b'\\') if escapes => j += 2,
+                        Some(b'\'') if bytes.get(j + 1) == Some(&b'\'') => j += 2,
+                        Some(b'\'') => break j + 1,
+                        Some(_) => j += 1,
+                        None => bail!("Unterminated string starting on line {}", line),
+                    }
+                }
+            }
+            b'"' => {
+                let mut j = i + 1;
+                loop {
+                    match bytes.get(j) {
+                        Some(b'"') if bytes.get(j + 1) == Some(&b'"') => j += 2,
+                        Some(b'"') => break j + 1,
+                        Some(_) => j += 1,
+                        None => bail!("Unterminated quoted identifier starting on line {}", line),
+                    }
+                }
+            }
+            b'$' if i == 0 || !is_ident(bytes[i - 1]) => {
+                // $tag$ where tag is empty or an identifier not starting with
+                // a digit ($1 is a parameter)
+                let tag_len = bytes[i + 1..]
+                    .iter()
+                    .take_while(|b| b.is_ascii_alphanumeric() || **b == b'_')
+                    .count();
+                let tag_end = i + 1 + tag_len;
+                let is_quote = bytes.get(tag_end) == Some(&b'$')
+                    && !bytes.get(i + 1).is_some_and(u8::is_ascii_digit);
+                if is_quote {
+                    let delimiter = &script[i..=tag_end];
+                    match script[tag_end + 1..].find(delimiter) {
+                        Some(n) => tag_end + 1 + n + delimiter.len(),
+                        None => bail!("Unterminated {} quote starting on line {}", delimiter, line),
+                    }
+                } else {
+                    i + 1
+                }
+            }
+            _ => i + 1,
+        };
+        line += newlines(&bytes[i..end]);
+        i = end;
+    }
+    if let Some((from, from_line)) = start {
+        statements.push(ScriptStatement { sql: script[from..].trim_end(), line: from_line });
+    }
+    Ok(statements)
+}
+
+pub(crate) fn truncate_statement(table: &str, options: &TruncateOptions) -> anyhow::Result<String> {
+    validate_qualified(table)?;
+
+    let mut sql = format!("TRUNCATE TABLE {}", quote_qualified(table));
+    if options.restart_identity {
+        sql.push_str(" RESTART IDENTITY");
+    }
+    if options.cascade {
+        sql.push_str(" CASCADE");
+    }
+    Ok(sql)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_database_statement() {
+        assert_eq!(
+            create_database_statement("app", &CreateDatabaseOptions::default()),
+            "CREATE DATABASE \"app\""
+        );
+        let options = CreateDatabaseOptions {
+            owner: Some("app_owner".into()),
+            encoding: Some("UTF8".into()),
+            locale: Some("C".into()),
+            template: Some("template0".into()),
+            connection_limit: Some(20),
+        };
+        assert_eq!(
+            create_database_statement("app", &options),
+            "CREATE DATABASE \"app\" OWNER \"app_owner\" TEMPLATE \"template0\" \
+             ENCODING 'UTF8' LOCALE 'C' CONNECTION LIMIT 20"
+        );
+    }
+
+    #[test]
+    fn test_create_policy_statement() {
+        assert_eq!(
+            create_policy_statement("docs", "all_rows", &PolicyOptions::default()).unwrap(),
+            "CREATE POLICY \"all_rows\" ON \"docs\" AS PERMISSIVE FOR ALL TO PUBLIC"
+        );
+        let options = PolicyOptions {
+            command: PolicyCommand::Update,
+            restrictive: true,
+            roles: vec!["app".into(), "Odd role".into()],
+            using: Some("owner = current_user".into()),
+            with_check: Some("owner = current_user".into()),
+        };
+        assert_eq!(
+            create_policy_statement("kb.docs", "own_rows", &options).unwrap(),
+            "CREATE POLICY \"own_rows\" ON \"kb\".\"docs\" AS RESTRICTIVE FOR UPDATE \
+             TO \"app\", \"Odd role\" USING (owner = current_user) \
+             WITH CHECK (owner = current_user)"
+        );
+        assert!(create_policy_statement("a.b.c", "p", &PolicyOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_split_sql() {
+        let script = "-- schema\n\
+            CREATE TABLE t (a TEXT DEFAULT 'x;y', \"b;\" INT);\n\
+            /* block /* nested ; */ still comment */\n\
+            CREATE FUNCTION f() RETURNS int AS $body$\n\
+            BEGIN RETURN 1; END;\n\
+            $body$ LANGUAGE plpgsql;;\n\
+            SELECT E'it\\'s;', $$a;b$$, $1::text\n";
+        let statements = split_sql(script).unwrap();
+        assert_eq!(statements.len(), 3);
+        assert_eq!(statements[0], ScriptStatement {
+            sql: "CREATE TABLE t (a TEXT DEFAULT 'x;y', \"b;\" INT)",
+            line: 2,
+        });
+        assert_eq!(statements[1].line, 4);
+        assert!(statements[1].sql.ends_with("$body$ LANGUAGE plpgsql"));
+        assert_eq!(statements[2].sql, "SELECT E'it\\'s;', $$a;b$$, $1::text");
+        assert_eq!(statements[2].line, 7);
+
+        assert!(split_sql("SELECT 1; -- only a comment\n").unwrap().len() == 1);
+        assert!(split_sql("SELECT $x$ unterminated").is_err());
+        assert!(split_sql("SELECT 'open").is_err());
+    }
 }
@@ -0,0 +1,174 @@
+//! Query plans from `EXPLAIN (FORMAT JSON)`, parsed into typed nodes so
+//! tests can assert on them, e.g. that a hot query keeps using its index.
+
+use anyhow::{Context, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::error::Result;
+use crate::statement_tracing::traced;
+
+/// What `explain` asks `EXPLAIN` for.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ExplainOptions {
+    /// Run the statement and report actual times and row counts.
+    pub analyze: bool,
+    /// Report buffer usage (the block counters of `PlanNode`).
+    pub buffers: bool,
+}
+
+/// A parsed plan: the root node and, with `analyze`, the overall timings.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QueryPlan {
+    #[serde(rename = "Plan")]
+    pub root: PlanNode,
+    #[serde(rename = "Planning Time")]
+    pub planning_time_ms: Option<f64>,
+    #[serde(rename = "Execution Time")]
+    pub execution_time_ms: Option<f64>,
+}
+
+impl QueryPlan {
+    /// All nodes, depth first from the root.
+    pub fn nodes(&self) -> Vec<&PlanNode> {
+        let mut nodes = Vec::new();
+        let mut pending = vec![&self.root];
+        while let Some(node) = pending.pop() {
+            nodes.push(node);
+            pending.extend(node.plans.iter().rev());
+        }
+        nodes
+    }
+
+    /// Nodes of a type, e.g. `"Seq Scan"` or `"Index Only Scan"`.
+    pub fn find(&self, node_type: &str) -> Vec<&PlanNode> {
+        self.nodes().into_iter().filter(|n| n.node_type == node_type).collect()
+    }
+}
+
+/// One plan node. Row counts are floats because PostgreSQL 18 reports
+/// averaged actual rows with decimals. Properties without a field here are
+/// kept in `extra` under their `EXPLAIN` names.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PlanNode {
+    #[serde(rename = "Node Type")]
+    pub node_type: String,
+    #[serde(rename = "Relation Name")]
+    pub relation: Option<String>,
+    #[serde(rename = "Index Name")]
+    pub index: Option<String>,
+    #[serde(rename = "Startup Cost")]
+    pub startup_cost: f64,
+    #[serde(rename = "Total Cost")]
+    pub total_cost: f64,
+    /// Estimated rows per loop.
+    #[serde(rename = "Plan Rows")]
+    pub plan_rows: f64,
+    #[serde(rename = "Actual Startup Time")]
+    pub actual_startup_time_ms: Option<f64>,
+    #[serde(rename = "Actual Total Time")]
+    pub actual_total_time_ms: Option<f64>,
+    /// Actual rows per loop.
+    #[serde(rename = "Actual Rows")]
+    pub actual_rows: Option<f64>,
+    #[serde(rename = "Actual Loops")]
+    pub actual_loops: Option<f64>,
+    #[serde(rename = "Shared Hit Blocks")]
+    pub shared_hit_blocks: Option<i64>,
+    #[serde(rename = "Shared Read Blocks")]
+    pub shared_read_blocks: Option<i64>,
+    #[serde(rename = "Plans", default)]
+    pub plans: Vec<PlanNode>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Plan `sql` with `EXPLAIN (FORMAT JSON)` and parse the result.
+///
+/// With `analyze` the statement runs, inside a transaction that is rolled
+/// back, so explaining `INSERT`/`UPDATE`/`DELETE` leaves the data unchanged
+/// (sequences still advance). `sql` is a single statement without bind
+/// parameters.
+///
+/// # Example
+/// ```rust,no_run
+/// use pg_toolkit::analyze::{ExplainOptions, explain};
+///
+/// # async fn example(pool: &sqlx::PgPool) -> anyhow::Result<()> {
+/// let sql = "SELECT * FROM users WHERE email = 'a@example.com'";
+/// let plan = explain(pool, sql, &ExplainOptions::default()).await?;
+/// assert!(plan.find("Seq Scan").is_empty(), "users lookup no longer uses its index");
+/// # Ok(())
+/// # }
+/// ```
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn explain(pool: &PgPool, sql: &str, options: &ExplainOptions) -> Result<QueryPlan> {
+    traced(async {
+        let sql = sql.trim().trim_end_matches(';').trim_end();
+        if sql.is_empty() {
+            return Err(anyhow!("Query to explain is empty").into());
+        }
+        let statement = format!(
+            "EXPLAIN (FORMAT JSON, ANALYZE {}, BUFFERS {}) {}",
+            options.analyze, options.buffers, sql
+        );
+
+        let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+        let output: serde_json::Value = sqlx::query_scalar(&statement)
+            .fetch_one(&mut *tx)
+            .await
+            .context("Failed to run EXPLAIN")?;
+        tx.rollback().await.context("Failed to roll back EXPLAIN")?;
+
+        Ok(parse_plan(output)?)
+    })
+    .await
+}
+
+/// `EXPLAIN (FORMAT JSON)` returns a one-element array of plans.
+fn parse_plan(output: serde_json::Value) -> anyhow::Result<QueryPlan> {
+    let mut plans: Vec<QueryPlan> =
+        serde_json::from_value(output).context("Failed to parse EXPLAIN output")?;
+    if plans.len() != 1 {
+        bail!("Expected one plan from EXPLAIN, got {}", plans.len());
+    }
+    Ok(plans.remove(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_plan() {
+        let output = serde_json::json!([{
+            "Plan": {
+                "Node Type": "Hash Join", "Join Type": "Inner",
+                "Startup Cost": 1.5, "Total Cost": 10.25, "Plan Rows": 100, "Plan Width": 8,
+                "Actual Startup Time": 0.1, "Actual Total Time": 0.9,
+                "Actual Rows": 98.5, "Actual Loops": 1,
+                "Plans": [
+                    {"Node Type": "Seq Scan", "Relation Name": "a", "Startup Cost": 0.0,
+                     "Total Cost": 4.0, "Plan Rows": 100, "Plan Width": 4},
+                    {"Node Type": "Hash", "Startup Cost": 1.0, "Total Cost": 1.0,
+                     "Plan Rows": 10, "Plan Width": 4,
+                     "Plans": [{"Node Type": "Index Scan", "Relation Name": "b",
+                                "Index Name": "b_pkey", "Startup Cost": 0.1,
+                                "Total Cost": 1.0, "Plan Rows": 10, "Plan Width": 4}]}
+                ]
+            },
+            "Planning Time": 0.05,
+            "Execution Time": 1.2
+        }]);
+        let plan = parse_plan(output).unwrap();
+        assert_eq!(plan.root.node_type, "Hash Join");
+        assert_eq!(plan.root.actual_rows, Some(98.5));
+        assert_eq!(plan.root.extra["Join Type"], "Inner");
+        assert_eq!(plan.execution_time_ms, Some(1.2));
+        let types: Vec<&str> = plan.nodes().iter().map(|n| n.node_type.as_str()).collect();
+        assert_eq!(types, ["Hash Join", "Seq Scan", "Hash", "Index Scan"]);
+        assert_eq!(plan.find("Index Scan")[0].index.as_deref(), Some("b_pkey"));
+
+        assert!(parse_plan(serde_json::json!([])).is_err());
+    }
+}
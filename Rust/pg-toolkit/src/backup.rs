@@ -0,0 +1,376 @@
+//! Database backup and restore via `pg_dump` / `pg_restore`.
+//!
+//! Connection settings come from the `PgConfig`; the password and TLS settings
+//! are passed through the environment (`PGPASSWORD`, `PGSSLMODE`, ...) so they
+//! never show up in the process list. With `verbose`, the tools' progress
+//! lines are forwarded to `tracing`; on failure their stderr is returned in
+//! the error.
+
+use anyhow::{Context, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+
+use crate::config::PgConfig;
+use crate::error::Result;
+use crate::statement_tracing::traced;
+
+/// Lines of stderr kept for error messages.
+const STDERR_TAIL_LINES: usize = 20;
+
+/// `pg_dump` output format.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DumpFormat {
+    /// Compressed archive for `pg_restore` (`-F c`)
+    #[default]
+    Custom,
+    /// SQL script, restored with `psql` (`-F p`)
+    Plain,
+    /// One file per table; supports parallel dumps (`-F d`)
+    Directory,
+    /// Tar archive for `pg_restore` (`-F t`)
+    Tar,
+}
+
+impl DumpFormat {
+    fn flag(self) -> &'static str {
+        match self {
+            DumpFormat::Custom => "c",
+            DumpFormat::Plain => "p",
+            DumpFormat::Directory => "d",
+            DumpFormat::Tar => "t",
+        }
+    }
+
+    /// File extension for default output paths (none for directories).
+    fn extension(self) -> Option<&'static str> {
+        match self {
+            DumpFormat::Custom => Some("dump"),
+            DumpFormat::Plain => Some("sql"),
+            DumpFormat::Directory => None,
+            DumpFormat::Tar => Some("tar"),
+        }
+    }
+}
+
+/// Options for `dump_database`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DumpOptions {
+    pub format: DumpFormat,
+    /// Output path; default: `<database>_<UTC timestamp>.<ext>` in the
+    /// current directory.
+    pub output: Option<PathBuf>,
+    /// Dump only the schema, no data (`--schema-only`).
+    pub schema_only: bool,
+    /// Dump only the data, no schema (`--data-only`).
+    pub data_only: bool,
+    /// Dump only these schemas (`--schema`).
+    pub schemas: Vec<String>,
+    /// Dump only these tables (`--table`).
+    pub tables: Vec<String>,
+    /// Skip these tables (`--exclude-table`).
+    pub exclude_tables: Vec<String>,
+    /// Parallel jobs; directory format only (`--jobs`).
+    pub jobs: Option<u32>,
+    /// Omit ownership commands (`--no-owner`).
+    pub no_owner: bool,
+    /// Report progress through `tracing` (`--verbose`).
+    pub verbose: bool,
+    /// Directory holding pg_dump; default: found on PATH.
+    pub bin_dir: Option<PathBuf>,
+}
+
+/// Options for `restore_database_with`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RestoreOptions {
+    /// Drop objects before recreating them (`--clean --if-exists`).
+    pub clean: bool,
+    /// Omit ownership commands (`--no-owner`).
+    pub no_owner: bool,
+    /// Parallel jobs (`--jobs`).
+    pub jobs: Option<u32>,
+    /// Report progress through `tracing` (`--verbose`).
+    pub verbose: bool,
+    /// Directory holding pg_restore and psql; default: found on PATH.
+    pub bin_dir: Option<PathBuf>,
+}
+
+/// Dump the config's database. Returns the path written.
+///
+/// The database must already be set on the config.
+#[tracing::instrument(level = "debug", skip(config))]
+pub async fn dump_database(config: &PgConfig, options: &DumpOptions) -> Result<PathBuf> {
+    traced(async {
+        let database = required_database(config)?;
+        if options.schema_only && options.data_only {
+            return Err(anyhow!("schema_only and data_only cannot both be set").into());
+        }
+        if options.jobs.is_some() && options.format != DumpFormat::Directory {
+            return Err(anyhow!("Parallel dumps (jobs) need the directory format").into());
+        }
+
+        let output = options.output.clone().unwrap_or_else(|| {
+            let stem = format!("{}_{}", database, chrono::Utc::now().format("%Y%m%dT%H%M%SZ"));
+            match options.format.extension() {
+                Some(ext) => PathBuf::from(format!("{}.{}", stem, ext)),
+                None => PathBuf::from(stem),
+            }
+        });
+
+        tracing::info!("Dumping database '{}' to {:?}", database, output);
+        let program = binary(options.bin_dir.as_deref(), "pg_dump");
+        run(&program, &dump_args(config, options, &output), config, options.verbose).await?;
+        tracing::info!("Dumped database '{}' to {:?}", database, output);
+        Ok(output)
+    })
+    .await
+}
+
+/// Restore a dump into the config's database with default options.
+///
+/// See `restore_database_with`.
+#[tracing::instrument(level = "debug", skip(config, path), fields(path = ?path.as_ref()))]
+pub async fn restore_database(config: &PgConfig, path: impl AsRef<Path>) -> Result<()> {
+    traced(async {
+        restore_database_with(config, path, &RestoreOptions::default()).await
+    })
+    .await
+}
+
+/// Restore a dump into the config's database, which must already exist.
+///
+/// Plain-format dumps (SQL files) are run with `psql`; archives and dump
+/// directories with `pg_restore`. Both stop at the first error.
+#[tracing::instrument(level = "debug", skip(config, path), fields(path = ?path.as_ref()))]
+pub async fn restore_database_with(
+    config: &PgConfig,
+    path: impl AsRef<Path>,
+    options: &RestoreOptions,
+) -> Result<()> {
+    traced(async {
+        let path = path.as_ref();
+        let database = required_database(config)?;
+        if !path.exists() {
+            return Err(anyhow!("Dump not found: {:?}", path).into());
+        }
+
+        tracing::info!("Restoring {:?} into database '{}'", path, database);
+        if is_plain_dump(path)? {
+            let program = binary(options.bin_dir.as_deref(), "psql");
+            run(&program, &psql_restore_args(config, path), config, options.verbose).await?;
+        } else {
+            let program = binary(options.bin_dir.as_deref(), "pg_restore");
+            run(&program, &restore_args(config, path, options), config, options.verbose).await?;
+        }
+        tracing::info!("Restored {:?} into database '{}'", path, database);
+        Ok(())
+    })
+    .await
+}
+
+fn required_database(config: &PgConfig) -> anyhow::Result<&str> {
+    config
+        .database
+        .as_deref()
+        .context("PgConfig has no database set; backups need a target database")
+}
+
+/// `-h`, `-p`, `-U`, `-d` for the config (the socket directory, if set, as
+/// the host).
+fn connection_args(config: &PgConfig) -> Vec<String> {
+    let host = config.socket_dir.as_ref().unwrap_or(&config.host);
+    vec![
+        "--host".to_string(),
+        host.clone(),
+        "--port".to_string(),
+        config.port.to_string(),
+        "--username".to_string(),
+        config.user.clone(),
+        "--dbname".to_string(),
+        config.database.clone().unwrap_or_default(),
+        "--no-password".to_string(),
+    ]
+}
+
+/// pg_dump arguments for `options`, writing to `output`.
+pub fn dump_args(config: &PgConfig, options: &DumpOptions, output: &Path) -> Vec<String> {
+    let mut args = connection_args(config);
+    args.push(format!("--format={}", options.format.flag()));
+    args.push(format!("--file={}", output.display()));
+    if options.schema_only {
+        args.push("--schema-only".to_string());
+    }
+    if options.data_only {
+        args.push("--data-only".to_string());
+    }
+    args.extend(options.schemas.iter().map(|s| format!("--schema={}", s)));
+    args.extend(options.tables.iter().map(|t| format!("--table={}", t)));
+    args.extend(options.exclude_tables.iter().map(|t| format!("--exclude-table={}", t)));
+    if let Some(jobs) = options.jobs {
+        args.push(format!("--jobs={}", jobs));
+    }
+    if options.no_owner {
+        args.push("--no-owner".to_string());
+    }
+    if options.verbose {
+        args.push("--verbose".to_string());
+    }
+    args
+}
+
+/// pg_restore arguments restoring `path` with `options`.
+pub fn restore_args(config: &PgConfig, path: &Path, options: &RestoreOptions) -> Vec<String> {
+    let mut args = connection_args(config);
+    args.push("--exit-on-error".to_string());
+    if options.clean {
+        args.push("--clean".to_string());
+        args.push("--if-exists".to_string());
+    }
+    if options.no_owner {
+        args.push("--no-owner".to_string());
+    }
+    if let Some(jobs) = options.jobs {
+        args.push(format!("--jobs={}", jobs));
+    }
+    if options.verbose {
+        args.push("--verbose".to_string());
+    }
+    args.push(path.display().to_string());
+    args
+}
+
+fn psql_restore_args(config: &PgConfig, path: &Path) -> Vec<String> {
+    let mut args = connection_args(config);
+    args.extend([
+        "--quiet".to_string(),
+        "--set=ON_ERROR_STOP=1".to_string(),
+        "--single-transaction".to_string(),
+        format!("--file={}", path.display()),
+    ]);
+    args
+}
+
+/// True for plain SQL dumps; archives start with `PGDMP` (custom) or are tar
+/// files or directories.
+fn is_plain_dump(path: &Path) -> anyhow::Result<bool> {
+    if path.is_dir() {
+        return Ok(false);
+    }
+    let mut header = [0u8; 5];
+    let read = std::io::Read::read(
+        &mut std::fs::File::open(path).with_context(|| format!("Failed to open {:?}", path))?,
+        &mut header,
+    )?;
+    let is_tar = path.extension().is_some_and(|ext| ext == "tar");
+    Ok(&header[..read] != b"PGDMP" && !is_tar)
+}
+
+fn binary(bin_dir: Option<&Path>, name: &str) -> PathBuf {
+    match bin_dir {
+        Some(dir) => dir.join(name),
+        None => PathBuf::from(name),
+    }
+}
+
+/// Environment for the client tools: password and TLS settings.
+fn client_env(config: &PgConfig) -> Vec<(&'static str, String)> {
+    let mut env = vec![("PGPASSWORD", config.password.clone())];
+    let tls = [
+        ("PGSSLMODE", &config.sslmode),
+        ("PGSSLROOTCERT", &config.sslrootcert),
+        ("PGSSLCERT", &config.sslcert),
+        ("PGSSLKEY", &config.sslkey),
+    ];
+    env.extend(tls.into_iter().filter_map(|(k, v)| v.clone().map(|v| (k, v))));
+    env
+}
+
+/// Run a client tool, forwarding stderr to `tracing` when `verbose` and
+/// including its tail in the error on failure.
+async fn run(program: &Path, args: &[String], config: &PgConfig, verbose: bool) -> anyhow::Result<()> {
+    let name = program.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    let mut child = Command::new(program)
+        .args(args)
+        .envs(client_env(config))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run {:?}; is it installed?", program))?;
+
+    let mut tail = VecDeque::with_capacity(STDERR_TAIL_LINES);
+    if let Some(stderr) = child.stderr.take() {
+        let mut lines = BufReader::new(stderr).lines();
+        while let Some(line) = lines.next_line().await? {
+            if verbose {
+                tracing::info!("{}: {}", name, line);
+            }
+            if tail.len() == STDERR_TAIL_LINES {
+                tail.pop_front();
+            }
+            tail.push_back(line);
+        }
+    }
+
+    let status = child.wait().await.with_context(|| format!("Failed to wait for {}", name))?;
+    if !status.success() {
+        bail!(
+            "{} failed ({}):\n{}",
+            name,
+            status,
+            tail.into_iter().collect::<Vec<_>>().join("\n")
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dump_args() {
+        let config = PgConfig::new("db.example.com", 6543, "admin", "secret", Some("app"));
+        let options = DumpOptions {
+            format: DumpFormat::Directory,
+            schema_only: true,
+            tables: vec!["public.users".to_string()],
+            jobs: Some(4),
+            ..DumpOptions::default()
+        };
+        let args = dump_args(&config, &options, Path::new("/backups/app"));
+        assert_eq!(
+            args,
+            [
+                "--host", "db.example.com", "--port", "6543", "--username", "admin",
+                "--dbname", "app", "--no-password", "--format=d", "--file=/backups/app",
+                "--schema-only", "--table=public.users", "--jobs=4",
+            ]
+        );
+        assert!(!args.iter().any(|a| a.contains("secret")));
+    }
+
+    #[test]
+    fn test_restore_args_and_client_env() {
+        let config = PgConfig {
+            socket_dir: Some("/var/run/postgresql".to_string()),
+            sslmode: Some("require".to_string()),
+            ..PgConfig::new("localhost", 5432, "u", "p", Some("app"))
+        };
+        let options = RestoreOptions { clean: true, ..RestoreOptions::default() };
+        let args = restore_args(&config, Path::new("app.dump"), &options);
+        assert_eq!(&args[..2], ["--host", "/var/run/postgresql"]);
+        assert_eq!(
+            &args[9..],
+            ["--exit-on-error", "--clean", "--if-exists", "app.dump"]
+        );
+        assert_eq!(
+            client_env(&config),
+            [("PGPASSWORD", "p".to_string()), ("PGSSLMODE", "require".to_string())]
+        );
+    }
+}
@@ -0,0 +1,156 @@
+//! High-throughput inserts with `COPY ... FROM STDIN`.
+//!
+//! Rows are streamed to the server as CSV in chunks, so memory use stays flat
+//! however many rows there are. Values are given in Postgres' text
+//! representation (what a `'...'` literal of the column type would contain,
+//! e.g. `42`, `2024-01-01`, `[0.1,0.2]` for pgvector); `None` is NULL.
+
+use anyhow::{Context, bail};
+use sqlx::{PgConnection, PgPool};
+
+use crate::error::Result;
+use crate::identifier::{quote_identifier, quote_qualified};
+use crate::statement_tracing::{self, traced};
+
+/// Bytes buffered before a chunk is sent to the server.
+const COPY_CHUNK_BYTES: usize = 1 << 20;
+
+/// Bulk insert `rows` into `table` (optionally `schema.table`) with COPY.
+///
+/// Each row must have one value per column. The copy is atomic: on any error,
+/// including a malformed row, nothing is inserted. Returns the rows copied.
+///
+/// # Example
+/// ```rust,no_run
+/// # async fn example(pool: &sqlx::PgPool) -> anyhow::Result<()> {
+/// let rows = vec![
+///     vec![Some("1"), Some("hello")],
+///     vec![Some("2"), None],
+/// ];
+/// let copied = pg_toolkit::bulk::copy_in(pool, "notes", &["id", "body"], rows).await?;
+/// assert_eq!(copied, 2);
+/// # Ok(())
+/// # }
+/// ```
+#[tracing::instrument(level = "debug", skip(pool, rows), fields(rows_affected))]
+pub async fn copy_in<R, V>(pool: &PgPool, table: &str, columns: &[&str], rows: R) -> Result<u64>
+where
+    R: IntoIterator,
+    R::Item: IntoIterator<Item = Option<V>>,
+    V: AsRef<str>,
+{
+    traced(async {
+        let mut conn = pool.acquire().await.context("Failed to acquire connection")?;
+        let copied = copy_in_on(&mut conn, table, columns, rows).await?;
+        statement_tracing::rows_affected(copied);
+        Ok(copied)
+    })
+    .await
+}
+
+/// `copy_in` on a given connection, e.g. inside a transaction.
+pub(crate) async fn copy_in_on<R, V>(
+    conn: &mut PgConnection,
+    table: &str,
+    columns: &[&str],
+    rows: R,
+) -> anyhow::Result<u64>
+where
+    R: IntoIterator,
+    R::Item: IntoIterator<Item = Option<V>>,
+    V: AsRef<str>,
+{
+    if columns.is_empty() {
+        bail!("copy_in into '{}' needs at least one column", table);
+    }
+    let statement = copy_statement(table, columns);
+    let mut copy = conn
+        .copy_in_raw(&statement)
+        .await
+        .with_context(|| format!("Failed to start COPY into '{}'", table))?;
+
+    let mut buffer = Vec::with_capacity(COPY_CHUNK_BYTES + 4096);
+    for (index, row) in rows.into_iter().enumerate() {
+        let values = push_csv_row(&mut buffer, row);
+        if values != columns.len() {
+            copy.abort("row has the wrong number of values").await.ok();
+            bail!(
+                "Row {} has {} values but {} columns were given for '{}'",
+                index,
+                values,
+                columns.len(),
+                table
+            );
+        }
+        if buffer.len() >= COPY_CHUNK_BYTES {
+            copy.send(buffer.as_slice())
+                .await
+                .with_context(|| format!("COPY into '{}' failed", table))?;
+            buffer.clear();
+        }
+    }
+    if !buffer.is_empty() {
+        copy.send(buffer.as_slice())
+            .await
+            .with_context(|| format!("COPY into '{}' failed", table))?;
+    }
+
+    let copied = copy
+        .finish()
+        .await
+        .with_context(|| format!("COPY into '{}' failed", table))?;
+    tracing::info!("Copied {} rows into '{}'", copied, table);
+    Ok(copied)
+}
+
+fn copy_statement(table: &str, columns: &[&str]) -> String {
+    let columns: Vec<String> = columns.iter().map(|c| quote_identifier(c)).collect();
+    format!(
+        "COPY {} ({}) FROM STDIN WITH (FORMAT csv)",
+        quote_qualified(table),
+        columns.join(", ")
+    )
+}
+
+/// Append one CSV line: values are always quoted, so an unquoted empty field
+/// is unambiguously NULL. Returns the number of values written.
+fn push_csv_row<V: AsRef<str>>(buffer: &mut Vec<u8>, row: impl IntoIterator<Item = Option<V>>) -> usize {
+    let mut count = 0;
+    for value in row {
+        if count > 0 {
+            buffer.push(b',');
+        }
+        if let Some(value) = value {
+            buffer.push(b'"');
+            buffer.extend_from_slice(value.as_ref().replace('"', "\"\"").as_bytes());
+            buffer.push(b'"');
+        }
+        count += 1;
+    }
+    buffer.push(b'\n');
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_statement() {
+        assert_eq!(
+            copy_statement("kb.chunks", &["document_id", "content"]),
+            "COPY \"kb\".\"chunks\" (\"document_id\", \"content\") FROM STDIN WITH (FORMAT csv)"
+        );
+    }
+
+    #[test]
+    fn test_push_csv_row() {
+        let mut buffer = vec![];
+        let count = push_csv_row(&mut buffer, [Some("a,b"), None, Some("say \"hi\"\n"), Some("")]);
+        assert_eq!(count, 4);
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "\"a,b\",,\"say \"\"hi\"\"\n\",\"\"\n"
+        );
+    }
+}
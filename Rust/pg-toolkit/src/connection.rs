@@ -1,12 +1,153 @@
 //! PostgreSQL connection pooling.
 
 use crate::config::PgConfig;
-use sqlx::PgPool;
+use crate::error::Result;
+use crate::identifier::quote_literal;
+use serde::{Deserialize, Serialize};
+use crate::statement_tracing::parse_level;
+use sqlx::pool::PoolConnection;
+use sqlx::{ConnectOptions, Executor, PgPool, Postgres};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions, PgSslMode};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Connect options for the config's database, including TLS settings, the
+/// Unix socket directory and the application name.
+///
+/// sqlx traces every statement it executes (target `sqlx::query`) at debug;
+/// `log_statements: off` silences those events on the config's connections.
+/// The toolkit's own reports are set process-wide by
+/// `statement_tracing::configure`.
+///
+/// Without a configured `application_name`, the `PGAPPNAME` environment
+/// variable is used, else the running binary's name (see
+/// `default_application_name`).
+///
+/// Fails if `sslmode` is not a valid mode or `log_statements` not a valid
+/// level.
+pub fn connect_options(config: &PgConfig) -> Result<PgConnectOptions, sqlx::Error> {
+    let mut options = PgConnectOptions::new()
+        .host(&config.host)
+        .port(config.port)
+        .username(&config.user)
+        .password(&config.password);
+    if let Some(dir) = &config.socket_dir {
+        options = options.socket(dir);
+    }
+    if let Some(database) = &config.database {
+        options = options.database(database);
+    }
+    if let Some(mode) = &config.sslmode {
+        options = options.ssl_mode(mode.parse::<PgSslMode>()?);
+    }
+    if let Some(path) = &config.sslrootcert {
+        options = options.ssl_root_cert(path);
+    }
+    if let Some(path) = &config.sslcert {
+        options = options.ssl_client_cert(path);
+    }
+    if let Some(path) = &config.sslkey {
+        options = options.ssl_client_key(path);
+    }
+    if !config.options.is_empty() {
+        options = options.options(&config.options);
+    }
+    if let Some(level) = &config.log_statements
+        && parse_level(level)?.into_level().is_none()
+    {
+        options = options.disable_statement_logging();
+    }
+    match &config.application_name {
+        Some(name) => options = options.application_name(name),
+        // PgConnectOptions::new() already read PGAPPNAME
+        None if options.get_application_name().is_none() => {
+            if let Some(name) = default_application_name() {
+                options = options.application_name(&name);
+            }
+        }
+        None => {}
+    }
+    Ok(options)
+}
+
+/// The running binary's file name without extension (e.g. "kb-server"), or
+/// `None` if it cannot be determined.
+pub fn default_application_name() -> Option<String> {
+    let exe = std::env::current_exe().ok()?;
+    Some(exe.file_stem()?.to_str()?.to_string())
+}
+
+/// The config's session settings as `(name, value)` pairs, in the form
+/// `set_config` takes; unset fields are omitted.
+pub fn session_settings(config: &PgConfig) -> Vec<(&'static str, String)> {
+    let timeouts = [
+        ("statement_timeout", config.statement_timeout_ms),
+        ("lock_timeout", config.lock_timeout_ms),
+        ("idle_in_transaction_session_timeout", config.idle_in_transaction_session_timeout_ms),
+    ];
+    let mut settings: Vec<(&'static str, String)> = timeouts
+        .into_iter()
+        .filter_map(|(name, ms)| ms.map(|ms| (name, format!("{}ms", ms))))
+        .collect();
+    if let Some(path) = &config.search_path {
+        settings.push(("search_path", path.clone()));
+    }
+    settings
+}
+
+/// One statement applying `settings` to a session with `set_config`, which,
+/// unlike `SET`, takes a search path list as a plain string.
+fn session_settings_sql(settings: &[(&str, String)]) -> String {
+    let calls: Vec<String> = settings
+        .iter()
+        .map(|(name, value)| {
+            format!("set_config({}, {}, false)", quote_literal(name), quote_literal(value))
+        })
+        .collect();
+    format!("SELECT {}", calls.join(", "))
+}
+
+/// Pool options from the config's tuning fields; unset fields keep sqlx's
+/// defaults, and an idle timeout or max lifetime of 0 disables it.
+///
+/// The config's session settings (see `session_settings`) are applied to each
+/// new connection in `after_connect`.
+pub fn pool_options(config: &PgConfig) -> PgPoolOptions {
+    let mut options = PgPoolOptions::new();
+    if let Some(max) = config.max_connections {
+        options = options.max_connections(max);
+    }
+    if let Some(min) = config.min_connections {
+        options = options.min_connections(min);
+    }
+    if let Some(secs) = config.acquire_timeout_secs {
+        options = options.acquire_timeout(Duration::from_secs(secs));
+    }
+    if let Some(secs) = config.idle_timeout_secs {
+        options = options.idle_timeout((secs > 0).then(|| Duration::from_secs(secs)));
+    }
+    if let Some(secs) = config.max_lifetime_secs {
+        options = options.max_lifetime((secs > 0).then(|| Duration::from_secs(secs)));
+    }
+    let settings = session_settings(config);
+    if !settings.is_empty() {
+        let sql = session_settings_sql(&settings);
+        options = options.after_connect(move |conn, _meta| {
+            let sql = sql.clone();
+            Box::pin(async move {
+                conn.execute(sql.as_str()).await?;
+                Ok(())
+            })
+        });
+    }
+    options
+}
 
 /// Create a new PostgreSQL connection pool from the given configuration.
 ///
-/// This is a thin wrapper around `PgPool::connect` that uses the config's
-/// connection string. The pool will be configured with default sqlx settings.
+/// Connects with the config's connect options, including TLS (see
+/// `connect_options`), and pool tuning options (see `pool_options`).
 ///
 /// # Example
 /// ```rust,no_run
@@ -20,20 +161,376 @@ use sqlx::PgPool;
 ///     Ok(())
 /// }
 /// ```
-pub async fn create_pool(config: &PgConfig) -> Result<PgPool, sqlx::Error> {
-    PgPool::connect(&config.connection_string()).await
+pub async fn create_pool(config: &PgConfig) -> Result<PgPool> {
+    let options = connect_options(config)?;
+    Ok(pool_options(config).connect_with(options).await?)
+}
+
+/// Create a connection pool without connecting.
+///
+/// Connections are opened on first use, so this succeeds without a live
+/// database and connection errors surface from the first query instead.
+/// Only an invalid config (e.g. an unknown `sslmode`) fails here.
+pub fn create_pool_lazy(config: &PgConfig) -> Result<PgPool> {
+    let options = connect_options(config)?;
+    Ok(pool_options(config).connect_lazy_with(options))
 }
 
 /// Create a connection pool to the system "postgres" database.
 ///
 /// This is useful for admin operations like creating or dropping databases
-/// when you don't yet have a connection to the target database.
-pub async fn create_system_pool(config: &PgConfig) -> Result<PgPool, sqlx::Error> {
-    PgPool::connect(&config.system_connection_string()).await
+/// when you don't yet have a connection to the target database. It uses
+/// sqlx's default pool settings and no session settings, since admin pools
+/// are short-lived.
+pub async fn create_system_pool(config: &PgConfig) -> Result<PgPool> {
+    let options = connect_options(config)?.database("postgres");
+    Ok(PgPool::connect_with(options).await?)
+}
+
+/// Upper bounds, in seconds, of the acquire wait histogram buckets.
+pub const ACQUIRE_WAIT_BUCKETS_SECS: [f64; 10] =
+    [0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 5.0];
+
+/// Point-in-time pool usage, sampled by `PoolMonitor::sample`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PoolMetrics {
+    /// Open connections, idle or in use.
+    pub size: u32,
+    pub idle: u32,
+    pub in_use: u32,
+    pub max_connections: u32,
+    /// Time spent waiting in `PoolMonitor::acquire`, since the monitor was
+    /// created.
+    pub acquire_wait: WaitHistogram,
+}
+
+/// Cumulative histogram of wait times, in the Prometheus layout.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WaitHistogram {
+    /// `(upper bound in seconds, waits at or under it)`, one per
+    /// `ACQUIRE_WAIT_BUCKETS_SECS` entry.
+    pub buckets: Vec<(f64, u64)>,
+    /// All waits, including those over the largest bound.
+    pub count: u64,
+    pub sum_secs: f64,
+    /// Acquires that failed (e.g. timed out); not counted in the buckets.
+    pub failures: u64,
+}
+
+/// Wraps a pool to record how long `acquire` waits, and samples pool usage.
+/// Cheap to clone; clones share the recorded waits.
+///
+/// Only acquires made through `PoolMonitor::acquire` are timed; queries run
+/// directly on the pool still show up in `size` / `idle` / `in_use`.
+#[derive(Debug, Clone)]
+pub struct PoolMonitor {
+    pool: PgPool,
+    waits: Arc<WaitRecorder>,
+}
+
+#[derive(Debug, Default)]
+struct WaitRecorder {
+    /// Per-bucket (non-cumulative) counts; the last slot is over every bound.
+    counts: [AtomicU64; ACQUIRE_WAIT_BUCKETS_SECS.len() + 1],
+    sum_nanos: AtomicU64,
+    failures: AtomicU64,
+    #[cfg(feature = "metrics")]
+    histogram: std::sync::OnceLock<prometheus::Histogram>,
+}
+
+impl WaitRecorder {
+    fn record(&self, wait: Duration) {
+        let secs = wait.as_secs_f64();
+        let bucket = ACQUIRE_WAIT_BUCKETS_SECS
+            .iter()
+            .position(|bound| secs <= *bound)
+            .unwrap_or(ACQUIRE_WAIT_BUCKETS_SECS.len());
+        self.counts[bucket].fetch_add(1, Ordering::Relaxed);
+        self.sum_nanos.fetch_add(wait.as_nanos() as u64, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        if let Some(histogram) = self.histogram.get() {
+            histogram.observe(secs);
+        }
+    }
+
+    fn histogram(&self) -> WaitHistogram {
+        let mut cumulative = 0;
+        let buckets = ACQUIRE_WAIT_BUCKETS_SECS
+            .iter()
+            .zip(&self.counts)
+            .map(|(bound, count)| {
+                cumulative += count.load(Ordering::Relaxed);
+                (*bound, cumulative)
+            })
+            .collect();
+        WaitHistogram {
+            buckets,
+            count: cumulative + self.counts[ACQUIRE_WAIT_BUCKETS_SECS.len()].load(Ordering::Relaxed),
+            sum_secs: self.sum_nanos.load(Ordering::Relaxed) as f64 / 1e9,
+            failures: self.failures.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl PoolMonitor {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool, waits: Arc::default() }
+    }
+
+    /// The monitored pool.
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+
+    /// Acquire a connection, recording how long it took.
+    pub async fn acquire(&self) -> Result<PoolConnection<Postgres>, sqlx::Error> {
+        let start = Instant::now();
+        let result = self.pool.acquire().await;
+        match &result {
+            Ok(_) => self.waits.record(start.elapsed()),
+            Err(_) => {
+                self.waits.failures.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        result
+    }
+
+    /// Current pool usage and the acquire waits recorded so far.
+    pub fn sample(&self) -> PoolMetrics {
+        let size = self.pool.size();
+        let idle = self.pool.num_idle() as u32;
+        PoolMetrics {
+            size,
+            idle,
+            in_use: size.saturating_sub(idle),
+            max_connections: self.pool.options().get_max_connections(),
+            acquire_wait: self.waits.histogram(),
+        }
+    }
+
+    /// Register Prometheus metrics for this pool, labelled `pool="<name>"`:
+    /// `pg_pool_connections{state="idle"|"in_use"}` and
+    /// `pg_pool_max_connections` gauges, sampled at scrape time, and a
+    /// `pg_pool_acquire_wait_seconds` histogram fed by `acquire`.
+    ///
+    /// Fails if metrics for a pool with the same name are already registered,
+    /// or this monitor (or a clone) was registered before.
+    #[cfg(feature = "metrics")]
+    pub fn register(&self, registry: &prometheus::Registry, name: &str) -> prometheus::Result<()> {
+        use prometheus::{Histogram, HistogramOpts, IntGauge, IntGaugeVec, Opts};
+
+        let connections = IntGaugeVec::new(
+            Opts::new("pg_pool_connections", "Open pool connections by state")
+                .const_label("pool", name),
+            &["state"],
+        )?;
+        let max_connections = IntGauge::with_opts(
+            Opts::new("pg_pool_max_connections", "Maximum pool connections")
+                .const_label("pool", name),
+        )?;
+        let acquire_wait = Histogram::with_opts(
+            HistogramOpts::new("pg_pool_acquire_wait_seconds", "Time waiting to acquire a connection")
+                .const_label("pool", name)
+                .buckets(ACQUIRE_WAIT_BUCKETS_SECS.to_vec()),
+        )?;
+
+        registry.register(Box::new(acquire_wait.clone()))?;
+        if self.waits.histogram.set(acquire_wait.clone()).is_err() {
+            registry.unregister(Box::new(acquire_wait))?;
+            return Err(prometheus::Error::AlreadyReg);
+        }
+        registry.register(Box::new(PoolCollector {
+            monitor: self.clone(),
+            connections,
+            max_connections,
+        }))
+    }
+}
+
+/// Samples pool gauges when Prometheus collects.
+#[cfg(feature = "metrics")]
+struct PoolCollector {
+    monitor: PoolMonitor,
+    connections: prometheus::IntGaugeVec,
+    max_connections: prometheus::IntGauge,
+}
+
+#[cfg(feature = "metrics")]
+impl prometheus::core::Collector for PoolCollector {
+    fn desc(&self) -> Vec<&prometheus::core::Desc> {
+        let mut descs = self.connections.desc();
+        descs.extend(self.max_connections.desc());
+        descs
+    }
+
+    fn collect(&self) -> Vec<prometheus::proto::MetricFamily> {
+        let metrics = self.monitor.sample();
+        self.connections.with_label_values(&["idle"]).set(metrics.idle.into());
+        self.connections.with_label_values(&["in_use"]).set(metrics.in_use.into());
+        self.max_connections.set(metrics.max_connections.into());
+
+        let mut families = self.connections.collect();
+        families.extend(self.max_connections.collect());
+        families
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use tracing::level_filters::LevelFilter;
+
+    #[test]
+    fn test_pool_options() {
+        let config = PgConfig {
+            max_connections: Some(25),
+            min_connections: Some(2),
+            acquire_timeout_secs: Some(5),
+            idle_timeout_secs: Some(0),
+            ..PgConfig::default()
+        };
+        let options = pool_options(&config);
+        assert_eq!(options.get_max_connections(), 25);
+        assert_eq!(options.get_min_connections(), 2);
+        assert_eq!(options.get_acquire_timeout(), Duration::from_secs(5));
+        assert_eq!(options.get_idle_timeout(), None);
+        assert_eq!(options.get_max_lifetime(), PgPoolOptions::new().get_max_lifetime());
+    }
+
+    #[test]
+    fn test_session_settings() {
+        assert!(session_settings(&PgConfig::default()).is_empty());
+
+        let config = PgConfig {
+            statement_timeout_ms: Some(30000),
+            idle_in_transaction_session_timeout_ms: Some(0),
+            search_path: Some("app, public".to_string()),
+            ..PgConfig::default()
+        };
+        let settings = session_settings(&config);
+        assert_eq!(
+            settings,
+            [
+                ("statement_timeout", "30000ms".to_string()),
+                ("idle_in_transaction_session_timeout", "0ms".to_string()),
+                ("search_path", "app, public".to_string()),
+            ]
+        );
+        assert_eq!(
+            session_settings_sql(&settings[..1]),
+            "SELECT set_config('statement_timeout', '30000ms', false)"
+        );
+    }
+
+    #[test]
+    fn test_connect_options_application_name() {
+        let config = PgConfig {
+            application_name: Some("search-api".to_string()),
+            ..PgConfig::default()
+        };
+        let options = connect_options(&config).unwrap();
+        assert_eq!(options.get_application_name(), Some("search-api"));
+
+        // Falls back to PGAPPNAME or the test binary's name
+        let options = connect_options(&PgConfig::default()).unwrap();
+        let expected = std::env::var("PGAPPNAME").ok().or_else(default_application_name);
+        assert_eq!(options.get_application_name(), expected.as_deref());
+        assert!(default_application_name().unwrap().starts_with("pg_toolkit"));
+    }
+
+    #[test]
+    fn test_statement_tracing_levels() {
+        assert_eq!(parse_level("info").unwrap(), LevelFilter::INFO);
+        assert_eq!(parse_level("OFF").unwrap(), LevelFilter::OFF);
+        assert!(parse_level("loud").is_err());
+
+        let config = PgConfig {
+            log_statements: Some("off".to_string()),
+            slow_statement_ms: Some(0),
+            ..PgConfig::default()
+        };
+        assert!(connect_options(&config).is_ok());
+        let invalid = PgConfig {
+            log_statements: Some("loud".to_string()),
+            ..PgConfig::default()
+        };
+        assert!(connect_options(&invalid).is_err());
+        assert!(crate::statement_tracing::configure(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_connect_options_tls() {
+        let config = PgConfig {
+            sslmode: Some("verify-ca".to_string()),
+            sslrootcert: Some("/certs/ca.pem".to_string()),
+            ..PgConfig::new("db.example.com", 6543, "user", "pass", Some("mydb"))
+        };
+        let options = connect_options(&config).unwrap();
+        assert_eq!(options.get_host(), "db.example.com");
+        assert_eq!(options.get_port(), 6543);
+        assert_eq!(options.get_database(), Some("mydb"));
+        assert!(matches!(options.get_ssl_mode(), PgSslMode::VerifyCa));
+
+        let invalid = PgConfig {
+            sslmode: Some("sometimes".to_string()),
+            ..PgConfig::default()
+        };
+        assert!(connect_options(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_connect_options_socket_dir() {
+        let config = PgConfig {
+            socket_dir: Some("/var/run/postgresql".to_string()),
+            ..PgConfig::default()
+        };
+        let options = connect_options(&config).unwrap();
+        assert_eq!(
+            options.get_socket(),
+            Some(&std::path::PathBuf::from("/var/run/postgresql"))
+        );
+    }
+
+    #[test]
+    fn test_wait_histogram_buckets() {
+        let recorder = WaitRecorder::default();
+        recorder.record(Duration::from_micros(500));
+        recorder.record(Duration::from_millis(20));
+        recorder.record(Duration::from_secs(10));
+
+        let histogram = recorder.histogram();
+        assert_eq!(histogram.count, 3);
+        assert_eq!(histogram.buckets[0], (0.001, 1));
+        // 0.025s bucket and above include the 20ms wait
+        assert_eq!(histogram.buckets[3], (0.025, 2));
+        assert_eq!(histogram.buckets.last(), Some(&(5.0, 2)));
+        assert!((histogram.sum_secs - 10.0205).abs() < 1e-9);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn test_register_prometheus_metrics() {
+        let pool = create_pool_lazy(&PgConfig { max_connections: Some(7), ..PgConfig::default() })
+            .unwrap();
+        let monitor = PoolMonitor::new(pool);
+        let registry = prometheus::Registry::new();
+        monitor.register(&registry, "primary").unwrap();
+        // A clone shares the histogram, so cannot be registered again
+        assert!(monitor.clone().register(&prometheus::Registry::new(), "other").is_err());
+
+        monitor.waits.record(Duration::from_millis(3));
+        let families = registry.gather();
+        let names: Vec<&str> = families.iter().map(|f| f.get_name()).collect();
+        assert_eq!(
+            names,
+            ["pg_pool_acquire_wait_seconds", "pg_pool_connections", "pg_pool_max_connections"]
+        );
+        let max = &families[2].get_metric()[0];
+        assert_eq!(max.get_gauge().get_value(), 7.0);
+        assert_eq!(max.get_label()[0].get_value(), "primary");
+        assert_eq!(families[0].get_metric()[0].get_histogram().get_sample_count(), 1);
+    }
+
     #[test]
     fn test_create_pool_requires_running_db() {
         // This test documents that create_pool requires a running database.
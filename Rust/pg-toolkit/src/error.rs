@@ -0,0 +1,211 @@
+//! Typed classification of toolkit errors.
+//!
+//! The toolkit's functions return `Result<T>`, whose `Error` sorts a failure
+//! by cause, so callers can decide to retry, create the missing database or
+//! give up without matching on messages:
+//!
+//! ```rust,no_run
+//! use pg_toolkit::{Error, PgConfig, admin, create_pool};
+//!
+//! # async fn run(config: PgConfig) -> pg_toolkit::Result<()> {
+//! let pool = match create_pool(&config).await {
+//!     Err(Error::MissingDatabase(_)) => {
+//!         admin::create_database(&config, config.database.as_deref().unwrap()).await?;
+//!         create_pool(&config).await?
+//!     }
+//!     other => other?,
+//! };
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Every variant wraps the original error, with context describing what
+//! failed, and converts into `anyhow::Error` with `?`. `Error::from`
+//! classifies an `anyhow::Error` or a bare `sqlx::Error` the same way, e.g.
+//! one returned from a `transaction::with_retry` closure.
+
+/// SQLSTATE codes behind each classification.
+mod sqlstate {
+    pub const INVALID_AUTHORIZATION: &str = "28000";
+    pub const INVALID_PASSWORD: &str = "28P01";
+    pub const INVALID_CATALOG_NAME: &str = "3D000";
+    pub const INSUFFICIENT_PRIVILEGE: &str = "42501";
+    /// Class 08: connection exceptions.
+    pub const CONNECTION_CLASS: &str = "08";
+    pub const ADMIN_SHUTDOWN: &str = "57P01";
+    pub const CRASH_SHUTDOWN: &str = "57P02";
+    pub const CANNOT_CONNECT_NOW: &str = "57P03";
+}
+
+/// `Result` with a classified toolkit `Error`.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A toolkit error, classified by cause.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The server could not be reached, or the connection was lost: network
+    /// and TLS errors, pool timeouts, server shutdown. Often transient.
+    #[error(transparent)]
+    Connection(anyhow::Error),
+    /// The server rejected the role or password.
+    #[error(transparent)]
+    Authentication(anyhow::Error),
+    /// The database named in the config does not exist.
+    #[error(transparent)]
+    MissingDatabase(anyhow::Error),
+    /// The role lacks a privilege the operation needs.
+    #[error(transparent)]
+    Permission(anyhow::Error),
+    /// Any other error reported while running a statement.
+    #[error(transparent)]
+    Query(anyhow::Error),
+    /// Not a database error, e.g. invalid configuration or a failed
+    /// `pg_dump`.
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl Error {
+    /// The first `sqlx::Error` in the error's chain, if any.
+    pub fn sqlx_error(&self) -> Option<&sqlx::Error> {
+        self.inner().chain().find_map(|cause| {
+            cause
+                .downcast_ref::<sqlx::Error>()
+                .or_else(|| cause.downcast_ref::<Error>().and_then(Error::sqlx_error))
+        })
+    }
+
+    /// SQLSTATE code of the underlying database error, if any.
+    pub fn code(&self) -> Option<String> {
+        self.sqlx_error()?.as_database_error()?.code().map(|code| code.into_owned())
+    }
+
+    pub fn inner(&self) -> &anyhow::Error {
+        match self {
+            Error::Connection(e)
+            | Error::Authentication(e)
+            | Error::MissingDatabase(e)
+            | Error::Permission(e)
+            | Error::Query(e)
+            | Error::Other(e) => e,
+        }
+    }
+
+    /// Constructor of `self`'s variant, to classify another error the same.
+    fn variant(&self) -> fn(anyhow::Error) -> Error {
+        match self {
+            Error::Connection(_) => Error::Connection,
+            Error::Authentication(_) => Error::Authentication,
+            Error::MissingDatabase(_) => Error::MissingDatabase,
+            Error::Permission(_) => Error::Permission,
+            Error::Query(_) => Error::Query,
+            Error::Other(_) => Error::Other,
+        }
+    }
+
+    pub fn into_inner(self) -> anyhow::Error {
+        match self {
+            Error::Connection(e)
+            | Error::Authentication(e)
+            | Error::MissingDatabase(e)
+            | Error::Permission(e)
+            | Error::Query(e)
+            | Error::Other(e) => e,
+        }
+    }
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(error: anyhow::Error) -> Self {
+        // Already classified, e.g. an `Error` passed through `?` as anyhow,
+        // perhaps with context added
+        let variant = error
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<Error>())
+            .map(Error::variant);
+        if let Some(variant) = variant {
+            return variant(error);
+        }
+        let Some(sqlx_error) = error.chain().find_map(|cause| cause.downcast_ref::<sqlx::Error>())
+        else {
+            return Error::Other(error);
+        };
+        let code = sqlx_error
+            .as_database_error()
+            .and_then(|e| e.code())
+            .map(|code| code.into_owned());
+
+        match (sqlx_error, code.as_deref()) {
+            (_, Some(sqlstate::INVALID_AUTHORIZATION | sqlstate::INVALID_PASSWORD)) => {
+                Error::Authentication(error)
+            }
+            (_, Some(sqlstate::INVALID_CATALOG_NAME)) => Error::MissingDatabase(error),
+            (_, Some(sqlstate::INSUFFICIENT_PRIVILEGE)) => Error::Permission(error),
+            (_, Some(
+                sqlstate::ADMIN_SHUTDOWN | sqlstate::CRASH_SHUTDOWN | sqlstate::CANNOT_CONNECT_NOW,
+            )) => Error::Connection(error),
+            (_, Some(code)) if code.starts_with(sqlstate::CONNECTION_CLASS) => {
+                Error::Connection(error)
+            }
+            (
+                sqlx::Error::Io(_)
+                | sqlx::Error::Tls(_)
+                | sqlx::Error::Protocol(_)
+                | sqlx::Error::PoolTimedOut
+                | sqlx::Error::PoolClosed
+                | sqlx::Error::WorkerCrashed,
+                _,
+            ) => Error::Connection(error),
+            (sqlx::Error::Configuration(_), _) => Error::Other(error),
+            _ => Error::Query(error),
+        }
+    }
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(error: sqlx::Error) -> Self {
+        anyhow::Error::new(error).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_classifies_by_cause() {
+        assert!(matches!(Error::from(sqlx::Error::PoolTimedOut), Error::Connection(_)));
+        assert!(matches!(Error::from(sqlx::Error::RowNotFound), Error::Query(_)));
+        assert!(matches!(
+            Error::from(sqlx::Error::Configuration("bad".into())),
+            Error::Other(_)
+        ));
+        assert!(matches!(Error::from(anyhow::anyhow!("pg_dump failed")), Error::Other(_)));
+    }
+
+    #[test]
+    fn test_keeps_context() {
+        let error: anyhow::Error = Err::<(), _>(sqlx::Error::PoolClosed)
+            .context("Failed to list tables")
+            .unwrap_err();
+        let error = Error::from(error);
+        assert!(matches!(error, Error::Connection(_)));
+        assert_eq!(error.to_string(), "Failed to list tables");
+        assert!(matches!(error.sqlx_error(), Some(sqlx::Error::PoolClosed)));
+        assert_eq!(error.code(), None);
+
+        // Context added over a classified error keeps its classification
+        let wrapped = Err::<(), _>(Error::from(sqlx::Error::PoolClosed))
+            .context("Failed to connect to system database")
+            .unwrap_err();
+        let wrapped = Error::from(wrapped);
+        assert!(matches!(wrapped, Error::Connection(_)));
+        assert_eq!(wrapped.to_string(), "Failed to connect to system database");
+        assert!(matches!(wrapped.sqlx_error(), Some(sqlx::Error::PoolClosed)));
+
+        // Converts back with `?`
+        let back: anyhow::Error = error.into();
+        assert_eq!(format!("{:#}", back), format!("Failed to list tables: {}", sqlx::Error::PoolClosed));
+    }
+}
@@ -0,0 +1,135 @@
+//! Export query results as CSV or JSON, streamed to a writer.
+//!
+//! Rows are written as they arrive from the server, so extracts of any size
+//! run in flat memory. The query is embedded as a subquery, so it must be a
+//! single statement without bind parameters (a trailing `;` is allowed).
+
+use anyhow::{Context, bail};
+use futures_util::TryStreamExt;
+use sqlx::PgPool;
+use sqlx::postgres::PgPoolCopyExt;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::error::Result;
+use crate::statement_tracing::{self, traced};
+
+/// Write the rows of `sql` to `writer` as CSV with a header row, using
+/// `COPY ... TO STDOUT`. NULL is an unquoted empty field. Returns the number
+/// of rows written.
+///
+/// # Example
+/// ```rust,no_run
+/// # async fn example(pool: &sqlx::PgPool) -> anyhow::Result<()> {
+/// let mut file = tokio::fs::File::create("users.csv").await?;
+/// pg_toolkit::export::query_to_csv(pool, "SELECT id, email FROM users", &mut file).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[tracing::instrument(level = "debug", skip(pool, writer), fields(rows_affected))]
+pub async fn query_to_csv<W>(pool: &PgPool, sql: &str, writer: &mut W) -> Result<u64>
+where
+    W: AsyncWrite + Unpin,
+{
+    traced(async {
+        let statement = format!("COPY ({}) TO STDOUT WITH (FORMAT csv, HEADER)", subquery(sql)?);
+        let mut stream = pool
+            .copy_out_raw(&statement)
+            .await
+            .context("Failed to start CSV export")?;
+
+        let mut lines = CsvLineCounter::default();
+        while let Some(chunk) = stream.try_next().await.context("CSV export failed")? {
+            lines.feed(&chunk);
+            writer.write_all(&chunk).await.context("Failed to write CSV export")?;
+        }
+        writer.flush().await.context("Failed to write CSV export")?;
+
+        // The header is one line
+        let rows = lines.count.saturating_sub(1);
+        statement_tracing::rows_affected(rows);
+        tracing::info!("Exported {} rows as CSV", rows);
+        Ok(rows)
+    })
+    .await
+}
+
+/// Write the rows of `sql` to `writer` as a JSON array of objects keyed by
+/// column name, one row per line. Values use Postgres' JSON encoding
+/// (`row_to_json`), so timestamps are ISO 8601 strings and `json` columns
+/// are nested. Returns the number of rows written.
+#[tracing::instrument(level = "debug", skip(pool, writer), fields(rows_affected))]
+pub async fn query_to_json<W>(pool: &PgPool, sql: &str, writer: &mut W) -> Result<u64>
+where
+    W: AsyncWrite + Unpin,
+{
+    traced(async {
+        let statement = format!("SELECT row_to_json(q)::text FROM ({}) q", subquery(sql)?);
+        let mut rows = sqlx::query_scalar::<_, String>(&statement).fetch(pool);
+
+        writer.write_all(b"[").await.context("Failed to write JSON export")?;
+        let mut count = 0u64;
+        while let Some(row) = rows.try_next().await.context("JSON export failed")? {
+            let separator: &[u8] = if count == 0 { b"\n" } else { b",\n" };
+            writer.write_all(separator).await.context("Failed to write JSON export")?;
+            writer.write_all(row.as_bytes()).await.context("Failed to write JSON export")?;
+            count += 1;
+        }
+        let end: &[u8] = if count == 0 { b"]\n" } else { b"\n]\n" };
+        writer.write_all(end).await.context("Failed to write JSON export")?;
+        writer.flush().await.context("Failed to write JSON export")?;
+
+        statement_tracing::rows_affected(count);
+        tracing::info!("Exported {} rows as JSON", count);
+        Ok(count)
+    })
+    .await
+}
+
+/// The query with surrounding whitespace and any trailing `;` removed, so it
+/// can be wrapped in parentheses.
+fn subquery(sql: &str) -> anyhow::Result<&str> {
+    let sql = sql.trim().trim_end_matches(';').trim_end();
+    if sql.is_empty() {
+        bail!("Export query is empty");
+    }
+    Ok(sql)
+}
+
+/// Counts CSV records across chunks: newlines outside quoted fields.
+#[derive(Default)]
+struct CsvLineCounter {
+    in_quotes: bool,
+    count: u64,
+}
+
+impl CsvLineCounter {
+    fn feed(&mut self, chunk: &[u8]) {
+        for &byte in chunk {
+            match byte {
+                // An escaped quote ("") toggles twice
+                b'"' => self.in_quotes = !self.in_quotes,
+                b'\n' if !self.in_quotes => self.count += 1,
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subquery() {
+        assert_eq!(subquery("  SELECT 1;\n").unwrap(), "SELECT 1");
+        assert!(subquery(" ; ").is_err());
+    }
+
+    #[test]
+    fn test_csv_line_counter() {
+        let mut counter = CsvLineCounter::default();
+        counter.feed(b"id,note\n1,\"two\n");
+        counter.feed(b"lines \"\"quoted\"\"\"\n2,\n");
+        assert_eq!(counter.count, 3);
+    }
+}
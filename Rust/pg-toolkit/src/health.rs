@@ -0,0 +1,106 @@
+//! One-call database health check, e.g. to back a service's `/healthz`.
+
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use std::time::{Duration, Instant};
+
+use crate::statement_tracing::traced;
+
+/// Result of `check`. When the server cannot be reached, `connected` is
+/// false, `error` says why and the measurements are unset.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct HealthReport {
+    pub connected: bool,
+    /// Round trip of a trivial query on an already-acquired connection.
+    pub latency: Option<Duration>,
+    /// Whether the server is a standby (in recovery).
+    pub in_recovery: bool,
+    /// On a standby, time since the last replayed transaction committed on
+    /// the primary. This grows while the primary is idle, too.
+    pub replication_lag: Option<Duration>,
+    /// Client connections open on the server, across all databases.
+    pub connections: Option<i64>,
+    pub max_connections: Option<i64>,
+    /// `connections / max_connections`, from 0.0 to 1.0.
+    pub saturation: Option<f64>,
+    /// Age of the oldest open transaction in another session.
+    pub longest_transaction: Option<Duration>,
+    pub error: Option<String>,
+}
+
+impl HealthReport {
+    /// Reachable, and every measurement was taken.
+    pub fn is_healthy(&self) -> bool {
+        self.connected && self.error.is_none()
+    }
+}
+
+type HealthRow = (bool, Option<f64>, i64, i64, Option<f64>);
+
+/// Measure connectivity, latency, replication lag, connection saturation and
+/// the longest running transaction. Never fails: problems are reported in
+/// the returned `HealthReport`.
+///
+/// # Example
+/// ```rust,no_run
+/// # async fn example(pool: &sqlx::PgPool) {
+/// let report = pg_toolkit::health::check(pool).await;
+/// if !report.is_healthy() {
+///     eprintln!("database unhealthy: {:?}", report.error);
+/// }
+/// # }
+/// ```
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn check(pool: &PgPool) -> HealthReport {
+    traced(async {
+        let mut conn = match pool.acquire().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                return HealthReport {
+                    error: Some(format!("Failed to connect: {}", e)),
+                    ..HealthReport::default()
+                };
+            }
+        };
+        let mut report = HealthReport { connected: true, ..HealthReport::default() };
+
+        let started = Instant::now();
+        if let Err(e) = sqlx::query("SELECT 1").execute(&mut *conn).await {
+            report.connected = false;
+            report.error = Some(format!("Failed to run query: {}", e));
+            return report;
+        }
+        report.latency = Some(started.elapsed());
+
+        match measure(&mut conn).await {
+            Ok((in_recovery, lag, connections, max_connections, longest)) => {
+                report.in_recovery = in_recovery;
+                report.replication_lag = lag.map(Duration::from_secs_f64);
+                report.connections = Some(connections);
+                report.max_connections = Some(max_connections);
+                report.saturation =
+                    (max_connections > 0).then(|| connections as f64 / max_connections as f64);
+                report.longest_transaction = longest.map(Duration::from_secs_f64);
+            }
+            Err(e) => report.error = Some(format!("Failed to read server statistics: {}", e)),
+        }
+        report
+    })
+    .await
+}
+
+async fn measure(conn: &mut PgConnection) -> Result<HealthRow, sqlx::Error> {
+    sqlx::query_as::<_, HealthRow>(
+        "SELECT pg_is_in_recovery(), \
+                CASE WHEN pg_is_in_recovery() THEN \
+                    greatest(extract(epoch FROM now() - pg_last_xact_replay_timestamp()), 0)::float8 \
+                END, \
+                (SELECT count(*) FROM pg_stat_activity WHERE backend_type = 'client backend'), \
+                current_setting('max_connections')::int8, \
+                (SELECT greatest(extract(epoch FROM max(clock_timestamp() - xact_start)), 0)::float8 \
+                 FROM pg_stat_activity \
+                 WHERE backend_type = 'client backend' AND pid <> pg_backend_pid())",
+    )
+    .fetch_one(conn)
+    .await
+}
@@ -0,0 +1,89 @@
+//! Quoting for SQL identifiers and literals that have to be formatted into
+//! statements (DDL and COPY cannot take them as bind parameters).
+
+use anyhow::anyhow;
+
+use crate::error::Result;
+
+/// Longest identifier PostgreSQL keeps (`NAMEDATALEN - 1`); longer names are
+/// silently truncated by the server.
+pub const MAX_IDENTIFIER_LEN: usize = 63;
+
+/// Quote a single identifier, doubling embedded double quotes.
+pub fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Quote a possibly schema-qualified name (`schema.table`) part by part.
+pub fn quote_qualified(name: &str) -> String {
+    name.split('.').map(quote_identifier).collect::<Vec<_>>().join(".")
+}
+
+/// Check that a possibly schema-qualified name (`table` or `schema.table`)
+/// can be quoted faithfully: at most one dot, and every part non-empty, free
+/// of NUL bytes and no longer than `MAX_IDENTIFIER_LEN` bytes.
+pub fn validate_qualified(name: &str) -> Result<()> {
+    let parts: Vec<&str> = name.split('.').collect();
+    if parts.len() > 2 {
+        return Err(anyhow!("Invalid name '{}': expected 'name' or 'schema.name'", name).into());
+    }
+    for part in parts {
+        if part.is_empty() {
+            return Err(anyhow!("Invalid name '{}': empty identifier", name).into());
+        }
+        if part.contains('\0') {
+            return Err(anyhow!("Invalid name '{}': identifiers cannot contain NUL", name).into());
+        }
+        if part.len() > MAX_IDENTIFIER_LEN {
+            return Err(anyhow!(
+                "Invalid name '{}': '{}' is longer than {} bytes",
+                name, part, MAX_IDENTIFIER_LEN
+            ).into());
+        }
+    }
+    Ok(())
+}
+
+/// Quote a string literal, as PostgreSQL's `quote_literal` does: single
+/// quotes are doubled, and a value containing backslashes becomes an `E''`
+/// string with them doubled, so it reads the same whatever
+/// `standard_conforming_strings` is set to.
+pub fn quote_literal(value: &str) -> String {
+    let quoted = value.replace('\'', "''");
+    if value.contains('\\') {
+        format!("E'{}'", quoted.replace('\\', "\\\\"))
+    } else {
+        format!("'{}'", quoted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_identifier() {
+        assert_eq!(quote_identifier("users"), "\"users\"");
+        assert_eq!(quote_identifier("Odd \"name\""), "\"Odd \"\"name\"\"\"");
+        assert_eq!(quote_qualified("kb.chunks"), "\"kb\".\"chunks\"");
+    }
+
+    #[test]
+    fn test_validate_qualified() {
+        assert!(validate_qualified("users").is_ok());
+        assert!(validate_qualified("kb.Odd \"name\"").is_ok());
+        assert!(validate_qualified("").is_err());
+        assert!(validate_qualified("kb.").is_err());
+        assert!(validate_qualified("a.b.c").is_err());
+        assert!(validate_qualified("bad\0name").is_err());
+        assert!(validate_qualified(&"x".repeat(64)).is_err());
+        assert!(validate_qualified(&"x".repeat(63)).is_ok());
+    }
+
+    #[test]
+    fn test_quote_literal() {
+        assert_eq!(quote_literal("plain"), "'plain'");
+        assert_eq!(quote_literal("it's"), "'it''s'");
+        assert_eq!(quote_literal("a\\b'c"), "E'a\\\\b''c'");
+    }
+}
@@ -3,9 +3,16 @@
 //! Query-only operations for inspecting an existing database: listing tables,
 //! checking existence, column info, etc. None of these mutate the schema.
 
-use anyhow::{Context, Result};
+use anyhow::{Context, anyhow};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
+use std::time::Duration;
+
+use crate::error::Result;
+use crate::identifier::{quote_identifier, quote_qualified, validate_qualified};
+use crate::schema_diff::{self, SchemaSnapshot};
+use crate::statement_tracing::traced;
 
 /// Metadata for a single user table, mirroring the columns exposed by
 /// `pg_tables` (minus system schemas).
@@ -25,81 +32,1384 @@ pub struct TableInfo {
     pub row_security: bool,
 }
 
+/// Metadata for a user view, from `pg_views`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ViewInfo {
+    pub schema: String,
+    pub name: String,
+    pub owner: String,
+    /// The view's `SELECT` statement, as reconstructed by PostgreSQL.
+    pub definition: String,
+}
+
+/// Metadata for a materialized view, from `pg_matviews`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MaterializedViewInfo {
+    pub schema: String,
+    pub name: String,
+    pub owner: String,
+    pub definition: String,
+    pub has_indexes: bool,
+    /// False if created `WITH NO DATA` and not yet refreshed.
+    pub is_populated: bool,
+}
+
+/// A partitioned table and its partition key.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PartitionedTable {
+    pub schema: String,
+    pub name: String,
+    /// Key definition, e.g. `RANGE (created_at)`.
+    pub key: String,
+}
+
+/// One partition of a partitioned table.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PartitionInfo {
+    pub schema: String,
+    pub name: String,
+    /// Bound clause, e.g. `FOR VALUES FROM ('2024-01-01') TO ('2024-02-01')`
+    /// or `DEFAULT`.
+    pub bound: String,
+    /// The partition is itself partitioned.
+    pub is_partitioned: bool,
+}
+
+/// On-disk size of a user table, in bytes.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TableSize {
+    pub schema: String,
+    pub name: String,
+    /// Everything: heap, indexes, TOAST and free space / visibility maps.
+    pub total_bytes: i64,
+    /// Main heap only.
+    pub table_bytes: i64,
+    /// All indexes on the table.
+    pub index_bytes: i64,
+    /// TOAST table and its index (out-of-line storage for large values).
+    pub toast_bytes: i64,
+}
+
+/// Metadata for a single table column, from `information_schema.columns`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ColumnInfo {
+    pub name: String,
+    /// 1-based position in the table.
+    pub ordinal_position: i32,
+    /// SQL type name, e.g. `"integer"`, `"character varying"` or, for
+    /// extension and domain types, `"USER-DEFINED"`.
+    pub data_type: String,
+    /// Underlying type name, e.g. `"int4"`, `"varchar"` or `"vector"`.
+    pub udt_name: String,
+    pub is_nullable: bool,
+    /// Default expression, e.g. `"nextval('users_id_seq'::regclass)"`.
+    pub default: Option<String>,
+    /// Declared length for character types, e.g. 255 for `VARCHAR(255)`.
+    pub max_length: Option<i32>,
+    /// `GENERATED ... AS IDENTITY` column.
+    pub is_identity: bool,
+    /// `GENERATED ALWAYS AS (...) STORED` column.
+    pub is_generated: bool,
+}
+
+/// An index on a user table.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IndexInfo {
+    pub name: String,
+    /// Full `CREATE INDEX` statement, as from `pg_get_indexdef`.
+    pub definition: String,
+    /// Access method, e.g. `"btree"`, `"gin"` or `"hnsw"`.
+    pub method: String,
+    pub is_unique: bool,
+    pub is_primary: bool,
+    /// Key columns in index order; expression keys appear as their expression.
+    pub columns: Vec<String>,
+    /// On-disk size in bytes.
+    pub size_bytes: i64,
+}
+
+/// Usage statistics for one index on a user table, counted since the
+/// statistics were last reset.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IndexUsage {
+    pub schema: String,
+    pub table: String,
+    pub index: String,
+    /// Index scans started.
+    pub scans: i64,
+    /// Index entries returned by scans.
+    pub tuples_read: i64,
+    /// Live table rows fetched by simple index scans.
+    pub tuples_fetched: i64,
+    /// Index blocks read from disk, and found in shared buffers.
+    pub blocks_read: i64,
+    pub blocks_hit: i64,
+    /// On-disk size in bytes.
+    pub size_bytes: i64,
+    /// Backs a unique or primary key constraint, so is needed even if never
+    /// scanned.
+    pub is_unique: bool,
+}
+
+/// A table's primary key constraint.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PrimaryKey {
+    /// Constraint name, e.g. `"users_pkey"`.
+    pub name: String,
+    /// Key columns in constraint order.
+    pub columns: Vec<String>,
+}
+
+/// A foreign key constraint from a table to the table it references.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ForeignKey {
+    pub name: String,
+    /// Referencing columns, paired positionally with `referenced_columns`.
+    pub columns: Vec<String>,
+    pub referenced_schema: String,
+    pub referenced_table: String,
+    pub referenced_columns: Vec<String>,
+    /// Referential action, as in SQL: `"NO ACTION"`, `"RESTRICT"`,
+    /// `"CASCADE"`, `"SET NULL"` or `"SET DEFAULT"`.
+    pub on_delete: String,
+    pub on_update: String,
+}
+
+/// A row-level security policy, from `pg_policies`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PolicyInfo {
+    pub schema: String,
+    pub table: String,
+    pub name: String,
+    /// Permissive policies are combined by OR, restrictive ones by AND.
+    pub permissive: bool,
+    /// Roles the policy applies to; `["public"]` for every role.
+    pub roles: Vec<String>,
+    /// `"ALL"`, `"SELECT"`, `"INSERT"`, `"UPDATE"` or `"DELETE"`.
+    pub command: String,
+    /// `USING` expression, as deparsed by the server.
+    pub using: Option<String>,
+    /// `WITH CHECK` expression, as deparsed by the server.
+    pub with_check: Option<String>,
+}
+
+/// A logical replication publication in the current database.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PublicationInfo {
+    pub name: String,
+    pub owner: String,
+    /// `FOR ALL TABLES`, including tables created later.
+    pub all_tables: bool,
+    /// Operations replicated.
+    pub inserts: bool,
+    pub updates: bool,
+    pub deletes: bool,
+    pub truncates: bool,
+    /// Published tables as `schema.name`, sorted; for `all_tables`, every
+    /// table currently included.
+    pub tables: Vec<String>,
+}
+
+/// A logical replication subscription in the current database.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SubscriptionInfo {
+    pub name: String,
+    pub owner: String,
+    pub enabled: bool,
+    /// Publications subscribed to on the publisher.
+    pub publications: Vec<String>,
+    /// Replication slot on the publisher; `None` once dissociated.
+    pub slot_name: Option<String>,
+    pub tables: Vec<SubscriptionTable>,
+    /// Process ID of the apply worker; `None` while it is not running, e.g.
+    /// disabled or unable to connect.
+    pub worker_pid: Option<i32>,
+    /// Last WAL location received from the publisher.
+    pub received_lsn: Option<String>,
+    /// When the last message from the publisher arrived.
+    pub last_message_at: Option<DateTime<Utc>>,
+}
+
+/// A table a subscription replicates into and its synchronization state.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SubscriptionTable {
+    /// `schema.name`.
+    pub table: String,
+    /// `"init"`, `"data copy"`, `"finished"`, `"synchronized"` or `"ready"`
+    /// (initial copy done, following the stream).
+    pub state: String,
+}
+
+/// Content fingerprint of a table, from `table_checksum`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TableChecksum {
+    pub rows: i64,
+    /// Hex MD5 over the sorted MD5s of the rows' text form.
+    pub checksum: String,
+}
+
+/// Version of the connected server.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ServerVersion {
+    /// e.g. 16 for PostgreSQL 16.2.
+    pub major: u32,
+    /// e.g. 2 for PostgreSQL 16.2.
+    pub minor: u32,
+    /// Full version string as reported by `server_version`, e.g.
+    /// `"16.2 (Debian 16.2-1.pgdg120+2)"`.
+    pub full: String,
+}
+
+/// The server session behind a connection, from `session_info`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CurrentSession {
+    /// Backend process ID, as in `pg_stat_activity.pid` and the argument of
+    /// `pg_cancel_backend`.
+    pub pid: i32,
+    /// Role privileges are checked against; differs from `session_user`
+    /// after `SET ROLE`.
+    pub current_user: String,
+    /// Role that logged in.
+    pub session_user: String,
+    pub database: String,
+    /// Client address as seen by the server; `None` over a Unix socket.
+    pub client_addr: Option<String>,
+    pub client_port: Option<i32>,
+    pub application_name: String,
+    /// When the connection was opened. `None` when `current_user` may not see
+    /// the session's statistics, e.g. after `SET ROLE` to a less privileged
+    /// role.
+    pub backend_start: Option<DateTime<Utc>>,
+    /// `transaction_isolation`, e.g. `"read committed"`.
+    pub isolation_level: String,
+    /// `transaction_read_only`, e.g. on a standby or under
+    /// `default_transaction_read_only`.
+    pub read_only: bool,
+}
+
+/// A setting's current value, typed by its `pg_settings.vartype`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum SettingValue {
+    Bool(bool),
+    Integer(i64),
+    Real(f64),
+    String(String),
+    /// One of the setting's `enumvals`.
+    Enum(String),
+}
+
+/// A server configuration parameter, from `pg_settings`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Setting {
+    pub name: String,
+    pub value: SettingValue,
+    /// Unit of a numeric value, e.g. `"8kB"` for `shared_buffers` or `"ms"`
+    /// for `statement_timeout`.
+    pub unit: Option<String>,
+    pub category: String,
+    pub description: String,
+    /// Where the current value came from, e.g. `"default"` or
+    /// `"configuration file"`.
+    pub source: String,
+    /// Changed in the configuration file but awaiting a server restart.
+    pub pending_restart: bool,
+}
+
+impl Setting {
+    /// Value in bytes, for integer settings with a memory unit.
+    pub fn as_bytes(&self) -> Option<i64> {
+        let SettingValue::Integer(value) = self.value else {
+            return None;
+        };
+        let (multiplier, base) = split_unit(self.unit.as_deref()?);
+        let base: i64 = match base {
+            "B" => 1,
+            "kB" => 1 << 10,
+            "MB" => 1 << 20,
+            "GB" => 1 << 30,
+            "TB" => 1 << 40,
+            _ => return None,
+        };
+        value.checked_mul(multiplier)?.checked_mul(base)
+    }
+
+    /// Value as a duration, for numeric settings with a time unit. Negative
+    /// values (e.g. `-1` meaning "disabled") give `None`.
+    pub fn as_duration(&self) -> Option<Duration> {
+        let value = match self.value {
+            SettingValue::Integer(value) => value as f64,
+            SettingValue::Real(value) => value,
+            _ => return None,
+        };
+        let (multiplier, base) = split_unit(self.unit.as_deref()?);
+        let seconds = match base {
+            "us" => 1e-6,
+            "ms" => 1e-3,
+            "s" => 1.0,
+            "min" => 60.0,
+            "h" => 3600.0,
+            "d" => 86400.0,
+            _ => return None,
+        };
+        Duration::try_from_secs_f64(value * multiplier as f64 * seconds).ok()
+    }
+}
+
+/// Split a `pg_settings.unit` such as `"8kB"` into `(8, "kB")`.
+fn split_unit(unit: &str) -> (i64, &str) {
+    let digits = unit.len() - unit.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let multiplier = unit[..digits].parse().unwrap_or(1);
+    (multiplier, &unit[digits..])
+}
+
 /// Return true if a table with the given name exists in the public schema.
+#[tracing::instrument(level = "debug", skip(pool))]
 pub async fn table_exists(pool: &PgPool, table_name: &str) -> Result<bool> {
-    let exists: Option<i32> = sqlx::query_scalar(
-        "SELECT 1 FROM information_schema.tables \
-         WHERE table_schema = 'public' AND table_name = $1"
-    )
-    .bind(table_name)
-    .fetch_optional(pool)
+    traced(async {
+        table_exists_in(pool, "public", table_name).await
+    })
     .await
-    .with_context(|| format!("Failed to check if table '{}' exists", table_name))?;
+}
+
+/// Return true if a table with the given name exists in the given schema.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn table_exists_in(pool: &PgPool, schema: &str, table_name: &str) -> Result<bool> {
+    traced(async {
+        let exists: Option<i32> = sqlx::query_scalar(
+            "SELECT 1 FROM information_schema.tables \
+             WHERE table_schema = $1 AND table_name = $2"
+        )
+        .bind(schema)
+        .bind(table_name)
+        .fetch_optional(pool)
+        .await
+        .with_context(|| format!("Failed to check if table '{}.{}' exists", schema, table_name))?;
 
-    Ok(exists.is_some())
+        Ok(exists.is_some())
+    })
+    .await
+}
+
+/// List user schemas, excluding system and temporary schemas, ordered by name.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn list_schemas(pool: &PgPool) -> Result<Vec<String>> {
+    traced(async {
+        let names: Vec<String> = sqlx::query_scalar(
+            "SELECT nspname::text FROM pg_namespace \
+             WHERE nspname NOT IN ('information_schema', 'pg_catalog') \
+               AND nspname NOT LIKE 'pg_toast%' \
+               AND nspname NOT LIKE 'pg_temp_%' \
+             ORDER BY nspname",
+        )
+        .fetch_all(pool)
+        .await
+        .context("Failed to list schemas")?;
+
+        Ok(names)
+    })
+    .await
 }
 
 /// List all user tables across all non-system schemas, with full metadata.
 ///
 /// Excludes `information_schema` and `pg_catalog`. Results are ordered by
 /// schema then table name.
+#[tracing::instrument(level = "debug", skip(pool))]
 pub async fn list_tables(pool: &PgPool) -> Result<Vec<TableInfo>> {
-    let rows = sqlx::query_as::<_, (String, String, String, Option<String>, bool, bool, bool, bool)>(
-        "SELECT schemaname, tablename, tableowner, tablespace, \
-                hasindexes, hasrules, hastriggers, rowsecurity \
-         FROM pg_tables \
-         WHERE schemaname NOT IN ('information_schema', 'pg_catalog') \
-         ORDER BY schemaname, tablename",
-    )
-    .fetch_all(pool)
+    traced(async {
+        let rows = sqlx::query_as::<_, (String, String, String, Option<String>, bool, bool, bool, bool)>(
+            "SELECT schemaname, tablename, tableowner, tablespace, \
+                    hasindexes, hasrules, hastriggers, rowsecurity \
+             FROM pg_tables \
+             WHERE schemaname NOT IN ('information_schema', 'pg_catalog') \
+             ORDER BY schemaname, tablename",
+        )
+        .fetch_all(pool)
+        .await
+        .context("Failed to list tables")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(schema, name, owner, tablespace, has_indexes, has_rules, has_triggers, row_security)| {
+                TableInfo { schema, name, owner, tablespace, has_indexes, has_rules, has_triggers, row_security }
+            })
+            .collect())
+    })
     .await
-    .context("Failed to list tables")?;
+}
 
-    Ok(rows
-        .into_iter()
-        .map(|(schema, name, owner, tablespace, has_indexes, has_rules, has_triggers, row_security)| {
-            TableInfo { schema, name, owner, tablespace, has_indexes, has_rules, has_triggers, row_security }
-        })
-        .collect())
+/// List the tables in one schema, ordered by name.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn list_tables_in_schema(pool: &PgPool, schema: &str) -> Result<Vec<TableInfo>> {
+    traced(async {
+        let rows = sqlx::query_as::<_, (String, String, String, Option<String>, bool, bool, bool, bool)>(
+            "SELECT schemaname, tablename, tableowner, tablespace, \
+                    hasindexes, hasrules, hastriggers, rowsecurity \
+             FROM pg_tables \
+             WHERE schemaname = $1 \
+             ORDER BY tablename",
+        )
+        .bind(schema)
+        .fetch_all(pool)
+        .await
+        .with_context(|| format!("Failed to list tables in schema '{}'", schema))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(schema, name, owner, tablespace, has_indexes, has_rules, has_triggers, row_security)| {
+                TableInfo { schema, name, owner, tablespace, has_indexes, has_rules, has_triggers, row_security }
+            })
+            .collect())
+    })
+    .await
 }
 
 /// List just the table names in non-system schemas.
 ///
 /// Cheaper than `list_tables` when you only need names.
+#[tracing::instrument(level = "debug", skip(pool))]
 pub async fn list_table_names(pool: &PgPool) -> Result<Vec<String>> {
-    let names: Vec<String> = sqlx::query_scalar(
-        "SELECT tablename FROM pg_tables \
-         WHERE schemaname NOT IN ('information_schema', 'pg_catalog') \
-         ORDER BY tablename",
-    )
-    .fetch_all(pool)
+    traced(async {
+        let names: Vec<String> = sqlx::query_scalar(
+            "SELECT tablename FROM pg_tables \
+             WHERE schemaname NOT IN ('information_schema', 'pg_catalog') \
+             ORDER BY tablename",
+        )
+        .fetch_all(pool)
+        .await
+        .context("Failed to list table names")?;
+
+        Ok(names)
+    })
+    .await
+}
+
+/// List all views across non-system schemas, ordered by schema then name.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn list_views(pool: &PgPool) -> Result<Vec<ViewInfo>> {
+    traced(async {
+        let rows = sqlx::query_as::<_, (String, String, String, String)>(
+            "SELECT schemaname, viewname, viewowner, definition \
+             FROM pg_views \
+             WHERE schemaname NOT IN ('information_schema', 'pg_catalog') \
+             ORDER BY schemaname, viewname",
+        )
+        .fetch_all(pool)
+        .await
+        .context("Failed to list views")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(schema, name, owner, definition)| ViewInfo { schema, name, owner, definition })
+            .collect())
+    })
+    .await
+}
+
+/// List all materialized views across non-system schemas, ordered by schema
+/// then name.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn list_materialized_views(pool: &PgPool) -> Result<Vec<MaterializedViewInfo>> {
+    traced(async {
+        let rows = sqlx::query_as::<_, (String, String, String, String, bool, bool)>(
+            "SELECT schemaname, matviewname, matviewowner, definition, hasindexes, ispopulated \
+             FROM pg_matviews \
+             WHERE schemaname NOT IN ('information_schema', 'pg_catalog') \
+             ORDER BY schemaname, matviewname",
+        )
+        .fetch_all(pool)
+        .await
+        .context("Failed to list materialized views")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(schema, name, owner, definition, has_indexes, is_populated)| {
+                MaterializedViewInfo { schema, name, owner, definition, has_indexes, is_populated }
+            })
+            .collect())
+    })
+    .await
+}
+
+/// List partitioned (parent) tables in user schemas.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn list_partitioned_tables(pool: &PgPool) -> Result<Vec<PartitionedTable>> {
+    traced(async {
+        let rows = sqlx::query_as::<_, (String, String, String)>(
+            "SELECT n.nspname::text, c.relname::text, pg_get_partkeydef(c.oid) \
+             FROM pg_partitioned_table p \
+             JOIN pg_class c ON c.oid = p.partrelid \
+             JOIN pg_namespace n ON n.oid = c.relnamespace \
+             WHERE n.nspname NOT IN ('information_schema', 'pg_catalog') \
+             ORDER BY n.nspname, c.relname",
+        )
+        .fetch_all(pool)
+        .await
+        .context("Failed to list partitioned tables")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(schema, name, key)| PartitionedTable { schema, name, key })
+            .collect())
+    })
+    .await
+}
+
+/// List the direct partitions of `parent` (`name` in the public schema or
+/// `schema.name`), ordered by name. Fails if `parent` does not exist.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn list_partitions(pool: &PgPool, parent: &str) -> Result<Vec<PartitionInfo>> {
+    traced(async {
+        let exists: bool = sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL")
+            .bind(quote_qualified(parent))
+            .fetch_one(pool)
+            .await
+            .with_context(|| format!("Failed to look up table '{}'", parent))?;
+        if !exists {
+            return Err(anyhow!("Table '{}' does not exist", parent).into());
+        }
+        let rows = sqlx::query_as::<_, (String, String, String, bool)>(
+            "SELECT n.nspname::text, c.relname::text, pg_get_expr(c.relpartbound, c.oid), \
+                    c.relkind = 'p' \
+             FROM pg_inherits i \
+             JOIN pg_class c ON c.oid = i.inhrelid \
+             JOIN pg_namespace n ON n.oid = c.relnamespace \
+             WHERE i.inhparent = to_regclass($1) AND c.relispartition \
+             ORDER BY n.nspname, c.relname",
+        )
+        .bind(quote_qualified(parent))
+        .fetch_all(pool)
+        .await
+        .with_context(|| format!("Failed to list partitions of '{}'", parent))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(schema, name, bound, is_partitioned)| PartitionInfo {
+                schema,
+                name,
+                bound,
+                is_partitioned,
+            })
+            .collect())
+    })
+    .await
+}
+
+type PolicyRow = (String, String, String, bool, Vec<String>, String, Option<String>, Option<String>);
+
+/// List the row-level security policies on `table` (`name` in the public
+/// schema or `schema.name`), ordered by name. Whether they are enforced is
+/// `TableInfo::row_security`. Fails if `table` does not exist.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn list_policies(pool: &PgPool, table: &str) -> Result<Vec<PolicyInfo>> {
+    traced(async {
+        let exists: bool = sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL")
+            .bind(quote_qualified(table))
+            .fetch_one(pool)
+            .await
+            .with_context(|| format!("Failed to look up table '{}'", table))?;
+        if !exists {
+            return Err(anyhow!("Table '{}' does not exist", table).into());
+        }
+        let rows = sqlx::query_as::<_, PolicyRow>(
+            "SELECT p.schemaname::text, p.tablename::text, p.policyname::text, \
+                    p.permissive = 'PERMISSIVE', p.roles::text[], p.cmd, p.qual, p.with_check \
+             FROM pg_policies p \
+             JOIN pg_namespace n ON n.nspname = p.schemaname \
+             JOIN pg_class c ON c.relnamespace = n.oid AND c.relname = p.tablename \
+             WHERE c.oid = to_regclass($1) \
+             ORDER BY p.policyname",
+        )
+        .bind(quote_qualified(table))
+        .fetch_all(pool)
+        .await
+        .with_context(|| format!("Failed to list policies on '{}'", table))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(schema, table, name, permissive, roles, command, using, with_check)| PolicyInfo {
+                schema,
+                table,
+                name,
+                permissive,
+                roles,
+                command,
+                using,
+                with_check,
+            })
+            .collect())
+    })
     .await
-    .context("Failed to list table names")?;
+}
+
+type PublicationRow = (String, String, bool, bool, bool, bool, bool, Vec<String>);
+
+/// List the publications of the current database with their tables, by name.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn list_publications(pool: &PgPool) -> Result<Vec<PublicationInfo>> {
+    traced(async {
+        let rows = sqlx::query_as::<_, PublicationRow>(
+            "SELECT p.pubname::text, pg_get_userbyid(p.pubowner)::text, p.puballtables, \
+                    p.pubinsert, p.pubupdate, p.pubdelete, p.pubtruncate, \
+                    ARRAY(SELECT format('%s.%s', t.schemaname, t.tablename) \
+                          FROM pg_publication_tables t \
+                          WHERE t.pubname = p.pubname \
+                          ORDER BY 1) \
+             FROM pg_publication p \
+             ORDER BY p.pubname",
+        )
+        .fetch_all(pool)
+        .await
+        .context("Failed to list publications")?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(name, owner, all_tables, inserts, updates, deletes, truncates, tables)| PublicationInfo {
+                    name,
+                    owner,
+                    all_tables,
+                    inserts,
+                    updates,
+                    deletes,
+                    truncates,
+                    tables,
+                },
+            )
+            .collect())
+    })
+    .await
+}
+
+type SubscriptionRow = (
+    String,
+    String,
+    bool,
+    Vec<String>,
+    Option<String>,
+    Vec<String>,
+    Vec<String>,
+    Option<i32>,
+    Option<String>,
+    Option<DateTime<Utc>>,
+);
+
+/// List the subscriptions of the current database with their tables and
+/// apply worker status, by name. The connection string is not read, as it
+/// may hold a password (and only superusers may see it).
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn list_subscriptions(pool: &PgPool) -> Result<Vec<SubscriptionInfo>> {
+    traced(async {
+        let rows = sqlx::query_as::<_, SubscriptionRow>(
+            "SELECT s.subname::text, pg_get_userbyid(s.subowner)::text, s.subenabled, \
+                    s.subpublications, s.subslotname::text, \
+                    ARRAY(SELECT format('%s.%s', n.nspname, c.relname) \
+                          FROM pg_subscription_rel r \
+                          JOIN pg_class c ON c.oid = r.srrelid \
+                          JOIN pg_namespace n ON n.oid = c.relnamespace \
+                          WHERE r.srsubid = s.oid \
+                          ORDER BY 1), \
+                    ARRAY(SELECT CASE r.srsubstate \
+                                     WHEN 'i' THEN 'init' WHEN 'd' THEN 'data copy' \
+                                     WHEN 'f' THEN 'finished' WHEN 's' THEN 'synchronized' \
+                                     WHEN 'r' THEN 'ready' ELSE r.srsubstate::text END \
+                          FROM pg_subscription_rel r \
+                          JOIN pg_class c ON c.oid = r.srrelid \
+                          JOIN pg_namespace n ON n.oid = c.relnamespace \
+                          WHERE r.srsubid = s.oid \
+                          ORDER BY format('%s.%s', n.nspname, c.relname)), \
+                    w.pid, w.received_lsn::text, w.last_msg_receipt_time \
+             FROM pg_subscription s \
+             LEFT JOIN pg_stat_subscription w ON w.subid = s.oid AND w.relid IS NULL \
+             WHERE s.subdbid = (SELECT oid FROM pg_database WHERE datname = current_database()) \
+             ORDER BY s.subname",
+        )
+        .fetch_all(pool)
+        .await
+        .context("Failed to list subscriptions")?;
+
+        Ok(rows.into_iter().map(subscription_from_row).collect())
+    })
+    .await
+}
+
+fn subscription_from_row(row: SubscriptionRow) -> SubscriptionInfo {
+    let (
+        name,
+        owner,
+        enabled,
+        publications,
+        slot_name,
+        tables,
+        states,
+        worker_pid,
+        received_lsn,
+        last_message_at,
+    ) = row;
+    SubscriptionInfo {
+        name,
+        owner,
+        enabled,
+        publications,
+        slot_name,
+        tables: tables
+            .into_iter()
+            .zip(states)
+            .map(|(table, state)| SubscriptionTable { table, state })
+            .collect(),
+        worker_pid,
+        received_lsn,
+        last_message_at,
+    }
+}
+
+/// Return the parent of a partition as `schema.name`, or `None` if `table`
+/// is not a partition.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn partition_parent(pool: &PgPool, table: &str) -> Result<Option<String>> {
+    traced(async {
+        let parent: Option<String> = sqlx::query_scalar(
+            "SELECT format('%s.%s', n.nspname, p.relname) \
+             FROM pg_inherits i \
+             JOIN pg_class c ON c.oid = i.inhrelid \
+             JOIN pg_class p ON p.oid = i.inhparent \
+             JOIN pg_namespace n ON n.oid = p.relnamespace \
+             WHERE i.inhrelid = to_regclass($1) AND c.relispartition",
+        )
+        .bind(quote_qualified(table))
+        .fetch_optional(pool)
+        .await
+        .with_context(|| format!("Failed to look up parent of '{}'", table))?;
 
-    Ok(names)
+        Ok(parent)
+    })
+    .await
 }
 
 /// Return a list of column names for the given table.
+#[tracing::instrument(level = "debug", skip(pool))]
 pub async fn list_columns(pool: &PgPool, table_name: &str) -> Result<Vec<String>> {
-    let names: Vec<String> = sqlx::query_scalar(
-        "SELECT column_name FROM information_schema.columns \
-         WHERE table_schema = 'public' AND table_name = $1 \
-         ORDER BY ordinal_position"
+    traced(async {
+        list_columns_in(pool, "public", table_name).await
+    })
+    .await
+}
+
+/// Return a list of column names for a table in the given schema.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn list_columns_in(pool: &PgPool, schema: &str, table_name: &str) -> Result<Vec<String>> {
+    traced(async {
+        let names: Vec<String> = sqlx::query_scalar(
+            "SELECT column_name FROM information_schema.columns \
+             WHERE table_schema = $1 AND table_name = $2 \
+             ORDER BY ordinal_position"
+        )
+        .bind(schema)
+        .bind(table_name)
+        .fetch_all(pool)
+        .await
+        .with_context(|| format!("Failed to list columns for table '{}.{}'", schema, table_name))?;
+
+        Ok(names)
+    })
+    .await
+}
+
+/// Return full column metadata for `table_name` (`name` in the public schema
+/// or `schema.name`), in column order.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn list_columns_detailed(pool: &PgPool, table_name: &str) -> Result<Vec<ColumnInfo>> {
+    traced(async {
+        let rows = sqlx::query_as::<_, (String, i32, String, String, bool, Option<String>, Option<i32>, bool, bool)>(
+            "SELECT column_name, ordinal_position::int4, data_type, udt_name, \
+                    is_nullable = 'YES', column_default, character_maximum_length::int4, \
+                    is_identity = 'YES', is_generated = 'ALWAYS' \
+             FROM information_schema.columns \
+             WHERE (table_schema, table_name) = ( \
+                 SELECT n.nspname::text, c.relname::text FROM pg_class c \
+                 JOIN pg_namespace n ON n.oid = c.relnamespace \
+                 WHERE c.oid = to_regclass($1)) \
+             ORDER BY ordinal_position",
+        )
+        .bind(quote_qualified(table_name))
+        .fetch_all(pool)
+        .await
+        .with_context(|| format!("Failed to list columns for table '{}'", table_name))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(name, ordinal_position, data_type, udt_name, is_nullable, default, max_length, is_identity, is_generated)| {
+                ColumnInfo {
+                    name,
+                    ordinal_position,
+                    data_type,
+                    udt_name,
+                    is_nullable,
+                    default,
+                    max_length,
+                    is_identity,
+                    is_generated,
+                }
+            })
+            .collect())
+    })
+    .await
+}
+
+/// Return the primary key of `table_name` (`name` in the public schema or
+/// `schema.name`), or `None` if it has none.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn primary_key(pool: &PgPool, table_name: &str) -> Result<Option<PrimaryKey>> {
+    traced(async {
+        let row = sqlx::query_as::<_, (String, Vec<String>)>(&format!(
+            "SELECT con.conname, {} \
+             FROM pg_constraint con \
+             WHERE con.contype = 'p' AND con.conrelid = to_regclass($1)",
+            constraint_columns("con.conrelid", "con.conkey"),
+        ))
+        .bind(quote_qualified(table_name))
+        .fetch_optional(pool)
+        .await
+        .with_context(|| format!("Failed to get primary key for table '{}'", table_name))?;
+
+        Ok(row.map(|(name, columns)| PrimaryKey { name, columns }))
+    })
+    .await
+}
+
+/// List the foreign keys declared on `table_name` (`name` in the public
+/// schema or `schema.name`), ordered by constraint name.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn list_foreign_keys(pool: &PgPool, table_name: &str) -> Result<Vec<ForeignKey>> {
+    traced(async {
+        let rows = sqlx::query_as::<_, (String, Vec<String>, String, String, Vec<String>, String, String)>(&format!(
+            "SELECT con.conname, {}, rn.nspname, rc.relname, {}, \
+                    {}, {} \
+             FROM pg_constraint con \
+             JOIN pg_class rc ON rc.oid = con.confrelid \
+             JOIN pg_namespace rn ON rn.oid = rc.relnamespace \
+             WHERE con.contype = 'f' AND con.conrelid = to_regclass($1) \
+             ORDER BY con.conname",
+            constraint_columns("con.conrelid", "con.conkey"),
+            constraint_columns("con.confrelid", "con.confkey"),
+            referential_action("con.confdeltype"),
+            referential_action("con.confupdtype"),
+        ))
+        .bind(quote_qualified(table_name))
+        .fetch_all(pool)
+        .await
+        .with_context(|| format!("Failed to list foreign keys for table '{}'", table_name))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(name, columns, referenced_schema, referenced_table, referenced_columns, on_delete, on_update)| {
+                ForeignKey {
+                    name,
+                    columns,
+                    referenced_schema,
+                    referenced_table,
+                    referenced_columns,
+                    on_delete,
+                    on_update,
+                }
+            })
+            .collect())
+    })
+    .await
+}
+
+/// SQL for the column names of a `pg_constraint` key array, in key order.
+fn constraint_columns(relid: &str, key: &str) -> String {
+    format!(
+        "ARRAY(SELECT a.attname::text FROM unnest({key}) WITH ORDINALITY AS k(attnum, ord) \
+               JOIN pg_attribute a ON a.attrelid = {relid} AND a.attnum = k.attnum \
+               ORDER BY k.ord)"
     )
-    .bind(table_name)
-    .fetch_all(pool)
+}
+
+/// SQL mapping a `pg_constraint` action code to its SQL spelling.
+fn referential_action(column: &str) -> String {
+    format!(
+        "CASE {column} WHEN 'r' THEN 'RESTRICT' WHEN 'c' THEN 'CASCADE' \
+              WHEN 'n' THEN 'SET NULL' WHEN 'd' THEN 'SET DEFAULT' \
+              ELSE 'NO ACTION' END"
+    )
+}
+
+/// Return the comment on a table, or on one of its columns when `column` is
+/// given. `table` is a name in the public schema or `schema.table`. Returns
+/// `None` if there is no comment, and an error if the table or column does
+/// not exist.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn get_comment(pool: &PgPool, table: &str, column: Option<&str>) -> Result<Option<String>> {
+    traced(async {
+        let row: Option<(Option<i32>, Option<String>)> = sqlx::query_as(
+            "SELECT a.attnum::int4, \
+                    CASE WHEN $2::text IS NULL THEN obj_description(c.oid, 'pg_class') \
+                         ELSE col_description(c.oid, a.attnum) END \
+             FROM pg_class c \
+             LEFT JOIN pg_attribute a ON a.attrelid = c.oid AND a.attname = $2 AND NOT a.attisdropped \
+             WHERE c.oid = to_regclass($1)",
+        )
+        .bind(quote_qualified(table))
+        .bind(column)
+        .fetch_optional(pool)
+        .await
+        .with_context(|| format!("Failed to get comment on '{}'", table))?;
+
+        let Some((attnum, comment)) = row else {
+            return Err(anyhow!("Table '{}' does not exist", table).into());
+        };
+        if let Some(column) = column
+            && attnum.is_none()
+        {
+            return Err(anyhow!("Column '{}' does not exist on '{}'", column, table).into());
+        }
+        Ok(comment)
+    })
     .await
-    .with_context(|| format!("Failed to list columns for table '{}'", table_name))?;
+}
 
-    Ok(names)
+/// Capture the public schema's tables, columns, indexes and constraints as a
+/// `SchemaSnapshot`, which serializes to JSON or YAML for snapshot tests and
+/// can be compared with `schema_diff::diff`.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn dump_schema(pool: &PgPool) -> Result<SchemaSnapshot> {
+    traced(async {
+        schema_diff::snapshot(pool, "public").await
+    })
+    .await
 }
 
 /// Return the current database name the pool is connected to.
+#[tracing::instrument(level = "debug", skip(pool))]
 pub async fn current_database(pool: &PgPool) -> Result<String> {
-    let name: String = sqlx::query_scalar("SELECT current_database()")
+    traced(async {
+        let name: String = sqlx::query_scalar("SELECT current_database()")
+            .fetch_one(pool)
+            .await
+            .context("Failed to get current database name")?;
+
+        Ok(name)
+    })
+    .await
+}
+
+/// Return the role privileges are checked against (`current_user`).
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn current_user(pool: &PgPool) -> Result<String> {
+    traced(async {
+        let name: String = sqlx::query_scalar("SELECT current_user::text")
+            .fetch_one(pool)
+            .await
+            .context("Failed to get current user")?;
+
+        Ok(name)
+    })
+    .await
+}
+
+type SessionRow = (
+    i32,
+    String,
+    String,
+    String,
+    Option<String>,
+    Option<i32>,
+    String,
+    Option<DateTime<Utc>>,
+    String,
+    bool,
+);
+
+/// Describe the session of the pool connection that runs the query. Other
+/// connections of the pool are separate sessions with their own pid.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn session_info(pool: &PgPool) -> Result<CurrentSession> {
+    traced(async {
+        let (
+            pid,
+            current_user,
+            session_user,
+            database,
+            client_addr,
+            client_port,
+            application_name,
+            backend_start,
+            isolation_level,
+            read_only,
+        ) = sqlx::query_as::<_, SessionRow>(
+            "SELECT pg_backend_pid(), current_user::text, session_user::text, \
+                    current_database()::text, host(inet_client_addr()), inet_client_port(), \
+                    current_setting('application_name'), \
+                    (SELECT backend_start FROM pg_stat_activity WHERE pid = pg_backend_pid()), \
+                    current_setting('transaction_isolation'), \
+                    current_setting('transaction_read_only')::bool",
+        )
+        .fetch_one(pool)
+        .await
+        .context("Failed to get session info")?;
+
+        Ok(CurrentSession {
+            pid,
+            current_user,
+            session_user,
+            database,
+            client_addr,
+            client_port,
+            application_name,
+            backend_start,
+            isolation_level,
+            read_only,
+        })
+    })
+    .await
+}
+
+/// Return the version of the connected server.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn server_version(pool: &PgPool) -> Result<ServerVersion> {
+    traced(async {
+        let (num, full): (String, String) = sqlx::query_as(
+            "SELECT current_setting('server_version_num'), current_setting('server_version')"
+        )
         .fetch_one(pool)
         .await
-        .context("Failed to get current database name")?;
+        .context("Failed to get server version")?;
+
+        // e.g. 160002 for 16.2 (PostgreSQL 10 and later)
+        let num: u32 = num
+            .parse()
+            .with_context(|| format!("Unexpected server_version_num '{}'", num))?;
+        Ok(ServerVersion { major: num / 10000, minor: num % 10000, full })
+    })
+    .await
+}
+
+/// Return one server setting by name, or `None` if there is no such setting.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn get_setting(pool: &PgPool, name: &str) -> Result<Option<Setting>> {
+    traced(async {
+        let row = sqlx::query_as::<_, SettingRow>(&format!(
+            "SELECT {} FROM pg_settings WHERE name = lower($1)", SETTING_COLUMNS
+        ))
+        .bind(name)
+        .fetch_optional(pool)
+        .await
+        .with_context(|| format!("Failed to get setting '{}'", name))?;
+
+        Ok(row.map(setting_from_row).transpose()?)
+    })
+    .await
+}
+
+/// List server settings, ordered by name. With a `filter`, only settings
+/// whose name contains it (case-insensitively) are returned.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn list_settings(pool: &PgPool, filter: Option<&str>) -> Result<Vec<Setting>> {
+    traced(async {
+        let rows = sqlx::query_as::<_, SettingRow>(&format!(
+            "SELECT {} FROM pg_settings \
+             WHERE $1::text IS NULL OR strpos(name, lower($1)) > 0 \
+             ORDER BY name",
+            SETTING_COLUMNS
+        ))
+        .bind(filter)
+        .fetch_all(pool)
+        .await
+        .context("Failed to list settings")?;
+
+        Ok(rows.into_iter().map(setting_from_row).collect::<anyhow::Result<_>>()?)
+    })
+    .await
+}
+
+const SETTING_COLUMNS: &str =
+    "name, setting, vartype, unit, category, short_desc, source, pending_restart";
+
+type SettingRow = (String, String, String, Option<String>, String, String, String, bool);
+
+fn setting_from_row(row: SettingRow) -> anyhow::Result<Setting> {
+    let (name, setting, vartype, unit, category, description, source, pending_restart) = row;
+    let value = match vartype.as_str() {
+        "bool" => SettingValue::Bool(setting == "on"),
+        "integer" => SettingValue::Integer(setting.parse().with_context(|| {
+            format!("Setting '{}' has non-integer value '{}'", name, setting)
+        })?),
+        "real" => SettingValue::Real(setting.parse().with_context(|| {
+            format!("Setting '{}' has non-numeric value '{}'", name, setting)
+        })?),
+        "enum" => SettingValue::Enum(setting),
+        _ => SettingValue::String(setting),
+    };
+    Ok(Setting { name, value, unit, category, description, source, pending_restart })
+}
+
+/// Size of the current database in bytes.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn database_size(pool: &PgPool) -> Result<i64> {
+    traced(async {
+        let size: i64 = sqlx::query_scalar("SELECT pg_database_size(current_database())")
+            .fetch_one(pool)
+            .await
+            .context("Failed to get database size")?;
+
+        Ok(size)
+    })
+    .await
+}
+
+/// Sizes of all user tables across non-system schemas, largest first.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn table_sizes(pool: &PgPool) -> Result<Vec<TableSize>> {
+    traced(async {
+        let rows = sqlx::query_as::<_, (String, String, i64, i64, i64, i64)>(
+            "SELECT n.nspname, c.relname, \
+                    pg_total_relation_size(c.oid), \
+                    pg_relation_size(c.oid), \
+                    pg_indexes_size(c.oid), \
+                    CASE WHEN c.reltoastrelid = 0 THEN 0 \
+                         ELSE pg_total_relation_size(c.reltoastrelid) END \
+             FROM pg_class c \
+             JOIN pg_namespace n ON n.oid = c.relnamespace \
+             WHERE c.relkind IN ('r', 'p', 'm') \
+               AND n.nspname NOT IN ('information_schema', 'pg_catalog') \
+               AND n.nspname NOT LIKE 'pg_toast%' \
+             ORDER BY 3 DESC, n.nspname, c.relname",
+        )
+        .fetch_all(pool)
+        .await
+        .context("Failed to get table sizes")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(schema, name, total_bytes, table_bytes, index_bytes, toast_bytes)| {
+                TableSize { schema, name, total_bytes, table_bytes, index_bytes, toast_bytes }
+            })
+            .collect())
+    })
+    .await
+}
+
+/// Usage statistics for every index on user tables, ordered by schema,
+/// table and index name.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn index_usage(pool: &PgPool) -> Result<Vec<IndexUsage>> {
+    traced(async {
+        let rows = sqlx::query_as::<_, IndexUsageRow>(
+            "SELECT s.schemaname::text, s.relname::text, s.indexrelname::text, \
+                    s.idx_scan, s.idx_tup_read, s.idx_tup_fetch, \
+                    coalesce(io.idx_blks_read, 0), coalesce(io.idx_blks_hit, 0), \
+                    pg_relation_size(s.indexrelid), i.indisunique \
+             FROM pg_stat_user_indexes s \
+             JOIN pg_index i ON i.indexrelid = s.indexrelid \
+             LEFT JOIN pg_statio_user_indexes io ON io.indexrelid = s.indexrelid \
+             ORDER BY s.schemaname, s.relname, s.indexrelname",
+        )
+        .fetch_all(pool)
+        .await
+        .context("Failed to get index usage")?;
+
+        Ok(rows.into_iter().map(index_usage_from_row).collect())
+    })
+    .await
+}
+
+/// Indexes that have never been scanned and do not enforce uniqueness,
+/// largest first: candidates for dropping. Only meaningful once the
+/// statistics cover a representative workload (and check replicas, whose
+/// scans are counted separately).
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn find_unused_indexes(pool: &PgPool) -> Result<Vec<IndexUsage>> {
+    traced(async {
+        let mut unused: Vec<IndexUsage> = index_usage(pool)
+            .await?
+            .into_iter()
+            .filter(|index| index.scans == 0 && !index.is_unique)
+            .collect();
+        unused.sort_by_key(|index| std::cmp::Reverse(index.size_bytes));
+        Ok(unused)
+    })
+    .await
+}
+
+type IndexUsageRow = (String, String, String, i64, i64, i64, i64, i64, i64, bool);
+
+fn index_usage_from_row(row: IndexUsageRow) -> IndexUsage {
+    let (
+        schema,
+        table,
+        index,
+        scans,
+        tuples_read,
+        tuples_fetched,
+        blocks_read,
+        blocks_hit,
+        size_bytes,
+        is_unique,
+    ) = row;
+    IndexUsage {
+        schema,
+        table,
+        index,
+        scans,
+        tuples_read,
+        tuples_fetched,
+        blocks_read,
+        blocks_hit,
+        size_bytes,
+        is_unique,
+    }
+}
+
+/// Planner's row count estimate for `table_name` (`name` in the public schema
+/// or `schema.name`), from
+/// `pg_class.reltuples`. Costs nothing to read, and is as fresh as the last
+/// VACUUM, ANALYZE or autovacuum.
+///
+/// Returns `None` if the table has never been vacuumed or analyzed, and an
+/// error if it does not exist.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn estimate_row_count(pool: &PgPool, table_name: &str) -> Result<Option<i64>> {
+    traced(async {
+        let estimate: Option<f32> = sqlx::query_scalar(
+            "SELECT c.reltuples FROM pg_class c \
+             WHERE c.oid = to_regclass($1) AND c.relkind IN ('r', 'p', 'm')"
+        )
+        .bind(quote_qualified(table_name))
+        .fetch_optional(pool)
+        .await
+        .with_context(|| format!("Failed to estimate rows for table '{}'", table_name))?;
+
+        let estimate = estimate.with_context(|| format!("Table '{}' does not exist", table_name))?;
+        Ok((estimate >= 0.0).then(|| estimate.round() as i64))
+    })
+    .await
+}
+
+/// Exact row count of a table in the public schema (`count(*)`, which scans
+/// the table; see `estimate_row_count` for large tables).
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn count_rows(pool: &PgPool, table_name: &str) -> Result<i64> {
+    traced(async {
+        let count: i64 = sqlx::query_scalar(&format!(
+            "SELECT count(*) FROM public.{}", quote_identifier(table_name)
+        ))
+        .fetch_one(pool)
+        .await
+        .with_context(|| format!("Failed to count rows in table '{}'", table_name))?;
+
+        Ok(count)
+    })
+    .await
+}
+
+/// Fingerprint the contents of `table` (`name` in the public schema or
+/// `schema.name`) over `columns`, or over whole rows if `columns` is empty.
+///
+/// Each row's text form is hashed and the hashes are combined in sorted
+/// order, so the result does not depend on physical row order or collation
+/// and two databases holding the same data agree. Column types must match
+/// too, and settings that change text output (`DateStyle`, `TimeZone`,
+/// `extra_float_digits`) must be the same on both sides. Scans the table and
+/// sorts one hash per row on the server.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn table_checksum(pool: &PgPool, table: &str, columns: &[&str]) -> Result<TableChecksum> {
+    traced(async {
+        validate_qualified(table)?;
+        let exists: bool = sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL")
+            .bind(quote_qualified(table))
+            .fetch_one(pool)
+            .await
+            .with_context(|| format!("Failed to look up table '{}'", table))?;
+        if !exists {
+            return Err(anyhow!("Table '{}' does not exist", table).into());
+        }
+
+        let row = if columns.is_empty() {
+            "t.*".to_string()
+        } else {
+            columns.iter().map(|c| format!("t.{}", quote_identifier(c))).collect::<Vec<_>>().join(", ")
+        };
+        let (rows, checksum): (i64, String) = sqlx::query_as(&format!(
+            "SELECT count(*), md5(coalesce(string_agg(h, '' ORDER BY h COLLATE \"C\"), '')) \
+             FROM (SELECT md5(ROW({})::text) AS h FROM {} t) rows",
+            row,
+            quote_qualified(table)
+        ))
+        .fetch_one(pool)
+        .await
+        .with_context(|| format!("Failed to checksum table '{}'", table))?;
+
+        Ok(TableChecksum { rows, checksum })
+    })
+    .await
+}
+
+/// List the indexes on `table_name` (`name` in the public schema or
+/// `schema.name`), ordered by name.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn list_indexes(pool: &PgPool, table_name: &str) -> Result<Vec<IndexInfo>> {
+    traced(async {
+        let rows = sqlx::query_as::<_, (String, String, String, bool, bool, Vec<String>, i64)>(
+            "SELECT ic.relname, pg_get_indexdef(i.indexrelid), am.amname, \
+                    i.indisunique, i.indisprimary, \
+                    ARRAY(SELECT pg_get_indexdef(i.indexrelid, k, true) \
+                          FROM generate_series(1, i.indnkeyatts) AS k ORDER BY k), \
+                    pg_relation_size(i.indexrelid) \
+             FROM pg_index i \
+             JOIN pg_class ic ON ic.oid = i.indexrelid \
+             JOIN pg_am am ON am.oid = ic.relam \
+             WHERE i.indrelid = to_regclass($1) \
+             ORDER BY ic.relname",
+        )
+        .bind(quote_qualified(table_name))
+        .fetch_all(pool)
+        .await
+        .with_context(|| format!("Failed to list indexes for table '{}'", table_name))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(name, definition, method, is_unique, is_primary, columns, size_bytes)| {
+                IndexInfo { name, definition, method, is_unique, is_primary, columns, size_bytes }
+            })
+            .collect())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setting(value: SettingValue, unit: Option<&str>) -> Setting {
+        Setting {
+            name: "test".to_string(),
+            value,
+            unit: unit.map(str::to_string),
+            category: String::new(),
+            description: String::new(),
+            source: "default".to_string(),
+            pending_restart: false,
+        }
+    }
+
+    #[test]
+    fn test_setting_units() {
+        // shared_buffers = 128MB
+        let shared_buffers = setting(SettingValue::Integer(16384), Some("8kB"));
+        assert_eq!(shared_buffers.as_bytes(), Some(128 * 1024 * 1024));
+        assert_eq!(shared_buffers.as_duration(), None);
+
+        let timeout = setting(SettingValue::Integer(1500), Some("ms"));
+        assert_eq!(timeout.as_duration(), Some(Duration::from_millis(1500)));
+        assert_eq!(timeout.as_bytes(), None);
+
+        let naptime = setting(SettingValue::Integer(1), Some("min"));
+        assert_eq!(naptime.as_duration(), Some(Duration::from_secs(60)));
 
-    Ok(name)
+        assert_eq!(setting(SettingValue::Integer(-1), Some("ms")).as_duration(), None);
+        assert_eq!(setting(SettingValue::Integer(100), None).as_bytes(), None);
+        assert_eq!(setting(SettingValue::Bool(true), Some("kB")).as_bytes(), None);
+    }
 }
@@ -23,10 +23,38 @@
 //! ```
 
 pub mod admin;
+pub mod analyze;
+pub mod backup;
+pub mod bulk;
 pub mod config;
 pub mod connection;
+pub mod error;
+pub mod export;
+pub mod health;
+pub mod identifier;
 pub mod introspection;
+pub mod maintenance;
+pub mod migrations;
+pub mod monitoring;
+pub mod notify;
+pub mod pool_set;
+pub mod query_builder;
+pub mod schema_diff;
+pub mod seed;
+pub mod statement_tracing;
+pub mod stream;
+#[cfg(feature = "test-support")]
+pub mod testing;
+pub mod transaction;
 
 pub use config::PgConfig;
-pub use connection::create_pool;
-pub use introspection::TableInfo;
+pub use error::{Error, Result};
+pub use connection::{create_pool, create_pool_lazy};
+pub use pool_set::{PoolSet, PoolSetConfig};
+pub use schema_diff::SchemaSnapshot;
+pub use introspection::{
+    ColumnInfo, CurrentSession, ForeignKey, IndexInfo, IndexUsage, MaterializedViewInfo,
+    PartitionInfo, PartitionedTable, PolicyInfo, PrimaryKey, PublicationInfo, ServerVersion,
+    Setting, SettingValue, SubscriptionInfo, SubscriptionTable, TableChecksum, TableInfo,
+    TableSize, ViewInfo,
+};
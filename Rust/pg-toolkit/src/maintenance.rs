@@ -0,0 +1,256 @@
+//! Routine maintenance: rebuilding indexes and estimating bloat.
+//!
+//! Unlike `admin`, these operate on objects inside the current database and
+//! take the application pool.
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::error::Result;
+use crate::identifier::quote_qualified;
+use crate::statement_tracing::traced;
+
+/// What `reindex` rebuilds. Names are in the public schema or `schema.name`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ReindexTarget {
+    /// Every index on a table, including its TOAST table's.
+    Table(String),
+    /// A single index.
+    Index(String),
+}
+
+impl ReindexTarget {
+    fn keyword(&self) -> &'static str {
+        match self {
+            ReindexTarget::Table(_) => "TABLE",
+            ReindexTarget::Index(_) => "INDEX",
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            ReindexTarget::Table(name) | ReindexTarget::Index(name) => name,
+        }
+    }
+}
+
+/// Rebuild an index, or all indexes on a table, e.g. to remove bloat.
+///
+/// With `concurrently = true`, uses `REINDEX ... CONCURRENTLY`, which builds
+/// the replacement alongside the old index so writes are not blocked, at the
+/// cost of a slower rebuild. It cannot run inside a transaction.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn reindex(pool: &PgPool, target: &ReindexTarget, concurrently: bool) -> Result<()> {
+    traced(async {
+        let mode = if concurrently { " CONCURRENTLY" } else { "" };
+        sqlx::query(&format!(
+            "REINDEX {}{} {}", target.keyword(), mode, quote_qualified(target.name())
+        ))
+        .execute(pool)
+        .await
+        .with_context(|| format!(
+            "Failed to reindex {} '{}'", target.keyword().to_lowercase(), target.name()))?;
+
+        tracing::info!(
+            "Reindexed {} '{}' (concurrently={})",
+            target.keyword().to_lowercase(),
+            target.name(),
+            concurrently
+        );
+        Ok(())
+    })
+    .await
+}
+
+/// Estimated bloat of a table, or of one of its B-tree indexes: space taken
+/// beyond what the live rows need at the relation's fillfactor.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BloatEstimate {
+    pub schema: String,
+    pub table: String,
+    /// Set for an index estimate, `None` for the table itself.
+    pub index: Option<String>,
+    /// On-disk size in bytes (for tables, including TOAST).
+    pub real_size_bytes: i64,
+    /// Estimated wasted bytes.
+    pub bloat_bytes: i64,
+    /// `bloat_bytes` as a percentage of `real_size_bytes`.
+    pub bloat_percent: f64,
+    pub fillfactor: i32,
+    /// The estimate is unreliable: the table has not been analyzed, or has
+    /// columns without statistics (e.g. of type `name`).
+    pub is_approximate: bool,
+}
+
+type BloatRow = (String, String, Option<String>, i64, i64, f64, i32, bool);
+
+/// Estimate bloat for every user table and B-tree index from planner
+/// statistics, without reading the relations (the widely used estimation
+/// queries from the PostgreSQL wiki / ioguix/pgsql-bloat-estimation).
+/// Results are most accurate right after `ANALYZE`. Largest bloat first.
+///
+/// Rules of thumb: a table much above its fillfactor headroom is a
+/// candidate for `VACUUM FULL` (or pg_repack); an index with high bloat for
+/// `reindex`. Indexes on tables never analyzed are not listed.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn estimate_bloat(pool: &PgPool) -> Result<Vec<BloatEstimate>> {
+    traced(async {
+        let tables = sqlx::query_as::<_, BloatRow>(TABLE_BLOAT_SQL)
+            .fetch_all(pool)
+            .await
+            .context("Failed to estimate table bloat")?;
+        let indexes = sqlx::query_as::<_, BloatRow>(INDEX_BLOAT_SQL)
+            .fetch_all(pool)
+            .await
+            .context("Failed to estimate index bloat")?;
+
+        let mut estimates: Vec<BloatEstimate> =
+            tables.into_iter().chain(indexes).map(bloat_from_row).collect();
+        estimates.sort_by_key(|e| std::cmp::Reverse(e.bloat_bytes));
+        Ok(estimates)
+    })
+    .await
+}
+
+fn bloat_from_row(row: BloatRow) -> BloatEstimate {
+    let (schema, table, index, real_size_bytes, bloat_bytes, bloat_percent, fillfactor, is_approximate) =
+        row;
+    BloatEstimate {
+        schema,
+        table,
+        index,
+        real_size_bytes,
+        bloat_bytes,
+        bloat_percent,
+        fillfactor,
+        is_approximate,
+    }
+}
+
+/// Heap bloat: estimated pages from row count and average row width (from
+/// `pg_stats`) against actual pages.
+const TABLE_BLOAT_SQL: &str = "\
+SELECT schemaname::text, tblname::text, NULL::text, (bs * tblpages)::int8, \
+       CASE WHEN tblpages - est_tblpages_ff > 0 \
+            THEN ((tblpages - est_tblpages_ff) * bs)::int8 ELSE 0 END, \
+       CASE WHEN tblpages > 0 AND tblpages - est_tblpages_ff > 0 \
+            THEN (100 * (tblpages - est_tblpages_ff) / tblpages)::float8 ELSE 0 END, \
+       fillfactor::int4, is_na \
+FROM ( \
+  SELECT ceil(reltuples / ((bs - page_hdr) * fillfactor / (tpl_size * 100))) \
+           + ceil(toasttuples / 4) AS est_tblpages_ff, \
+         tblpages, fillfactor, bs, schemaname, tblname, is_na \
+  FROM ( \
+    SELECT (4 + tpl_hdr_size + tpl_data_size + (2 * ma) \
+            - CASE WHEN tpl_hdr_size % ma = 0 THEN ma ELSE tpl_hdr_size % ma END \
+            - CASE WHEN ceil(tpl_data_size)::int % ma = 0 THEN ma \
+                   ELSE ceil(tpl_data_size)::int % ma END) AS tpl_size, \
+           (heappages + toastpages) AS tblpages, reltuples, toasttuples, bs, page_hdr, \
+           schemaname, tblname, fillfactor, is_na \
+    FROM ( \
+      SELECT tbl.oid AS tblid, ns.nspname AS schemaname, tbl.relname AS tblname, \
+             tbl.reltuples, tbl.relpages AS heappages, \
+             coalesce(toast.relpages, 0) AS toastpages, \
+             coalesce(toast.reltuples, 0) AS toasttuples, \
+             coalesce(substring(array_to_string(tbl.reloptions, ' ') \
+                                FROM 'fillfactor=([0-9]+)')::smallint, 100) AS fillfactor, \
+             current_setting('block_size')::numeric AS bs, \
+             CASE WHEN version() ~ 'mingw32' OR version() ~ '64-bit|x86_64|ppc64|ia64|amd64' \
+                  THEN 8 ELSE 4 END AS ma, \
+             24 AS page_hdr, \
+             23 + CASE WHEN max(coalesce(s.null_frac, 0)) > 0 \
+                       THEN (7 + count(s.attname)) / 8 ELSE 0::int END AS tpl_hdr_size, \
+             sum((1 - coalesce(s.null_frac, 0)) * coalesce(s.avg_width, 0)) AS tpl_data_size, \
+             bool_or(att.atttypid = 'pg_catalog.name'::regtype) \
+               OR sum(CASE WHEN att.attnum > 0 THEN 1 ELSE 0 END) <> count(s.attname) \
+               OR tbl.reltuples < 0 AS is_na \
+      FROM pg_attribute att \
+      JOIN pg_class tbl ON att.attrelid = tbl.oid \
+      JOIN pg_namespace ns ON ns.oid = tbl.relnamespace \
+      LEFT JOIN pg_stats s ON s.schemaname = ns.nspname AND s.tablename = tbl.relname \
+                          AND s.inherited = false AND s.attname = att.attname \
+      LEFT JOIN pg_class toast ON tbl.reltoastrelid = toast.oid \
+      WHERE NOT att.attisdropped AND att.attnum > 0 \
+        AND tbl.relkind IN ('r', 'm') \
+        AND ns.nspname NOT IN ('information_schema', 'pg_catalog') \
+        AND ns.nspname NOT LIKE 'pg_toast%' \
+      GROUP BY 1, 2, 3, 4, 5, 6, 7, 8, 9, 10 \
+    ) AS s \
+  ) AS s2 \
+) AS s3";
+
+/// B-tree bloat: estimated leaf pages from row count and key width against
+/// actual pages.
+const INDEX_BLOAT_SQL: &str = "\
+SELECT nspname::text, tblname::text, idxname::text, (bs * relpages)::int8, \
+       CASE WHEN relpages > est_pages_ff \
+            THEN (bs * (relpages - est_pages_ff))::int8 ELSE 0 END, \
+       CASE WHEN relpages > est_pages_ff \
+            THEN (100 * (relpages - est_pages_ff)::float8 / relpages) ELSE 0 END, \
+       fillfactor::int4, is_na \
+FROM ( \
+  SELECT coalesce(1 + ceil(reltuples / floor((bs - pageopqdata - pagehdr) * fillfactor \
+                                             / (100 * (4 + nulldatahdrwidth)::float8))), 0) \
+           AS est_pages_ff, \
+         bs, nspname, tblname, idxname, relpages, fillfactor, is_na \
+  FROM ( \
+    SELECT maxalign, bs, nspname, tblname, idxname, reltuples, relpages, fillfactor, \
+           (index_tuple_hdr_bm + maxalign \
+            - CASE WHEN index_tuple_hdr_bm % maxalign = 0 THEN maxalign \
+                   ELSE index_tuple_hdr_bm % maxalign END \
+            + nulldatawidth + maxalign \
+            - CASE WHEN nulldatawidth = 0 THEN 0 \
+                   WHEN nulldatawidth::integer % maxalign = 0 THEN maxalign \
+                   ELSE nulldatawidth::integer % maxalign END)::numeric AS nulldatahdrwidth, \
+           pagehdr, pageopqdata, is_na \
+    FROM ( \
+      SELECT n.nspname, i.tblname, i.idxname, i.reltuples, i.relpages, i.fillfactor, \
+             current_setting('block_size')::numeric AS bs, \
+             CASE WHEN version() ~ 'mingw32' OR version() ~ '64-bit|x86_64|ppc64|ia64|amd64' \
+                  THEN 8 ELSE 4 END AS maxalign, \
+             24 AS pagehdr, 16 AS pageopqdata, \
+             CASE WHEN max(coalesce(s.null_frac, 0)) = 0 THEN 8 \
+                  ELSE 8 + ((32 + 8 - 1) / 8) END AS index_tuple_hdr_bm, \
+             sum((1 - coalesce(s.null_frac, 0)) * coalesce(s.avg_width, 1024)) AS nulldatawidth, \
+             max(CASE WHEN i.atttypid = 'pg_catalog.name'::regtype THEN 1 ELSE 0 END) > 0 \
+               OR i.reltuples < 0 AS is_na \
+      FROM ( \
+        SELECT ct.relname AS tblname, ct.relnamespace, ic.idxname, ic.reltuples, ic.relpages, \
+               ic.fillfactor, coalesce(a1.attname, a2.attname) AS attname, \
+               coalesce(a1.atttypid, a2.atttypid) AS atttypid, \
+               CASE WHEN a1.attnum IS NULL THEN ic.idxname ELSE ct.relname END AS attrelname \
+        FROM ( \
+          SELECT idxname, reltuples, relpages, tbloid, idxoid, fillfactor, indkey, \
+                 generate_series(1, indnatts) AS attpos \
+          FROM ( \
+            SELECT ci.relname AS idxname, ci.reltuples, ci.relpages, i.indrelid AS tbloid, \
+                   i.indexrelid AS idxoid, \
+                   coalesce(substring(array_to_string(ci.reloptions, ' ') \
+                                      FROM 'fillfactor=([0-9]+)')::smallint, 90) AS fillfactor, \
+                   i.indnatts, \
+                   string_to_array(textin(int2vectorout(i.indkey)), ' ')::int[] AS indkey \
+            FROM pg_index i \
+            JOIN pg_class ci ON ci.oid = i.indexrelid \
+            WHERE ci.relam = (SELECT oid FROM pg_am WHERE amname = 'btree') \
+              AND ci.relpages > 0 \
+          ) AS idx_data \
+        ) AS ic \
+        JOIN pg_class ct ON ct.oid = ic.tbloid \
+        LEFT JOIN pg_attribute a1 ON ic.indkey[ic.attpos] <> 0 \
+                                 AND a1.attrelid = ic.tbloid \
+                                 AND a1.attnum = ic.indkey[ic.attpos] \
+        LEFT JOIN pg_attribute a2 ON ic.indkey[ic.attpos] = 0 \
+                                 AND a2.attrelid = ic.idxoid \
+                                 AND a2.attnum = ic.attpos \
+      ) i \
+      JOIN pg_namespace n ON n.oid = i.relnamespace \
+      JOIN pg_stats s ON s.schemaname = n.nspname \
+                     AND s.tablename = i.attrelname \
+                     AND s.attname = i.attname \
+      WHERE n.nspname NOT IN ('information_schema', 'pg_catalog') \
+        AND n.nspname NOT LIKE 'pg_toast%' \
+      GROUP BY 1, 2, 3, 4, 5, 6, 7, 8, 9, 10 \
+    ) AS rows_data_stats \
+  ) AS rows_hdr_pdg_stats \
+) AS relation_stats";
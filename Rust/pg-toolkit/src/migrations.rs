@@ -0,0 +1,342 @@
+//! Versioned schema migrations.
+//!
+//! A `Migrator` applies ordered SQL migrations, from a directory or embedded
+//! strings, and records each applied version in the `_pg_toolkit_migrations`
+//! table. Each migration runs in its own transaction under an advisory lock,
+//! so concurrent migrators do not apply the same version twice. Statements
+//! that cannot run inside a transaction (e.g. `CREATE INDEX CONCURRENTLY`)
+//! are not supported.
+
+use anyhow::{Context, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::error::Result;
+use crate::statement_tracing::traced;
+
+/// Table recording applied migrations.
+pub const MIGRATIONS_TABLE: &str = "_pg_toolkit_migrations";
+
+/// Advisory lock key held while applying or reverting a migration.
+const MIGRATION_LOCK_KEY: i64 = 0x7067_746f_6f6c_6b74;
+
+/// A single migration: SQL to apply and, optionally, SQL to revert it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Migration {
+    /// Ordering key; migrations apply in ascending version order.
+    pub version: i64,
+    pub name: String,
+    pub up: String,
+    /// Reverting SQL; `None` makes the migration irreversible.
+    pub down: Option<String>,
+}
+
+impl Migration {
+    pub fn new(version: i64, name: impl Into<String>, up: impl Into<String>) -> Self {
+        Self {
+            version,
+            name: name.into(),
+            up: up.into(),
+            down: None,
+        }
+    }
+
+    /// Set the SQL that reverts this migration.
+    pub fn with_down(mut self, down: impl Into<String>) -> Self {
+        self.down = Some(down.into());
+        self
+    }
+}
+
+/// Whether a known migration has been applied.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub name: String,
+    /// When it was applied; `None` if pending.
+    pub applied_at: Option<DateTime<Utc>>,
+}
+
+/// Applies and reverts an ordered set of migrations.
+#[derive(Debug, Clone, Default)]
+pub struct Migrator {
+    migrations: Vec<Migration>,
+}
+
+impl Migrator {
+    /// Create a migrator from embedded migrations. Fails on duplicate versions.
+    pub fn new(mut migrations: Vec<Migration>) -> Result<Self> {
+        migrations.sort_by_key(|m| m.version);
+        if let Some(pair) = migrations.windows(2).find(|w| w[0].version == w[1].version) {
+            return Err(anyhow!(
+                "Duplicate migration version {} ('{}' and '{}')",
+                pair[0].version,
+                pair[0].name,
+                pair[1].name
+            ).into());
+        }
+        Ok(Self { migrations })
+    }
+
+    /// Load migrations from `.sql` files in a directory.
+    ///
+    /// Files are named `<version>_<name>.up.sql` with an optional matching
+    /// `<version>_<name>.down.sql`; a plain `<version>_<name>.sql` is an
+    /// irreversible up migration. Other files are ignored.
+    pub fn from_dir(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let entries = std::fs::read_dir(path)
+            .with_context(|| format!("Failed to read migrations directory: {:?}", path))?;
+
+        let mut ups: BTreeMap<i64, (String, String)> = BTreeMap::new();
+        let mut downs: BTreeMap<i64, String> = BTreeMap::new();
+        for entry in entries {
+            let file = entry
+                .with_context(|| format!("Failed to read migrations directory: {:?}", path))?
+                .path();
+            let Some(file_name) = file.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let Some(stem) = file_name.strip_suffix(".sql") else {
+                continue;
+            };
+            let (stem, is_down) = match stem.strip_suffix(".down") {
+                Some(stem) => (stem, true),
+                None => (stem.strip_suffix(".up").unwrap_or(stem), false),
+            };
+            let (version, name) = parse_migration_stem(stem)
+                .with_context(|| format!("Invalid migration file name: {:?}", file))?;
+            let sql = std::fs::read_to_string(&file)
+                .with_context(|| format!("Failed to read migration: {:?}", file))?;
+
+            let previous = if is_down {
+                downs.insert(version, sql).map(|_| ())
+            } else {
+                ups.insert(version, (name.to_string(), sql)).map(|_| ())
+            };
+            if previous.is_some() {
+                return Err(anyhow!("Duplicate migration version {} in {:?}", version, path).into());
+            }
+        }
+
+        if let Some(version) = downs.keys().find(|v| !ups.contains_key(v)) {
+            return Err(anyhow!("Down migration {} has no matching up migration", version).into());
+        }
+        let migrations = ups
+            .into_iter()
+            .map(|(version, (name, up))| Migration {
+                version,
+                name,
+                up,
+                down: downs.remove(&version),
+            })
+            .collect();
+        Self::new(migrations)
+    }
+
+    /// Known migrations, in version order.
+    pub fn migrations(&self) -> &[Migration] {
+        &self.migrations
+    }
+
+    /// Apply every pending migration in version order. Returns the versions
+    /// applied, which is empty when the database is up to date.
+    #[tracing::instrument(level = "debug", skip(self, pool))]
+    pub async fn migrate_up(&self, pool: &PgPool) -> Result<Vec<i64>> {
+        traced(async {
+            ensure_migrations_table(pool).await?;
+
+            let mut applied_now = vec![];
+            for migration in &self.migrations {
+                let mut tx = pool.begin().await.context("Failed to begin migration")?;
+                lock(&mut tx).await?;
+                if is_applied(&mut tx, migration.version).await? {
+                    continue;
+                }
+
+                sqlx::raw_sql(&migration.up)
+                    .execute(&mut *tx)
+                    .await
+                    .with_context(|| format!(
+                        "Migration {} ({}) failed", migration.version, migration.name))?;
+                sqlx::query(&format!(
+                    "INSERT INTO {} (version, name) VALUES ($1, $2)", MIGRATIONS_TABLE))
+                    .bind(migration.version)
+                    .bind(&migration.name)
+                    .execute(&mut *tx)
+                    .await
+                    .context("Failed to record migration")?;
+                tx.commit().await.context("Failed to commit migration")?;
+
+                tracing::info!("Applied migration {} ({})", migration.version, migration.name);
+                applied_now.push(migration.version);
+            }
+            Ok(applied_now)
+        })
+        .await
+    }
+
+    /// Revert the `steps` most recently applied migrations, newest first.
+    /// Returns the versions reverted. Fails without reverting anything further
+    /// on an irreversible or unknown migration.
+    #[tracing::instrument(level = "debug", skip(self, pool))]
+    pub async fn migrate_down(&self, pool: &PgPool, steps: usize) -> Result<Vec<i64>> {
+        traced(async {
+            ensure_migrations_table(pool).await?;
+
+            let mut reverted = vec![];
+            for _ in 0..steps {
+                let mut tx = pool.begin().await.context("Failed to begin migration")?;
+                lock(&mut tx).await?;
+                let latest: Option<i64> = sqlx::query_scalar(&format!(
+                    "SELECT max(version) FROM {}", MIGRATIONS_TABLE))
+                    .fetch_one(&mut *tx)
+                    .await
+                    .context("Failed to read applied migrations")?;
+                let Some(version) = latest else {
+                    break;
+                };
+
+                let Some(migration) = self.migrations.iter().find(|m| m.version == version) else {
+                    return Err(anyhow!("Applied migration {} is not known to this migrator", version).into());
+                };
+                let Some(down) = &migration.down else {
+                    return Err(anyhow!("Migration {} ({}) is irreversible", version, migration.name).into());
+                };
+
+                sqlx::raw_sql(down)
+                    .execute(&mut *tx)
+                    .await
+                    .with_context(|| format!(
+                        "Reverting migration {} ({}) failed", version, migration.name))?;
+                sqlx::query(&format!("DELETE FROM {} WHERE version = $1", MIGRATIONS_TABLE))
+                    .bind(version)
+                    .execute(&mut *tx)
+                    .await
+                    .context("Failed to record migration revert")?;
+                tx.commit().await.context("Failed to commit migration revert")?;
+
+                tracing::info!("Reverted migration {} ({})", version, migration.name);
+                reverted.push(version);
+            }
+            Ok(reverted)
+        })
+        .await
+    }
+
+    /// Applied state of every known migration, in version order.
+    #[tracing::instrument(level = "debug", skip(self, pool))]
+    pub async fn status(&self, pool: &PgPool) -> Result<Vec<MigrationStatus>> {
+        traced(async {
+            ensure_migrations_table(pool).await?;
+
+            let applied: BTreeMap<i64, DateTime<Utc>> = sqlx::query_as::<_, (i64, DateTime<Utc>)>(
+                &format!("SELECT version, applied_at FROM {}", MIGRATIONS_TABLE))
+                .fetch_all(pool)
+                .await
+                .context("Failed to read applied migrations")?
+                .into_iter()
+                .collect();
+
+            Ok(self
+                .migrations
+                .iter()
+                .map(|m| MigrationStatus {
+                    version: m.version,
+                    name: m.name.clone(),
+                    applied_at: applied.get(&m.version).copied(),
+                })
+                .collect())
+        })
+        .await
+    }
+}
+
+/// Split `0001_create_users` into `(1, "create_users")`.
+fn parse_migration_stem(stem: &str) -> anyhow::Result<(i64, &str)> {
+    let (version, name) = stem.split_once('_').unwrap_or((stem, ""));
+    let version = version
+        .parse()
+        .with_context(|| format!("'{}' does not start with a numeric version", stem))?;
+    Ok((version, name))
+}
+
+async fn ensure_migrations_table(pool: &PgPool) -> anyhow::Result<()> {
+    sqlx::query(&format!(
+        "CREATE TABLE IF NOT EXISTS {} (\
+             version BIGINT PRIMARY KEY, \
+             name TEXT NOT NULL, \
+             applied_at TIMESTAMPTZ NOT NULL DEFAULT now())",
+        MIGRATIONS_TABLE
+    ))
+    .execute(pool)
+    .await
+    .with_context(|| format!("Failed to create {} table", MIGRATIONS_TABLE))?;
+    Ok(())
+}
+
+async fn lock(tx: &mut sqlx::PgTransaction<'_>) -> anyhow::Result<()> {
+    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut **tx)
+        .await
+        .context("Failed to acquire migration lock")?;
+    Ok(())
+}
+
+async fn is_applied(tx: &mut sqlx::PgTransaction<'_>, version: i64) -> anyhow::Result<bool> {
+    let exists: Option<i32> = sqlx::query_scalar(&format!(
+        "SELECT 1 FROM {} WHERE version = $1", MIGRATIONS_TABLE))
+        .bind(version)
+        .fetch_optional(&mut **tx)
+        .await
+        .context("Failed to read applied migrations")?;
+    Ok(exists.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_sorts_and_rejects_duplicates() {
+        let migrator = Migrator::new(vec![
+            Migration::new(2, "second", "SELECT 2"),
+            Migration::new(1, "first", "SELECT 1"),
+        ])
+        .unwrap();
+        let versions: Vec<i64> = migrator.migrations().iter().map(|m| m.version).collect();
+        assert_eq!(versions, [1, 2]);
+
+        assert!(Migrator::new(vec![
+            Migration::new(1, "a", "SELECT 1"),
+            Migration::new(1, "b", "SELECT 1"),
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_from_dir() {
+        let dir = std::env::temp_dir().join(format!("pg_toolkit_migrations_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("0001_create_users.up.sql"), "CREATE TABLE users (id INT);").unwrap();
+        std::fs::write(dir.join("0001_create_users.down.sql"), "DROP TABLE users;").unwrap();
+        std::fs::write(dir.join("0002_seed.sql"), "INSERT INTO users VALUES (1);").unwrap();
+        std::fs::write(dir.join("README.md"), "ignored").unwrap();
+
+        let migrator = Migrator::from_dir(&dir).unwrap();
+        let migrations = migrator.migrations();
+        assert_eq!(migrations.len(), 2);
+        assert_eq!(migrations[0].name, "create_users");
+        assert_eq!(migrations[0].down.as_deref(), Some("DROP TABLE users;"));
+        assert_eq!(migrations[1].version, 2);
+        assert_eq!(migrations[1].down, None);
+
+        std::fs::write(dir.join("first.sql"), "SELECT 1").unwrap();
+        assert!(Migrator::from_dir(&dir).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,268 @@
+//! Live server activity from `pg_stat_activity`.
+//!
+//! Views of what client sessions are doing right now, and helpers to end
+//! them: useful for finding connection leaks, lock waits and runaway queries.
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::time::Duration;
+
+use crate::config::PgConfig;
+use crate::connection::create_system_pool;
+use crate::error::Result;
+use crate::statement_tracing::traced;
+
+/// One client session, from a row of `pg_stat_activity`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SessionInfo {
+    /// Backend process id, as taken by `pg_terminate_backend`.
+    pub pid: i32,
+    pub user: Option<String>,
+    pub database: Option<String>,
+    pub application_name: String,
+    /// Client IP address; `None` for unix socket connections.
+    pub client_addr: Option<String>,
+    /// `"active"`, `"idle"`, `"idle in transaction"`, etc.
+    pub state: Option<String>,
+    /// Current query, or the last one if the session is idle.
+    pub query: Option<String>,
+    /// Class of what the session is waiting on, e.g. `"Lock"` or `"Client"`.
+    pub wait_event_type: Option<String>,
+    pub wait_event: Option<String>,
+    pub backend_start: Option<DateTime<Utc>>,
+    pub query_start: Option<DateTime<Utc>>,
+    /// Time since the current (or last) query started.
+    pub query_duration: Option<Duration>,
+    /// Time since the session entered its current state.
+    pub state_duration: Option<Duration>,
+}
+
+/// Which sessions `cancel_queries_running_longer_than` applies to. Unset
+/// fields match everything.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct CancelFilter {
+    pub database: Option<String>,
+    pub user: Option<String>,
+    pub application_name: Option<String>,
+    /// Report the matching sessions without cancelling anything.
+    pub dry_run: bool,
+}
+
+/// Columns selected for `SessionInfo`, in field order.
+const SESSION_COLUMNS: &str = "pid, usename::text, datname::text, application_name, \
+     host(client_addr), state, query, wait_event_type, wait_event, \
+     backend_start, query_start, \
+     greatest(extract(epoch FROM clock_timestamp() - query_start), 0)::float8, \
+     greatest(extract(epoch FROM clock_timestamp() - state_change), 0)::float8";
+
+type SessionRow = (
+    i32,
+    Option<String>,
+    Option<String>,
+    String,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<DateTime<Utc>>,
+    Option<DateTime<Utc>>,
+    Option<f64>,
+    Option<f64>,
+);
+
+fn session_from_row(row: SessionRow) -> SessionInfo {
+    let (
+        pid,
+        user,
+        database,
+        application_name,
+        client_addr,
+        state,
+        query,
+        wait_event_type,
+        wait_event,
+        backend_start,
+        query_start,
+        query_secs,
+        state_secs,
+    ) = row;
+    SessionInfo {
+        pid,
+        user,
+        database,
+        application_name,
+        client_addr,
+        state,
+        query,
+        wait_event_type,
+        wait_event,
+        backend_start,
+        query_start,
+        query_duration: query_secs.map(Duration::from_secs_f64),
+        state_duration: state_secs.map(Duration::from_secs_f64),
+    }
+}
+
+/// List client sessions on the server, excluding the one running this query
+/// and background workers, ordered by pid.
+///
+/// Without superuser or `pg_read_all_stats`, other roles' sessions are listed
+/// with `query` and wait details hidden.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn list_activity(pool: &PgPool) -> Result<Vec<SessionInfo>> {
+    traced(async {
+        let rows = sqlx::query_as::<_, SessionRow>(&format!(
+            "SELECT {} FROM pg_stat_activity \
+             WHERE backend_type = 'client backend' AND pid <> pg_backend_pid() \
+             ORDER BY pid",
+            SESSION_COLUMNS
+        ))
+        .fetch_all(pool)
+        .await
+        .context("Failed to list activity")?;
+
+        Ok(rows.into_iter().map(session_from_row).collect())
+    })
+    .await
+}
+
+/// Terminate one backend with `pg_terminate_backend`. Returns false if no
+/// such process exists.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn terminate_backend(pool: &PgPool, pid: i32) -> Result<bool> {
+    traced(async {
+        let terminated: bool = sqlx::query_scalar("SELECT pg_terminate_backend($1)")
+            .bind(pid)
+            .fetch_one(pool)
+            .await
+            .with_context(|| format!("Failed to terminate backend {}", pid))?;
+
+        if terminated {
+            tracing::info!("Terminated backend {}", pid);
+        }
+        Ok(terminated)
+    })
+    .await
+}
+
+// The candidate sessions are materialized first so the planner cannot call
+// pg_terminate_backend on rows before the other filters have been applied.
+
+/// Terminate every session connected to `database` other than the caller's.
+/// Returns the pids terminated.
+///
+/// Run this from a pool on a different database (e.g. the system pool) before
+/// dropping or renaming `database`.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn terminate_connections(pool: &PgPool, database: &str) -> Result<Vec<i32>> {
+    traced(async {
+        let pids: Vec<i32> = sqlx::query_scalar(
+            "WITH targets AS MATERIALIZED ( \
+                 SELECT pid FROM pg_stat_activity \
+                 WHERE datname = $1 AND pid <> pg_backend_pid()) \
+             SELECT pid FROM targets WHERE pg_terminate_backend(pid) ORDER BY pid",
+        )
+        .bind(database)
+        .fetch_all(pool)
+        .await
+        .with_context(|| format!("Failed to terminate connections to '{}'", database))?;
+
+        if !pids.is_empty() {
+            tracing::info!("Terminated {} connection(s) to '{}'", pids.len(), database);
+        }
+        Ok(pids)
+    })
+    .await
+}
+
+/// Terminate sessions on `database` that have been idle, or idle in a
+/// transaction, for longer than `idle_longer_than`. Returns the pids
+/// terminated.
+#[tracing::instrument(level = "debug", skip(config))]
+pub async fn terminate_idle_connections(
+    config: &PgConfig,
+    database: &str,
+    idle_longer_than: Duration,
+) -> Result<Vec<i32>> {
+    traced(async {
+        let pool = create_system_pool(config).await
+            .context("Failed to connect to system database")?;
+
+        let pids: Vec<i32> = sqlx::query_scalar(
+            "WITH targets AS MATERIALIZED ( \
+                 SELECT pid FROM pg_stat_activity \
+                 WHERE datname = $1 AND pid <> pg_backend_pid() \
+                   AND state LIKE 'idle%' \
+                   AND state_change < clock_timestamp() - make_interval(secs => $2)) \
+             SELECT pid FROM targets WHERE pg_terminate_backend(pid) ORDER BY pid",
+        )
+        .bind(database)
+        .bind(idle_longer_than.as_secs_f64())
+        .fetch_all(&pool)
+        .await
+        .with_context(|| format!("Failed to terminate idle connections to '{}'", database))?;
+
+        if !pids.is_empty() {
+            tracing::info!(
+                "Terminated {} connection(s) to '{}' idle for over {:?}",
+                pids.len(),
+                database,
+                idle_longer_than
+            );
+        }
+        Ok(pids)
+    })
+    .await
+}
+
+/// Cancel active queries that have been running for longer than `duration`
+/// and match `filter`, with `pg_cancel_backend`. The sessions stay connected;
+/// only their current query is interrupted. Returns the sessions cancelled,
+/// or with `filter.dry_run`, those that would have been.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn cancel_queries_running_longer_than(
+    pool: &PgPool,
+    duration: Duration,
+    filter: &CancelFilter,
+) -> Result<Vec<SessionInfo>> {
+    traced(async {
+        let rows = sqlx::query_as::<_, SessionRow>(&format!(
+            "WITH targets AS MATERIALIZED ( \
+                 SELECT {} FROM pg_stat_activity \
+                 WHERE backend_type = 'client backend' AND pid <> pg_backend_pid() \
+                   AND state = 'active' \
+                   AND query_start < clock_timestamp() - make_interval(secs => $1) \
+                   AND ($2::text IS NULL OR datname = $2) \
+                   AND ($3::text IS NULL OR usename = $3) \
+                   AND ($4::text IS NULL OR application_name = $4)) \
+             SELECT * FROM targets \
+             WHERE CASE WHEN $5 THEN true ELSE pg_cancel_backend(pid) END \
+             ORDER BY pid",
+            SESSION_COLUMNS
+        ))
+        .bind(duration.as_secs_f64())
+        .bind(&filter.database)
+        .bind(&filter.user)
+        .bind(&filter.application_name)
+        .bind(filter.dry_run)
+        .fetch_all(pool)
+        .await
+        .context("Failed to cancel long-running queries")?;
+
+        let sessions: Vec<SessionInfo> = rows.into_iter().map(session_from_row).collect();
+        if !filter.dry_run {
+            for session in &sessions {
+                tracing::info!(
+                    "Cancelled query on backend {} running for {:?}",
+                    session.pid,
+                    session.query_duration.unwrap_or_default()
+                );
+            }
+        }
+        Ok(sessions)
+    })
+    .await
+}
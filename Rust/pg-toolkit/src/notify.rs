@@ -0,0 +1,115 @@
+//! LISTEN/NOTIFY: subscribe to notification channels and send notifications.
+//!
+//! `listen` keeps a dedicated connection subscribed and re-subscribes after
+//! the connection drops (server restart, failover, terminated backend).
+//! Notifications sent while disconnected are lost, so consumers that cache
+//! should treat a reconnect as "anything may have changed"; it is logged as a
+//! warning.
+
+use anyhow::Context;
+use futures_util::Stream;
+use futures_util::stream;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use sqlx::postgres::PgListener;
+use std::time::Duration;
+
+use crate::config::PgConfig;
+use crate::connection::{connect_options, pool_options};
+use crate::error::Result;
+use crate::statement_tracing::traced;
+
+/// First delay before retrying after a listener error.
+const INITIAL_RETRY_DELAY: Duration = Duration::from_millis(100);
+/// Cap for the doubling retry delay.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// A notification received on a subscribed channel.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Notification {
+    pub channel: String,
+    pub payload: String,
+    /// Backend process ID of the notifying session.
+    pub process_id: u32,
+}
+
+/// Subscribe to `channel` and stream its notifications.
+///
+/// Connects (and subscribes) before returning, so connection errors surface
+/// here; afterwards errors are logged and retried with capped backoff and the
+/// stream never ends. Drop the stream to unsubscribe.
+///
+/// # Example
+/// ```rust,no_run
+/// use futures_util::StreamExt;
+/// use pg_toolkit::{PgConfig, notify::listen};
+///
+/// # async fn example() -> anyhow::Result<()> {
+/// let mut notifications = Box::pin(listen(&PgConfig::from_env(), "cache_invalidation").await?);
+/// while let Some(notification) = notifications.next().await {
+///     println!("{}: {}", notification.channel, notification.payload);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[tracing::instrument(level = "debug", skip(config))]
+pub async fn listen(config: &PgConfig, channel: &str) -> Result<impl Stream<Item = Notification> + use<>> {
+    traced(async {
+        // The listener takes a connection from this pool again after losing one
+        let pool = pool_options(config)
+            .max_connections(1)
+            .min_connections(0)
+            .connect_lazy_with(connect_options(config)?);
+        let mut listener = PgListener::connect_with(&pool)
+            .await
+            .context("Failed to connect listener")?;
+        listener
+            .listen(channel)
+            .await
+            .with_context(|| format!("Failed to LISTEN on '{}'", channel))?;
+
+        Ok(stream::unfold(listener, |mut listener| async move {
+            let mut delay = INITIAL_RETRY_DELAY;
+            loop {
+                match listener.try_recv().await {
+                    Ok(Some(notification)) => {
+                        let notification = Notification {
+                            channel: notification.channel().to_string(),
+                            payload: notification.payload().to_string(),
+                            process_id: notification.process_id(),
+                        };
+                        return Some((notification, listener));
+                    }
+                    // Connection lost; the listener has re-subscribed
+                    Ok(None) => {
+                        tracing::warn!(
+                            "Listener connection lost and re-established; \
+                             notifications may have been missed");
+                    }
+                    Err(e) => {
+                        tracing::warn!("Listener error ({}); retrying in {:?}", e, delay);
+                        tokio::time::sleep(delay).await;
+                        delay = (delay * 2).min(MAX_RETRY_DELAY);
+                    }
+                }
+            }
+        }))
+    })
+    .await
+}
+
+/// Send `payload` on `channel` (`pg_notify`). Delivered when the current
+/// transaction commits, or immediately outside one.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn notify(pool: &PgPool, channel: &str, payload: &str) -> Result<()> {
+    traced(async {
+        sqlx::query("SELECT pg_notify($1, $2)")
+            .bind(channel)
+            .bind(payload)
+            .execute(pool)
+            .await
+            .with_context(|| format!("Failed to notify on '{}'", channel))?;
+        Ok(())
+    })
+    .await
+}
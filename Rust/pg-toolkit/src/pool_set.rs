@@ -0,0 +1,191 @@
+//! Primary plus read-replica pools.
+//!
+//! A `PoolSet` sends writes to the primary and spreads reads round-robin
+//! across replicas, falling back to the primary when none are configured.
+//! Routing is by call site: nothing stops a write being issued on `read()`,
+//! and replica lag means a read may not yet see a write just made.
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::config::{PgConfig, deserialize_optional_number, read_yaml};
+use crate::connection::create_pool;
+use crate::error::Result;
+use crate::statement_tracing::traced;
+
+/// Where a read replica differs from the primary. Everything else (user,
+/// database, TLS, pool tuning, ...) is taken from the primary's config.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReplicaConfig {
+    pub host: String,
+    /// Defaults to the primary's port.
+    #[serde(default, deserialize_with = "deserialize_optional_number")]
+    pub port: Option<u16>,
+}
+
+/// Config for a `PoolSet`: the primary's `PgConfig` plus a `replicas` list.
+///
+/// In YAML the primary's keys sit at the top level next to `replicas`:
+///
+/// ```yaml
+/// host: "db-primary"
+/// port: 5432
+/// user: "app"
+/// password: "secret"
+/// database: "app"
+/// replicas:
+///   - host: "db-replica-1"
+///   - host: "db-replica-2"
+///     port: 5433
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PoolSetConfig {
+    #[serde(flatten)]
+    pub primary: PgConfig,
+    #[serde(default)]
+    pub replicas: Vec<ReplicaConfig>,
+}
+
+impl PoolSetConfig {
+    /// Load from a YAML file laid out as above, expanding `${VAR}`
+    /// placeholders as `PgConfig::from_yaml` does.
+    pub fn from_yaml(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let config: Self = serde_yaml::from_value(read_yaml(path)?)
+            .with_context(|| format!("Failed to parse config file: {:?}", path))?;
+        Ok(config)
+    }
+
+    /// Full connection config for each replica, in order.
+    pub fn replica_configs(&self) -> Vec<PgConfig> {
+        self.replicas
+            .iter()
+            .map(|replica| PgConfig {
+                host: replica.host.clone(),
+                port: replica.port.unwrap_or(self.primary.port),
+                ..self.primary.clone()
+            })
+            .collect()
+    }
+}
+
+/// A primary pool and zero or more read-replica pools. Cheap to clone; clones
+/// share the pools and the round-robin position.
+#[derive(Debug, Clone)]
+pub struct PoolSet {
+    primary: PgPool,
+    replicas: Arc<[PgPool]>,
+    next_replica: Arc<AtomicUsize>,
+}
+
+impl PoolSet {
+    /// Build from existing pools.
+    pub fn new(primary: PgPool, replicas: Vec<PgPool>) -> Self {
+        Self {
+            primary,
+            replicas: replicas.into(),
+            next_replica: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Connect to the primary and every replica. Fails if any of them is
+    /// unreachable.
+    #[tracing::instrument(level = "debug", skip(config))]
+    pub async fn connect(config: &PoolSetConfig) -> Result<Self> {
+        traced(async {
+            let primary = create_pool(&config.primary)
+                .await
+                .with_context(|| format!("Failed to connect to primary at {}", config.primary.host))?;
+            let mut replicas = Vec::with_capacity(config.replicas.len());
+            for replica in config.replica_configs() {
+                let pool = create_pool(&replica).await.with_context(|| {
+                    format!("Failed to connect to replica at {}:{}", replica.host, replica.port)
+                })?;
+                replicas.push(pool);
+            }
+            tracing::info!("Connected pool set with {} replica(s)", replicas.len());
+            Ok(Self::new(primary, replicas))
+        })
+        .await
+    }
+
+    /// Pool for writes, and for reads that must see their own writes.
+    pub fn write(&self) -> &PgPool {
+        &self.primary
+    }
+
+    /// Pool for reads: the next replica in round-robin order, or the primary
+    /// if there are no replicas.
+    pub fn read(&self) -> &PgPool {
+        if self.replicas.is_empty() {
+            return &self.primary;
+        }
+        let i = self.next_replica.fetch_add(1, Ordering::Relaxed) % self.replicas.len();
+        &self.replicas[i]
+    }
+
+    pub fn replicas(&self) -> &[PgPool] {
+        &self.replicas
+    }
+
+    /// Close the primary and all replica pools.
+    pub async fn close(&self) {
+        self.primary.close().await;
+        for replica in self.replicas.iter() {
+            replica.close().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::create_pool_lazy;
+
+    #[test]
+    fn test_config_from_yaml() {
+        let config: PoolSetConfig = serde_yaml::from_str(
+            "host: primary\nport: 5432\nuser: app\npassword: secret\ndatabase: app\n\
+             replicas:\n  - host: replica-1\n  - host: replica-2\n    port: 5433\n",
+        )
+        .unwrap();
+        assert_eq!(config.primary.host, "primary");
+        assert_eq!(config.primary.database.as_deref(), Some("app"));
+
+        let replicas = config.replica_configs();
+        assert_eq!(replicas.len(), 2);
+        assert_eq!((replicas[0].host.as_str(), replicas[0].port), ("replica-1", 5432));
+        assert_eq!((replicas[1].host.as_str(), replicas[1].port), ("replica-2", 5433));
+        assert_eq!(replicas[1].user, "app");
+        assert_eq!(replicas[1].database.as_deref(), Some("app"));
+
+        let no_replicas: PoolSetConfig = serde_yaml::from_str(
+            "host: primary\nport: 5432\nuser: app\npassword: secret\ndatabase: null\n",
+        )
+        .unwrap();
+        assert!(no_replicas.replicas.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_read_round_robin() {
+        let pool = |host: &str| {
+            create_pool_lazy(&PgConfig { host: host.into(), ..PgConfig::default() }).unwrap()
+        };
+        let host = |pool: &PgPool| pool.connect_options().get_host().to_string();
+
+        let alone = PoolSet::new(pool("primary"), vec![]);
+        assert_eq!(host(alone.read()), "primary");
+
+        let set = PoolSet::new(pool("primary"), vec![pool("replica-a"), pool("replica-b")]);
+        let clone = set.clone();
+        assert_eq!(host(set.write()), "primary");
+        assert_eq!(host(set.read()), "replica-a");
+        assert_eq!(host(clone.read()), "replica-b");
+        assert_eq!(host(set.read()), "replica-a");
+        assert_eq!(set.replicas().len(), 2);
+    }
+}
@@ -0,0 +1,151 @@
+//! Builders for statements that are tedious and error-prone to concatenate by
+//! hand. Identifiers are validated and quoted; values stay bind parameters.
+
+use anyhow::{anyhow, bail};
+
+use crate::error::Result;
+use crate::identifier::{MAX_IDENTIFIER_LEN, quote_identifier, quote_qualified, validate_qualified};
+
+/// Build `INSERT INTO table (columns) VALUES ($1, ...) ON CONFLICT
+/// (conflict_target) DO UPDATE SET col = EXCLUDED.col, ...`, with one bind
+/// parameter per column in `columns` order. With no `update_columns` the
+/// statement is `ON CONFLICT ... DO NOTHING` (and `conflict_target` may be
+/// empty, to skip rows violating any constraint).
+///
+/// `table` may be schema-qualified. Every update column must also be
+/// inserted, since `EXCLUDED` only holds the proposed row.
+///
+/// # Example
+/// ```rust
+/// let sql = pg_toolkit::query_builder::upsert(
+///     "users",
+///     &["email", "name"],
+///     &["email"],
+///     &["name"],
+/// )
+/// .unwrap();
+/// assert_eq!(
+///     sql,
+///     "INSERT INTO \"users\" (\"email\", \"name\") VALUES ($1, $2) \
+///      ON CONFLICT (\"email\") DO UPDATE SET \"name\" = EXCLUDED.\"name\""
+/// );
+/// ```
+pub fn upsert(
+    table: &str,
+    columns: &[&str],
+    conflict_target: &[&str],
+    update_columns: &[&str],
+) -> Result<String> {
+    validate_qualified(table)?;
+    if columns.is_empty() {
+        return Err(anyhow!("Upsert into '{}' needs at least one column", table).into());
+    }
+    for (i, column) in columns.iter().enumerate() {
+        validate_column(column)?;
+        if columns[..i].contains(column) {
+            return Err(anyhow!("Column '{}' is listed twice", column).into());
+        }
+    }
+    for column in conflict_target {
+        validate_column(column)?;
+    }
+    for column in update_columns {
+        if !columns.contains(column) {
+            return Err(anyhow!("Update column '{}' is not among the inserted columns", column).into());
+        }
+    }
+    if !update_columns.is_empty() && conflict_target.is_empty() {
+        return Err(anyhow!("ON CONFLICT DO UPDATE needs a conflict target").into());
+    }
+
+    let placeholders: Vec<String> = (1..=columns.len()).map(|i| format!("${}", i)).collect();
+    let mut sql = format!(
+        "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT",
+        quote_qualified(table),
+        quote_list(columns),
+        placeholders.join(", ")
+    );
+    if !conflict_target.is_empty() {
+        sql.push_str(&format!(" ({})", quote_list(conflict_target)));
+    }
+    if update_columns.is_empty() {
+        sql.push_str(" DO NOTHING");
+    } else {
+        let assignments: Vec<String> = update_columns
+            .iter()
+            .map(|column| {
+                let quoted = quote_identifier(column);
+                format!("{} = EXCLUDED.{}", quoted, quoted)
+            })
+            .collect();
+        sql.push_str(&format!(" DO UPDATE SET {}", assignments.join(", ")));
+    }
+    Ok(sql)
+}
+
+/// A column name is a single identifier: non-empty, without NUL bytes and no
+/// longer than `MAX_IDENTIFIER_LEN` bytes. Dots are part of the name.
+fn validate_column(name: &str) -> anyhow::Result<()> {
+    if name.is_empty() {
+        bail!("Invalid column name: empty identifier");
+    }
+    if name.contains('\0') {
+        bail!("Invalid column name '{}': identifiers cannot contain NUL", name);
+    }
+    if name.len() > MAX_IDENTIFIER_LEN {
+        bail!(
+            "Invalid column name '{}': longer than {} bytes",
+            name, MAX_IDENTIFIER_LEN
+        );
+    }
+    Ok(())
+}
+
+fn quote_list(names: &[&str]) -> String {
+    names.iter().map(|name| quote_identifier(name)).collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upsert_do_update() {
+        let sql = upsert("kb.docs", &["id", "title", "body"], &["id"], &["title", "body"]).unwrap();
+        assert_eq!(
+            sql,
+            "INSERT INTO \"kb\".\"docs\" (\"id\", \"title\", \"body\") VALUES ($1, $2, $3) \
+             ON CONFLICT (\"id\") DO UPDATE SET \"title\" = EXCLUDED.\"title\", \
+             \"body\" = EXCLUDED.\"body\""
+        );
+    }
+
+    #[test]
+    fn test_upsert_do_nothing() {
+        assert_eq!(
+            upsert("tags", &["name"], &["name"], &[]).unwrap(),
+            "INSERT INTO \"tags\" (\"name\") VALUES ($1) ON CONFLICT (\"name\") DO NOTHING"
+        );
+        assert_eq!(
+            upsert("tags", &["name"], &[], &[]).unwrap(),
+            "INSERT INTO \"tags\" (\"name\") VALUES ($1) ON CONFLICT DO NOTHING"
+        );
+    }
+
+    #[test]
+    fn test_upsert_quotes_identifiers() {
+        let sql = upsert("Odd \"t\"", &["a.b"], &["a.b"], &["a.b"]).unwrap();
+        assert!(sql.starts_with("INSERT INTO \"Odd \"\"t\"\"\" (\"a.b\")"));
+    }
+
+    #[test]
+    fn test_upsert_rejects_invalid_input() {
+        assert!(upsert("t", &[], &[], &[]).is_err());
+        assert!(upsert("a.b.c", &["x"], &[], &[]).is_err());
+        assert!(upsert("t", &["x", "x"], &[], &[]).is_err());
+        assert!(upsert("t", &[""], &[], &[]).is_err());
+        assert!(upsert("t", &["x"], &["bad\0"], &[]).is_err());
+        assert!(upsert("t", &["x"], &["x"], &["y"]).is_err());
+        assert!(upsert("t", &["x"], &[], &["x"]).is_err());
+    }
+}
@@ -0,0 +1,412 @@
+//! Schema drift detection.
+//!
+//! A `SchemaSnapshot` records the tables, columns, indexes and constraints of
+//! one schema,
+//! either captured from a live database or written by hand (e.g. in YAML) as
+//! a declarative description. `diff` compares an expected snapshot against an
+//! actual one and reports every difference as a structured `SchemaDiff`.
+//!
+//! Column types use PostgreSQL's `format_type` spelling, e.g. `integer`,
+//! `character varying(255)`, `timestamp with time zone` or `vector(1536)`.
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::error::Result;
+use crate::statement_tracing::traced;
+
+/// Tables of one schema, by name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SchemaSnapshot {
+    pub tables: BTreeMap<String, TableSchema>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TableSchema {
+    pub columns: BTreeMap<String, ColumnSchema>,
+    /// Index name to its `CREATE INDEX` definition. In a hand-written
+    /// snapshot the definition may be left out (`~`) to only require that the
+    /// index exists.
+    #[serde(default)]
+    pub indexes: BTreeMap<String, Option<String>>,
+    /// Constraint name to its definition from `pg_get_constraintdef`, e.g.
+    /// `PRIMARY KEY (id)` or `CHECK ((price > 0))`; optional as for indexes.
+    #[serde(default)]
+    pub constraints: BTreeMap<String, Option<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ColumnSchema {
+    /// Type as spelled by `format_type`, e.g. `character varying(255)`.
+    pub data_type: String,
+    #[serde(default = "default_nullable")]
+    pub nullable: bool,
+}
+
+fn default_nullable() -> bool {
+    true
+}
+
+/// A column, for columns present on only one side.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ColumnRef {
+    pub table: String,
+    pub column: String,
+}
+
+/// An index, for indexes present on only one side.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IndexRef {
+    pub table: String,
+    pub index: String,
+}
+
+/// A constraint, for constraints present on only one side.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConstraintRef {
+    pub table: String,
+    pub constraint: String,
+}
+
+/// A column, index or constraint present on both sides that differs.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Mismatch<T> {
+    pub table: String,
+    /// Column, index or constraint name.
+    pub name: String,
+    pub expected: T,
+    pub actual: T,
+}
+
+/// Differences between an expected and an actual schema. "Missing" items are
+/// expected but absent; "extra" items exist but were not expected.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SchemaDiff {
+    pub missing_tables: Vec<String>,
+    pub extra_tables: Vec<String>,
+    pub missing_columns: Vec<ColumnRef>,
+    pub extra_columns: Vec<ColumnRef>,
+    pub type_mismatches: Vec<Mismatch<String>>,
+    /// Expected and actual nullability (`true` = nullable).
+    pub nullability_mismatches: Vec<Mismatch<bool>>,
+    pub missing_indexes: Vec<IndexRef>,
+    pub extra_indexes: Vec<IndexRef>,
+    pub index_mismatches: Vec<Mismatch<String>>,
+    pub missing_constraints: Vec<ConstraintRef>,
+    pub extra_constraints: Vec<ConstraintRef>,
+    pub constraint_mismatches: Vec<Mismatch<String>>,
+}
+
+impl SchemaDiff {
+    /// True if the schemas match.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl SchemaSnapshot {
+    /// Load a declarative schema description from YAML:
+    ///
+    /// ```yaml
+    /// tables:
+    ///   users:
+    ///     columns:
+    ///       id: { data_type: integer, nullable: false }
+    ///       email: { data_type: "character varying(255)" }
+    ///     indexes:
+    ///       users_pkey: ~
+    ///     constraints:
+    ///       users_pkey: "PRIMARY KEY (id)"
+    /// ```
+    pub fn from_yaml(path: impl AsRef<Path>) -> Result<Self> {
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read schema file: {:?}", path.as_ref()))?;
+        let snapshot: Self = serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse schema file: {:?}", path.as_ref()))?;
+        Ok(snapshot)
+    }
+
+    /// Serialize in the layout `from_yaml` reads, e.g. to commit as a
+    /// snapshot test fixture.
+    pub fn to_yaml(&self) -> Result<String> {
+        Ok(serde_yaml::to_string(self).context("Failed to serialize schema snapshot")?)
+    }
+}
+
+/// Capture the tables (including partitioned tables), columns, indexes and
+/// constraints of `schema` in the connected database. `NOT NULL` is recorded
+/// as column nullability rather than as a constraint.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn snapshot(pool: &PgPool, schema: &str) -> Result<SchemaSnapshot> {
+    traced(async {
+        let tables: Vec<String> = sqlx::query_scalar(
+            "SELECT c.relname::text FROM pg_class c \
+             JOIN pg_namespace n ON n.oid = c.relnamespace \
+             WHERE n.nspname = $1 AND c.relkind IN ('r', 'p')",
+        )
+        .bind(schema)
+        .fetch_all(pool)
+        .await
+        .with_context(|| format!("Failed to list tables in schema '{}'", schema))?;
+
+        let columns = sqlx::query_as::<_, (String, String, String, bool)>(
+            "SELECT c.relname::text, a.attname::text, format_type(a.atttypid, a.atttypmod), \
+                    a.attnotnull \
+             FROM pg_attribute a \
+             JOIN pg_class c ON c.oid = a.attrelid \
+             JOIN pg_namespace n ON n.oid = c.relnamespace \
+             WHERE n.nspname = $1 AND c.relkind IN ('r', 'p') \
+               AND a.attnum > 0 AND NOT a.attisdropped",
+        )
+        .bind(schema)
+        .fetch_all(pool)
+        .await
+        .with_context(|| format!("Failed to list columns in schema '{}'", schema))?;
+
+        let indexes = sqlx::query_as::<_, (String, String, String)>(
+            "SELECT tc.relname::text, ic.relname::text, pg_get_indexdef(i.indexrelid) \
+             FROM pg_index i \
+             JOIN pg_class ic ON ic.oid = i.indexrelid \
+             JOIN pg_class tc ON tc.oid = i.indrelid \
+             JOIN pg_namespace n ON n.oid = tc.relnamespace \
+             WHERE n.nspname = $1 AND tc.relkind IN ('r', 'p')",
+        )
+        .bind(schema)
+        .fetch_all(pool)
+        .await
+        .with_context(|| format!("Failed to list indexes in schema '{}'", schema))?;
+
+        let constraints = sqlx::query_as::<_, (String, String, String)>(
+            "SELECT c.relname::text, con.conname::text, pg_get_constraintdef(con.oid) \
+             FROM pg_constraint con \
+             JOIN pg_class c ON c.oid = con.conrelid \
+             JOIN pg_namespace n ON n.oid = c.relnamespace \
+             WHERE n.nspname = $1 AND c.relkind IN ('r', 'p') AND con.contype <> 'n'",
+        )
+        .bind(schema)
+        .fetch_all(pool)
+        .await
+        .with_context(|| format!("Failed to list constraints in schema '{}'", schema))?;
+
+        let mut snapshot = SchemaSnapshot::default();
+        for table in tables {
+            snapshot.tables.insert(table, TableSchema::default());
+        }
+        for (table, column, data_type, not_null) in columns {
+            if let Some(t) = snapshot.tables.get_mut(&table) {
+                t.columns.insert(column, ColumnSchema { data_type, nullable: !not_null });
+            }
+        }
+        for (table, index, definition) in indexes {
+            if let Some(t) = snapshot.tables.get_mut(&table) {
+                t.indexes.insert(index, Some(definition));
+            }
+        }
+        for (table, constraint, definition) in constraints {
+            if let Some(t) = snapshot.tables.get_mut(&table) {
+                t.constraints.insert(constraint, Some(definition));
+            }
+        }
+        Ok(snapshot)
+    })
+    .await
+}
+
+/// Compare two databases' copies of `schema`, e.g. production (`expected`)
+/// against staging (`actual`).
+#[tracing::instrument(level = "debug", skip(expected, actual))]
+pub async fn diff_databases(expected: &PgPool, actual: &PgPool, schema: &str) -> Result<SchemaDiff> {
+    traced(async {
+        let expected = snapshot(expected, schema).await.context("Failed to snapshot expected schema")?;
+        let actual = snapshot(actual, schema).await.context("Failed to snapshot actual schema")?;
+        Ok(diff(&expected, &actual))
+    })
+    .await
+}
+
+/// Compare an expected schema against an actual one. Results are ordered by
+/// table, then column, index or constraint name.
+pub fn diff(expected: &SchemaSnapshot, actual: &SchemaSnapshot) -> SchemaDiff {
+    let mut result = SchemaDiff::default();
+
+    for name in actual.tables.keys().filter(|t| !expected.tables.contains_key(*t)) {
+        result.extra_tables.push(name.clone());
+    }
+    for (table, want) in &expected.tables {
+        let Some(have) = actual.tables.get(table) else {
+            result.missing_tables.push(table.clone());
+            continue;
+        };
+
+        for (column, want_column) in &want.columns {
+            let Some(have_column) = have.columns.get(column) else {
+                result.missing_columns.push(ColumnRef { table: table.clone(), column: column.clone() });
+                continue;
+            };
+            if want_column.data_type != have_column.data_type {
+                result.type_mismatches.push(Mismatch {
+                    table: table.clone(),
+                    name: column.clone(),
+                    expected: want_column.data_type.clone(),
+                    actual: have_column.data_type.clone(),
+                });
+            }
+            if want_column.nullable != have_column.nullable {
+                result.nullability_mismatches.push(Mismatch {
+                    table: table.clone(),
+                    name: column.clone(),
+                    expected: want_column.nullable,
+                    actual: have_column.nullable,
+                });
+            }
+        }
+        for column in have.columns.keys().filter(|c| !want.columns.contains_key(*c)) {
+            result.extra_columns.push(ColumnRef { table: table.clone(), column: column.clone() });
+        }
+
+        let indexes = diff_definitions(table, &want.indexes, &have.indexes);
+        result.missing_indexes.extend(
+            indexes.missing.into_iter().map(|index| IndexRef { table: table.clone(), index }));
+        result.extra_indexes.extend(
+            indexes.extra.into_iter().map(|index| IndexRef { table: table.clone(), index }));
+        result.index_mismatches.extend(indexes.mismatches);
+
+        let constraints = diff_definitions(table, &want.constraints, &have.constraints);
+        result.missing_constraints.extend(constraints.missing.into_iter().map(|constraint| {
+            ConstraintRef { table: table.clone(), constraint }
+        }));
+        result.extra_constraints.extend(constraints.extra.into_iter().map(|constraint| {
+            ConstraintRef { table: table.clone(), constraint }
+        }));
+        result.constraint_mismatches.extend(constraints.mismatches);
+    }
+    result
+}
+
+/// Named objects (indexes or constraints) present on one side only, and
+/// those whose definitions differ.
+struct DefinitionDiff {
+    missing: Vec<String>,
+    extra: Vec<String>,
+    mismatches: Vec<Mismatch<String>>,
+}
+
+/// Definitions are compared only when both sides have one.
+fn diff_definitions(
+    table: &str,
+    want: &BTreeMap<String, Option<String>>,
+    have: &BTreeMap<String, Option<String>>,
+) -> DefinitionDiff {
+    let mut result = DefinitionDiff { missing: vec![], extra: vec![], mismatches: vec![] };
+    for (name, want_definition) in want {
+        let Some(have_definition) = have.get(name) else {
+            result.missing.push(name.clone());
+            continue;
+        };
+        if let (Some(want_definition), Some(have_definition)) = (want_definition, have_definition)
+            && want_definition != have_definition
+        {
+            result.mismatches.push(Mismatch {
+                table: table.to_string(),
+                name: name.clone(),
+                expected: want_definition.clone(),
+                actual: have_definition.clone(),
+            });
+        }
+    }
+    result.extra = have.keys().filter(|name| !want.contains_key(*name)).cloned().collect();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(data_type: &str, nullable: bool) -> ColumnSchema {
+        ColumnSchema { data_type: data_type.to_string(), nullable }
+    }
+
+    #[test]
+    fn test_diff() {
+        let expected: SchemaSnapshot = serde_yaml::from_str(
+            "tables:\n\
+             \x20 users:\n\
+             \x20   columns:\n\
+             \x20     id: { data_type: integer, nullable: false }\n\
+             \x20     email: { data_type: \"character varying(255)\" }\n\
+             \x20     name: { data_type: text }\n\
+             \x20   indexes:\n\
+             \x20     users_pkey: ~\n\
+             \x20     users_email: \"CREATE UNIQUE INDEX users_email ON public.users USING btree (email)\"\n\
+             \x20 audit_log:\n\
+             \x20   columns:\n\
+             \x20     id: { data_type: bigint }\n",
+        )
+        .unwrap();
+        assert_eq!(diff(&expected, &expected), SchemaDiff::default());
+
+        let mut users = TableSchema::default();
+        users.columns.insert("id".into(), column("bigint", false));
+        users.columns.insert("email".into(), column("character varying(255)", false));
+        users.columns.insert("created_at".into(), column("timestamp with time zone", true));
+        users.indexes.insert("users_pkey".into(), Some("CREATE UNIQUE INDEX users_pkey ...".into()));
+        users.indexes.insert(
+            "users_email".into(),
+            Some("CREATE INDEX users_email ON public.users USING btree (email)".into()),
+        );
+        users.indexes.insert("users_created".into(), Some("CREATE INDEX users_created ...".into()));
+        let mut actual = SchemaSnapshot::default();
+        actual.tables.insert("users".into(), users);
+        actual.tables.insert("tmp".into(), TableSchema::default());
+
+        let result = diff(&expected, &actual);
+        assert!(!result.is_empty());
+        assert_eq!(result.missing_tables, ["audit_log"]);
+        assert_eq!(result.extra_tables, ["tmp"]);
+        assert_eq!(result.missing_columns, [ColumnRef { table: "users".into(), column: "name".into() }]);
+        assert_eq!(result.extra_columns, [ColumnRef { table: "users".into(), column: "created_at".into() }]);
+        assert_eq!(result.type_mismatches.len(), 1);
+        assert_eq!(result.type_mismatches[0].name, "id");
+        assert_eq!(
+            (result.type_mismatches[0].expected.as_str(), result.type_mismatches[0].actual.as_str()),
+            ("integer", "bigint")
+        );
+        assert_eq!(result.nullability_mismatches.len(), 1);
+        assert_eq!(result.nullability_mismatches[0].name, "email");
+        assert!(result.nullability_mismatches[0].expected);
+        assert!(result.missing_indexes.is_empty());
+        assert_eq!(result.extra_indexes, [IndexRef { table: "users".into(), index: "users_created".into() }]);
+        assert_eq!(result.index_mismatches.len(), 1);
+        assert_eq!(result.index_mismatches[0].name, "users_email");
+        assert!(result.missing_constraints.is_empty() && result.constraint_mismatches.is_empty());
+
+        // Round-trips through YAML
+        let yaml = expected.to_yaml().unwrap();
+        assert_eq!(serde_yaml::from_str::<SchemaSnapshot>(&yaml).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_diff_constraints() {
+        let mut want = TableSchema::default();
+        want.constraints.insert("items_pkey".into(), Some("PRIMARY KEY (id)".into()));
+        want.constraints.insert("items_price_check".into(), Some("CHECK ((price > 0))".into()));
+        want.constraints.insert("items_sku_key".into(), None);
+        let mut have = TableSchema::default();
+        have.constraints.insert("items_pkey".into(), Some("PRIMARY KEY (id)".into()));
+        have.constraints.insert("items_price_check".into(), Some("CHECK ((price >= 0))".into()));
+
+        let snapshot = |table: TableSchema| SchemaSnapshot {
+            tables: BTreeMap::from([("items".to_string(), table)]),
+        };
+        let result = diff(&snapshot(want), &snapshot(have));
+        assert_eq!(
+            result.missing_constraints,
+            [ConstraintRef { table: "items".into(), constraint: "items_sku_key".into() }]
+        );
+        assert_eq!(result.constraint_mismatches.len(), 1);
+        assert_eq!(result.constraint_mismatches[0].actual, "CHECK ((price >= 0))");
+    }
+}
@@ -0,0 +1,349 @@
+//! Seed tables from CSV or JSON fixtures, for tests and dev environments.
+//!
+//! CSV files need a header row naming the columns; an unquoted empty field is
+//! NULL and `""` is the empty string. JSON files hold an array of objects
+//! keyed by column, where a key missing from an object is NULL. Values are
+//! coerced through Postgres' text input, so `"42"`, `42` and `"2024-01-01"`
+//! all load into matching columns; JSON arrays load into array columns and
+//! other arrays and objects into `json`/`jsonb` columns.
+//!
+//! Each load is one transaction: with `truncate` set, the table is emptied
+//! and refilled atomically. Rows are sent with `COPY` (see `bulk`).
+
+use anyhow::{Context, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+use std::path::Path;
+
+use crate::admin::{TruncateOptions, truncate_statement};
+use crate::bulk::copy_in_on;
+use crate::error::Result;
+use crate::statement_tracing::{self, traced};
+use crate::identifier::{quote_qualified, validate_qualified};
+
+/// Fixture file format.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SeedFormat {
+    Csv,
+    Json,
+}
+
+impl SeedFormat {
+    /// Pick the format from a `.csv` or `.json` extension.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        match path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase).as_deref() {
+            Some("csv") => Ok(Self::Csv),
+            Some("json") => Ok(Self::Json),
+            _ => Err(anyhow!("Cannot tell seed format of {:?}: expected a .csv or .json file", path).into()),
+        }
+    }
+}
+
+/// Options for loading seed data.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SeedOptions {
+    /// Truncate the table first, in the same transaction as the load.
+    #[serde(default)]
+    pub truncate: Option<TruncateOptions>,
+}
+
+/// Load a `.csv` or `.json` file into `table` (optionally `schema.table`).
+/// Returns the rows inserted.
+///
+/// # Example
+/// ```rust,no_run
+/// # async fn example(pool: &sqlx::PgPool) -> anyhow::Result<()> {
+/// use pg_toolkit::admin::TruncateOptions;
+/// use pg_toolkit::seed::{SeedOptions, seed_file};
+///
+/// let options = SeedOptions { truncate: Some(TruncateOptions::default()) };
+/// seed_file(pool, "users", "fixtures/users.csv", &options).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[tracing::instrument(level = "debug", skip(pool, path), fields(path = ?path.as_ref()))]
+pub async fn seed_file(
+    pool: &PgPool,
+    table: &str,
+    path: impl AsRef<Path>,
+    options: &SeedOptions,
+) -> Result<u64> {
+    traced(async {
+        let path = path.as_ref();
+        let format = SeedFormat::from_path(path)?;
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read seed file: {:?}", path))?;
+        let rows = match format {
+            SeedFormat::Csv => seed_csv(pool, table, &content, options).await,
+            SeedFormat::Json => seed_json(pool, table, &content, options).await,
+        }
+        .with_context(|| format!("Failed to seed '{}' from {:?}", table, path))?;
+        Ok(rows)
+    })
+    .await
+}
+
+/// Load CSV text, with a header row, into `table`.
+#[tracing::instrument(level = "debug", skip(pool, csv, options), fields(rows_affected))]
+pub async fn seed_csv(pool: &PgPool, table: &str, csv: &str, options: &SeedOptions) -> Result<u64> {
+    traced(async {
+        let mut records = parse_csv(csv)?.into_iter();
+        let Some(header) = records.next() else {
+            return Err(anyhow!("CSV for '{}' is empty: expected a header row", table).into());
+        };
+        let columns = header
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| name.with_context(|| format!("CSV header column {} is empty", i + 1)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let rows: Vec<Vec<Option<String>>> = records.collect();
+        for (index, row) in rows.iter().enumerate() {
+            if row.len() != columns.len() {
+                return Err(anyhow!(
+                    "CSV row {} has {} values but the header has {} columns",
+                    index + 1,
+                    row.len(),
+                    columns.len()
+                ).into());
+            }
+        }
+        let known = table_columns(pool, table).await?;
+        let copied = load(pool, table, &known, &columns, rows, options).await?;
+        statement_tracing::rows_affected(copied);
+        Ok(copied)
+    })
+    .await
+}
+
+/// Load a JSON array of objects into `table`.
+#[tracing::instrument(level = "debug", skip(pool, json, options), fields(rows_affected))]
+pub async fn seed_json(pool: &PgPool, table: &str, json: &str, options: &SeedOptions) -> Result<u64> {
+    traced(async {
+        let value: Value = serde_json::from_str(json).context("Failed to parse seed JSON")?;
+        let Value::Array(items) = value else {
+            return Err(anyhow!("Seed JSON for '{}' must be an array of objects", table).into());
+        };
+        let mut objects = Vec::with_capacity(items.len());
+        for (index, item) in items.into_iter().enumerate() {
+            let Value::Object(object) = item else {
+                return Err(anyhow!("Seed JSON item {} for '{}' is not an object", index, table).into());
+            };
+            objects.push(object);
+        }
+
+        let mut columns: Vec<String> = vec![];
+        for key in objects.iter().flat_map(|object| object.keys()) {
+            if !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+        let known = table_columns(pool, table).await?;
+        let is_array: Vec<bool> = columns
+            .iter()
+            .map(|c| known.iter().any(|(name, is_array)| name == c && *is_array))
+            .collect();
+
+        let rows = objects
+            .iter()
+            .map(|object| {
+                columns
+                    .iter()
+                    .zip(&is_array)
+                    .map(|(column, is_array)| object.get(column).and_then(|v| coerce(v, *is_array)))
+                    .collect()
+            })
+            .collect();
+        let copied = load(pool, table, &known, &columns, rows, options).await?;
+        statement_tracing::rows_affected(copied);
+        Ok(copied)
+    })
+    .await
+}
+
+/// Check `columns` against the table's `known` columns, then insert `rows`.
+async fn load(
+    pool: &PgPool,
+    table: &str,
+    known: &[(String, bool)],
+    columns: &[String],
+    rows: Vec<Vec<Option<String>>>,
+    options: &SeedOptions,
+) -> anyhow::Result<u64> {
+    let unknown: Vec<&str> = columns
+        .iter()
+        .filter(|c| !known.iter().any(|(name, _)| name == *c))
+        .map(String::as_str)
+        .collect();
+    if !unknown.is_empty() {
+        bail!("Table '{}' has no column(s): {}", table, unknown.join(", "));
+    }
+
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+    if let Some(truncate) = &options.truncate {
+        sqlx::query(&truncate_statement(table, truncate)?)
+            .execute(&mut *tx)
+            .await
+            .with_context(|| format!("Failed to truncate table '{}'", table))?;
+    }
+    let copied = if rows.is_empty() {
+        0
+    } else {
+        let columns: Vec<&str> = columns.iter().map(String::as_str).collect();
+        copy_in_on(&mut tx, table, &columns, rows).await?
+    };
+    tx.commit().await.context("Failed to commit seed data")?;
+
+    tracing::info!("Seeded {} rows into '{}'", copied, table);
+    Ok(copied)
+}
+
+/// Column names of `table`, each with whether it is an array type. Fails if
+/// the table does not exist.
+async fn table_columns(pool: &PgPool, table: &str) -> anyhow::Result<Vec<(String, bool)>> {
+    validate_qualified(table)?;
+    let columns = sqlx::query_as::<_, (String, bool)>(
+        "SELECT a.attname::text, t.typcategory = 'A' \
+         FROM pg_attribute a \
+         JOIN pg_type t ON t.oid = a.atttypid \
+         WHERE a.attrelid = to_regclass($1) AND a.attnum > 0 AND NOT a.attisdropped \
+         ORDER BY a.attnum",
+    )
+    .bind(quote_qualified(table))
+    .fetch_all(pool)
+    .await
+    .with_context(|| format!("Failed to list columns for table '{}'", table))?;
+    if columns.is_empty() {
+        bail!("Table '{}' does not exist", table);
+    }
+    Ok(columns)
+}
+
+/// Text representation of a JSON value for a column; `None` is NULL.
+fn coerce(value: &Value, is_array: bool) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        Value::Array(items) if is_array => Some(array_literal(items)),
+        // Numbers and booleans, or JSON text for json/jsonb columns
+        other => Some(other.to_string()),
+    }
+}
+
+/// Postgres array literal, e.g. `{"a","b c",NULL}`; nested arrays become
+/// multidimensional arrays.
+fn array_literal(items: &[Value]) -> String {
+    let elements: Vec<String> = items
+        .iter()
+        .map(|item| match item {
+            Value::Null => "NULL".to_string(),
+            Value::Array(inner) => array_literal(inner),
+            Value::String(s) => quote_array_element(s),
+            other => quote_array_element(&other.to_string()),
+        })
+        .collect();
+    format!("{{{}}}", elements.join(","))
+}
+
+fn quote_array_element(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Parse RFC 4180 CSV into records. Unquoted empty fields are `None`; blank
+/// lines are skipped.
+fn parse_csv(input: &str) -> anyhow::Result<Vec<Vec<Option<String>>>> {
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut chars = input.chars().peekable();
+    let mut line = 1;
+    loop {
+        let field = if chars.peek() == Some(&'"') {
+            chars.next();
+            let mut value = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        value.push('"');
+                    }
+                    Some('"') => break,
+                    Some(c) => {
+                        if c == '\n' {
+                            line += 1;
+                        }
+                        value.push(c);
+                    }
+                    None => bail!("Unterminated quoted CSV field on line {}", line),
+                }
+            }
+            Some(value)
+        } else {
+            let mut value = String::new();
+            while let Some(&c) = chars.peek() {
+                if matches!(c, ',' | '\n' | '\r') {
+                    break;
+                }
+                value.push(c);
+                chars.next();
+            }
+            (!value.is_empty()).then_some(value)
+        };
+        record.push(field);
+
+        let end_of_input = match chars.next() {
+            Some(',') => continue,
+            Some('\r') => {
+                if chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                false
+            }
+            Some('\n') => false,
+            None => true,
+            Some(c) => bail!("Unexpected '{}' after quoted CSV field on line {}", c, line),
+        };
+        line += 1;
+        if record.len() == 1 && record[0].is_none() {
+            record.clear();
+        } else {
+            records.push(std::mem::take(&mut record));
+        }
+        if end_of_input {
+            return Ok(records);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_csv() {
+        let records = parse_csv("id,name,note\r\n1,\"Smith, J\",\n\n2,\"say \"\"hi\"\"\nthere\",\"\"\n")
+            .unwrap();
+        assert_eq!(
+            records,
+            vec![
+                vec![Some("id".into()), Some("name".into()), Some("note".into())],
+                vec![Some("1".into()), Some("Smith, J".into()), None],
+                vec![Some("2".into()), Some("say \"hi\"\nthere".into()), Some("".into())],
+            ]
+        );
+        assert!(parse_csv("a,\"b\n").is_err());
+        assert!(parse_csv("\"a\"b\n").is_err());
+    }
+
+    #[test]
+    fn test_coerce() {
+        let value: Value = serde_json::json!([1, "two \"2\"", null, [true]]);
+        assert_eq!(coerce(&value, true).unwrap(), "{\"1\",\"two \\\"2\\\"\",NULL,{\"true\"}}");
+        assert_eq!(coerce(&value, false).unwrap(), "[1,\"two \\\"2\\\"\",null,[true]]");
+        assert_eq!(coerce(&serde_json::json!(2.5), false).unwrap(), "2.5");
+        assert_eq!(coerce(&Value::Null, false), None);
+    }
+}
@@ -0,0 +1,123 @@
+//! Statement tracing for the toolkit's helpers.
+//!
+//! Every async helper that talks to the server runs in a debug `tracing`
+//! span named after the helper, with its arguments as fields (never the
+//! config or pool) and `rows_affected` where the helper knows them. When a
+//! helper returns, an event with target `pg_toolkit::statement` reports its
+//! duration: at the configured level (default debug; off silences it), or
+//! at warn when it took longer than the slow-statement threshold (default
+//! 1000 ms; 0 disables).
+//!
+//! Helpers take a pool rather than a config, so the level and threshold are
+//! process-wide. Creating a pool leaves them alone; call `configure` once at
+//! startup to apply a config's `log_statements` and `slow_statement_ms`.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::time::{Duration, Instant};
+
+use tracing::Level;
+use tracing::level_filters::LevelFilter;
+
+use crate::config::PgConfig;
+use crate::error::Result;
+
+/// `LevelFilter` as stored in `LEVEL`: 0 is off, 1 error ... 5 trace.
+static LEVEL: AtomicU8 = AtomicU8::new(4);
+static SLOW_STATEMENT_MS: AtomicU64 = AtomicU64::new(1000);
+
+/// Parse a `log_statements` level: off, error, warn, info, debug or trace.
+pub fn parse_level(level: &str) -> Result<LevelFilter, sqlx::Error> {
+    level.parse().map_err(|_| {
+        sqlx::Error::Configuration(format!(
+            "Invalid log_statements level '{}': expected off, error, warn, info, debug or trace",
+            level
+        ).into())
+    })
+}
+
+/// Apply the config's `log_statements` and `slow_statement_ms`, where set.
+/// Fails, changing nothing, if `log_statements` is not a valid level.
+pub fn configure(config: &PgConfig) -> Result<()> {
+    if let Some(level) = &config.log_statements {
+        let code = match parse_level(level)?.into_level() {
+            None => 0,
+            Some(Level::ERROR) => 1,
+            Some(Level::WARN) => 2,
+            Some(Level::INFO) => 3,
+            Some(Level::DEBUG) => 4,
+            Some(_) => 5,
+        };
+        LEVEL.store(code, Ordering::Relaxed);
+    }
+    if let Some(ms) = config.slow_statement_ms {
+        SLOW_STATEMENT_MS.store(ms, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+/// Run a helper's body, then report it in the current span (see the module
+/// docs).
+pub(crate) async fn traced<T>(body: impl Future<Output = T>) -> T {
+    let started = Instant::now();
+    let output = body.await;
+    report(started.elapsed());
+    output
+}
+
+/// Record rows affected on the current helper's span.
+pub(crate) fn rows_affected(rows: u64) {
+    tracing::Span::current().record("rows_affected", rows);
+}
+
+fn report(elapsed: Duration) {
+    let elapsed_ms = elapsed.as_secs_f64() * 1000.0;
+    let slow_ms = SLOW_STATEMENT_MS.load(Ordering::Relaxed);
+
+    macro_rules! report {
+        ($level:expr) => {
+            tracing::event!(target: "pg_toolkit::statement", $level, elapsed_ms, "Statement finished")
+        };
+    }
+    if slow_ms > 0 && elapsed >= Duration::from_millis(slow_ms) {
+        report!(Level::WARN);
+        return;
+    }
+    match LEVEL.load(Ordering::Relaxed) {
+        0 => {}
+        1 => report!(Level::ERROR),
+        2 => report!(Level::WARN),
+        3 => report!(Level::INFO),
+        4 => report!(Level::DEBUG),
+        _ => report!(Level::TRACE),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configure() {
+        let invalid = PgConfig {
+            log_statements: Some("loud".to_string()),
+            slow_statement_ms: Some(5),
+            ..PgConfig::default()
+        };
+        assert!(configure(&invalid).is_err());
+        assert_eq!(LEVEL.load(Ordering::Relaxed), 4);
+
+        let config = PgConfig {
+            log_statements: Some("info".to_string()),
+            slow_statement_ms: Some(250),
+            ..PgConfig::default()
+        };
+        configure(&config).unwrap();
+        assert_eq!(LEVEL.load(Ordering::Relaxed), 3);
+        assert_eq!(SLOW_STATEMENT_MS.load(Ordering::Relaxed), 250);
+
+        // Unset fields keep the current settings
+        configure(&PgConfig::default()).unwrap();
+        assert_eq!(LEVEL.load(Ordering::Relaxed), 3);
+    }
+}
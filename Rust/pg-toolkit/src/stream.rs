@@ -0,0 +1,46 @@
+//! Stream large result sets row by row.
+//!
+//! Rows are decoded as they arrive from the server and the server waits while
+//! the consumer is slow, so memory stays bounded however many rows the query
+//! returns. The stream holds a pool connection, and the query's snapshot,
+//! until it ends or is dropped.
+
+use anyhow::Context;
+use futures_util::{Stream, StreamExt};
+use sqlx::PgPool;
+use sqlx::postgres::{PgArguments, PgRow};
+
+use crate::error::Result;
+
+/// Run `sql` with the bind parameters in `binds` and stream its rows. Errors,
+/// including a failure to start the query, arrive as the stream's items.
+///
+/// # Example
+/// ```rust,no_run
+/// use futures_util::TryStreamExt;
+/// use sqlx::{Arguments, Row, postgres::PgArguments};
+///
+/// # async fn example(pool: &sqlx::PgPool) -> anyhow::Result<()> {
+/// let mut binds = PgArguments::default();
+/// binds.add("2024-01-01").map_err(|e| anyhow::anyhow!(e))?;
+/// let mut rows = pg_toolkit::stream::fetch_streaming(
+///     pool,
+///     "SELECT id, body FROM events WHERE created_at >= $1::date",
+///     binds,
+/// );
+/// while let Some(row) = rows.try_next().await? {
+///     let id: i64 = row.try_get("id")?;
+///     // ...
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn fetch_streaming<'a>(
+    pool: &'a PgPool,
+    sql: &'a str,
+    binds: PgArguments,
+) -> impl Stream<Item = Result<PgRow>> + 'a {
+    sqlx::query_with(sql, binds)
+        .fetch(pool)
+        .map(|row| -> Result<PgRow> { Ok(row.context("Failed to fetch streamed row")?) })
+}
@@ -0,0 +1,306 @@
+//! Throwaway databases for integration tests (feature `test-support`).
+//!
+//! `TestDb` creates an empty database per test. For suites with heavy schema
+//! setup, `TemplateDb` runs the setup once and stamps out per-test copies
+//! with `CREATE DATABASE ... TEMPLATE`, which copies files instead of
+//! replaying DDL.
+//!
+//! ```toml
+//! [dev-dependencies]
+//! pg-toolkit = { path = "../pg-toolkit", features = ["test-support"] }
+//! ```
+//!
+//! # Example
+//! ```rust,no_run
+//! use pg_toolkit::testing::TestDb;
+//!
+//! #[tokio::test]
+//! async fn test_something() {
+//!     let Some(test_db) = TestDb::new().await else {
+//!         eprintln!("Skipping test: PostgreSQL not available");
+//!         return;
+//!     };
+//!     let pool = test_db.pool().await.unwrap();
+//!     // ...
+//!     test_db.drop().await;
+//! }
+//! ```
+
+use anyhow::Context;
+use sqlx::PgPool;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::admin::{
+    CreateDatabaseOptions, create_database, create_database_with, database_exists, drop_database,
+};
+use crate::config::PgConfig;
+use crate::connection::{create_pool, create_system_pool};
+use crate::error::Result;
+use crate::identifier::quote_identifier;
+
+/// Prefix of the databases `TestDb::new` creates.
+pub const DEFAULT_PREFIX: &str = "pg_toolkit_test";
+
+/// Generate a database name that is unique across parallel tests and test
+/// processes: `{prefix}_{millis}_{pid}_{counter}`.
+pub fn unique_db_name(prefix: &str) -> String {
+    // Tests in one binary run in parallel and may start in the same millisecond
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    format!(
+        "{}_{}_{}_{}",
+        prefix,
+        timestamp,
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Test database guard that creates a database on construction and drops it
+/// when the guard goes away, including when the test panics.
+///
+/// Prefer ending a test with `test_db.drop().await`, which cleans up on the
+/// test's own runtime; the `Drop` fallback blocks on a helper thread.
+pub struct TestDb {
+    config: PgConfig,
+    db_name: String,
+    dropped: bool,
+}
+
+impl TestDb {
+    /// Create a database on the server from `PgConfig::from_env()`. Returns
+    /// `None`, with a warning, if the server is unavailable, so tests can
+    /// skip instead of failing.
+    pub async fn new() -> Option<Self> {
+        let config = PgConfig::from_env();
+
+        // Try to connect to system database first
+        if let Err(e) = create_system_pool(&config).await {
+            eprintln!(
+                "Warning: Could not connect to PostgreSQL ({}). Skipping integration tests.",
+                e
+            );
+            return None;
+        }
+
+        match Self::create(&config, DEFAULT_PREFIX).await {
+            Ok(db) => Some(db),
+            Err(e) => {
+                eprintln!("Failed to create test database: {:#}", e);
+                None
+            }
+        }
+    }
+
+    /// Create a uniquely named database `{prefix}_...` on the server
+    /// `config` points at.
+    pub async fn create(config: &PgConfig, prefix: &str) -> Result<Self> {
+        let db_name = unique_db_name(prefix);
+        create_database(config, &db_name)
+            .await
+            .with_context(|| format!("Failed to create test database '{}'", db_name))?;
+
+        Ok(Self {
+            config: config.clone(),
+            db_name,
+            dropped: false,
+        })
+    }
+
+    pub fn db_name(&self) -> &str {
+        &self.db_name
+    }
+
+    /// Server configuration, without the test database selected.
+    pub fn config(&self) -> &PgConfig {
+        &self.config
+    }
+
+    /// Server configuration with the test database selected.
+    pub fn config_with_db(&self) -> PgConfig {
+        self.config.with_database(&self.db_name)
+    }
+
+    /// Open a pool on the test database.
+    pub async fn pool(&self) -> Result<PgPool> {
+        let pool = create_pool(&self.config_with_db())
+            .await
+            .with_context(|| format!("Failed to connect to test database '{}'", self.db_name))?;
+        Ok(pool)
+    }
+
+    /// Drop the database now, terminating any open connections to it.
+    pub async fn drop(mut self) {
+        if let Err(e) = drop_database(&self.config, &self.db_name).await {
+            eprintln!("Failed to drop test database '{}': {:#}", self.db_name, e);
+        }
+        self.dropped = true;
+    }
+}
+
+impl Drop for TestDb {
+    fn drop(&mut self) {
+        if self.dropped {
+            return;
+        }
+        // Async code cannot run in Drop, and a runtime cannot be started on
+        // one of the test runtime's threads, so clean up on a fresh thread and
+        // wait for it: the test process may exit as soon as the test returns.
+        let config = self.config.clone();
+        let db_name = self.db_name.clone();
+        let cleanup = std::thread::spawn(move || {
+            let Ok(runtime) = tokio::runtime::Runtime::new() else {
+                return;
+            };
+            runtime.block_on(async {
+                let _ = drop_database(&config, &db_name).await;
+            });
+        });
+        let _ = cleanup.join();
+    }
+}
+
+/// A database built once and marked as a template, from which per-test
+/// databases are created. Dropped, like `TestDb`, when the guard goes away.
+///
+/// To share one template across the tests of a binary, keep it in a static
+/// `tokio::sync::OnceCell`; statics are never dropped, so give the template
+/// a fixed name and `create` replaces the previous run's copy.
+///
+/// # Example
+/// ```rust,no_run
+/// use pg_toolkit::{PgConfig, testing::TemplateDb};
+///
+/// # async fn example() -> anyhow::Result<()> {
+/// let template = TemplateDb::create(&PgConfig::from_env(), "app_template", async |pool| {
+///     sqlx::raw_sql("CREATE TABLE users (id SERIAL PRIMARY KEY, email TEXT UNIQUE)")
+///         .execute(pool)
+///         .await?;
+///     Ok(())
+/// })
+/// .await?;
+/// let test_db = template.instantiate().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct TemplateDb {
+    config: PgConfig,
+    name: String,
+    dropped: bool,
+}
+
+impl TemplateDb {
+    /// Create database `name`, replacing any existing one, run `setup` on it
+    /// and mark it as a template. Connections to it are then disallowed, as
+    /// `CREATE DATABASE ... TEMPLATE` needs it to have none.
+    pub async fn create(
+        config: &PgConfig,
+        name: &str,
+        setup: impl AsyncFnOnce(&PgPool) -> Result<()>,
+    ) -> Result<Self> {
+        drop_template(config, name).await?;
+        create_database(config, name)
+            .await
+            .with_context(|| format!("Failed to create template database '{}'", name))?;
+        let template = Self {
+            config: config.clone(),
+            name: name.to_string(),
+            dropped: false,
+        };
+
+        let pool = create_pool(&config.with_database(name))
+            .await
+            .with_context(|| format!("Failed to connect to template database '{}'", name))?;
+        let result = setup(&pool).await;
+        pool.close().await;
+        result.with_context(|| format!("Failed to set up template database '{}'", name))?;
+
+        let system = create_system_pool(config)
+            .await
+            .context("Failed to connect to system database")?;
+        sqlx::query(&format!(
+            "ALTER DATABASE {} WITH IS_TEMPLATE true ALLOW_CONNECTIONS false",
+            quote_identifier(name)
+        ))
+        .execute(&system)
+        .await
+        .with_context(|| format!("Failed to mark '{}' as a template", name))?;
+
+        tracing::info!("Template database '{}' is ready", name);
+        Ok(template)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Create a uniquely named copy of the template, dropped like any other
+    /// `TestDb`. Copies are named `{name}_...`, so keep template names short
+    /// enough (about 30 bytes) for the result to fit in 63 bytes.
+    pub async fn instantiate(&self) -> Result<TestDb> {
+        let db_name = unique_db_name(&self.name);
+        let options = CreateDatabaseOptions {
+            template: Some(self.name.clone()),
+            ..CreateDatabaseOptions::default()
+        };
+        create_database_with(&self.config, &db_name, &options)
+            .await
+            .with_context(|| format!("Failed to create '{}' from template '{}'", db_name, self.name))?;
+        Ok(TestDb {
+            config: self.config.clone(),
+            db_name,
+            dropped: false,
+        })
+    }
+
+    /// Drop the template now. Databases created from it are unaffected.
+    pub async fn drop(mut self) {
+        if let Err(e) = drop_template(&self.config, &self.name).await {
+            eprintln!("Failed to drop template database '{}': {:#}", self.name, e);
+        }
+        self.dropped = true;
+    }
+}
+
+impl Drop for TemplateDb {
+    fn drop(&mut self) {
+        if self.dropped {
+            return;
+        }
+        // See TestDb's Drop
+        let config = self.config.clone();
+        let name = self.name.clone();
+        let cleanup = std::thread::spawn(move || {
+            let Ok(runtime) = tokio::runtime::Runtime::new() else {
+                return;
+            };
+            runtime.block_on(async {
+                let _ = drop_template(&config, &name).await;
+            });
+        });
+        let _ = cleanup.join();
+    }
+}
+
+/// Drop a database that may be marked as a template, which has to be
+/// unmarked first. No-op if it does not exist.
+async fn drop_template(config: &PgConfig, name: &str) -> Result<()> {
+    if !database_exists(config, name).await? {
+        return Ok(());
+    }
+    let system = create_system_pool(config)
+        .await
+        .context("Failed to connect to system database")?;
+    sqlx::query(&format!(
+        "ALTER DATABASE {} WITH IS_TEMPLATE false ALLOW_CONNECTIONS true",
+        quote_identifier(name)
+    ))
+    .execute(&system)
+    .await
+    .with_context(|| format!("Failed to unmark template '{}'", name))?;
+    drop_database(config, name).await
+}
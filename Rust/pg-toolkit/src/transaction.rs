@@ -0,0 +1,182 @@
+//! Transactions that retry on serialization failures and deadlocks.
+//!
+//! Under `REPEATABLE READ` and `SERIALIZABLE`, Postgres aborts transactions
+//! that conflict with concurrent ones (SQLSTATE 40001), and any isolation
+//! level can hit a deadlock (40P01). Both are safe to retry from the start,
+//! which `with_retry` does with capped exponential backoff.
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, PgTransaction};
+use std::time::Duration;
+
+use crate::error::{Error, Result};
+use crate::statement_tracing::traced;
+
+/// SQLSTATE for serialization_failure.
+const SERIALIZATION_FAILURE: &str = "40001";
+/// SQLSTATE for deadlock_detected.
+const DEADLOCK_DETECTED: &str = "40P01";
+
+/// Transaction isolation level.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IsolationLevel {
+    /// Postgres' default
+    #[default]
+    ReadCommitted,
+    RepeatableRead,
+    Serializable,
+}
+
+impl IsolationLevel {
+    fn as_sql(self) -> &'static str {
+        match self {
+            IsolationLevel::ReadCommitted => "READ COMMITTED",
+            IsolationLevel::RepeatableRead => "REPEATABLE READ",
+            IsolationLevel::Serializable => "SERIALIZABLE",
+        }
+    }
+}
+
+/// How often and how quickly `with_retry_policy` retries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Attempts in total, including the first (default: 5).
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubles on each retry (default: 10ms).
+    pub initial_backoff: Duration,
+    /// Cap for the delay (default: 1s).
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `retry` (1-based).
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+/// Run `f` in a transaction at `isolation` and commit, retrying the whole
+/// transaction on serialization failures and deadlocks with the default
+/// `RetryPolicy`.
+///
+/// `f` may run more than once, so it should not have side effects outside
+/// the transaction. Any other error rolls back and is returned immediately.
+///
+/// # Example
+/// ```rust,no_run
+/// use pg_toolkit::transaction::{IsolationLevel, with_retry};
+///
+/// # async fn example(pool: &sqlx::PgPool) -> anyhow::Result<()> {
+/// let balance: i64 = with_retry(pool, IsolationLevel::Serializable, async |tx| {
+///     sqlx::query("UPDATE accounts SET balance = balance - 10 WHERE id = 1")
+///         .execute(&mut **tx)
+///         .await?;
+///     Ok(sqlx::query_scalar("SELECT balance FROM accounts WHERE id = 1")
+///         .fetch_one(&mut **tx)
+///         .await?)
+/// })
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[tracing::instrument(level = "debug", skip(pool, f))]
+pub async fn with_retry<T, F>(pool: &PgPool, isolation: IsolationLevel, f: F) -> Result<T>
+where
+    F: AsyncFnMut(&mut PgTransaction<'static>) -> anyhow::Result<T>,
+{
+    traced(async {
+        with_retry_policy(pool, isolation, &RetryPolicy::default(), f).await
+    })
+    .await
+}
+
+/// `with_retry` with an explicit retry policy.
+#[tracing::instrument(level = "debug", skip(pool, f))]
+pub async fn with_retry_policy<T, F>(
+    pool: &PgPool,
+    isolation: IsolationLevel,
+    policy: &RetryPolicy,
+    mut f: F,
+) -> Result<T>
+where
+    F: AsyncFnMut(&mut PgTransaction<'static>) -> anyhow::Result<T>,
+{
+    traced(async {
+        let mut attempt = 1;
+        loop {
+            let error = match run_once(pool, isolation, &mut f).await {
+                Ok(value) => return Ok(value),
+                Err(error) => Error::from(error),
+            };
+            if attempt >= policy.max_attempts || !is_retryable(&error) {
+                return Err(error);
+            }
+
+            let delay = policy.backoff(attempt);
+            tracing::debug!(
+                "Transaction attempt {} failed ({:#}); retrying in {:?}",
+                attempt,
+                error,
+                delay
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    })
+    .await
+}
+
+async fn run_once<T, F>(pool: &PgPool, isolation: IsolationLevel, f: &mut F) -> anyhow::Result<T>
+where
+    F: AsyncFnMut(&mut PgTransaction<'static>) -> anyhow::Result<T>,
+{
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+    sqlx::query(&format!("SET TRANSACTION ISOLATION LEVEL {}", isolation.as_sql()))
+        .execute(&mut *tx)
+        .await
+        .context("Failed to set isolation level")?;
+    // Dropping `tx` on error rolls it back
+    let value = f(&mut tx).await?;
+    // Serializable transactions can also fail at commit
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(value)
+}
+
+/// True if `error` was caused by a serialization failure or deadlock.
+pub fn is_retryable(error: &Error) -> bool {
+    matches!(error.code().as_deref(), Some(SERIALIZATION_FAILURE | DEADLOCK_DETECTED))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(1), Duration::from_millis(10));
+        assert_eq!(policy.backoff(2), Duration::from_millis(20));
+        assert_eq!(policy.backoff(4), Duration::from_millis(80));
+        assert_eq!(policy.backoff(20), Duration::from_secs(1));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_is_retryable_ignores_other_errors() {
+        assert!(!is_retryable(&anyhow::anyhow!("boom").into()));
+        assert!(!is_retryable(&sqlx::Error::RowNotFound.into()));
+    }
+}
@@ -1,7 +1,13 @@
 //! Integration tests for pg-toolkit admin module.
 //!
-//! Tests: create_database, drop_database, database_exists,
-//!        create_extension, extension_exists, list_databases, list_extensions
+//! Tests: create_database, drop_database, rename_database, database_exists,
+//!        create_extension, extension_exists, list_databases, list_extensions,
+//!        refresh_materialized_view, truncate_table, extension_version,
+//!        available_extension_versions, update_extension, create_range_partition,
+//!        detach_partition, execute_sql_file, create_database_with,
+//!        drop_database_with, list_databases_detailed, enable_row_level_security,
+//!        disable_row_level_security, force_row_level_security, create_policy,
+//!        drop_policy, list_policies, set_table_owner, reassign_owned
 //!
 //! Run with:
 //!   cargo test --test test_admin
@@ -11,25 +17,25 @@
 use pg_toolkit::{
     PgConfig,
     admin::{
-        create_database, drop_database, database_exists, create_extension,
-        extension_exists, list_databases, list_extensions,
+        create_database, drop_database, rename_database, database_exists, create_extension,
+        extension_exists, list_databases, list_extensions, refresh_materialized_view,
+        truncate_table, TruncateOptions, extension_version, available_extension_versions,
+        update_extension, create_range_partition, detach_partition, execute_sql_file,
+        create_database_with, CreateDatabaseOptions, drop_database_with, DropDatabaseOptions,
+        list_databases_detailed, enable_row_level_security, disable_row_level_security,
+        force_row_level_security, create_policy, drop_policy, PolicyCommand, PolicyOptions,
+        set_table_owner, reassign_owned,
     },
     connection::create_pool,
+    introspection::{
+        list_partitioned_tables, list_partitions, partition_parent, list_policies, list_tables,
+    },
 };
-use std::time::{SystemTime, UNIX_EPOCH};
+use pg_toolkit::testing::unique_db_name;
 
 mod common;
 use common::TestDb;
 
-/// Generate a unique database name for testing.
-fn test_db_name() -> String {
-    let timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_millis();
-    format!("pg_toolkit_admin_test_{}", timestamp)
-}
-
 #[tokio::test]
 async fn test_create_and_drop_database() {
     let test_db = match TestDb::new().await {
@@ -62,7 +68,7 @@ async fn test_database_lifecycle_idempotent() {
         return;
     }
 
-    let db_name = test_db_name();
+    let db_name = unique_db_name("pg_toolkit_admin_test");
 
     // Create should succeed
     create_database(&config, &db_name)
@@ -182,3 +188,572 @@ async fn test_list_extensions() {
 
     test_db.drop().await;
 }
+
+#[tokio::test]
+async fn test_rename_database() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+    let config = test_db.config().clone();
+    let old = test_db.db_name().to_string();
+    let new = format!("{}_renamed", old);
+
+    // An open connection to the old name is terminated
+    let pool = create_pool(&test_db.config_with_db()).await.expect("Failed to connect");
+    rename_database(&config, &old, &new).await.expect("Rename should succeed");
+    assert!(!database_exists(&config, &old).await.unwrap());
+    assert!(database_exists(&config, &new).await.unwrap());
+    assert!(sqlx::query("SELECT 1").execute(&pool).await.is_err());
+
+    // Renaming again is a no-op
+    rename_database(&config, &old, &new).await.expect("Second rename should be a no-op");
+
+    // Neither name exists
+    assert!(rename_database(&config, "pg_toolkit_missing_db", "pg_toolkit_other").await.is_err());
+
+    drop(pool);
+    drop_database(&config, &new).await.unwrap();
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_refresh_materialized_view() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    sqlx::raw_sql(
+        "CREATE TABLE events (kind TEXT); \
+         INSERT INTO events VALUES ('click'), ('click'), ('view'); \
+         CREATE MATERIALIZED VIEW kind_counts AS \
+             SELECT kind, count(*) AS n FROM events GROUP BY kind WITH NO DATA; \
+         CREATE UNIQUE INDEX ON kind_counts (kind)",
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create materialized view");
+
+    // CONCURRENTLY is rejected until the view has been populated once
+    assert!(refresh_materialized_view(&pool, "kind_counts", true).await.is_err());
+    refresh_materialized_view(&pool, "kind_counts", false).await.expect("Failed to refresh");
+
+    sqlx::query("INSERT INTO events VALUES ('view')").execute(&pool).await.unwrap();
+    refresh_materialized_view(&pool, "public.kind_counts", true)
+        .await
+        .expect("Failed to refresh concurrently");
+
+    let views: i64 = sqlx::query_scalar("SELECT n FROM kind_counts WHERE kind = 'view'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(views, 2);
+
+    let err = refresh_materialized_view(&pool, "missing_view", false).await.unwrap_err();
+    assert!(err.to_string().contains("does not exist"));
+
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_truncate_table() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    sqlx::raw_sql(
+        "CREATE TABLE parents (id SERIAL PRIMARY KEY); \
+         CREATE TABLE children (parent_id INTEGER REFERENCES parents); \
+         INSERT INTO parents DEFAULT VALUES; INSERT INTO parents DEFAULT VALUES; \
+         INSERT INTO children VALUES (1)",
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create test tables");
+
+    // Referenced by children, so needs CASCADE
+    assert!(truncate_table(&pool, "parents", &TruncateOptions::default()).await.is_err());
+
+    let options = TruncateOptions { cascade: true, restart_identity: true };
+    truncate_table(&pool, "public.parents", &options).await.expect("Failed to truncate");
+
+    let children: i64 = sqlx::query_scalar("SELECT count(*) FROM children")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(children, 0);
+
+    let next_id: i32 = sqlx::query_scalar("INSERT INTO parents DEFAULT VALUES RETURNING id")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(next_id, 1);
+
+    let err = truncate_table(&pool, "a.b.c", &TruncateOptions::default()).await.unwrap_err();
+    assert!(err.to_string().contains("Invalid name"));
+
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_extension_versions() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    // citext ships with contrib and has several versions
+    let versions = available_extension_versions(&pool, "citext").await.unwrap();
+    if versions.len() < 2 {
+        eprintln!("Skipping test: citext with multiple versions not available");
+        test_db.drop().await;
+        return;
+    }
+    let (oldest, latest) = (versions[0].clone(), versions[versions.len() - 1].clone());
+    assert!(available_extension_versions(&pool, "no_such_extension").await.unwrap().is_empty());
+
+    assert_eq!(extension_version(&pool, "citext").await.unwrap(), None);
+    assert!(update_extension(&pool, "citext", None).await.is_err());
+
+    sqlx::query(&format!("CREATE EXTENSION citext VERSION '{}'", oldest))
+        .execute(&pool)
+        .await
+        .expect("Failed to create extension at oldest version");
+    assert_eq!(extension_version(&pool, "citext").await.unwrap(), Some(oldest.clone()));
+
+    let next = update_extension(&pool, "citext", Some(versions[1].as_str()))
+        .await
+        .expect("Failed to update to explicit version");
+    assert_eq!(next, versions[1]);
+
+    let default = update_extension(&pool, "citext", None).await.expect("Failed to update");
+    assert_eq!(default, latest);
+    assert_eq!(extension_version(&pool, "citext").await.unwrap(), Some(latest));
+
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_range_partitions() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    sqlx::raw_sql(
+        "CREATE SCHEMA events; \
+         CREATE TABLE events.log (id BIGINT, created_at DATE NOT NULL) \
+             PARTITION BY RANGE (created_at)",
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create partitioned table");
+
+    let parents = list_partitioned_tables(&pool).await.unwrap();
+    assert_eq!(parents.len(), 1);
+    assert_eq!((parents[0].schema.as_str(), parents[0].name.as_str()), ("events", "log"));
+    assert_eq!(parents[0].key, "RANGE (created_at)");
+
+    create_range_partition(&pool, "events.log", "events.log_2024_01", "2024-01-01", "2024-02-01")
+        .await
+        .expect("Failed to create partition");
+    create_range_partition(&pool, "events.log", "events.log_2024_02", "2024-02-01", "2024-03-01")
+        .await
+        .expect("Failed to create partition");
+    // Idempotent
+    create_range_partition(&pool, "events.log", "events.log_2024_01", "2024-01-01", "2024-02-01")
+        .await
+        .expect("Second create should be a no-op");
+    // Overlapping bounds are rejected
+    assert!(
+        create_range_partition(&pool, "events.log", "events.overlap", "2024-01-15", "2024-02-15")
+            .await
+            .is_err()
+    );
+
+    sqlx::query("INSERT INTO events.log VALUES (1, '2024-01-10'), (2, '2024-02-10')")
+        .execute(&pool)
+        .await
+        .expect("Rows should route to partitions");
+
+    let partitions = list_partitions(&pool, "events.log").await.unwrap();
+    let names: Vec<&str> = partitions.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["log_2024_01", "log_2024_02"]);
+    assert_eq!(partitions[0].bound, "FOR VALUES FROM ('2024-01-01') TO ('2024-02-01')");
+    assert!(!partitions[0].is_partitioned);
+    assert_eq!(
+        partition_parent(&pool, "events.log_2024_01").await.unwrap().as_deref(),
+        Some("events.log")
+    );
+    assert_eq!(partition_parent(&pool, "events.log").await.unwrap(), None);
+    assert!(list_partitions(&pool, "events.missing").await.is_err());
+
+    detach_partition(&pool, "events.log", "events.log_2024_01", true)
+        .await
+        .expect("Failed to detach partition");
+    assert_eq!(list_partitions(&pool, "events.log").await.unwrap().len(), 1);
+    assert_eq!(partition_parent(&pool, "events.log_2024_01").await.unwrap(), None);
+    let kept: i64 = sqlx::query_scalar("SELECT count(*) FROM events.log_2024_01")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(kept, 1);
+
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_execute_sql_file() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    let dir = std::env::temp_dir().join(test_db.db_name());
+    std::fs::create_dir_all(&dir).unwrap();
+    let script = dir.join("schema.sql");
+    std::fs::write(
+        &script,
+        "-- Bootstrap schema\n\
+         CREATE SCHEMA app;\n\
+         SET search_path = app;\n\
+         CREATE TABLE items (name TEXT NOT NULL DEFAULT 'a;b');\n\
+         CREATE FUNCTION add_item(n TEXT) RETURNS void AS $$\n\
+         BEGIN\n\
+             INSERT INTO items VALUES (n);\n\
+         END;\n\
+         $$ LANGUAGE plpgsql;\n\
+         /* seed */ SELECT add_item('first'); INSERT INTO items DEFAULT VALUES\n",
+    )
+    .unwrap();
+
+    let executed = execute_sql_file(&pool, &script).await.expect("Failed to execute script");
+    assert_eq!(executed, 6);
+    let names: Vec<String> = sqlx::query_scalar("SELECT name FROM app.items ORDER BY name")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(names, ["a;b", "first"]);
+
+    let broken = dir.join("broken.sql");
+    std::fs::write(&broken, "CREATE TABLE ok (id INT);\n\nINSERT INTO missing VALUES (1);\n").unwrap();
+    let err = execute_sql_file(&pool, &broken).await.unwrap_err();
+    let message = format!("{:#}", err);
+    assert!(message.contains("Statement 2 (line 3)"), "{}", message);
+    assert!(message.contains("INSERT INTO missing"), "{}", message);
+    assert!(message.contains("does not exist"), "{}", message);
+
+    std::fs::remove_dir_all(&dir).ok();
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_create_database_with_options() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+    let config = test_db.config().clone();
+    let system = pg_toolkit::connection::create_system_pool(&config).await.unwrap();
+
+    let owner = format!("owner_{}", test_db.db_name());
+    sqlx::query(&format!("CREATE ROLE \"{}\"", owner)).execute(&system).await.unwrap();
+
+    let db_name = unique_db_name("pg_toolkit_options_test");
+    let options = CreateDatabaseOptions {
+        owner: Some(owner.clone()),
+        encoding: Some("UTF8".to_string()),
+        locale: Some("C".to_string()),
+        template: Some("template0".to_string()),
+        connection_limit: Some(5),
+    };
+    create_database_with(&config, &db_name, &options).await.expect("Failed to create database");
+    // Idempotent
+    create_database_with(&config, &db_name, &options).await.expect("Second create should be a no-op");
+
+    let (db_owner, encoding, collate, limit): (String, String, String, i32) = sqlx::query_as(
+        "SELECT pg_get_userbyid(datdba)::text, pg_encoding_to_char(encoding)::text, \
+                datcollate::text, datconnlimit \
+         FROM pg_database WHERE datname = $1",
+    )
+    .bind(&db_name)
+    .fetch_one(&system)
+    .await
+    .unwrap();
+    assert_eq!((db_owner, encoding, collate, limit), (owner.clone(), "UTF8".into(), "C".into(), 5));
+
+    drop_database(&config, &db_name).await.unwrap();
+    sqlx::query(&format!("DROP ROLE \"{}\"", owner)).execute(&system).await.unwrap();
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_drop_database_with_force() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+    let config = test_db.config().clone();
+    let name = test_db.db_name().to_string();
+
+    let pool = create_pool(&test_db.config_with_db()).await.expect("Failed to connect");
+    sqlx::query("SELECT 1").execute(&pool).await.unwrap();
+
+    // Without force, an open session blocks the drop
+    let err = drop_database_with(&config, &name, &DropDatabaseOptions::default())
+        .await
+        .unwrap_err();
+    let message = format!("{:#}", err);
+    assert!(message.contains("still connected"), "{}", message);
+    assert!(database_exists(&config, &name).await.unwrap());
+
+    drop_database_with(&config, &name, &DropDatabaseOptions { force: true })
+        .await
+        .expect("Forced drop should disconnect sessions");
+    assert!(!database_exists(&config, &name).await.unwrap());
+    assert!(sqlx::query("SELECT 1").execute(&pool).await.is_err());
+
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_list_databases_detailed() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let pool = create_pool(&test_db.config_with_db()).await.expect("Failed to connect");
+    sqlx::query("SELECT 1").execute(&pool).await.unwrap();
+
+    let databases = list_databases_detailed(test_db.config()).await.expect("Failed to list");
+    let ours = databases
+        .iter()
+        .find(|d| d.name == test_db.db_name())
+        .expect("Should find the test database");
+    assert_eq!(ours.owner, test_db.config().user);
+    assert!(!ours.encoding.is_empty());
+    assert!(ours.size_bytes.unwrap() > 0);
+    assert!(ours.connections >= 1);
+    assert_eq!(ours.connection_limit, -1);
+    assert!(!ours.is_template && ours.allow_connections);
+
+    let template0 = databases.iter().find(|d| d.name == "template0").unwrap();
+    assert!(template0.is_template && !template0.allow_connections);
+
+    pool.close().await;
+    test_db.drop().await;
+}
+
+/// Rows of `docs` visible to `role`.
+async fn visible_docs(pool: &sqlx::PgPool, role: &str) -> i64 {
+    let mut tx = pool.begin().await.unwrap();
+    sqlx::query(&format!("SET LOCAL ROLE {}", role))
+        .execute(&mut *tx)
+        .await
+        .expect("Failed to set role");
+    sqlx::query_scalar("SELECT count(*) FROM docs")
+        .fetch_one(&mut *tx)
+        .await
+        .expect("Failed to count docs")
+}
+
+#[tokio::test]
+async fn test_row_level_security() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    let reader = unique_db_name("rls_reader");
+    sqlx::raw_sql(&format!(
+        "CREATE ROLE {reader} NOLOGIN; \
+         CREATE TABLE docs (id INT PRIMARY KEY, owner TEXT NOT NULL); \
+         INSERT INTO docs VALUES (1, '{reader}'), (2, '{reader}'), (3, 'someone_else'); \
+         GRANT SELECT ON docs TO {reader}"
+    ))
+    .execute(&pool)
+    .await
+    .expect("Failed to set up table and role");
+
+    assert_eq!(visible_docs(&pool, &reader).await, 3);
+
+    enable_row_level_security(&pool, "docs").await.expect("Failed to enable RLS");
+    let docs = list_tables(&pool).await.unwrap().into_iter().find(|t| t.name == "docs").unwrap();
+    assert!(docs.row_security);
+    // No policy yet: nothing visible
+    assert_eq!(visible_docs(&pool, &reader).await, 0);
+
+    let options = PolicyOptions {
+        command: PolicyCommand::Select,
+        roles: vec![reader.clone()],
+        using: Some("owner = current_user".into()),
+        ..PolicyOptions::default()
+    };
+    create_policy(&pool, "public.docs", "own_docs", &options)
+        .await
+        .expect("Failed to create policy");
+    assert_eq!(visible_docs(&pool, &reader).await, 2);
+
+    let policies = list_policies(&pool, "docs").await.expect("Failed to list policies");
+    assert_eq!(policies.len(), 1);
+    let policy = &policies[0];
+    assert_eq!((policy.schema.as_str(), policy.table.as_str()), ("public", "docs"));
+    assert_eq!(policy.name, "own_docs");
+    assert!(policy.permissive);
+    assert_eq!(policy.roles, vec![reader.clone()]);
+    assert_eq!(policy.command, "SELECT");
+    assert_eq!(policy.using.as_deref(), Some("(owner = CURRENT_USER)"));
+    assert_eq!(policy.with_check, None);
+
+    // The owner bypasses policies unless forced; superusers always do
+    let owner_count = || async {
+        sqlx::query_scalar::<_, i64>("SELECT count(*) FROM docs").fetch_one(&pool).await.unwrap()
+    };
+    let is_superuser: bool =
+        sqlx::query_scalar("SELECT rolsuper FROM pg_roles WHERE rolname = current_user")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    force_row_level_security(&pool, "docs", true).await.expect("Failed to force RLS");
+    if !is_superuser {
+        assert_eq!(owner_count().await, 0);
+    }
+    force_row_level_security(&pool, "docs", false).await.expect("Failed to unforce RLS");
+    assert_eq!(owner_count().await, 3);
+
+    drop_policy(&pool, "docs", "own_docs").await.expect("Failed to drop policy");
+    assert!(list_policies(&pool, "docs").await.unwrap().is_empty());
+    drop_policy(&pool, "docs", "own_docs")
+        .await
+        .expect("Dropping a missing policy should be a no-op");
+    assert_eq!(visible_docs(&pool, &reader).await, 0);
+
+    disable_row_level_security(&pool, "docs").await.expect("Failed to disable RLS");
+    assert_eq!(visible_docs(&pool, &reader).await, 3);
+
+    assert!(list_policies(&pool, "no_such_table").await.is_err());
+
+    sqlx::raw_sql(&format!("DROP OWNED BY {reader}; DROP ROLE {reader}"))
+        .execute(&pool)
+        .await
+        .expect("Failed to drop role");
+    pool.close().await;
+    test_db.drop().await;
+}
+
+/// Owner of a relation.
+async fn owner_of(pool: &sqlx::PgPool, name: &str) -> String {
+    sqlx::query_scalar(
+        "SELECT pg_get_userbyid(relowner)::text FROM pg_class WHERE oid = to_regclass($1)",
+    )
+    .bind(name)
+    .fetch_one(pool)
+    .await
+    .expect("Failed to read owner")
+}
+
+#[tokio::test]
+async fn test_set_table_owner_and_reassign_owned() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    let leaving = unique_db_name("owner_leaving");
+    let successor = unique_db_name("owner_successor");
+    sqlx::raw_sql(&format!(
+        "CREATE ROLE {leaving} NOLOGIN; \
+         CREATE ROLE {successor} NOLOGIN; \
+         CREATE SCHEMA kb; \
+         CREATE TABLE kb.docs (id SERIAL PRIMARY KEY); \
+         CREATE TABLE notes (id INT)"
+    ))
+    .execute(&pool)
+    .await
+    .expect("Failed to set up roles and tables");
+
+    set_table_owner(&pool, "kb.docs", &leaving).await.expect("Failed to set owner");
+    set_table_owner(&pool, "notes", &leaving).await.expect("Failed to set owner");
+    assert_eq!(owner_of(&pool, "kb.docs").await, leaving);
+    assert_eq!(owner_of(&pool, "kb.docs_id_seq").await, leaving);
+
+    // The role cannot be dropped while it owns objects
+    assert!(sqlx::query(&format!("DROP ROLE {leaving}")).execute(&pool).await.is_err());
+
+    reassign_owned(&pool, &leaving, &successor).await.expect("Failed to reassign owned");
+    assert_eq!(owner_of(&pool, "kb.docs").await, successor);
+    assert_eq!(owner_of(&pool, "kb.docs_id_seq").await, successor);
+    assert_eq!(owner_of(&pool, "notes").await, successor);
+
+    sqlx::query(&format!("DROP ROLE {leaving}"))
+        .execute(&pool)
+        .await
+        .expect("Failed to drop role after reassigning");
+
+    assert!(set_table_owner(&pool, "notes", "no_such_role").await.is_err());
+    assert!(reassign_owned(&pool, "no_such_role", &successor).await.is_err());
+
+    sqlx::raw_sql(&format!("DROP OWNED BY {successor}; DROP ROLE {successor}"))
+        .execute(&pool)
+        .await
+        .expect("Failed to drop role");
+    pool.close().await;
+    test_db.drop().await;
+}
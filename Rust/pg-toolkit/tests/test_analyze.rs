@@ -0,0 +1,72 @@
+//! Integration tests for pg-toolkit analyze module.
+//!
+//! Tests: explain
+//!
+//! Run with:
+//!   cargo test --test test_analyze
+//!
+//! Requires PostgreSQL running (see Scripts/DockerBuilds/knowledge-base/docker-compose.yml)
+
+use pg_toolkit::{
+    analyze::{ExplainOptions, explain},
+    connection::create_pool,
+};
+
+mod common;
+use common::TestDb;
+
+#[tokio::test]
+async fn test_explain() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let pool = create_pool(&test_db.config_with_db()).await.expect("Failed to connect");
+    sqlx::raw_sql(
+        "CREATE TABLE users (id INT PRIMARY KEY, email TEXT NOT NULL); \
+         CREATE INDEX users_email ON users (email); \
+         INSERT INTO users SELECT i, 'user' || i || '@example.com' FROM generate_series(1, 10000) i; \
+         ANALYZE users",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let plan = explain(
+        &pool,
+        "SELECT id FROM users WHERE email = 'user42@example.com';",
+        &ExplainOptions::default(),
+    )
+    .await
+    .expect("Failed to explain");
+    assert!(plan.find("Seq Scan").is_empty(), "{:#?}", plan);
+    assert!(plan.nodes().iter().any(|n| n.index.as_deref() == Some("users_email")));
+    assert!(plan.root.total_cost > 0.0);
+    assert_eq!(plan.root.actual_rows, None);
+    assert_eq!(plan.execution_time_ms, None);
+
+    let options = ExplainOptions { analyze: true, buffers: true };
+    let plan = explain(&pool, "SELECT count(*) FROM users", &options)
+        .await
+        .expect("Failed to explain analyze");
+    assert_eq!(plan.root.node_type, "Aggregate");
+    assert_eq!(plan.root.actual_rows, Some(1.0));
+    assert!(plan.execution_time_ms.is_some());
+    assert!(plan.root.shared_hit_blocks.is_some());
+
+    // Explaining a write with ANALYZE runs it, then rolls it back
+    explain(&pool, "DELETE FROM users", &options).await.expect("Failed to explain DELETE");
+    let count: i64 = sqlx::query_scalar("SELECT count(*) FROM users")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 10000);
+
+    assert!(explain(&pool, "SELECT * FROM no_such_table", &options).await.is_err());
+
+    test_db.drop().await;
+}
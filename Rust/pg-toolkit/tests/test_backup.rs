@@ -0,0 +1,79 @@
+//! Integration tests for pg-toolkit backup module.
+//!
+//! Tests: dump_database, restore_database
+//!
+//! Run with:
+//!   cargo test --test test_backup
+//!
+//! Requires PostgreSQL running (see Scripts/DockerBuilds/knowledge-base/docker-compose.yml)
+//! and pg_dump / pg_restore / psql on PATH.
+
+use pg_toolkit::{
+    backup::{DumpFormat, DumpOptions, dump_database, restore_database},
+    connection::create_pool,
+};
+
+mod common;
+use common::TestDb;
+
+async fn round_trip(format: DumpFormat) {
+    let (source, target) = match (TestDb::new().await, TestDb::new().await) {
+        (Some(source), Some(target)) => (source, target),
+        _ => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+    if std::process::Command::new("pg_dump").arg("--version").output().is_err() {
+        eprintln!("Skipping test: pg_dump not on PATH");
+        return;
+    }
+
+    let pool = create_pool(&source.config_with_db()).await.expect("Failed to connect");
+    sqlx::raw_sql(
+        "CREATE TABLE notes (id INT PRIMARY KEY, body TEXT); \
+         INSERT INTO notes VALUES (1, 'first'), (2, 'second');",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let dir = std::env::temp_dir().join(format!("pg_toolkit_backup_{}", source.db_name()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let options = DumpOptions {
+        format,
+        output: Some(dir.join("dump")),
+        no_owner: true,
+        ..DumpOptions::default()
+    };
+    let path = dump_database(&source.config_with_db(), &options).await.unwrap();
+    assert!(path.exists());
+
+    restore_database(&target.config_with_db(), &path).await.unwrap();
+    let restored = create_pool(&target.config_with_db()).await.expect("Failed to connect");
+    let bodies: Vec<String> = sqlx::query_scalar("SELECT body FROM notes ORDER BY id")
+        .fetch_all(&restored)
+        .await
+        .unwrap();
+    assert_eq!(bodies, ["first", "second"]);
+
+    // Restoring again into a non-empty database fails with pg's error
+    let error = restore_database(&target.config_with_db(), &path).await.unwrap_err();
+    assert!(format!("{:#}", error).contains("already exists"), "{:#}", error);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+    drop(pool);
+    drop(restored);
+    source.drop().await;
+    target.drop().await;
+}
+
+#[tokio::test]
+async fn test_dump_and_restore_custom_format() {
+    round_trip(DumpFormat::Custom).await;
+}
+
+#[tokio::test]
+async fn test_dump_and_restore_plain_format() {
+    round_trip(DumpFormat::Plain).await;
+}
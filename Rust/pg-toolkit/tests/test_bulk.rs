@@ -0,0 +1,65 @@
+//! Integration tests for pg-toolkit bulk module.
+//!
+//! Tests: copy_in
+//!
+//! Run with:
+//!   cargo test --test test_bulk
+//!
+//! Requires PostgreSQL running (see Scripts/DockerBuilds/knowledge-base/docker-compose.yml)
+
+use pg_toolkit::{bulk::copy_in, connection::create_pool};
+
+mod common;
+use common::TestDb;
+
+#[tokio::test]
+async fn test_copy_in() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let pool = create_pool(&test_db.config_with_db()).await.expect("Failed to connect");
+    sqlx::raw_sql(
+        "CREATE SCHEMA kb; \
+         CREATE TABLE kb.notes (id INT PRIMARY KEY, body TEXT, tags TEXT[])",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let rows = (0..10_000).map(|i| {
+        vec![
+            Some(i.to_string()),
+            (i % 2 == 0).then(|| format!("note, \"{}\"\nline two", i)),
+            Some("{a,b}".to_string()),
+        ]
+    });
+    let copied = copy_in(&pool, "kb.notes", &["id", "body", "tags"], rows).await.unwrap();
+    assert_eq!(copied, 10_000);
+
+    let (body, nulls): (String, i64) = sqlx::query_as(
+        "SELECT (SELECT body FROM kb.notes WHERE id = 4), \
+                (SELECT count(*) FROM kb.notes WHERE body IS NULL)",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(body, "note, \"4\"\nline two");
+    assert_eq!(nulls, 5_000);
+
+    // A short row aborts the whole copy
+    let bad = vec![vec![Some("10001"), Some("x"), Some("{}")], vec![Some("10002")]];
+    assert!(copy_in(&pool, "kb.notes", &["id", "body", "tags"], bad).await.is_err());
+    let count: i64 = sqlx::query_scalar("SELECT count(*) FROM kb.notes")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 10_000);
+
+    drop(pool);
+    test_db.drop().await;
+}
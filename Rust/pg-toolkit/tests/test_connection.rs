@@ -1,6 +1,7 @@
 //! Integration tests for pg-toolkit connection module.
 //!
-//! Tests: PgConfig, create_pool, create_system_pool
+//! Tests: PgConfig, create_pool, create_system_pool, session settings,
+//!        application_name, PoolMonitor, options
 //!
 //! Run with:
 //!   cargo test --test test_connection
@@ -9,7 +10,7 @@
 
 use pg_toolkit::{
     PgConfig,
-    connection::{create_pool, create_system_pool},
+    connection::{PoolMonitor, create_pool, create_pool_lazy, create_system_pool},
     admin::database_exists,
 };
 
@@ -99,3 +100,199 @@ async fn test_database_creation_and_pool_connection() {
 
     test_db.drop().await;
 }
+
+#[tokio::test]
+async fn test_create_pool_over_unix_socket() {
+    // Set PG_SOCKET_DIR to the server's socket directory to run this test
+    let config = PgConfig::from_env();
+    if config.socket_dir.is_none() {
+        eprintln!("Skipping test: PG_SOCKET_DIR not set");
+        return;
+    }
+
+    let pool = match create_system_pool(&config).await {
+        Ok(pool) => pool,
+        Err(e) => {
+            eprintln!("Skipping test: PostgreSQL not available over socket ({})", e);
+            return;
+        }
+    };
+
+    // An empty inet_client_addr() means the session came in over the socket
+    let client_addr: Option<String> = sqlx::query_scalar("SELECT inet_client_addr()::text")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(client_addr, None);
+}
+
+#[tokio::test]
+async fn test_create_pool_lazy_defers_connection_errors() {
+    // Nothing listens on port 1; the pool is still created
+    let unreachable = PgConfig {
+        port: 1,
+        acquire_timeout_secs: Some(1),
+        ..PgConfig::from_env()
+    };
+    let pool = create_pool_lazy(&unreachable).expect("lazy pool needs no database");
+    assert!(sqlx::query("SELECT 1").execute(&pool).await.is_err());
+
+    let config = PgConfig::from_env();
+    if create_system_pool(&config).await.is_err() {
+        eprintln!("Skipping live part: PostgreSQL not available");
+        return;
+    }
+    let pool = create_pool_lazy(&config.with_database("postgres")).unwrap();
+    let one: i32 = sqlx::query_scalar("SELECT 1").fetch_one(&pool).await.unwrap();
+    assert_eq!(one, 1);
+}
+
+#[tokio::test]
+async fn test_pool_applies_session_settings() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = PgConfig {
+        statement_timeout_ms: Some(200),
+        lock_timeout_ms: Some(1500),
+        idle_in_transaction_session_timeout_ms: Some(60000),
+        search_path: Some("app, public".to_string()),
+        max_connections: Some(2),
+        ..test_db.config_with_db()
+    };
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    // Every pooled connection gets the settings, not just the first
+    let mut first = pool.acquire().await.unwrap();
+    let mut second = pool.acquire().await.unwrap();
+    for conn in [&mut first, &mut second] {
+        let (statement, lock, idle, path): (String, String, String, String) = sqlx::query_as(
+            "SELECT current_setting('statement_timeout'), current_setting('lock_timeout'), \
+                    current_setting('idle_in_transaction_session_timeout'), \
+                    current_setting('search_path')",
+        )
+        .fetch_one(&mut **conn)
+        .await
+        .unwrap();
+        assert_eq!(statement, "200ms");
+        assert_eq!(lock, "1500ms");
+        assert_eq!(idle, "1min");
+        assert_eq!(path, "app, public");
+    }
+    drop((first, second));
+
+    let err = sqlx::query("SELECT pg_sleep(2)").execute(&pool).await.unwrap_err();
+    assert!(err.to_string().contains("statement timeout"), "{}", err);
+
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_pool_sets_application_name() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = PgConfig {
+        application_name: Some("pg-toolkit-tests".to_string()),
+        ..test_db.config_with_db()
+    };
+    let pool = create_pool(&config).await.expect("Failed to connect");
+    let name: String = sqlx::query_scalar("SELECT current_setting('application_name')")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(name, "pg-toolkit-tests");
+    pool.close().await;
+
+    let pool = create_pool(&test_db.config_with_db()).await.expect("Failed to connect");
+    let name: String = sqlx::query_scalar("SELECT current_setting('application_name')")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert!(!name.is_empty(), "application_name should default to the binary name");
+
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_pool_monitor_samples_usage() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = PgConfig {
+        max_connections: Some(3),
+        ..test_db.config_with_db()
+    };
+    let monitor = PoolMonitor::new(create_pool(&config).await.expect("Failed to connect"));
+
+    let first = monitor.acquire().await.expect("Failed to acquire");
+    let second = monitor.acquire().await.expect("Failed to acquire");
+    let metrics = monitor.sample();
+    assert_eq!(metrics.max_connections, 3);
+    assert_eq!(metrics.in_use, 2);
+    assert_eq!(metrics.size, metrics.idle + metrics.in_use);
+    assert_eq!(metrics.acquire_wait.count, 2);
+    assert_eq!(metrics.acquire_wait.failures, 0);
+
+    // Connections are returned to the pool in the background
+    drop((first, second));
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    let metrics = monitor.sample();
+    assert_eq!(metrics.in_use, 0);
+    assert_eq!(metrics.idle, metrics.size);
+
+    monitor.pool().close().await;
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_pool_applies_options() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = PgConfig {
+        options: [("work_mem", "64MB"), ("geqo", "off")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+        ..test_db.config_with_db()
+    };
+    let pool = create_pool(&config).await.expect("Failed to connect");
+    let (work_mem, geqo): (String, String) =
+        sqlx::query_as("SELECT current_setting('work_mem'), current_setting('geqo')")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(work_mem, "64MB");
+    assert_eq!(geqo, "off");
+    pool.close().await;
+
+    // Unknown parameters are rejected by the server at connect time
+    let invalid = PgConfig {
+        options: [("no_such_parameter".to_string(), "1".to_string())].into_iter().collect(),
+        ..test_db.config_with_db()
+    };
+    assert!(create_pool(&invalid).await.is_err());
+
+    test_db.drop().await;
+}
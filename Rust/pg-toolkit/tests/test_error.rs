@@ -0,0 +1,78 @@
+//! Integration tests for pg-toolkit error module.
+//!
+//! Tests: Error classification of real server errors, from pool creation,
+//! admin functions and queries
+//!
+//! Run with:
+//!   cargo test --test test_error
+//!
+//! Requires PostgreSQL running (see Scripts/DockerBuilds/knowledge-base/docker-compose.yml)
+
+use anyhow::Context;
+use pg_toolkit::{Error, PgConfig, admin, connection::create_pool};
+
+mod common;
+use common::TestDb;
+
+#[tokio::test]
+async fn test_classifies_server_errors() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+    let config = test_db.config_with_db();
+
+    let missing = create_pool(&config.with_database("pg_toolkit_no_such_database"))
+        .await
+        .unwrap_err();
+    assert!(matches!(missing, Error::MissingDatabase(_)), "{:?}", missing);
+    assert_eq!(missing.code().as_deref(), Some("3D000"));
+
+    let unknown_role = PgConfig { user: "pg_toolkit_no_such_role".to_string(), ..config.clone() };
+    let err = create_pool(&unknown_role).await.unwrap_err();
+    assert!(matches!(err, Error::Authentication(_)), "{:?}", err);
+    // Admin functions report the same classification, with their context
+    let err = admin::create_database(&unknown_role, "pg_toolkit_never_created").await.unwrap_err();
+    assert!(matches!(err, Error::Authentication(_)), "{:?}", err);
+    assert_eq!(err.to_string(), "Failed to connect to system database");
+    assert!(err.code().is_some_and(|code| code.starts_with("28")), "{:?}", err);
+
+    // Nothing listens on port 1
+    let unreachable = PgConfig { port: 1, acquire_timeout_secs: Some(1), ..config.clone() };
+    let err = create_pool(&unreachable).await.unwrap_err();
+    assert!(matches!(err, Error::Connection(_)), "{:?}", err);
+
+    let pool = create_pool(&config).await.expect("Failed to connect");
+    let err = sqlx::query("SELEC 1")
+        .execute(&pool)
+        .await
+        .context("Failed to run query")
+        .map_err(Error::from)
+        .unwrap_err();
+    assert!(matches!(err, Error::Query(_)), "{:?}", err);
+    assert_eq!(err.to_string(), "Failed to run query");
+
+    // A role with no privileges on a table
+    let role = format!("reader_{}", test_db.db_name());
+    sqlx::raw_sql(&format!(
+        "CREATE TABLE secrets (id INTEGER); CREATE ROLE \"{}\" NOLOGIN", role
+    ))
+    .execute(&pool)
+    .await
+    .expect("Failed to create table and role");
+    let mut tx = pool.begin().await.unwrap();
+    sqlx::query(&format!("SET LOCAL ROLE \"{}\"", role)).execute(&mut *tx).await.unwrap();
+    let err = sqlx::query("SELECT * FROM secrets")
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::from)
+        .unwrap_err();
+    assert!(matches!(err, Error::Permission(_)), "{:?}", err);
+    tx.rollback().await.unwrap();
+    sqlx::query(&format!("DROP ROLE \"{}\"", role)).execute(&pool).await.unwrap();
+
+    test_db.drop().await;
+}
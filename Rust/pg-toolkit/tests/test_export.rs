@@ -0,0 +1,70 @@
+//! Integration tests for pg-toolkit export module.
+//!
+//! Tests: query_to_csv, query_to_json
+//!
+//! Run with:
+//!   cargo test --test test_export
+//!
+//! Requires PostgreSQL running (see Scripts/DockerBuilds/knowledge-base/docker-compose.yml)
+
+use pg_toolkit::{
+    connection::create_pool,
+    export::{query_to_csv, query_to_json},
+};
+
+mod common;
+use common::TestDb;
+
+#[tokio::test]
+async fn test_query_to_csv_and_json() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let pool = create_pool(&test_db.config_with_db()).await.expect("Failed to connect");
+    sqlx::raw_sql(
+        "CREATE TABLE notes (id INT, body TEXT, meta JSONB); \
+         INSERT INTO notes VALUES (1, 'plain', '{\"tag\": \"a\"}'), \
+             (2, 'has \"quotes\", commas\nand a newline', NULL)",
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create test table");
+
+    let mut csv = Vec::new();
+    let rows = query_to_csv(&pool, "SELECT id, body FROM notes ORDER BY id;", &mut csv)
+        .await
+        .expect("Failed to export CSV");
+    assert_eq!(rows, 2);
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "id,body\n1,plain\n2,\"has \"\"quotes\"\", commas\nand a newline\"\n"
+    );
+
+    let mut json = Vec::new();
+    let rows = query_to_json(&pool, "SELECT id, meta FROM notes ORDER BY id", &mut json)
+        .await
+        .expect("Failed to export JSON");
+    assert_eq!(rows, 2);
+    let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    assert_eq!(
+        value,
+        serde_json::json!([{"id": 1, "meta": {"tag": "a"}}, {"id": 2, "meta": null}])
+    );
+
+    // Empty results are still valid documents
+    let mut json = Vec::new();
+    assert_eq!(query_to_json(&pool, "SELECT * FROM notes WHERE false", &mut json).await.unwrap(), 0);
+    assert_eq!(serde_json::from_slice::<serde_json::Value>(&json).unwrap(), serde_json::json!([]));
+    let mut csv = Vec::new();
+    assert_eq!(query_to_csv(&pool, "SELECT id FROM notes WHERE false", &mut csv).await.unwrap(), 0);
+    assert_eq!(csv, b"id\n");
+
+    assert!(query_to_csv(&pool, "SELECT * FROM missing", &mut Vec::new()).await.is_err());
+
+    test_db.drop().await;
+}
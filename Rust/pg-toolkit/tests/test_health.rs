@@ -0,0 +1,65 @@
+//! Integration tests for pg-toolkit health module.
+//!
+//! Tests: check
+//!
+//! Run with:
+//!   cargo test --test test_health
+//!
+//! Requires PostgreSQL running (see Scripts/DockerBuilds/knowledge-base/docker-compose.yml)
+
+use pg_toolkit::{
+    PgConfig,
+    connection::{create_pool, create_pool_lazy},
+    health::check,
+};
+use std::time::Duration;
+
+mod common;
+use common::TestDb;
+
+#[tokio::test]
+async fn test_health_check() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let pool = create_pool(&test_db.config_with_db()).await.expect("Failed to connect");
+
+    // Hold a transaction open in another session
+    let mut tx = pool.begin().await.unwrap();
+    sqlx::query("SELECT txid_current()").execute(&mut *tx).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let report = check(&pool).await;
+    assert!(report.is_healthy(), "{:?}", report);
+    assert!(report.latency.is_some());
+    assert!(!report.in_recovery);
+    assert_eq!(report.replication_lag, None);
+    assert!(report.connections.unwrap() >= 2);
+    let saturation = report.saturation.unwrap();
+    assert!(saturation > 0.0 && saturation <= 1.0);
+    assert!(report.longest_transaction.unwrap() >= Duration::from_millis(200));
+    tx.rollback().await.unwrap();
+
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_health_check_unreachable() {
+    // Nothing listens on port 1
+    let unreachable = PgConfig {
+        port: 1,
+        acquire_timeout_secs: Some(1),
+        ..PgConfig::from_env()
+    };
+    let pool = create_pool_lazy(&unreachable).unwrap();
+    let report = check(&pool).await;
+    assert!(!report.connected);
+    assert!(!report.is_healthy());
+    assert!(report.error.is_some());
+    assert_eq!(report.latency, None);
+}
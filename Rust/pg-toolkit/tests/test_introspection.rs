@@ -1,7 +1,13 @@
 //! Integration tests for pg-toolkit introspection module.
 //!
 //! Tests: table_exists, list_tables, list_table_names, list_columns,
-//!        current_database
+//!        current_database, database_size, table_sizes, estimate_row_count,
+//!        count_rows, list_indexes, list_columns_detailed, primary_key,
+//!        list_foreign_keys, list_views, list_materialized_views, list_schemas,
+//!        table_exists_in, list_tables_in_schema, list_columns_in, get_comment,
+//!        server_version, get_setting, list_settings, dump_schema, index_usage,
+//!        find_unused_indexes, current_user, session_info, list_publications,
+//!        list_subscriptions, table_checksum
 //!
 //! Run with:
 //!   cargo test --test test_introspection
@@ -10,7 +16,18 @@
 
 use pg_toolkit::{
     connection::create_pool,
-    introspection::{table_exists, list_tables, list_table_names, list_columns, current_database},
+    introspection::{
+        table_exists, list_tables, list_table_names, list_columns, current_database,
+        database_size, table_sizes, estimate_row_count, count_rows,
+        list_indexes, list_columns_detailed, primary_key, list_foreign_keys,
+        list_views, list_materialized_views, list_schemas, table_exists_in,
+        list_tables_in_schema, list_columns_in, get_comment, server_version, get_setting,
+        list_settings, dump_schema, index_usage, find_unused_indexes, current_user,
+        session_info, list_publications, list_subscriptions, table_checksum, SettingValue,
+    },
+    schema_diff::{SchemaSnapshot, diff},
+    admin::{create_schema, set_comment},
+    testing::unique_db_name,
 };
 
 mod common;
@@ -176,6 +193,56 @@ async fn test_current_database() {
     test_db.drop().await;
 }
 
+#[tokio::test]
+async fn test_current_user_and_session_info() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let mut config = test_db.config_with_db();
+    config.application_name = Some("session_info_test".into());
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    assert_eq!(current_user(&pool).await.expect("Failed to get current user"), config.user);
+
+    let session = session_info(&pool).await.expect("Failed to get session info");
+    assert!(session.pid > 0);
+    assert_eq!(session.current_user, config.user);
+    assert_eq!(session.session_user, config.user);
+    assert_eq!(session.database, test_db.db_name());
+    assert_eq!(session.application_name, "session_info_test");
+    assert_eq!(session.client_addr.is_some(), session.client_port.is_some());
+    assert_eq!(session.isolation_level, "read committed");
+    assert!(!session.read_only);
+    assert!(session.backend_start.is_some());
+
+    // A role set at connect time changes current_user but not session_user
+    let role = unique_db_name("session_role");
+    sqlx::query(&format!("CREATE ROLE {} NOLOGIN", role))
+        .execute(&pool)
+        .await
+        .expect("Failed to create role");
+    let mut role_config = config.clone();
+    role_config.options.insert("role".into(), role.clone());
+    let role_pool = create_pool(&role_config).await.expect("Failed to connect with role");
+
+    assert_eq!(current_user(&role_pool).await.expect("Failed to get current user"), role);
+    let session = session_info(&role_pool).await.expect("Failed to get session info");
+    assert_eq!(session.current_user, role);
+    assert_eq!(session.session_user, config.user);
+
+    role_pool.close().await;
+    sqlx::query(&format!("DROP ROLE {}", role))
+        .execute(&pool)
+        .await
+        .expect("Failed to drop role");
+    test_db.drop().await;
+}
+
 #[tokio::test]
 async fn test_list_tables_excludes_system_tables() {
     let test_db = match TestDb::new().await {
@@ -225,3 +292,731 @@ async fn test_list_tables_excludes_system_tables() {
 
     test_db.drop().await;
 }
+
+#[tokio::test]
+async fn test_database_and_table_sizes() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    // Large values go to TOAST; the primary key adds an index
+    sqlx::raw_sql(
+        "CREATE TABLE sized_table (id SERIAL PRIMARY KEY, body TEXT); \
+         CREATE TABLE empty_table (id INTEGER); \
+         INSERT INTO sized_table (body) \
+         SELECT string_agg(md5(random()::text), '') FROM generate_series(1, 1000), \
+              generate_series(1, 20) g GROUP BY g",
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create test tables");
+
+    assert!(database_size(&pool).await.unwrap() > 0);
+
+    let sizes = table_sizes(&pool).await.expect("Failed to get table sizes");
+    assert_eq!(sizes[0].name, "sized_table", "largest table comes first");
+    let sized = &sizes[0];
+    assert_eq!(sized.schema, "public");
+    assert!(sized.table_bytes > 0);
+    assert!(sized.index_bytes > 0);
+    assert!(sized.toast_bytes > 0);
+    assert!(sized.total_bytes >= sized.table_bytes + sized.index_bytes + sized.toast_bytes);
+
+    let empty = sizes.iter().find(|t| t.name == "empty_table").unwrap();
+    assert_eq!(empty.table_bytes, 0);
+    assert_eq!(empty.index_bytes, 0);
+
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_estimate_and_count_rows() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    sqlx::raw_sql(
+        "CREATE TABLE counted (id INTEGER); \
+         INSERT INTO counted SELECT generate_series(1, 5000)",
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create test table");
+
+    // Never analyzed
+    assert_eq!(estimate_row_count(&pool, "counted").await.unwrap(), None);
+    assert_eq!(count_rows(&pool, "counted").await.unwrap(), 5000);
+
+    sqlx::query("ANALYZE counted").execute(&pool).await.unwrap();
+    assert_eq!(estimate_row_count(&pool, "counted").await.unwrap(), Some(5000));
+
+    assert!(estimate_row_count(&pool, "missing_table").await.is_err());
+    assert!(count_rows(&pool, "missing_table").await.is_err());
+
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_list_indexes() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    sqlx::raw_sql(
+        "CREATE TABLE indexed (id SERIAL PRIMARY KEY, email TEXT, tags TEXT[]); \
+         CREATE UNIQUE INDEX indexed_email_lower ON indexed (lower(email), id); \
+         CREATE INDEX indexed_tags ON indexed USING gin (tags)",
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create test table");
+
+    let indexes = list_indexes(&pool, "indexed").await.expect("Failed to list indexes");
+    let names: Vec<&str> = indexes.iter().map(|i| i.name.as_str()).collect();
+    assert_eq!(names, ["indexed_email_lower", "indexed_pkey", "indexed_tags"]);
+
+    let email = &indexes[0];
+    assert!(email.is_unique && !email.is_primary);
+    assert_eq!(email.method, "btree");
+    assert_eq!(email.columns, ["lower(email)", "id"]);
+    assert!(email.definition.starts_with("CREATE UNIQUE INDEX indexed_email_lower"));
+
+    assert!(indexes[1].is_primary);
+    assert_eq!(indexes[1].columns, ["id"]);
+    assert_eq!(indexes[2].method, "gin");
+    assert!(indexes.iter().all(|i| i.size_bytes > 0));
+
+    assert!(list_indexes(&pool, "missing_table").await.unwrap().is_empty());
+
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_list_columns_detailed() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    sqlx::query(
+        "CREATE TABLE detailed (\
+             id BIGINT GENERATED ALWAYS AS IDENTITY, \
+             email VARCHAR(255) NOT NULL, \
+             score INTEGER DEFAULT 0, \
+             doubled INTEGER GENERATED ALWAYS AS (score * 2) STORED)",
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create test table");
+
+    let columns = list_columns_detailed(&pool, "detailed").await.expect("Failed to list columns");
+    let names: Vec<&str> = columns.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["id", "email", "score", "doubled"]);
+
+    let id = &columns[0];
+    assert_eq!(id.ordinal_position, 1);
+    assert_eq!(id.data_type, "bigint");
+    assert!(id.is_identity && !id.is_generated && !id.is_nullable);
+
+    let email = &columns[1];
+    assert_eq!(email.data_type, "character varying");
+    assert_eq!(email.udt_name, "varchar");
+    assert_eq!(email.max_length, Some(255));
+    assert!(!email.is_nullable);
+
+    let score = &columns[2];
+    assert_eq!(score.default.as_deref(), Some("0"));
+    assert_eq!(score.max_length, None);
+    assert!(score.is_nullable);
+
+    assert!(columns[3].is_generated && !columns[3].is_identity);
+
+    assert!(list_columns_detailed(&pool, "missing_table").await.unwrap().is_empty());
+
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_primary_and_foreign_keys() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    sqlx::raw_sql(
+        "CREATE TABLE tenants (id INTEGER PRIMARY KEY); \
+         CREATE TABLE users (tenant_id INTEGER, user_id INTEGER, \
+             PRIMARY KEY (tenant_id, user_id)); \
+         CREATE TABLE posts (id INTEGER, author_tenant INTEGER, author_id INTEGER, \
+             tenant_id INTEGER REFERENCES tenants ON DELETE CASCADE, \
+             CONSTRAINT posts_author_fkey FOREIGN KEY (author_tenant, author_id) \
+                 REFERENCES users (tenant_id, user_id) ON DELETE SET NULL ON UPDATE RESTRICT)",
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create test tables");
+
+    let pk = primary_key(&pool, "users").await.unwrap().expect("users has a primary key");
+    assert_eq!(pk.name, "users_pkey");
+    assert_eq!(pk.columns, ["tenant_id", "user_id"]);
+    assert_eq!(primary_key(&pool, "posts").await.unwrap(), None);
+
+    let fks = list_foreign_keys(&pool, "posts").await.expect("Failed to list foreign keys");
+    assert_eq!(fks.len(), 2);
+
+    let author = &fks[0];
+    assert_eq!(author.name, "posts_author_fkey");
+    assert_eq!(author.columns, ["author_tenant", "author_id"]);
+    assert_eq!(author.referenced_schema, "public");
+    assert_eq!(author.referenced_table, "users");
+    assert_eq!(author.referenced_columns, ["tenant_id", "user_id"]);
+    assert_eq!(author.on_delete, "SET NULL");
+    assert_eq!(author.on_update, "RESTRICT");
+
+    let tenant = &fks[1];
+    assert_eq!(tenant.referenced_table, "tenants");
+    assert_eq!(tenant.referenced_columns, ["id"]);
+    assert_eq!(tenant.on_delete, "CASCADE");
+    assert_eq!(tenant.on_update, "NO ACTION");
+
+    assert!(list_foreign_keys(&pool, "tenants").await.unwrap().is_empty());
+
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_list_views() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    sqlx::raw_sql(
+        "CREATE TABLE events (id INTEGER, kind TEXT); \
+         CREATE VIEW clicks AS SELECT id FROM events WHERE kind = 'click'; \
+         CREATE MATERIALIZED VIEW kind_counts AS \
+             SELECT kind, count(*) AS n FROM events GROUP BY kind WITH NO DATA",
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create test views");
+
+    let views = list_views(&pool).await.expect("Failed to list views");
+    assert_eq!(views.len(), 1);
+    assert_eq!(views[0].schema, "public");
+    assert_eq!(views[0].name, "clicks");
+    assert_eq!(views[0].owner, config.user);
+    assert!(views[0].definition.contains("'click'"));
+
+    let matviews = list_materialized_views(&pool).await.expect("Failed to list materialized views");
+    assert_eq!(matviews.len(), 1);
+    assert_eq!(matviews[0].name, "kind_counts");
+    assert!(matviews[0].definition.contains("count(*)"));
+    assert!(!matviews[0].is_populated);
+    assert!(!matviews[0].has_indexes);
+
+    // Views are not tables
+    let names = list_table_names(&pool).await.unwrap();
+    assert!(!names.contains(&"clicks".to_string()));
+    assert!(!names.contains(&"kind_counts".to_string()));
+
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_schema_aware_introspection() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    assert!(!list_schemas(&pool).await.unwrap().contains(&"analytics".to_string()));
+    create_schema(&pool, "analytics").await.expect("Failed to create schema");
+    // Idempotent
+    create_schema(&pool, "analytics").await.expect("Failed to re-create schema");
+
+    let schemas = list_schemas(&pool).await.expect("Failed to list schemas");
+    assert!(schemas.contains(&"analytics".to_string()));
+    assert!(schemas.contains(&"public".to_string()));
+    assert!(!schemas.contains(&"pg_catalog".to_string()));
+
+    sqlx::query("CREATE TABLE analytics.daily (day DATE, visits INTEGER)")
+        .execute(&pool)
+        .await
+        .expect("Failed to create test table");
+
+    assert!(table_exists_in(&pool, "analytics", "daily").await.unwrap());
+    assert!(!table_exists_in(&pool, "public", "daily").await.unwrap());
+    assert!(!table_exists(&pool, "daily").await.unwrap());
+
+    let tables = list_tables_in_schema(&pool, "analytics").await.expect("Failed to list tables");
+    assert_eq!(tables.len(), 1);
+    assert_eq!(tables[0].schema, "analytics");
+    assert_eq!(tables[0].name, "daily");
+    assert!(list_tables_in_schema(&pool, "public").await.unwrap().is_empty());
+
+    let columns = list_columns_in(&pool, "analytics", "daily").await.unwrap();
+    assert_eq!(columns, ["day", "visits"]);
+    assert!(list_columns(&pool, "daily").await.unwrap().is_empty());
+
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_qualified_table_introspection() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    // A public table of the same name must not be picked up
+    sqlx::raw_sql(
+        "CREATE TABLE accounts (other TEXT); \
+         CREATE SCHEMA ledger; \
+         CREATE TABLE ledger.accounts (id INTEGER PRIMARY KEY, owner TEXT NOT NULL); \
+         CREATE TABLE ledger.entries ( \
+             id BIGINT PRIMARY KEY, \
+             account_id INTEGER REFERENCES ledger.accounts (id) ON DELETE CASCADE); \
+         CREATE INDEX entries_account_idx ON ledger.entries (account_id); \
+         INSERT INTO ledger.entries SELECT n, NULL FROM generate_series(1, 100) n; \
+         ANALYZE ledger.entries",
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create test tables");
+
+    let columns = list_columns_detailed(&pool, "ledger.accounts").await.unwrap();
+    let names: Vec<_> = columns.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["id", "owner"]);
+    assert!(!columns[1].is_nullable);
+
+    let pk = primary_key(&pool, "ledger.accounts").await.unwrap().expect("ledger.accounts has a primary key");
+    assert_eq!(pk.columns, ["id"]);
+    assert_eq!(primary_key(&pool, "accounts").await.unwrap(), None);
+
+    let fks = list_foreign_keys(&pool, "ledger.entries").await.unwrap();
+    assert_eq!(fks.len(), 1);
+    assert_eq!((fks[0].referenced_schema.as_str(), fks[0].referenced_table.as_str()), ("ledger", "accounts"));
+    assert_eq!(fks[0].on_delete, "CASCADE");
+
+    assert_eq!(estimate_row_count(&pool, "ledger.entries").await.unwrap(), Some(100));
+    assert!(estimate_row_count(&pool, "entries").await.is_err());
+
+    let indexes = list_indexes(&pool, "ledger.entries").await.unwrap();
+    let names: Vec<_> = indexes.iter().map(|i| i.name.as_str()).collect();
+    assert_eq!(names, ["entries_account_idx", "entries_pkey"]);
+    assert!(list_indexes(&pool, "entries").await.unwrap().is_empty());
+
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_table_and_column_comments() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    sqlx::raw_sql(
+        "CREATE TABLE documented (id INTEGER, body TEXT); \
+         CREATE SCHEMA kb; \
+         CREATE TABLE kb.chunks (id INTEGER)",
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create test tables");
+
+    assert_eq!(get_comment(&pool, "documented", None).await.unwrap(), None);
+
+    set_comment(&pool, "documented", None, Some("User's notes, with a \\ backslash"))
+        .await
+        .expect("Failed to set table comment");
+    set_comment(&pool, "documented", Some("body"), Some("Markdown text"))
+        .await
+        .expect("Failed to set column comment");
+    set_comment(&pool, "kb.chunks", Some("id"), Some("Chunk id"))
+        .await
+        .expect("Failed to set comment in another schema");
+
+    assert_eq!(
+        get_comment(&pool, "documented", None).await.unwrap().as_deref(),
+        Some("User's notes, with a \\ backslash")
+    );
+    assert_eq!(
+        get_comment(&pool, "documented", Some("body")).await.unwrap().as_deref(),
+        Some("Markdown text")
+    );
+    assert_eq!(get_comment(&pool, "documented", Some("id")).await.unwrap(), None);
+    assert_eq!(
+        get_comment(&pool, "kb.chunks", Some("id")).await.unwrap().as_deref(),
+        Some("Chunk id")
+    );
+
+    set_comment(&pool, "documented", None, None).await.expect("Failed to clear comment");
+    assert_eq!(get_comment(&pool, "documented", None).await.unwrap(), None);
+
+    assert!(get_comment(&pool, "missing_table", None).await.is_err());
+    assert!(get_comment(&pool, "documented", Some("missing_column")).await.is_err());
+
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_server_version_and_settings() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    let version = server_version(&pool).await.expect("Failed to get server version");
+    assert!(version.major >= 10);
+    assert!(version.full.starts_with(&format!("{}.{}", version.major, version.minor)));
+
+    let max_connections = get_setting(&pool, "max_connections")
+        .await
+        .unwrap()
+        .expect("max_connections exists");
+    assert!(matches!(max_connections.value, SettingValue::Integer(n) if n > 0));
+
+    let shared_buffers = get_setting(&pool, "SHARED_BUFFERS").await.unwrap().unwrap();
+    assert_eq!(shared_buffers.unit.as_deref(), Some("8kB"));
+    assert!(shared_buffers.as_bytes().unwrap() > 0);
+
+    let fsync = get_setting(&pool, "fsync").await.unwrap().unwrap();
+    assert!(matches!(fsync.value, SettingValue::Bool(_)));
+    let wal_level = get_setting(&pool, "wal_level").await.unwrap().unwrap();
+    assert!(matches!(wal_level.value, SettingValue::Enum(_)));
+
+    assert_eq!(get_setting(&pool, "no_such_setting").await.unwrap(), None);
+
+    let timeouts = list_settings(&pool, Some("timeout")).await.expect("Failed to list settings");
+    assert!(timeouts.iter().any(|s| s.name == "statement_timeout"));
+    assert!(timeouts.iter().all(|s| s.name.contains("timeout")));
+    assert!(list_settings(&pool, None).await.unwrap().len() > timeouts.len());
+
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_dump_schema() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    sqlx::raw_sql(
+        "CREATE TABLE items (id SERIAL PRIMARY KEY, sku TEXT NOT NULL UNIQUE, \
+             price NUMERIC(10, 2) CHECK (price > 0)); \
+         CREATE INDEX items_price_idx ON items (price)",
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create test table");
+
+    let snapshot = dump_schema(&pool).await.expect("Failed to dump schema");
+    let items = &snapshot.tables["items"];
+    assert_eq!(items.columns["price"].data_type, "numeric(10,2)");
+    assert!(!items.columns["sku"].nullable);
+    assert!(items.indexes.contains_key("items_price_idx"));
+    assert_eq!(items.constraints["items_pkey"].as_deref(), Some("PRIMARY KEY (id)"));
+    assert_eq!(items.constraints["items_sku_key"].as_deref(), Some("UNIQUE (sku)"));
+    assert_eq!(
+        items.constraints["items_price_check"].as_deref(),
+        Some("CHECK ((price > (0)::numeric))")
+    );
+
+    // Round-trips through JSON and YAML, and diffs clean against itself
+    let json = serde_json::to_string(&snapshot).unwrap();
+    assert_eq!(serde_json::from_str::<SchemaSnapshot>(&json).unwrap(), snapshot);
+    let yaml = snapshot.to_yaml().unwrap();
+    assert_eq!(serde_yaml::from_str::<SchemaSnapshot>(&yaml).unwrap(), snapshot);
+
+    sqlx::query("ALTER TABLE items DROP CONSTRAINT items_price_check")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let changed = diff(&snapshot, &dump_schema(&pool).await.unwrap());
+    assert_eq!(changed.missing_constraints.len(), 1);
+    assert_eq!(changed.missing_constraints[0].constraint, "items_price_check");
+
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_index_usage() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    sqlx::raw_sql(
+        "CREATE TABLE orders (id INT PRIMARY KEY, customer INT, note TEXT); \
+         CREATE INDEX orders_customer_idx ON orders (customer); \
+         CREATE INDEX orders_note_idx ON orders (note); \
+         INSERT INTO orders SELECT g, g % 10, 'n' || g FROM generate_series(1, 1000) g; \
+         ANALYZE orders",
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create test table");
+
+    let mut conn = pool.acquire().await.unwrap();
+    sqlx::query("SET enable_seqscan = off").execute(&mut *conn).await.unwrap();
+    sqlx::query("SELECT count(*) FROM orders WHERE customer = 3")
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+    drop(conn);
+
+    // Statistics are reported asynchronously; wait for the scan to show up
+    let mut usage = vec![];
+    for _ in 0..50 {
+        sqlx::query("SELECT pg_stat_clear_snapshot()").execute(&pool).await.unwrap();
+        usage = index_usage(&pool).await.expect("Failed to get index usage");
+        if usage.iter().any(|u| u.index == "orders_customer_idx" && u.scans > 0) {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    let names: Vec<&str> = usage.iter().map(|u| u.index.as_str()).collect();
+    assert_eq!(names, ["orders_customer_idx", "orders_note_idx", "orders_pkey"]);
+    let customer = &usage[0];
+    assert_eq!((customer.schema.as_str(), customer.table.as_str()), ("public", "orders"));
+    assert!(customer.scans > 0);
+    assert!(customer.size_bytes > 0);
+    assert!(usage[2].is_unique);
+
+    // The primary key is never reported, even if unscanned
+    let unused = find_unused_indexes(&pool).await.unwrap();
+    let unused: Vec<&str> = unused.iter().map(|u| u.index.as_str()).collect();
+    assert_eq!(unused, ["orders_note_idx"]);
+
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_list_publications_and_subscriptions() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    assert!(list_publications(&pool).await.expect("Failed to list publications").is_empty());
+    assert!(list_subscriptions(&pool).await.expect("Failed to list subscriptions").is_empty());
+
+    sqlx::raw_sql(
+        "CREATE SCHEMA kb; \
+         CREATE TABLE orders (id INT PRIMARY KEY); \
+         CREATE TABLE kb.docs (id INT PRIMARY KEY); \
+         CREATE TABLE audit (id INT PRIMARY KEY); \
+         CREATE PUBLICATION cdc FOR TABLE orders, kb.docs WITH (publish = 'insert, update'); \
+         CREATE PUBLICATION everything FOR ALL TABLES",
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create publications");
+
+    let publications = list_publications(&pool).await.expect("Failed to list publications");
+    assert_eq!(publications.len(), 2);
+    let cdc = &publications[0];
+    assert_eq!(cdc.name, "cdc");
+    assert_eq!(cdc.owner, config.user);
+    assert!(!cdc.all_tables);
+    assert!(cdc.inserts && cdc.updates && !cdc.deletes && !cdc.truncates);
+    assert_eq!(cdc.tables, vec!["kb.docs", "public.orders"]);
+    let everything = &publications[1];
+    assert!(everything.all_tables);
+    assert_eq!(everything.tables, vec!["kb.docs", "public.audit", "public.orders"]);
+
+    // A subscription can be created without reaching its publisher; it
+    // stays disabled and its slot is never created
+    let created = sqlx::query(
+        "CREATE SUBSCRIPTION mirror CONNECTION 'host=/nonexistent dbname=upstream' \
+         PUBLICATION cdc, other WITH (connect = false)",
+    )
+    .execute(&pool)
+    .await;
+    if let Err(e) = created {
+        eprintln!("Skipping subscription checks: {}", e);
+        test_db.drop().await;
+        return;
+    }
+
+    let subscriptions = list_subscriptions(&pool).await.expect("Failed to list subscriptions");
+    assert_eq!(subscriptions.len(), 1);
+    let mirror = &subscriptions[0];
+    assert_eq!(mirror.name, "mirror");
+    assert!(!mirror.enabled);
+    assert_eq!(mirror.publications, vec!["cdc", "other"]);
+    assert_eq!(mirror.slot_name.as_deref(), Some("mirror"));
+    assert!(mirror.tables.is_empty());
+    assert_eq!(mirror.worker_pid, None);
+    assert_eq!(mirror.last_message_at, None);
+
+    // Without a slot, dropping does not try to reach the publisher
+    sqlx::raw_sql(
+        "ALTER SUBSCRIPTION mirror SET (slot_name = NONE); \
+         DROP SUBSCRIPTION mirror",
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to drop subscription");
+
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_table_checksum() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    // Same rows, inserted in different orders
+    sqlx::raw_sql(
+        "CREATE SCHEMA restored; \
+         CREATE TABLE items (id INT PRIMARY KEY, name TEXT, price NUMERIC); \
+         CREATE TABLE restored.items (id INT PRIMARY KEY, name TEXT, price NUMERIC); \
+         INSERT INTO items SELECT i, 'item ' || i, i * 1.5 FROM generate_series(1, 500) i; \
+         INSERT INTO restored.items \
+             SELECT i, 'item ' || i, i * 1.5 FROM generate_series(500, 1, -1) i; \
+         CREATE TABLE empty (id INT)",
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create tables");
+
+    let original = table_checksum(&pool, "items", &[]).await.expect("Failed to checksum");
+    let restored = table_checksum(&pool, "restored.items", &[]).await.expect("Failed to checksum");
+    assert_eq!(original.rows, 500);
+    assert_eq!(original.checksum.len(), 32);
+    assert_eq!(original, restored);
+
+    sqlx::query("UPDATE restored.items SET price = price + 0.01 WHERE id = 250")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let changed = table_checksum(&pool, "restored.items", &[]).await.expect("Failed to checksum");
+    assert_eq!(changed.rows, 500);
+    assert_ne!(changed.checksum, original.checksum);
+
+    // Only the listed columns count
+    let subset = ["id", "name"];
+    assert_eq!(
+        table_checksum(&pool, "items", &subset).await.unwrap(),
+        table_checksum(&pool, "restored.items", &subset).await.unwrap()
+    );
+
+    // NULL and the empty string are told apart
+    sqlx::query("UPDATE restored.items SET price = price - 0.01 WHERE id = 250")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::raw_sql(
+        "UPDATE items SET name = NULL WHERE id = 1; \
+         UPDATE restored.items SET name = '' WHERE id = 1",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    assert_ne!(
+        table_checksum(&pool, "items", &[]).await.unwrap(),
+        table_checksum(&pool, "restored.items", &[]).await.unwrap()
+    );
+
+    let empty = table_checksum(&pool, "empty", &[]).await.expect("Failed to checksum");
+    assert_eq!(empty.rows, 0);
+    assert_eq!(empty.checksum, "d41d8cd98f00b204e9800998ecf8427e");
+
+    assert!(table_checksum(&pool, "no_such_table", &[]).await.is_err());
+    assert!(table_checksum(&pool, "items", &["no_such_column"]).await.is_err());
+
+    test_db.drop().await;
+}
@@ -0,0 +1,122 @@
+//! Integration tests for pg-toolkit maintenance module.
+//!
+//! Tests: reindex, estimate_bloat
+//!
+//! Run with:
+//!   cargo test --test test_maintenance
+//!
+//! Requires PostgreSQL running (see Scripts/DockerBuilds/knowledge-base/docker-compose.yml)
+
+use pg_toolkit::{
+    connection::create_pool,
+    maintenance::{ReindexTarget, estimate_bloat, reindex},
+};
+
+mod common;
+use common::TestDb;
+
+/// `pg_class.relfilenode` of a relation, which changes when it is rebuilt.
+async fn relfilenode(pool: &sqlx::PgPool, name: &str) -> i64 {
+    sqlx::query_scalar("SELECT relfilenode::int8 FROM pg_class WHERE oid = to_regclass($1)")
+        .bind(name)
+        .fetch_one(pool)
+        .await
+        .expect("Failed to read relfilenode")
+}
+
+#[tokio::test]
+async fn test_reindex() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    sqlx::raw_sql(
+        "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT); \
+         CREATE INDEX items_name ON items (name); \
+         INSERT INTO items SELECT i, 'item ' || i FROM generate_series(1, 100) i",
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create test table");
+
+    let before = relfilenode(&pool, "items_name").await;
+    reindex(&pool, &ReindexTarget::Index("items_name".into()), false)
+        .await
+        .expect("Failed to reindex index");
+    let after_index = relfilenode(&pool, "items_name").await;
+    assert_ne!(before, after_index);
+
+    let pkey_before = relfilenode(&pool, "items_pkey").await;
+    reindex(&pool, &ReindexTarget::Table("public.items".into()), true)
+        .await
+        .expect("Failed to reindex table concurrently");
+    assert_ne!(relfilenode(&pool, "items_name").await, after_index);
+    assert_ne!(relfilenode(&pool, "items_pkey").await, pkey_before);
+
+    assert!(reindex(&pool, &ReindexTarget::Index("missing_index".into()), false).await.is_err());
+
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_estimate_bloat() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    // Delete nine rows in ten without vacuuming: the pages stay, the rows go
+    sqlx::raw_sql(
+        "CREATE TABLE events (id INTEGER PRIMARY KEY, payload TEXT) \
+             WITH (autovacuum_enabled = false); \
+         CREATE TABLE steady (id INTEGER PRIMARY KEY, payload TEXT); \
+         INSERT INTO events SELECT i, repeat('x', 100) FROM generate_series(1, 20000) i; \
+         INSERT INTO steady SELECT i, repeat('x', 100) FROM generate_series(1, 20000) i; \
+         DELETE FROM events WHERE id % 10 <> 0; \
+         ANALYZE events; \
+         ANALYZE steady",
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create test tables");
+
+    let estimates = estimate_bloat(&pool).await.expect("Failed to estimate bloat");
+    let find = |table: &str, index: Option<&str>| {
+        estimates
+            .iter()
+            .find(|e| e.schema == "public" && e.table == table && e.index.as_deref() == index)
+            .unwrap_or_else(|| panic!("No estimate for {} {:?}", table, index))
+    };
+
+    let events = find("events", None);
+    assert!(!events.is_approximate);
+    assert!(events.real_size_bytes > 0);
+    assert!(events.bloat_percent > 50.0, "events bloat {}", events.bloat_percent);
+    assert!(events.bloat_bytes > 0);
+    assert_eq!(events.fillfactor, 100);
+
+    let steady = find("steady", None);
+    assert!(steady.bloat_percent < 20.0, "steady bloat {}", steady.bloat_percent);
+
+    let events_pkey = find("events", Some("events_pkey"));
+    assert!(events_pkey.bloat_percent > 50.0, "events_pkey bloat {}", events_pkey.bloat_percent);
+    assert_eq!(events_pkey.fillfactor, 90);
+
+    // Largest bloat first
+    assert!(estimates.windows(2).all(|w| w[0].bloat_bytes >= w[1].bloat_bytes));
+
+    test_db.drop().await;
+}
@@ -0,0 +1,99 @@
+//! Integration tests for pg-toolkit migrations module.
+//!
+//! Tests: Migrator::migrate_up, migrate_down, status
+//!
+//! Run with:
+//!   cargo test --test test_migrations
+//!
+//! Requires PostgreSQL running (see Scripts/DockerBuilds/knowledge-base/docker-compose.yml)
+
+use pg_toolkit::{
+    connection::create_pool,
+    introspection::table_exists,
+    migrations::{Migration, Migrator},
+};
+
+mod common;
+use common::TestDb;
+
+fn migrator() -> Migrator {
+    Migrator::new(vec![
+        Migration::new(1, "create_users", "CREATE TABLE users (id SERIAL PRIMARY KEY);")
+            .with_down("DROP TABLE users;"),
+        Migration::new(
+            2,
+            "add_email",
+            "ALTER TABLE users ADD COLUMN email TEXT; CREATE INDEX users_email ON users (email);",
+        )
+        .with_down("DROP INDEX users_email; ALTER TABLE users DROP COLUMN email;"),
+        Migration::new(3, "create_audit", "CREATE TABLE audit (id INT);"),
+    ])
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_migrate_up_down_and_status() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let pool = create_pool(&test_db.config_with_db()).await.expect("Failed to connect");
+    let migrator = migrator();
+    let reversible = Migrator::new(migrator.migrations()[..2].to_vec()).unwrap();
+
+    let status = reversible.status(&pool).await.unwrap();
+    assert!(status.iter().all(|s| s.applied_at.is_none()));
+
+    assert_eq!(reversible.migrate_up(&pool).await.unwrap(), [1, 2]);
+    // Already up to date
+    assert!(reversible.migrate_up(&pool).await.unwrap().is_empty());
+    let status = reversible.status(&pool).await.unwrap();
+    assert!(status.iter().all(|s| s.applied_at.is_some()));
+
+    assert_eq!(reversible.migrate_down(&pool, 1).await.unwrap(), [2]);
+    let status = reversible.status(&pool).await.unwrap();
+    assert!(status[0].applied_at.is_some());
+    assert!(status[1].applied_at.is_none());
+    assert_eq!(reversible.migrate_down(&pool, 5).await.unwrap(), [1]);
+    assert!(!table_exists(&pool, "users").await.unwrap());
+
+    // Migration 3 has no down SQL
+    assert_eq!(migrator.migrate_up(&pool).await.unwrap(), [1, 2, 3]);
+    assert!(migrator.migrate_down(&pool, 1).await.is_err());
+    assert!(table_exists(&pool, "audit").await.unwrap());
+
+    drop(pool);
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_failed_migration_rolls_back() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let pool = create_pool(&test_db.config_with_db()).await.expect("Failed to connect");
+    let migrator = Migrator::new(vec![
+        Migration::new(1, "ok", "CREATE TABLE kept (id INT);"),
+        Migration::new(2, "broken", "CREATE TABLE partial (id INT); SELECT * FROM missing;"),
+    ])
+    .unwrap();
+
+    assert!(migrator.migrate_up(&pool).await.is_err());
+    assert!(table_exists(&pool, "kept").await.unwrap());
+    assert!(!table_exists(&pool, "partial").await.unwrap());
+    let status = migrator.status(&pool).await.unwrap();
+    assert!(status[0].applied_at.is_some());
+    assert!(status[1].applied_at.is_none());
+
+    drop(pool);
+    test_db.drop().await;
+}
@@ -0,0 +1,180 @@
+//! Integration tests for pg-toolkit monitoring module.
+//!
+//! Tests: list_activity, terminate_backend, terminate_connections,
+//!        terminate_idle_connections, cancel_queries_running_longer_than
+//!
+//! Run with:
+//!   cargo test --test test_monitoring
+//!
+//! Requires PostgreSQL running (see Scripts/DockerBuilds/knowledge-base/docker-compose.yml)
+
+use pg_toolkit::{
+    connection::{create_pool, create_system_pool},
+    monitoring::{
+        CancelFilter, cancel_queries_running_longer_than, list_activity, terminate_backend,
+        terminate_connections, terminate_idle_connections,
+    },
+};
+use std::time::Duration;
+
+mod common;
+use common::TestDb;
+
+#[tokio::test]
+async fn test_list_activity() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    // Hold a second connection open in a known state.
+    let mut idle = pool.acquire().await.expect("Failed to acquire connection");
+    let idle_pid: i32 = sqlx::query_scalar("SELECT pg_backend_pid()")
+        .fetch_one(&mut *idle)
+        .await
+        .unwrap();
+    sqlx::query("SELECT pg_sleep(0.1)").execute(&mut *idle).await.unwrap();
+
+    let sessions = list_activity(&pool).await.expect("Failed to list activity");
+    let session = sessions
+        .iter()
+        .find(|s| s.pid == idle_pid)
+        .expect("Held connection should be listed");
+
+    assert_eq!(session.database.as_deref(), Some(test_db.db_name()));
+    assert_eq!(session.user.as_deref(), Some(config.user.as_str()));
+    assert_eq!(session.state.as_deref(), Some("idle"));
+    assert_eq!(session.query.as_deref(), Some("SELECT pg_sleep(0.1)"));
+    assert!(session.query_start.is_some());
+    assert!(session.query_duration.unwrap() >= Duration::from_millis(100));
+    assert!(session.state_duration.is_some());
+
+    drop(idle);
+    test_db.drop().await;
+}
+
+/// Pid of the backend behind a held connection.
+async fn backend_pid(conn: &mut sqlx::PgConnection) -> i32 {
+    sqlx::query_scalar("SELECT pg_backend_pid()")
+        .fetch_one(conn)
+        .await
+        .expect("Failed to get backend pid")
+}
+
+#[tokio::test]
+async fn test_terminate_backends() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+    let system = create_system_pool(&config).await.expect("Failed to connect");
+
+    let mut first = pool.acquire().await.unwrap();
+    let mut second = pool.acquire().await.unwrap();
+    let first_pid = backend_pid(&mut first).await;
+    let second_pid = backend_pid(&mut second).await;
+
+    // Nothing has been idle for an hour
+    let none = terminate_idle_connections(&config, test_db.db_name(), Duration::from_secs(3600))
+        .await
+        .expect("Failed to terminate idle connections");
+    assert!(none.is_empty());
+
+    assert!(terminate_backend(&system, first_pid).await.expect("Failed to terminate"));
+    assert!(sqlx::query("SELECT 1").execute(&mut *first).await.is_err());
+    first.detach();
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let idle = terminate_idle_connections(&config, test_db.db_name(), Duration::from_millis(100))
+        .await
+        .expect("Failed to terminate idle connections");
+    assert_eq!(idle, [second_pid]);
+    second.detach();
+
+    let mut third = pool.acquire().await.unwrap();
+    let third_pid = backend_pid(&mut third).await;
+    let all = terminate_connections(&system, test_db.db_name()).await.unwrap();
+    assert!(all.contains(&third_pid));
+    third.detach();
+
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_cancel_long_running_queries() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    let mut conn = pool.acquire().await.unwrap();
+    let pid = backend_pid(&mut conn).await;
+    let sleeper = tokio::spawn(async move {
+        let result = sqlx::query("SELECT pg_sleep(30)").execute(&mut *conn).await;
+        (result, conn)
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let mut filter = CancelFilter {
+        database: Some(test_db.db_name().to_string()),
+        dry_run: true,
+        ..Default::default()
+    };
+
+    // Not running long enough yet
+    let none = cancel_queries_running_longer_than(&pool, Duration::from_secs(10), &filter)
+        .await
+        .unwrap();
+    assert!(none.is_empty());
+
+    let would_cancel = cancel_queries_running_longer_than(&pool, Duration::from_millis(100), &filter)
+        .await
+        .expect("Dry run failed");
+    assert_eq!(would_cancel.len(), 1);
+    assert_eq!(would_cancel[0].pid, pid);
+    assert_eq!(would_cancel[0].query.as_deref(), Some("SELECT pg_sleep(30)"));
+
+    filter.application_name = Some("some_other_app".to_string());
+    assert!(cancel_queries_running_longer_than(&pool, Duration::from_millis(100), &filter)
+        .await
+        .unwrap()
+        .is_empty());
+
+    filter.application_name = None;
+    filter.dry_run = false;
+    let cancelled = cancel_queries_running_longer_than(&pool, Duration::from_millis(100), &filter)
+        .await
+        .expect("Cancel failed");
+    assert_eq!(cancelled.len(), 1);
+
+    let (result, mut conn) = tokio::time::timeout(Duration::from_secs(5), sleeper)
+        .await
+        .expect("Query should have been cancelled")
+        .unwrap();
+    let err = result.unwrap_err();
+    assert!(err.to_string().contains("canceling statement"), "{}", err);
+
+    // The session survives cancellation
+    sqlx::query("SELECT 1").execute(&mut *conn).await.expect("Connection should still work");
+    drop(conn);
+
+    test_db.drop().await;
+}
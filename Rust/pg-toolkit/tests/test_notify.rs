@@ -0,0 +1,70 @@
+//! Integration tests for pg-toolkit notify module.
+//!
+//! Tests: listen, notify, reconnect after the listening backend is terminated
+//!
+//! Run with:
+//!   cargo test --test test_notify
+//!
+//! Requires PostgreSQL running (see Scripts/DockerBuilds/knowledge-base/docker-compose.yml)
+
+use futures_util::StreamExt;
+use pg_toolkit::{
+    connection::create_pool,
+    notify::{listen, notify},
+};
+use std::time::Duration;
+
+mod common;
+use common::TestDb;
+
+#[tokio::test]
+async fn test_listen_notify_and_reconnect() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let config = test_db.config_with_db();
+    let pool = create_pool(&config).await.expect("Failed to connect");
+    let mut notifications = Box::pin(listen(&config, "cache_events").await.unwrap());
+
+    notify(&pool, "cache_events", "users:1").await.unwrap();
+    let received = tokio::time::timeout(Duration::from_secs(5), notifications.next())
+        .await
+        .expect("notification not received")
+        .unwrap();
+    assert_eq!(received.channel, "cache_events");
+    assert_eq!(received.payload, "users:1");
+
+    // Kill the listening backend; the stream should resubscribe
+    sqlx::query(
+        "SELECT pg_terminate_backend(pid) FROM pg_stat_activity \
+         WHERE datname = current_database() AND pid <> pg_backend_pid() \
+           AND query LIKE 'LISTEN%'",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    // Notifications sent before the reconnect are lost, so keep sending
+    let received = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            notify(&pool, "cache_events", "users:2").await.unwrap();
+            if let Ok(Some(n)) =
+                tokio::time::timeout(Duration::from_millis(200), notifications.next()).await
+            {
+                return n;
+            }
+        }
+    })
+    .await
+    .expect("no notification after reconnect");
+    assert_eq!(received.payload, "users:2");
+
+    drop(notifications);
+    drop(pool);
+    test_db.drop().await;
+}
@@ -0,0 +1,52 @@
+//! Integration tests for pg-toolkit pool_set module.
+//!
+//! Tests: PoolSet::connect, read, write
+//!
+//! Run with:
+//!   cargo test --test test_pool_set
+//!
+//! Requires PostgreSQL running (see Scripts/DockerBuilds/knowledge-base/docker-compose.yml)
+
+use pg_toolkit::pool_set::{PoolSet, PoolSetConfig, ReplicaConfig};
+
+mod common;
+use common::TestDb;
+
+#[tokio::test]
+async fn test_pool_set_connect() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    // The test server stands in for its own replica
+    let primary = test_db.config_with_db();
+    let config = PoolSetConfig {
+        replicas: vec![ReplicaConfig { host: primary.host.clone(), port: None }],
+        primary,
+    };
+    let pools = PoolSet::connect(&config).await.expect("Failed to connect pool set");
+    assert_eq!(pools.replicas().len(), 1);
+
+    sqlx::query("CREATE TABLE routed (id INTEGER)").execute(pools.write()).await.unwrap();
+    sqlx::query("INSERT INTO routed VALUES (1)").execute(pools.write()).await.unwrap();
+    let count: i64 = sqlx::query_scalar("SELECT count(*) FROM routed")
+        .fetch_one(pools.read())
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+
+    // Nothing listens on port 1
+    let unreachable = PoolSetConfig {
+        primary: pg_toolkit::PgConfig { acquire_timeout_secs: Some(1), ..config.primary.clone() },
+        replicas: vec![ReplicaConfig { host: config.primary.host.clone(), port: Some(1) }],
+    };
+    let err = PoolSet::connect(&unreachable).await.unwrap_err();
+    assert!(format!("{:#}", err).contains("replica"));
+
+    pools.close().await;
+    test_db.drop().await;
+}
@@ -0,0 +1,55 @@
+//! Integration tests for pg-toolkit query_builder module.
+//!
+//! Tests: upsert
+//!
+//! Run with:
+//!   cargo test --test test_query_builder
+//!
+//! Requires PostgreSQL running (see Scripts/DockerBuilds/knowledge-base/docker-compose.yml)
+
+use pg_toolkit::{connection::create_pool, query_builder::upsert};
+
+mod common;
+use common::TestDb;
+
+#[tokio::test]
+async fn test_upsert() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let pool = create_pool(&test_db.config_with_db()).await.expect("Failed to connect");
+    sqlx::raw_sql(
+        "CREATE SCHEMA kb; \
+         CREATE TABLE kb.docs (id INT PRIMARY KEY, title TEXT NOT NULL, views INT DEFAULT 0)",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let sql = upsert("kb.docs", &["id", "title"], &["id"], &["title"])
+        .expect("Failed to build upsert");
+    for (id, title) in [(1, "first"), (2, "second"), (1, "first, renamed")] {
+        sqlx::query(&sql).bind(id).bind(title).execute(&pool).await.expect("Failed to upsert");
+    }
+    let rows: Vec<(i32, String)> = sqlx::query_as("SELECT id, title FROM kb.docs ORDER BY id")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(rows, vec![(1, "first, renamed".to_string()), (2, "second".to_string())]);
+
+    let insert_only = upsert("kb.docs", &["id", "title"], &["id"], &[]).unwrap();
+    let result = sqlx::query(&insert_only).bind(2).bind("ignored").execute(&pool).await.unwrap();
+    assert_eq!(result.rows_affected(), 0);
+    let title: String = sqlx::query_scalar("SELECT title FROM kb.docs WHERE id = 2")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(title, "second");
+
+    test_db.drop().await;
+}
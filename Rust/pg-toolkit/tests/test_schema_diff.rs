@@ -0,0 +1,64 @@
+//! Integration tests for pg-toolkit schema_diff module.
+//!
+//! Tests: snapshot, diff_databases
+//!
+//! Run with:
+//!   cargo test --test test_schema_diff
+//!
+//! Requires PostgreSQL running (see Scripts/DockerBuilds/knowledge-base/docker-compose.yml)
+
+use pg_toolkit::{
+    connection::create_pool,
+    schema_diff::{ColumnRef, IndexRef, diff_databases, snapshot},
+};
+
+mod common;
+use common::TestDb;
+
+#[tokio::test]
+async fn test_diff_databases() {
+    let (production, staging) = match (TestDb::new().await, TestDb::new().await) {
+        (Some(a), Some(b)) => (a, b),
+        _ => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+    let production_pool = create_pool(&production.config_with_db()).await.expect("Failed to connect");
+    let staging_pool = create_pool(&staging.config_with_db()).await.expect("Failed to connect");
+
+    sqlx::raw_sql(
+        "CREATE TABLE users (id SERIAL PRIMARY KEY, email VARCHAR(255) NOT NULL); \
+         CREATE INDEX users_email ON users (email)",
+    )
+    .execute(&production_pool)
+    .await
+    .expect("Failed to create production schema");
+    sqlx::raw_sql(
+        "CREATE TABLE users (id SERIAL PRIMARY KEY, email TEXT NOT NULL, nickname TEXT)",
+    )
+    .execute(&staging_pool)
+    .await
+    .expect("Failed to create staging schema");
+
+    let captured = snapshot(&production_pool, "public").await.expect("Failed to snapshot");
+    let users = &captured.tables["users"];
+    assert_eq!(users.columns["email"].data_type, "character varying(255)");
+    assert!(!users.columns["email"].nullable);
+    assert!(users.indexes.contains_key("users_pkey"));
+
+    let diff = diff_databases(&production_pool, &staging_pool, "public")
+        .await
+        .expect("Failed to diff");
+    assert!(diff.missing_tables.is_empty() && diff.extra_tables.is_empty());
+    assert_eq!(diff.extra_columns, [ColumnRef { table: "users".into(), column: "nickname".into() }]);
+    assert_eq!(diff.type_mismatches.len(), 1);
+    assert_eq!(diff.type_mismatches[0].actual, "text");
+    assert_eq!(diff.missing_indexes, [IndexRef { table: "users".into(), index: "users_email".into() }]);
+    assert!(diff.index_mismatches.is_empty());
+
+    assert!(diff_databases(&production_pool, &production_pool, "public").await.unwrap().is_empty());
+
+    production.drop().await;
+    staging.drop().await;
+}
@@ -0,0 +1,127 @@
+//! Integration tests for pg-toolkit seed module.
+//!
+//! Tests: seed_csv, seed_json, seed_file
+//!
+//! Run with:
+//!   cargo test --test test_seed
+//!
+//! Requires PostgreSQL running (see Scripts/DockerBuilds/knowledge-base/docker-compose.yml)
+
+use pg_toolkit::{
+    admin::TruncateOptions,
+    connection::create_pool,
+    seed::{SeedOptions, seed_csv, seed_file, seed_json},
+};
+
+mod common;
+use common::TestDb;
+
+#[tokio::test]
+async fn test_seed_csv_and_json() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let pool = create_pool(&test_db.config_with_db()).await.expect("Failed to connect");
+    sqlx::raw_sql(
+        "CREATE SCHEMA app; \
+         CREATE TABLE app.users (id SERIAL PRIMARY KEY, name TEXT NOT NULL, \
+             active BOOLEAN, joined DATE, tags TEXT[], profile JSONB)",
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create test table");
+
+    let csv = "name,active,joined\nada,yes,2024-01-02\n\"Lovelace, A\",,\n";
+    let copied = seed_csv(&pool, "app.users", csv, &SeedOptions::default())
+        .await
+        .expect("Failed to seed CSV");
+    assert_eq!(copied, 2);
+    let (active, joined): (Option<bool>, Option<String>) =
+        sqlx::query_as("SELECT active, joined::text FROM app.users WHERE name = 'ada'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!((active, joined.as_deref()), (Some(true), Some("2024-01-02")));
+
+    // Truncate-before-load replaces the rows and restarts the id sequence
+    let json = r#"[
+        {"name": "grace", "active": true, "tags": ["navy", "cobol \"1959\""], "profile": {"rank": 1}},
+        {"name": "alan", "joined": "1936-05-28"}
+    ]"#;
+    let options = SeedOptions {
+        truncate: Some(TruncateOptions { cascade: false, restart_identity: true }),
+    };
+    assert_eq!(seed_json(&pool, "app.users", json, &options).await.expect("Failed to seed JSON"), 2);
+    let rows: Vec<(i32, String, Option<Vec<String>>)> =
+        sqlx::query_as("SELECT id, name, tags FROM app.users ORDER BY id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].0, 1);
+    assert_eq!(rows[0].2, Some(vec!["navy".to_string(), "cobol \"1959\"".to_string()]));
+    let rank: i64 = sqlx::query_scalar("SELECT (profile->>'rank')::int8 FROM app.users WHERE id = 1")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(rank, 1);
+    assert_eq!((rows[1].1.as_str(), rows[1].2.as_ref()), ("alan", None));
+
+    // A failed load leaves the table as it was, even with truncate set
+    let err = seed_json(&pool, "app.users", r#"[{"name": null}]"#, &options).await;
+    assert!(err.is_err());
+    let err = seed_csv(&pool, "app.users", "name,nickname\nx,y\n", &options).await.unwrap_err();
+    assert!(err.to_string().contains("nickname"), "{}", err);
+    let count: i64 = sqlx::query_scalar("SELECT count(*) FROM app.users")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 2);
+
+    assert!(seed_csv(&pool, "missing", "a\n1\n", &SeedOptions::default()).await.is_err());
+
+    test_db.drop().await;
+}
+
+#[tokio::test]
+async fn test_seed_file() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let pool = create_pool(&test_db.config_with_db()).await.expect("Failed to connect");
+    sqlx::query("CREATE TABLE items (sku TEXT PRIMARY KEY, price NUMERIC)")
+        .execute(&pool)
+        .await
+        .expect("Failed to create test table");
+
+    let dir = std::env::temp_dir().join(test_db.db_name());
+    std::fs::create_dir_all(&dir).unwrap();
+    let csv_path = dir.join("items.csv");
+    let json_path = dir.join("items.json");
+    std::fs::write(&csv_path, "sku,price\r\na-1,9.99\r\n").unwrap();
+    std::fs::write(&json_path, r#"[{"sku": "b-2", "price": 5}]"#).unwrap();
+
+    let options = SeedOptions::default();
+    assert_eq!(seed_file(&pool, "items", &csv_path, &options).await.unwrap(), 1);
+    assert_eq!(seed_file(&pool, "items", &json_path, &options).await.unwrap(), 1);
+    assert!(seed_file(&pool, "items", dir.join("items.txt"), &options).await.is_err());
+
+    let total: String = sqlx::query_scalar("SELECT sum(price)::text FROM items")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(total, "14.99");
+
+    std::fs::remove_dir_all(&dir).ok();
+    test_db.drop().await;
+}
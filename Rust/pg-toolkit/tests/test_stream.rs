@@ -0,0 +1,66 @@
+//! Integration tests for pg-toolkit stream module.
+//!
+//! Tests: fetch_streaming
+//!
+//! Run with:
+//!   cargo test --test test_stream
+//!
+//! Requires PostgreSQL running (see Scripts/DockerBuilds/knowledge-base/docker-compose.yml)
+
+use futures_util::{StreamExt, TryStreamExt};
+use pg_toolkit::{connection::create_pool, stream::fetch_streaming};
+use sqlx::{Arguments, Row, postgres::PgArguments};
+
+mod common;
+use common::TestDb;
+
+#[tokio::test]
+async fn test_fetch_streaming() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    // One connection: a dropped stream has to give it back
+    let mut config = test_db.config_with_db();
+    config.max_connections = Some(1);
+    let pool = create_pool(&config).await.expect("Failed to connect");
+
+    let mut binds = PgArguments::default();
+    binds.add(200_000_i64).unwrap();
+    let mut rows = fetch_streaming(&pool, "SELECT i FROM generate_series(1, $1) i", binds);
+    let (mut count, mut sum) = (0_i64, 0_i64);
+    while let Some(row) = rows.try_next().await.expect("Failed to stream rows") {
+        let i: i64 = row.get(0);
+        count += 1;
+        sum += i;
+    }
+    drop(rows);
+    assert_eq!(count, 200_000);
+    assert_eq!(sum, 200_000 * 200_001 / 2);
+
+    // Stop early
+    let huge = "SELECT i FROM generate_series(1, 1000000) i";
+    let first: Vec<_> = fetch_streaming(&pool, huge, PgArguments::default())
+        .take(3)
+        .try_collect()
+        .await
+        .expect("Failed to stream rows");
+    assert_eq!(first.len(), 3);
+
+    let mut failing = fetch_streaming(&pool, "SELECT * FROM no_such_table", PgArguments::default());
+    let error = failing.next().await.expect("Expected an error item").unwrap_err();
+    assert!(format!("{:#}", error).contains("no_such_table"));
+    drop(failing);
+
+    let one: i32 = sqlx::query_scalar("SELECT 1")
+        .fetch_one(&pool)
+        .await
+        .expect("Connection not released");
+    assert_eq!(one, 1);
+
+    test_db.drop().await;
+}
@@ -0,0 +1,106 @@
+//! Integration tests for pg-toolkit testing module.
+//!
+//! Tests: TestDb, unique_db_name, TemplateDb
+//!
+//! Run with:
+//!   cargo test --test test_testing
+//!
+//! Requires PostgreSQL running (see Scripts/DockerBuilds/knowledge-base/docker-compose.yml)
+
+use pg_toolkit::{
+    admin::database_exists,
+    testing::{TemplateDb, TestDb, unique_db_name},
+};
+
+#[test]
+fn test_unique_db_name() {
+    let first = unique_db_name("app_test");
+    let second = unique_db_name("app_test");
+    assert!(first.starts_with("app_test_"));
+    assert_ne!(first, second);
+}
+
+#[tokio::test]
+async fn test_test_db_dropped_on_drop_and_on_panic() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+    let config = test_db.config().clone();
+
+    // Connections left open do not stop the cleanup
+    let pool = test_db.pool().await.expect("Failed to connect");
+    sqlx::query("SELECT 1").execute(&pool).await.unwrap();
+    let name = test_db.db_name().to_string();
+    drop(test_db);
+    assert!(!database_exists(&config, &name).await.unwrap());
+
+    // A panicking test still drops its database
+    let panicked = tokio::spawn(async {
+        let test_db = TestDb::create(&pg_toolkit::PgConfig::from_env(), "pg_toolkit_panic")
+            .await
+            .expect("Failed to create test database");
+        let name = test_db.db_name().to_string();
+        panic!("{}", name);
+    })
+    .await
+    .unwrap_err();
+    let payload = panicked.into_panic();
+    let name = payload.downcast_ref::<String>().expect("panic carries the name");
+    assert!(name.starts_with("pg_toolkit_panic_"));
+    assert!(!database_exists(&config, name).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_template_db() {
+    // Only used to probe for a server
+    let Some(probe) = TestDb::new().await else {
+        eprintln!("Skipping test: PostgreSQL not available");
+        return;
+    };
+    let config = probe.config().clone();
+    probe.drop().await;
+
+    let name = unique_db_name("tpl");
+    let template = TemplateDb::create(&config, &name, async |pool| {
+        sqlx::raw_sql(
+            "CREATE TABLE users (id SERIAL PRIMARY KEY, email TEXT UNIQUE); \
+             INSERT INTO users (email) VALUES ('admin@example.com')",
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    })
+    .await
+    .expect("Failed to create template");
+
+    // Copies start from the template's schema and data and are independent
+    let first = template.instantiate().await.expect("Failed to instantiate");
+    let second = template.instantiate().await.expect("Failed to instantiate");
+    let first_pool = first.pool().await.unwrap();
+    sqlx::query("INSERT INTO users (email) VALUES ('a@example.com')")
+        .execute(&first_pool)
+        .await
+        .unwrap();
+    let counts: Vec<i64> = user_counts(&[&first, &second]).await;
+    assert_eq!(counts, [2, 1]);
+    first_pool.close().await;
+
+    first.drop().await;
+    second.drop().await;
+    template.drop().await;
+    assert!(!database_exists(&config, &name).await.unwrap());
+}
+
+async fn user_counts(dbs: &[&TestDb]) -> Vec<i64> {
+    let mut counts = vec![];
+    for db in dbs {
+        let pool = db.pool().await.unwrap();
+        counts.push(sqlx::query_scalar("SELECT count(*) FROM users").fetch_one(&pool).await.unwrap());
+        pool.close().await;
+    }
+    counts
+}
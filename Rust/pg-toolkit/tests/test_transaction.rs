@@ -0,0 +1,95 @@
+//! Integration tests for pg-toolkit transaction module.
+//!
+//! Tests: with_retry, with_retry_policy
+//!
+//! Run with:
+//!   cargo test --test test_transaction
+//!
+//! Requires PostgreSQL running (see Scripts/DockerBuilds/knowledge-base/docker-compose.yml)
+
+use anyhow::Context;
+use pg_toolkit::{
+    connection::create_pool,
+    transaction::{IsolationLevel, RetryPolicy, with_retry, with_retry_policy},
+};
+use std::time::Duration;
+
+mod common;
+use common::TestDb;
+
+/// Fails with SQLSTATE `code` inside the transaction.
+async fn raise(tx: &mut sqlx::PgTransaction<'static>, code: &str) -> anyhow::Result<()> {
+    sqlx::raw_sql(&format!(
+        "DO $$ BEGIN RAISE EXCEPTION 'simulated' USING ERRCODE = '{}'; END $$",
+        code
+    ))
+    .execute(&mut **tx)
+    .await
+    .context("simulated failure")?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_with_retry() {
+    let test_db = match TestDb::new().await {
+        Some(db) => db,
+        None => {
+            eprintln!("Skipping test: PostgreSQL not available");
+            return;
+        }
+    };
+
+    let pool = create_pool(&test_db.config_with_db()).await.expect("Failed to connect");
+    sqlx::query("CREATE TABLE counter (n INT)").execute(&pool).await.unwrap();
+
+    // Serialization failures and deadlocks are retried; earlier attempts roll back
+    let mut attempts = 0;
+    let level: String = with_retry(&pool, IsolationLevel::Serializable, async |tx| {
+        attempts += 1;
+        sqlx::query("INSERT INTO counter VALUES (1)").execute(&mut **tx).await?;
+        match attempts {
+            1 => raise(tx, "40001").await?,
+            2 => raise(tx, "40P01").await?,
+            _ => {}
+        }
+        Ok(sqlx::query_scalar("SHOW transaction_isolation").fetch_one(&mut **tx).await?)
+    })
+    .await
+    .unwrap();
+    assert_eq!(attempts, 3);
+    assert_eq!(level, "serializable");
+    let rows: i64 = sqlx::query_scalar("SELECT count(*) FROM counter")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(rows, 1);
+
+    // Other errors are not retried
+    let mut attempts = 0;
+    let result: pg_toolkit::Result<()> = with_retry(&pool, IsolationLevel::ReadCommitted, async |tx| {
+        attempts += 1;
+        raise(tx, "23505").await
+    })
+    .await;
+    assert_eq!(result.unwrap_err().code().as_deref(), Some("23505"));
+    assert_eq!(attempts, 1);
+
+    // Retries stop after max_attempts
+    let policy = RetryPolicy {
+        max_attempts: 2,
+        initial_backoff: Duration::from_millis(1),
+        ..RetryPolicy::default()
+    };
+    let mut attempts = 0;
+    let result: pg_toolkit::Result<()> =
+        with_retry_policy(&pool, IsolationLevel::RepeatableRead, &policy, async |tx| {
+            attempts += 1;
+            raise(tx, "40001").await
+        })
+        .await;
+    assert!(result.is_err());
+    assert_eq!(attempts, 2);
+
+    drop(pool);
+    test_db.drop().await;
+}